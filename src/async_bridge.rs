@@ -64,6 +64,18 @@ impl AsyncBridge {
         Ok(Self::with_tokio_runtime(timeout, tokio))
     }
 
+    /// Creates a new instance using the provided tokio runtime handle, if any  
+    /// Otherwise a new tokio runtime will be created, as with [`AsyncBridge::new`]
+    pub fn with_optional_runtime_handle(
+        timeout: std::time::Duration,
+        handle: Option<tokio::runtime::Handle>,
+    ) -> Result<Self, Error> {
+        match handle {
+            Some(handle) => Ok(Self::with_runtime_handle(timeout, handle)),
+            None => Self::new(timeout),
+        }
+    }
+
     /// Creates a new instance with the provided options and a pre-configured tokio runtime.
     pub fn with_tokio_runtime(
         timeout: std::time::Duration,
@@ -131,11 +143,26 @@ pub trait AsyncBridgeExt {
         let rt = self.bridge().tokio_runtime();
         let heap_exhausted_token = self.bridge().heap_exhausted_token();
 
-        rt.block_on(async move {
+        let future = async move {
             tokio::select! {
                 result = tokio::time::timeout(timeout, f(self)) => result?,
                 () = heap_exhausted_token.cancelled() => Err(Error::HeapExhausted),
             }
-        })
+        };
+
+        // Tokio panics on a nested `block_on`, so detect being called from within a runtime
+        match tokio::runtime::Handle::try_current() {
+            Err(_) => rt.block_on(future),
+
+            // A multi-threaded runtime can hand this worker's other tasks off while we block
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| rt.block_on(future))
+            }
+
+            // A current-thread runtime cannot make progress while we block, so bail out instead
+            Ok(_) => Err(Error::Runtime(
+                "Cannot block from within a current-thread tokio runtime; use the `_async` variant of this function instead".to_string(),
+            )),
+        }
     }
 }
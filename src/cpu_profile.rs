@@ -0,0 +1,166 @@
+//! CPU profiling of javascript execution, through the V8 inspector's `Profiler` domain
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use deno_core::{
+    serde_json, InspectorMsgKind, InspectorSessionKind, JsRuntime, JsRuntimeInspector,
+    LocalInspectorSession,
+};
+
+use crate::Error;
+
+/// A CPU profile of javascript execution, from [`crate::Runtime::stop_cpu_profile`]
+///
+/// Holds the profile in the Chrome devtools `.cpuprofile` format, which can be saved
+/// with [`CpuProfile::write`] and loaded in the devtools performance tab
+#[derive(Debug, Clone)]
+pub struct CpuProfile {
+    name: String,
+    profile: serde_json::Value,
+}
+
+impl CpuProfile {
+    /// The name the profile was started with
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The time between the start and end of the profile
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        // Timestamps are in microseconds
+        let start = self.profile["startTime"].as_f64().unwrap_or_default();
+        let end = self.profile["endTime"].as_f64().unwrap_or_default();
+        Duration::from_secs_f64((end - start).max(0.0) / 1_000_000.0)
+    }
+
+    /// The number of samples taken while profiling
+    #[must_use]
+    pub fn sample_count(&self) -> usize {
+        self.profile["samples"].as_array().map_or(0, Vec::len)
+    }
+
+    /// Returns the names of the functions seen in the profile, one per call-tree node
+    /// Anonymous functions have an empty name, and the root and idle nodes are included as `(root)` and `(idle)`
+    #[must_use]
+    pub fn function_names(&self) -> Vec<&str> {
+        self.profile["nodes"]
+            .as_array()
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .filter_map(|node| node["callFrame"]["functionName"].as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The raw profile, as a `Profiler.Profile` object from the Chrome devtools protocol
+    #[must_use]
+    pub fn as_json(&self) -> &serde_json::Value {
+        &self.profile
+    }
+
+    /// Writes the profile in the `.cpuprofile` format
+    /// Note that devtools will only import files with a `.cpuprofile` extension
+    ///
+    /// # Errors
+    /// Will return an error if writing to `writer` fails
+    pub fn write(&self, writer: impl std::io::Write) -> Result<(), Error> {
+        serde_json::to_writer(writer, &self.profile)?;
+        Ok(())
+    }
+}
+
+/// A profiling session in progress
+pub(crate) struct CpuProfiler {
+    name: String,
+    session: LocalInspectorSession,
+    responses: Rc<RefCell<HashMap<i32, String>>>,
+    next_id: i32,
+}
+
+impl CpuProfiler {
+    /// The name the profile was started with
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Starts profiling, enabling the runtime's inspector if it was not already
+    pub fn start(
+        runtime: &mut JsRuntime,
+        name: &str,
+        sampling_interval: Option<Duration>,
+    ) -> Result<Self, Error> {
+        runtime.maybe_init_inspector();
+
+        let responses: Rc<RefCell<HashMap<i32, String>>> = Rc::default();
+        let session = {
+            let responses = responses.clone();
+            JsRuntimeInspector::create_local_session(
+                runtime.inspector(),
+                Box::new(move |msg| {
+                    if let InspectorMsgKind::Message(id) = msg.kind {
+                        responses.borrow_mut().insert(id, msg.content);
+                    }
+                }),
+                InspectorSessionKind::NonBlocking {
+                    wait_for_disconnect: false,
+                },
+            )
+        };
+
+        let mut profiler = Self {
+            name: name.to_string(),
+            session,
+            responses,
+            next_id: 0,
+        };
+
+        profiler.call("Profiler.enable", None)?;
+        if let Some(interval) = sampling_interval {
+            let interval = u64::try_from(interval.as_micros())
+                .unwrap_or(u64::MAX)
+                .max(1);
+            profiler.call(
+                "Profiler.setSamplingInterval",
+                Some(serde_json::json!({ "interval": interval })),
+            )?;
+        }
+        profiler.call("Profiler.start", None)?;
+        Ok(profiler)
+    }
+
+    /// Stops profiling, returning the collected profile
+    pub fn stop(mut self) -> Result<CpuProfile, Error> {
+        let mut result = self.call("Profiler.stop", None)?;
+        self.call("Profiler.disable", None)?;
+        Ok(CpuProfile {
+            name: self.name,
+            profile: result["profile"].take(),
+        })
+    }
+
+    /// Sends a protocol message, returning the result of the response
+    fn call(
+        &mut self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.session.post_message(id, method, params);
+
+        // Profiler methods are handled synchronously, so the response has already arrived
+        let response =
+            self.responses.borrow_mut().remove(&id).ok_or_else(|| {
+                Error::Runtime(format!("No response from the inspector to {method}"))
+            })?;
+        let mut response: serde_json::Value = serde_json::from_str(&response)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(Error::Runtime(format!("{method} failed: {error}")));
+        }
+        Ok(response["result"].take())
+    }
+}
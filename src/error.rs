@@ -2,7 +2,10 @@
 //! And some associated utilities
 use std::path::PathBuf;
 
-use deno_core::error::CoreErrorKind;
+use deno_core::{
+    error::{format_frame, CoreErrorKind, JsError, JsStackFrame, NoAnsiColors},
+    ModuleSpecifier,
+};
 use thiserror::Error;
 
 use crate::Module;
@@ -99,6 +102,107 @@ pub enum Error {
     #[class(generic)]
     #[error("Heap exhausted")]
     HeapExhausted,
+
+    /// Triggers when execution is stopped using an [`crate::InterruptHandle`]
+    #[class(generic)]
+    #[error("Execution was interrupted")]
+    Interrupted,
+
+    /// An error returned by a registered rust function, thrown in javascript as a custom class
+    #[class(inherit)]
+    #[error("{0}")]
+    Host(HostError),
+}
+
+/// An error that a function registered with [`crate::Runtime::register_function`] can return
+/// to throw it in javascript as an instance of a custom error class
+///
+/// The thrown error extends `Error`, has `name` as its class and `name` property,
+/// and carries `code` as a property if one was set
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{ Runtime, Error, HostError };
+///
+/// # fn main() -> Result<(), Error> {
+/// let mut runtime = Runtime::new(Default::default())?;
+/// runtime.register_function("fetch_quota", |_| {
+///     Err(HostError::new("RateLimitError", "Too many requests").with_code("E_RATE").into())
+/// })?;
+///
+/// let code: String = runtime.eval("
+///     try { rustyscript.functions.fetch_quota() } catch (e) { e instanceof Error && e.code }
+/// ")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HostError {
+    /// The name of the javascript class, such as `ValidationError`
+    pub name: String,
+
+    /// The message of the error
+    pub message: String,
+
+    /// An optional machine-readable code, set as the `code` property of the error
+    pub code: Option<String>,
+}
+
+impl HostError {
+    /// Creates an error thrown as an instance of the class `name`
+    pub fn new(name: impl ToString, message: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            message: message.to_string(),
+            code: None,
+        }
+    }
+
+    /// Sets the `code` property of the thrown error
+    #[must_use]
+    pub fn with_code(mut self, code: impl ToString) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+}
+
+impl std::fmt::Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl std::error::Error for HostError {}
+
+impl deno_error::JsErrorClass for HostError {
+    fn get_class(&self) -> std::borrow::Cow<'static, str> {
+        // Thrown as a plain error, then rebuilt as the named class by `rustyscript.functions`
+        std::borrow::Cow::Borrowed("Error")
+    }
+
+    fn get_message(&self) -> std::borrow::Cow<'static, str> {
+        self.message.clone().into()
+    }
+
+    fn get_additional_properties(&self) -> deno_error::AdditionalProperties {
+        let name = ("__rustyscript_host_error", self.name.clone());
+        let code = self.code.clone().map(|code| ("code", code));
+        Box::new(
+            std::iter::once(name)
+                .chain(code)
+                .map(|(key, value)| (key.into(), deno_error::PropertyValue::String(value.into()))),
+        )
+    }
+
+    fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+}
+
+impl From<HostError> for Error {
+    fn from(err: HostError) -> Self {
+        Self::Host(err)
+    }
 }
 
 impl From<deno_core::error::JsError> for Error {
@@ -114,6 +218,69 @@ impl From<Box<deno_core::error::JsError>> for Error {
 }
 
 impl Error {
+    /// Returns the stack trace of a javascript error, without the message  
+    /// One frame per line, in the same format as V8's `Error.stack` (`    at fn (file:line:col)`)
+    ///
+    /// Frames are source-mapped, so locations refer to the original (pre-transpilation) source
+    ///
+    /// Returns `None` if this is not a javascript error
+    #[must_use]
+    pub fn stack(&self) -> Option<String> {
+        let frames = self.stack_frames()?;
+        let stack = frames
+            .iter()
+            .map(|frame| format!("    at {}", format_frame::<NoAnsiColors>(frame, None)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(stack)
+    }
+
+    /// Returns the structured stack frames of a javascript error  
+    /// Returns `None` if this is not a javascript error
+    #[must_use]
+    pub fn stack_frames(&self) -> Option<&[JsStackFrame]> {
+        match self {
+            Error::JsError(e) => Some(&e.frames),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the runtime that returned this error is still usable
+    ///
+    /// Most errors, including javascript exceptions, timeouts, and interrupts, leave the runtime
+    /// in a usable state, so the call can be retried or another one made  
+    /// Errors that are not recoverable mean the runtime should be dropped and replaced:
+    /// - [`Error::HeapExhausted`] - the isolate was terminated at its heap limit
+    /// - [`Error::WorkerHasStopped`] - the worker thread running the runtime is gone
+    #[must_use]
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(self, Error::HeapExhausted | Error::WorkerHasStopped)
+    }
+
+    /// Rewrites the module specifiers in this error, see `RuntimeOptions::specifier_display`
+    ///
+    /// The messages of errors raised by the crate, such as [`Error::ModuleNotFound`], are rewritten in full  
+    /// For javascript errors, whose messages are written by scripts, only the file names of stack frames are,
+    /// along with the specifiers accepted by `is_module` - the modules the loader knows of
+    #[must_use]
+    pub(crate) fn display_specifiers(
+        self,
+        display: &dyn Fn(&ModuleSpecifier) -> String,
+        is_module: &dyn Fn(&ModuleSpecifier) -> bool,
+    ) -> Self {
+        let rewrite = |text: &str| crate::utilities::display_specifiers(text, display);
+        match self {
+            Error::JsError(mut e) => {
+                display_js_error_specifiers(&mut e, display, is_module);
+                Error::JsError(e)
+            }
+            Error::ModuleNotFound(message) => Error::ModuleNotFound(rewrite(&message)),
+            Error::Runtime(message) => Error::Runtime(rewrite(&message)),
+            Error::Timeout(message) => Error::Timeout(rewrite(&message)),
+            e => e,
+        }
+    }
+
     /// Formats an error for display in a terminal
     /// If the error is a `JsError`, it will attempt to highlight the source line
     /// in this format:
@@ -221,6 +388,49 @@ impl Error {
     }
 }
 
+/// Rewrites the module specifiers of a javascript error, its cause, and any errors it aggregates
+///
+/// Only the specifiers of its stack frames, and those accepted by `is_module`, are rewritten
+fn display_js_error_specifiers(
+    e: &mut JsError,
+    display: &dyn Fn(&ModuleSpecifier) -> String,
+    is_module: &dyn Fn(&ModuleSpecifier) -> bool,
+) {
+    let frame_files: Vec<ModuleSpecifier> = e
+        .frames
+        .iter()
+        .filter_map(|frame| frame.file_name.as_deref())
+        .filter_map(|file| ModuleSpecifier::parse(file).ok())
+        .collect();
+    let filter =
+        |specifier: &ModuleSpecifier| frame_files.contains(specifier) || is_module(specifier);
+    let rewrite = |text: &mut String| {
+        *text = crate::utilities::display_specifiers_where(text, display, &filter);
+    };
+
+    rewrite(&mut e.exception_message);
+    for text in [&mut e.message, &mut e.stack].into_iter().flatten() {
+        rewrite(text);
+    }
+    for frame in &mut e.frames {
+        if let Some(file) = &mut frame.file_name {
+            if let Ok(specifier) = ModuleSpecifier::parse(file) {
+                *file = display(&specifier);
+            }
+        }
+        if let Some(origin) = &mut frame.eval_origin {
+            rewrite(origin);
+        }
+    }
+
+    if let Some(cause) = &mut e.cause {
+        display_js_error_specifiers(cause, display, is_module);
+    }
+    for aggregated in e.aggregated.iter_mut().flatten() {
+        display_js_error_specifiers(aggregated, display, is_module);
+    }
+}
+
 #[macro_use]
 mod error_macro {
     /// Maps one error type to another
@@ -283,7 +493,109 @@ map_error!(deno_core::futures::channel::oneshot::Canceled, |e| {
 
 #[cfg(test)]
 mod test {
-    use crate::{error::ErrorFormattingOptions, Module, Runtime, RuntimeOptions, Undefined};
+    use crate::{
+        error::ErrorFormattingOptions, Error, HostError, Module, Runtime, RuntimeOptions, Undefined,
+    };
+
+    #[test]
+    fn test_is_recoverable() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let e = runtime
+            .eval::<Undefined>("throw new Error('oops')")
+            .unwrap_err();
+        assert!(e.is_recoverable());
+        let value: u32 = runtime.eval("1 + 1").unwrap();
+        assert_eq!(value, 2);
+
+        let e = runtime.eval::<u32>("'not a number'").unwrap_err();
+        assert!(e.is_recoverable());
+
+        assert!(Error::Interrupted.is_recoverable());
+        assert!(Error::Timeout("slow".to_string()).is_recoverable());
+        assert!(!Error::HeapExhausted.is_recoverable());
+        assert!(!Error::WorkerHasStopped.is_recoverable());
+    }
+
+    #[test]
+    fn test_host_error() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+        runtime
+            .register_function("limited", |_| {
+                Err(HostError::new("RateLimitError", "slow down")
+                    .with_code("E_RATE")
+                    .into())
+            })
+            .unwrap();
+        runtime
+            .register_function("proto", |_| {
+                Err(HostError::new("__proto__", "odd name").into())
+            })
+            .unwrap();
+        runtime
+            .register_async_function("invalid", |_| {
+                Box::pin(async { Err(HostError::new("ValidationError", "bad input").into()) })
+            })
+            .unwrap();
+
+        let module = Module::new(
+            "test.js",
+            "
+            const describe = (e) => [
+                e instanceof Error, e.constructor.name, e.name, e.message, e.code ?? null,
+            ];
+
+            let sync, again;
+            try { rustyscript.functions.limited(); } catch (e) { sync = e; }
+            try { rustyscript.functions.limited(); } catch (e) { again = e; }
+            export const limited = [...describe(sync), sync.constructor === again.constructor];
+
+            let proto;
+            try { rustyscript.functions.proto(); } catch (e) { proto = e; }
+            export const protoName = describe(proto);
+
+            export const invalid = await rustyscript.async_functions.invalid().catch(describe);
+        ",
+        );
+        let handle = runtime.load_module(&module).unwrap();
+
+        let limited: deno_core::serde_json::Value =
+            runtime.get_value(Some(&handle), "limited").unwrap();
+        assert_eq!(
+            limited,
+            deno_core::serde_json::json!([
+                true,
+                "RateLimitError",
+                "RateLimitError",
+                "slow down",
+                "E_RATE",
+                true
+            ])
+        );
+
+        let proto: deno_core::serde_json::Value =
+            runtime.get_value(Some(&handle), "protoName").unwrap();
+        assert_eq!(
+            proto,
+            deno_core::serde_json::json!([true, "__proto__", "__proto__", "odd name", null])
+        );
+
+        let invalid: deno_core::serde_json::Value =
+            runtime.get_value(Some(&handle), "invalid").unwrap();
+        assert_eq!(
+            invalid,
+            deno_core::serde_json::json!([
+                true,
+                "ValidationError",
+                "ValidationError",
+                "bad input",
+                null
+            ])
+        );
+
+        let e = HostError::new("RateLimitError", "slow down");
+        assert_eq!(Error::from(e).to_string(), "RateLimitError: slow down");
+    }
 
     #[test]
     #[rustfmt::skip]
@@ -5,7 +5,8 @@ use super::ExtensionTrait;
 
 mod wrapper;
 pub use wrapper::{
-    BroadcastChannelWrapper, IsolatedBroadcastChannel, IsolatedBroadcastChannelWrapper,
+    BridgeDirection, BroadcastChannelWrapper, IsolatedBroadcastChannel,
+    IsolatedBroadcastChannelWrapper,
 };
 
 extension!(
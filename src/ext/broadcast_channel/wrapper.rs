@@ -48,6 +48,21 @@
 //! sub1.send_sync(&mut runtime, "hello")?; // sub2 receives
 //! // JavaScript BroadcastChannel does NOT receive this
 //! ```
+//!
+//! ### Rust ↔ Rust, surfaced to JavaScript (bridged IsolatedBroadcastChannel)
+//! ```rust,ignore
+//! use rustyscript::{BridgeDirection, IsolatedBroadcastChannel, Runtime, RuntimeOptions};
+//!
+//! let options = RuntimeOptions::default();
+//! let js_channel = options.extension_options.broadcast_channel.clone();
+//! let mut runtime = Runtime::new(options)?;
+//!
+//! let channel = IsolatedBroadcastChannel::bridged(&js_channel, BridgeDirection::ToJs);
+//! let sub = channel.subscribe("my_channel")?;
+//!
+//! sub.send_sync(&mut runtime, "hello")?;
+//! // Other subscriptions, and JavaScript BroadcastChannel('my_channel'), receive this
+//! ```
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -55,7 +70,7 @@ use std::time::Duration;
 use deno_core::parking_lot::Mutex;
 use deno_web::InMemoryBroadcastChannel;
 use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::broadcast;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -69,6 +84,19 @@ struct InMemoryChannelMessage {
     uuid: Uuid,
 }
 
+type JsSender = Arc<Mutex<broadcast::Sender<InMemoryChannelMessage>>>;
+
+/// Get the sender shared with javascript's `BroadcastChannel`
+fn js_sender(channel: &InMemoryBroadcastChannel) -> JsSender {
+    // SAFETY: InMemoryBroadcastChannel is repr(Rust) tuple struct with single field:
+    // pub struct InMemoryBroadcastChannel(Arc<Mutex<broadcast::Sender<InMemoryChannelMessage>>>);
+    //
+    // We can access the field by transmuting to the inner type:
+    let sender: &JsSender =
+        unsafe { &*(channel as *const InMemoryBroadcastChannel as *const JsSender) };
+    sender.clone()
+}
+
 /// Helper struct to wrap a broadcast channel
 ///
 /// Takes care of some of the boilerplate for serialization/deserialization.
@@ -103,16 +131,7 @@ impl BroadcastChannelWrapper {
     /// # Errors
     /// Will return an error if the channel cannot be subscribed to
     pub fn new(channel: &InMemoryBroadcastChannel, name: impl ToString) -> Result<Self, Error> {
-        // SAFETY: InMemoryBroadcastChannel is repr(Rust) tuple struct with single field:
-        // pub struct InMemoryBroadcastChannel(Arc<Mutex<broadcast::Sender<InMemoryChannelMessage>>>);
-        //
-        // We can access the field by transmuting to the inner type:
-        let sender: &Arc<Mutex<broadcast::Sender<InMemoryChannelMessage>>> = unsafe {
-            &*(channel as *const InMemoryBroadcastChannel
-                as *const Arc<Mutex<broadcast::Sender<InMemoryChannelMessage>>>)
-        };
-
-        let sender = sender.clone();
+        let sender = js_sender(channel);
         let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
         let broadcast_rx = sender.lock().subscribe();
         let receiver = tokio::sync::Mutex::new((broadcast_rx, cancel_rx));
@@ -261,16 +280,38 @@ struct IsolatedChannelMessage {
     sender_id: Uuid,
 }
 
+/// Which messages cross between a bridged [`IsolatedBroadcastChannel`] and JavaScript's `BroadcastChannel`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BridgeDirection {
+    /// Messages sent from rust are also delivered to JavaScript channels of the same name
+    ToJs,
+
+    /// As with `ToJs`, and messages posted by JavaScript are also received by rust subscriptions of the same name
+    Bidirectional,
+}
+
+/// The JavaScript side of a bridged channel
+#[derive(Clone)]
+struct JsBridge {
+    sender: JsSender,
+    direction: BridgeDirection,
+
+    /// Identifies messages forwarded by this bridge, so they are not received back
+    uuid: Uuid,
+}
+
 /// An isolated broadcast channel that can be shared across multiple runtimes
 ///
 /// This is the backing storage for isolated broadcast channel communication.
 /// Clone this to share the channel between multiple wrappers.
 ///
-/// **Note**: This channel is isolated and does NOT share messages with JavaScript's
-/// `BroadcastChannel` API. For JavaScript integration, use `BroadcastChannelWrapper`.
+/// **Note**: By default this channel is isolated and does NOT share messages with JavaScript's
+/// `BroadcastChannel` API. Use [`IsolatedBroadcastChannel::bridged`] to forward messages across,
+/// or `BroadcastChannelWrapper` for full JavaScript integration.
 #[derive(Clone)]
 pub struct IsolatedBroadcastChannel {
     sender: Arc<Mutex<broadcast::Sender<IsolatedChannelMessage>>>,
+    bridge: Option<JsBridge>,
 }
 
 impl Default for IsolatedBroadcastChannel {
@@ -286,9 +327,34 @@ impl IsolatedBroadcastChannel {
         let (sender, _) = broadcast::channel(256);
         Self {
             sender: Arc::new(Mutex::new(sender)),
+            bridge: None,
+        }
+    }
+
+    /// Create a new isolated broadcast channel, that forwards messages to and from JavaScript's `BroadcastChannel`
+    ///
+    /// Messages between rust subscriptions stay on the isolated channel; only those crossing the bridge
+    /// go through the JavaScript channel, named after the subscription
+    ///
+    /// `js_channel` is the runtime's channel, from `RuntimeOptions::extension_options.broadcast_channel`
+    #[must_use]
+    pub fn bridged(js_channel: &InMemoryBroadcastChannel, direction: BridgeDirection) -> Self {
+        Self {
+            bridge: Some(JsBridge {
+                sender: js_sender(js_channel),
+                direction,
+                uuid: Uuid::new_v4(),
+            }),
+            ..Self::new()
         }
     }
 
+    /// Returns the direction messages are forwarded to JavaScript, if this channel is bridged
+    #[must_use]
+    pub fn bridge_direction(&self) -> Option<BridgeDirection> {
+        self.bridge.as_ref().map(|bridge| bridge.direction)
+    }
+
     /// Subscribe to this channel, creating a wrapper for sending/receiving messages
     ///
     /// # Errors
@@ -298,6 +364,13 @@ impl IsolatedBroadcastChannel {
     }
 }
 
+/// Receivers for an isolated subscription: the isolated channel, the JavaScript side of a bridge, and cancellation
+type IsolatedReceivers = (
+    broadcast::Receiver<IsolatedChannelMessage>,
+    Option<broadcast::Receiver<InMemoryChannelMessage>>,
+    mpsc::UnboundedReceiver<()>,
+);
+
 /// Helper struct to wrap an isolated broadcast channel subscription
 ///
 /// Takes care of some of the boilerplate for serialization/deserialization.
@@ -307,10 +380,7 @@ impl IsolatedBroadcastChannel {
 /// JavaScript's `BroadcastChannel` API.
 pub struct IsolatedBroadcastChannelWrapper {
     channel: IsolatedBroadcastChannel,
-    receiver: tokio::sync::Mutex<(
-        broadcast::Receiver<IsolatedChannelMessage>,
-        mpsc::UnboundedReceiver<()>,
-    )>,
+    receiver: tokio::sync::Mutex<IsolatedReceivers>,
     cancel_tx: mpsc::UnboundedSender<()>,
     name: String,
     uuid: Uuid,
@@ -326,7 +396,12 @@ impl IsolatedBroadcastChannelWrapper {
     pub fn new(channel: &IsolatedBroadcastChannel, name: impl ToString) -> Result<Self, Error> {
         let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
         let broadcast_rx = channel.sender.lock().subscribe();
-        let receiver = tokio::sync::Mutex::new((broadcast_rx, cancel_rx));
+        let js_rx = channel
+            .bridge
+            .as_ref()
+            .filter(|bridge| bridge.direction == BridgeDirection::Bidirectional)
+            .map(|bridge| bridge.sender.lock().subscribe());
+        let receiver = tokio::sync::Mutex::new((broadcast_rx, js_rx, cancel_rx));
         let uuid = Uuid::new_v4();
         let name = name.to_string();
 
@@ -364,9 +439,23 @@ impl IsolatedBroadcastChannelWrapper {
             .call_function_async(None, "broadcast_serialize", &data)
             .await?;
 
+        let name = Arc::new(self.name.clone());
+        let data = Arc::new(data);
+
+        // Forward across the bridge - the bytes are already in the format JavaScript expects
+        // Sending fails only when nothing is subscribed, which is not an error here
+        if let Some(bridge) = &self.channel.bridge {
+            let message = InMemoryChannelMessage {
+                name: name.clone(),
+                data: data.clone(),
+                uuid: bridge.uuid,
+            };
+            let _ = bridge.sender.lock().send(message);
+        }
+
         let message = IsolatedChannelMessage {
-            name: Arc::new(self.name.clone()),
-            data: Arc::new(data),
+            name,
+            data,
             sender_id: self.uuid,
         };
 
@@ -393,18 +482,27 @@ impl IsolatedBroadcastChannelWrapper {
         timeout: Option<Duration>,
     ) -> Result<Option<T>, Error> {
         let mut guard = self.receiver.lock().await;
-        let (broadcast_rx, cancel_rx) = &mut *guard;
+        let (broadcast_rx, js_rx, cancel_rx) = &mut *guard;
+        let bridge_uuid = self.channel.bridge.as_ref().map(|bridge| bridge.uuid);
 
         loop {
+            // Messages from either side, as (is_own_message, name, data)
+            let next = async {
+                tokio::select! {
+                    r = broadcast_rx.recv() => r.map(|m| (m.sender_id == self.uuid, m.name, m.data)),
+                    r = recv_js(js_rx.as_mut()) => r.map(|m| (Some(m.uuid) == bridge_uuid, m.name, m.data)),
+                }
+            };
+
             let result = if let Some(timeout) = timeout {
                 tokio::select! {
-                    r = broadcast_rx.recv() => r,
+                    r = next => r,
                     () = tokio::time::sleep(timeout) => return Ok(None),
                     _ = cancel_rx.recv() => return Ok(None),
                 }
             } else {
                 tokio::select! {
-                    r = broadcast_rx.recv() => r,
+                    r = next => r,
                     _ = cancel_rx.recv() => return Ok(None),
                 }
             };
@@ -412,16 +510,16 @@ impl IsolatedBroadcastChannelWrapper {
             use tokio::sync::broadcast::error::RecvError::*;
             match result {
                 Err(Closed) => return Ok(None),
-                Err(Lagged(_)) => continue, // Backlogged, messages dropped - try again
-                Ok(message) if message.sender_id == self.uuid => continue, // Self-send, skip
-                Ok(message) if *message.name != self.name => continue, // Different channel name
-                Ok(message) => {
+                // Backlogged and messages dropped, self-send, or forwarded by our bridge - try again
+                Err(Lagged(_)) | Ok((true, _, _)) => continue,
+                Ok((_, name, _)) if *name != self.name => continue, // Different channel name
+                Ok((_, _, data)) => {
                     // Deserialize through JavaScript for compatibility
                     let data: T = runtime
                         .call_function_async(
                             None,
                             "broadcast_deserialize",
-                            big_json_args!(Vec::clone(&message.data)),
+                            big_json_args!(Vec::clone(&data)),
                         )
                         .await?;
                     return Ok(Some(data));
@@ -461,6 +559,16 @@ impl Drop for IsolatedBroadcastChannelWrapper {
     }
 }
 
+/// Receive from the JavaScript side of a bridge, or wait forever if there is none
+async fn recv_js(
+    receiver: Option<&mut broadcast::Receiver<InMemoryChannelMessage>>,
+) -> Result<InMemoryChannelMessage, RecvError> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -538,4 +646,90 @@ mod test {
             assert!(result.is_none());
         });
     }
+
+    #[test]
+    fn test_isolated_broadcast_channel_bridge_to_js() {
+        let options = RuntimeOptions::default();
+        let js_channel = options.extension_options.broadcast_channel.clone();
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+
+        let channel = IsolatedBroadcastChannel::bridged(&js_channel, BridgeDirection::ToJs);
+        assert_eq!(channel.bridge_direction(), Some(BridgeDirection::ToJs));
+        let sender = channel.subscribe("bridged").expect("Could not subscribe");
+        let receiver = channel.subscribe("bridged").expect("Could not subscribe");
+
+        let module = Module::new(
+            "test.js",
+            "
+            globalThis.received = [];
+            const channel = new BroadcastChannel('bridged');
+            channel.onmessage = (event) => {
+                globalThis.received.push(event.data);
+                channel.close();
+            };
+        ",
+        );
+        runtime.load_module(&module).expect("Could not load module");
+
+        sender
+            .send_sync(&mut runtime, "surfaced")
+            .expect("Could not send message");
+        runtime
+            .block_on_event_loop(
+                deno_core::PollEventLoopOptions::default(),
+                Some(std::time::Duration::from_secs(1)),
+            )
+            .expect("Could not run the event loop");
+
+        let js_messages: Vec<String> = runtime.eval("globalThis.received").expect("Could not eval");
+        assert_eq!(js_messages, vec!["surfaced"]);
+
+        // The internal path still delivers the message
+        let value = receiver
+            .recv_sync::<String>(&mut runtime, Some(std::time::Duration::from_secs(1)))
+            .expect("Could not receive message");
+        assert_eq!(value.as_deref(), Some("surfaced"));
+    }
+
+    #[test]
+    fn test_isolated_broadcast_channel_bridge_bidirectional() {
+        let options = RuntimeOptions::default();
+        let js_channel = options.extension_options.broadcast_channel.clone();
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+
+        let channel =
+            IsolatedBroadcastChannel::bridged(&js_channel, BridgeDirection::Bidirectional);
+        let sender = channel.subscribe("bridged").expect("Could not subscribe");
+        let receiver = channel.subscribe("bridged").expect("Could not subscribe");
+
+        // Sent from rust - received once, not echoed back through the bridge
+        sender
+            .send_sync(&mut runtime, "from rust")
+            .expect("Could not send message");
+        let value = receiver
+            .recv_sync::<String>(&mut runtime, Some(std::time::Duration::from_secs(1)))
+            .expect("Could not receive message");
+        assert_eq!(value.as_deref(), Some("from rust"));
+
+        let value = receiver
+            .recv_sync::<String>(&mut runtime, Some(std::time::Duration::from_millis(100)))
+            .expect("Could not receive message");
+        assert!(value.is_none());
+
+        // Posted from javascript
+        let module = Module::new(
+            "test.js",
+            "
+            const channel = new BroadcastChannel('bridged');
+            channel.postMessage('from js');
+            channel.close();
+        ",
+        );
+        runtime.load_module(&module).expect("Could not load module");
+
+        let value = receiver
+            .recv_sync::<String>(&mut runtime, Some(std::time::Duration::from_secs(1)))
+            .expect("Could not receive message");
+        assert_eq!(value.as_deref(), Some("from js"));
+    }
 }
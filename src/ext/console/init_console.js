@@ -2,9 +2,20 @@ import * as _console from 'ext:deno_web/01_console.js';
 
 import { applyToGlobal, nonEnumerable } from 'ext:rustyscript/rustyscript.js';
 
-const consoleInstance = new _console.Console((msg, level) =>
-  globalThis.Deno.core.print(msg, level > 1),
-);
+const consoleInstance = new _console.Console((msg, level) => {
+  // Record error-level output for `RuntimeOptions::fail_on_console_error`
+  if (level > 2) {
+    globalThis.Deno.core.ops.op_report_error(
+      msg.replace(/\x1b\[[0-9;]*m/g, "").trimEnd(),
+    );
+  }
+
+  // Deliver the message to `RuntimeOptions::console_handler` instead of printing it, if one is set
+  const record = msg.replace(/\x1b\[[0-9;]*m/g, "").replace(/\n$/, "");
+  if (!globalThis.Deno.core.ops.op_console_record(record, level)) {
+    globalThis.Deno.core.print(msg, level > 1);
+  }
+});
 for (const name of [
   "log",
   "debug",
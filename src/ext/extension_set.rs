@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+/// A built-in API surface that can be removed from a runtime, without the need for feature flags
+///
+/// Disabling an API removes its globals (and any `Deno.*` members) from the runtime,
+/// so that feature-detection such as `typeof fetch === "undefined"` fails cleanly
+/// The underlying extension is still compiled in, and may be used by other extensions
+///
+/// Disabling an API whose crate feature is not enabled has no effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BuiltinExtension {
+    /// `fetch`, `Request`, `Response`, `Headers`, `FormData` and `EventSource`
+    Fetch,
+
+    /// `Deno.connect`, `Deno.listen`, and the other raw networking APIs
+    Net,
+
+    /// `WebSocket` and `WebSocketStream`
+    WebSocket,
+
+    /// `BroadcastChannel`
+    BroadcastChannel,
+
+    /// `localStorage`, `sessionStorage` and `Storage`
+    WebStorage,
+
+    /// `caches`, `CacheStorage` and `Cache`
+    Cache,
+
+    /// `crypto`, `Crypto`, `CryptoKey` and `SubtleCrypto`
+    Crypto,
+
+    /// `URL`, `URLSearchParams` and `URLPattern`
+    Url,
+
+    /// `Deno.openKv` and related types
+    Kv,
+
+    /// `Deno.cron`
+    Cron,
+
+    /// `Deno.dlopen` and the other FFI APIs
+    Ffi,
+}
+
+impl BuiltinExtension {
+    /// The global names provided by this API
+    /// Members of the `Deno` namespace are prefixed with `Deno.`
+    #[must_use]
+    pub fn globals(self) -> &'static [&'static str] {
+        match self {
+            Self::Fetch => &[
+                "fetch",
+                "Request",
+                "Response",
+                "Headers",
+                "FormData",
+                "EventSource",
+                "Deno.HttpClient",
+                "Deno.createHttpClient",
+            ],
+            Self::Net => &[
+                "Deno.connect",
+                "Deno.listen",
+                "Deno.resolveDns",
+                "Deno.listenDatagram",
+                "Deno.connectTls",
+                "Deno.listenTls",
+                "Deno.startTls",
+            ],
+            Self::WebSocket => &["WebSocket", "WebSocketStream"],
+            Self::BroadcastChannel => &[
+                "BroadcastChannel",
+                "broadcast_serialize",
+                "broadcast_deserialize",
+            ],
+            Self::WebStorage => &["localStorage", "sessionStorage", "Storage"],
+            Self::Cache => &["caches", "CacheStorage", "Cache"],
+            Self::Crypto => &["crypto", "Crypto", "CryptoKey", "SubtleCrypto"],
+            Self::Url => &["URL", "URLSearchParams", "URLPattern"],
+            Self::Kv => &["Deno.openKv", "Deno.AtomicOperation", "Deno.KvListIterator"],
+            Self::Cron => &["Deno.cron"],
+            Self::Ffi => &[
+                "Deno.dlopen",
+                "Deno.UnsafeCallback",
+                "Deno.UnsafePointer",
+                "Deno.UnsafePointerView",
+                "Deno.UnsafeFnPointer",
+            ],
+        }
+    }
+}
+
+/// A set of built-in APIs, used to disable them for a given runtime
+/// See [`BuiltinExtension`]
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{BuiltinExtension, ExtensionOptions, ExtensionSet, Runtime, RuntimeOptions};
+///
+/// # fn main() -> Result<(), rustyscript::Error> {
+/// let mut runtime = Runtime::new(RuntimeOptions {
+///     extension_options: ExtensionOptions {
+///         disabled: ExtensionSet::from([BuiltinExtension::Fetch, BuiltinExtension::WebSocket]),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// })?;
+///
+/// let missing: bool = runtime.eval("typeof fetch === 'undefined'")?;
+/// assert!(missing);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionSet(HashSet<BuiltinExtension>);
+impl ExtensionSet {
+    /// Create a new, empty set
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an API to the set
+    /// Returns false if it was already present
+    pub fn insert(&mut self, extension: BuiltinExtension) -> bool {
+        self.0.insert(extension)
+    }
+
+    /// Remove an API from the set
+    /// Returns false if it was not present
+    pub fn remove(&mut self, extension: BuiltinExtension) -> bool {
+        self.0.remove(&extension)
+    }
+
+    /// Check if the set contains a given API
+    #[must_use]
+    pub fn contains(&self, extension: BuiltinExtension) -> bool {
+        self.0.contains(&extension)
+    }
+
+    /// Returns true if the set is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the APIs in the set
+    pub fn iter(&self) -> impl Iterator<Item = BuiltinExtension> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Builds a script to remove the globals of every API in the set
+    pub(crate) fn removal_script(&self) -> String {
+        use std::fmt::Write;
+
+        let mut script = String::new();
+        for name in self.iter().flat_map(BuiltinExtension::globals) {
+            let _ = match name.strip_prefix("Deno.") {
+                Some(member) => writeln!(
+                    script,
+                    "if (globalThis.Deno) delete globalThis.Deno[\"{member}\"];"
+                ),
+                None => writeln!(script, "delete globalThis[\"{name}\"];"),
+            };
+        }
+        script
+    }
+}
+
+impl FromIterator<BuiltinExtension> for ExtensionSet {
+    fn from_iter<T: IntoIterator<Item = BuiltinExtension>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<const N: usize> From<[BuiltinExtension; N]> for ExtensionSet {
+    fn from(value: [BuiltinExtension; N]) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ExtensionOptions, Runtime, RuntimeOptions};
+
+    #[test]
+    fn test_disabled_extensions() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            extension_options: ExtensionOptions {
+                disabled: ExtensionSet::from([BuiltinExtension::Url, BuiltinExtension::Fetch]),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let missing: bool = runtime
+            .eval("typeof URL === 'undefined' && typeof fetch === 'undefined'")
+            .expect("Could not eval");
+        assert!(missing);
+
+        let present: bool = runtime
+            .eval("typeof rustyscript === 'object'")
+            .expect("Could not eval");
+        assert!(present);
+    }
+
+    #[test]
+    fn test_removal_script() {
+        assert!(ExtensionSet::new().removal_script().is_empty());
+
+        let script = ExtensionSet::from([BuiltinExtension::Cron]).removal_script();
+        assert_eq!(
+            script,
+            "if (globalThis.Deno) delete globalThis.Deno[\"cron\"];\n"
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use std::io::{Read, Write};
+
 use deno_core::{extension, Extension};
+use deno_io::StdioPipe;
 
 use super::ExtensionTrait;
 
@@ -34,6 +37,46 @@ impl ExtensionTrait<()> for tty::deno_tty {
     }
 }
 
+/// Replaces the pipes of `io_pipes` with the custom streams of `ExtensionOptions`, if any
+///
+/// Each stream is connected to its pipe by a background thread, which ends once the pipe is closed
+pub fn redirect_stdio(
+    pipes: Option<deno_io::Stdio>,
+    stdin: Option<Box<dyn Read + Send>>,
+    stdout: Option<Box<dyn Write + Send>>,
+    stderr: Option<Box<dyn Write + Send>>,
+) -> std::io::Result<Option<deno_io::Stdio>> {
+    if stdin.is_none() && stdout.is_none() && stderr.is_none() {
+        return Ok(pipes);
+    }
+
+    let mut pipes = pipes.unwrap_or_default();
+    if let Some(mut reader) = stdin {
+        let (read, mut write) = deno_io::pipe()?;
+        std::thread::spawn(move || {
+            let _ = std::io::copy(&mut reader, &mut write);
+        });
+        pipes.stdin = StdioPipe::file(read);
+    }
+    if let Some(writer) = stdout {
+        pipes.stdout = forward_output(writer)?;
+    }
+    if let Some(writer) = stderr {
+        pipes.stderr = forward_output(writer)?;
+    }
+    Ok(Some(pipes))
+}
+
+/// Creates a pipe whose output is copied into `writer`
+fn forward_output(mut writer: Box<dyn Write + Send>) -> std::io::Result<StdioPipe> {
+    let (mut read, write) = deno_io::pipe()?;
+    std::thread::spawn(move || {
+        let _ = std::io::copy(&mut read, &mut writer);
+        let _ = writer.flush();
+    });
+    Ok(StdioPipe::file(write))
+}
+
 pub fn extensions(pipes: Option<deno_io::Stdio>, is_snapshot: bool) -> Vec<Extension> {
     vec![
         deno_io::deno_io::build(pipes, is_snapshot),
@@ -0,0 +1,665 @@
+//! Key-value stores implemented in rust, for use with `Deno.openKv`, see [`KvBackend`]
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    num::NonZeroU32,
+    path::Path,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
+
+use deno_core::{futures::stream, OpState};
+use deno_error::JsErrorBox;
+use denokv_proto::{
+    AtomicWrite, CommitResult, Database, KvEntry, KvValue, MutationKind, QueueMessageHandle,
+    ReadRange, ReadRangeOutput, SnapshotReadOptions, Versionstamp, WatchStream,
+};
+
+use crate::Error;
+
+/// Storage for the entries of a key-value store opened with `Deno.openKv`
+///
+/// Keys and values are opaque bytes; versioning, expiry, atomic checks and mutations,
+/// and the `Deno.KvU64` operations are all handled on top of these three methods
+///
+/// Each commit is a single compare-and-write on an internal version key, so concurrent commits
+/// from any number of runtimes or stores sharing a backend are ordered by the backend itself  
+/// Backends must be safe to call from several threads at once
+///
+/// Queues (`enqueue`, `listenQueue`) and `watch` are not supported
+///
+/// Use with [`super::KvStore::new_custom`]
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use rustyscript::{ KvConfig, KvStore, MemoryKvBackend };
+///
+/// let backend = Arc::new(MemoryKvBackend::default());
+/// let store = KvStore::new_custom(backend, KvConfig::default());
+/// ```
+pub trait KvBackend: Send + Sync {
+    /// Get the value stored under a key, if any
+    ///
+    /// # Errors
+    /// Will return an error if the underlying storage fails
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Get at most `limit` entries with a key in `start..end`,
+    /// in ascending order of key - or descending order if `reverse` is set
+    ///
+    /// # Errors
+    /// Will return an error if the underlying storage fails
+    #[allow(clippy::type_complexity)]
+    fn range(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>;
+
+    /// Apply a batch of changes at once, if `guard` currently holds `expected` - a value of `None` deletes the key,
+    /// and an `expected` of `None` means the guard key must be unset
+    ///
+    /// The comparison and the batch must be atomic with respect to every other write,
+    /// and the batch must be applied completely, or not at all
+    ///
+    /// Returns false, without applying anything, if the guard key holds anything else
+    ///
+    /// # Errors
+    /// Will return an error if the underlying storage fails
+    fn write(
+        &self,
+        guard: &[u8],
+        expected: Option<&[u8]>,
+        batch: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    ) -> Result<bool, Error>;
+}
+
+/// A [`KvBackend`] that keeps its entries in memory
+///
+/// Can be shared between runtimes, which will then see the same entries
+#[derive(Default)]
+pub struct MemoryKvBackend(RwLock<BTreeMap<Vec<u8>, Vec<u8>>>);
+impl MemoryKvBackend {
+    /// The number of entries in the store, including internal bookkeeping
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns true if nothing has been written to the store
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .is_empty()
+    }
+}
+
+impl KvBackend for MemoryKvBackend {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let entries = self.0.read().map_err(|e| Error::Runtime(e.to_string()))?;
+        Ok(entries.get(key).cloned())
+    }
+
+    fn range(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+        if start >= end {
+            return Ok(vec![]);
+        }
+
+        let entries = self.0.read().map_err(|e| Error::Runtime(e.to_string()))?;
+        let range = entries.range(start.to_vec()..end.to_vec());
+        let clone = |(k, v): (&Vec<u8>, &Vec<u8>)| (k.clone(), v.clone());
+        Ok(if reverse {
+            range.rev().take(limit).map(clone).collect()
+        } else {
+            range.take(limit).map(clone).collect()
+        })
+    }
+
+    fn write(
+        &self,
+        guard: &[u8],
+        expected: Option<&[u8]>,
+        batch: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    ) -> Result<bool, Error> {
+        let mut entries = self.0.write().map_err(|e| Error::Runtime(e.to_string()))?;
+        if entries.get(guard).map(Vec::as_slice) != expected {
+            return Ok(false);
+        }
+
+        for (key, value) in batch {
+            match value {
+                Some(value) => entries.insert(key, value),
+                None => entries.remove(&key),
+            };
+        }
+        Ok(true)
+    }
+}
+
+/// Holds the version of the last commit - user keys never start with `0xff`
+const VERSION_KEY: &[u8] = &[0xff, b'v'];
+
+/// An entry as stored in a backend:
+/// `[encoding: u8][versionstamp: 10 bytes][expiry in ms since epoch: i64 BE, 0 if none][value]`
+struct StoredEntry {
+    value: KvValue,
+    versionstamp: Versionstamp,
+    expire_at: i64,
+}
+impl StoredEntry {
+    const HEADER_LEN: usize = 1 + 10 + 8;
+
+    fn encode(self) -> Vec<u8> {
+        let (value, encoding) = denokv_proto::encode_value_owned(self.value);
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + value.len());
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        bytes.push(encoding as u8);
+        bytes.extend_from_slice(&self.versionstamp);
+        bytes.extend_from_slice(&self.expire_at.to_be_bytes());
+        bytes.extend_from_slice(&value);
+        bytes
+    }
+
+    /// Decodes an entry, returning `None` if it has expired
+    fn decode(mut bytes: Vec<u8>, now: i64) -> Result<Option<Self>, JsErrorBox> {
+        if bytes.len() < Self::HEADER_LEN {
+            return Err(JsErrorBox::generic("Corrupted entry in key-value store"));
+        }
+
+        let payload = bytes.split_off(Self::HEADER_LEN);
+        let mut versionstamp = [0; 10];
+        versionstamp.copy_from_slice(&bytes[1..11]);
+        let mut expire_at = [0; 8];
+        expire_at.copy_from_slice(&bytes[11..]);
+        let expire_at = i64::from_be_bytes(expire_at);
+        if expire_at != 0 && expire_at <= now {
+            return Ok(None);
+        }
+
+        let value = denokv_proto::decode_value(payload, i64::from(bytes[0]))
+            .ok_or_else(|| JsErrorBox::generic("Unknown value encoding in key-value store"))?;
+        Ok(Some(Self {
+            value,
+            versionstamp,
+            expire_at,
+        }))
+    }
+}
+
+fn now_ms() -> i64 {
+    denokv_proto::time::utc_now().timestamp_millis()
+}
+
+fn backend_err(e: Error) -> JsErrorBox {
+    JsErrorBox::generic(e.to_string())
+}
+
+/// Adapts a [`KvBackend`] to the database interface used by `deno_kv`
+#[derive(Clone)]
+pub(crate) struct BackendDb {
+    backend: Arc<dyn KvBackend>,
+}
+impl BackendDb {
+    /// Returns the raw version key, and the version of the last commit
+    fn last_version(&self) -> Result<(Option<Vec<u8>>, u64), JsErrorBox> {
+        let Some(bytes) = self.backend.get(VERSION_KEY).map_err(backend_err)? else {
+            return Ok((None, 0));
+        };
+        let version = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| JsErrorBox::generic("Corrupted version in key-value store"))?;
+        Ok((Some(bytes), u64::from_be_bytes(version)))
+    }
+
+    /// Reads a range a page at a time, until enough unexpired entries are found
+    fn read_range(&self, range: ReadRange, now: i64) -> Result<ReadRangeOutput, JsErrorBox> {
+        let wanted = limit(range.limit);
+        let (mut start, mut end) = (range.start, range.end);
+
+        let mut entries = Vec::new();
+        while entries.len() < wanted {
+            let remaining = wanted - entries.len();
+            let page = self
+                .backend
+                .range(&start, &end, remaining, range.reverse)
+                .map_err(backend_err)?;
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+
+            // Continue after the last key seen, in the direction of the read
+            let exhausted = page.len() < remaining;
+            if range.reverse {
+                end.clone_from(last);
+            } else {
+                start.clone_from(last);
+                start.push(0);
+            }
+
+            for (key, bytes) in page {
+                if key.as_slice() == VERSION_KEY {
+                    continue;
+                }
+
+                if let Some(entry) = StoredEntry::decode(bytes, now)? {
+                    entries.push(KvEntry {
+                        key,
+                        value: entry.value,
+                        versionstamp: entry.versionstamp,
+                    });
+                }
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        Ok(ReadRangeOutput { entries })
+    }
+
+    /// Attempts to commit a write on top of the current version
+    ///
+    /// Returns `Ok(Err(()))` if another commit landed first, in which case the write should be retried
+    fn try_commit(
+        &self,
+        write: &AtomicWrite,
+        now: i64,
+    ) -> Result<Result<Option<CommitResult>, ()>, JsErrorBox> {
+        let (raw_version, version) = self.last_version()?;
+
+        // Pending changes, so that later mutations see earlier ones
+        let mut pending: HashMap<Vec<u8>, Option<Vec<u8>>> = HashMap::new();
+        let read = |pending: &HashMap<Vec<u8>, Option<Vec<u8>>>,
+                    key: &[u8]|
+         -> Result<Option<StoredEntry>, JsErrorBox> {
+            let bytes = match pending.get(key) {
+                Some(bytes) => bytes.clone(),
+                None => self.backend.get(key).map_err(backend_err)?,
+            };
+            match bytes {
+                Some(bytes) => StoredEntry::decode(bytes, now),
+                None => Ok(None),
+            }
+        };
+
+        for check in &write.checks {
+            let current = read(&pending, &check.key)?.map(|e| e.versionstamp);
+            if current != check.versionstamp {
+                // A check read before a concurrent commit may be stale
+                return if self.last_version()?.0 == raw_version {
+                    Ok(Ok(None))
+                } else {
+                    Ok(Err(()))
+                };
+            }
+        }
+
+        let version = version + 1;
+        let mut versionstamp = [0; 10];
+        versionstamp[..8].copy_from_slice(&version.to_be_bytes());
+
+        for mutation in &write.mutations {
+            let expire_at = mutation.expire_at.map_or(0, |t| t.timestamp_millis());
+            let mut key = mutation.key.clone();
+            let value = match &mutation.kind {
+                MutationKind::Set(value) => Some(value.clone()),
+                MutationKind::Delete => None,
+                MutationKind::Sum {
+                    value,
+                    min_v8,
+                    max_v8,
+                    ..
+                } => {
+                    // Clamping only applies within bounds, which `Deno.KvU64` sums cannot have
+                    if !min_v8.is_empty() || !max_v8.is_empty() {
+                        return Err(JsErrorBox::type_error(
+                            "Cannot use min/max parameters with KvU64 operands",
+                        ));
+                    }
+                    Some(mutate_u64(
+                        read(&pending, &key)?,
+                        "sum",
+                        value,
+                        u64::wrapping_add,
+                    )?)
+                }
+                MutationKind::Min(value) => {
+                    Some(mutate_u64(read(&pending, &key)?, "min", value, u64::min)?)
+                }
+                MutationKind::Max(value) => {
+                    Some(mutate_u64(read(&pending, &key)?, "max", value, u64::max)?)
+                }
+                MutationKind::SetSuffixVersionstampedKey(value) => {
+                    key.push(0x02);
+                    for byte in versionstamp {
+                        key.extend_from_slice(format!("{byte:02x}").as_bytes());
+                    }
+                    key.push(0x00);
+                    Some(value.clone())
+                }
+            };
+
+            let entry = value.map(|value| {
+                StoredEntry {
+                    value,
+                    versionstamp,
+                    expire_at,
+                }
+                .encode()
+            });
+            pending.insert(key, entry);
+        }
+
+        let mut batch: Vec<_> = pending.into_iter().collect();
+        batch.push((VERSION_KEY.to_vec(), Some(version.to_be_bytes().to_vec())));
+        let committed = self
+            .backend
+            .write(VERSION_KEY, raw_version.as_deref(), batch)
+            .map_err(backend_err)?;
+
+        Ok(if committed {
+            Ok(Some(CommitResult { versionstamp }))
+        } else {
+            Err(())
+        })
+    }
+}
+
+fn limit(limit: NonZeroU32) -> usize {
+    usize::try_from(limit.get()).unwrap_or(usize::MAX)
+}
+
+/// Applies `Deno.KvU64` `sum`, `min` and `max` mutations, using the operand if the key is unset
+fn mutate_u64(
+    existing: Option<StoredEntry>,
+    op_name: &str,
+    operand: &KvValue,
+    mutate: impl FnOnce(u64, u64) -> u64,
+) -> Result<KvValue, JsErrorBox> {
+    let KvValue::U64(operand) = *operand else {
+        return Err(JsErrorBox::type_error(format!(
+            "Failed to perform '{op_name}' mutation on a non-U64 operand"
+        )));
+    };
+
+    match existing.map(|e| e.value) {
+        None => Ok(KvValue::U64(operand)),
+        Some(KvValue::U64(existing)) => Ok(KvValue::U64(mutate(existing, operand))),
+        Some(_) => Err(JsErrorBox::type_error(format!(
+            "Failed to perform '{op_name}' mutation on a non-U64 value in the database"
+        ))),
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Database for BackendDb {
+    type QMH = Box<dyn QueueMessageHandle>;
+
+    async fn snapshot_read(
+        &self,
+        requests: Vec<ReadRange>,
+        _options: SnapshotReadOptions,
+    ) -> Result<Vec<ReadRangeOutput>, JsErrorBox> {
+        let now = now_ms();
+        requests
+            .into_iter()
+            .map(|range| self.read_range(range, now))
+            .collect()
+    }
+
+    async fn atomic_write(&self, write: AtomicWrite) -> Result<Option<CommitResult>, JsErrorBox> {
+        if !write.enqueues.is_empty() {
+            return Err(JsErrorBox::generic(
+                "Queues are not supported by this key-value store",
+            ));
+        }
+
+        let now = now_ms();
+        loop {
+            if let Ok(result) = self.try_commit(&write, now)? {
+                return Ok(result);
+            }
+        }
+    }
+
+    async fn dequeue_next_message(&self) -> Result<Option<Self::QMH>, JsErrorBox> {
+        Ok(None)
+    }
+
+    fn watch(&self, _keys: Vec<Vec<u8>>) -> WatchStream {
+        Box::pin(stream::once(async {
+            Err(JsErrorBox::generic(
+                "Watching keys is not supported by this key-value store",
+            ))
+        }))
+    }
+
+    fn close(&self) {}
+}
+
+/// Opens a [`BackendDb`], once the path given to `Deno.openKv` passes `WebPermissions::check_open`
+pub(crate) struct BackendDbHandler(BackendDb);
+impl BackendDbHandler {
+    pub fn new(backend: Arc<dyn KvBackend>) -> Self {
+        Self(BackendDb { backend })
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl deno_kv::DatabaseHandler for BackendDbHandler {
+    type DB = BackendDb;
+
+    async fn open(
+        &self,
+        state: Rc<RefCell<OpState>>,
+        path: Option<String>,
+    ) -> Result<Self::DB, JsErrorBox> {
+        let path = path.unwrap_or_else(|| ":default".to_string());
+
+        let state = state.borrow();
+        if let Some(permissions) = state.try_borrow::<crate::ext::web::PermissionsContainer>() {
+            let allowed = permissions.0.check_open(
+                true,
+                true,
+                true,
+                Cow::Borrowed(Path::new(&path)),
+                "Deno.openKv",
+            );
+            if allowed.is_none() {
+                return Err(JsErrorBox::new(
+                    "NotCapable",
+                    format!("Requires read and write access to \"{path}\", run again with the --allow-read and --allow-write flags"),
+                ));
+            }
+        }
+
+        Ok(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        AllowlistWebPermissions, ExtensionOptions, KvConfig, KvStore, Module, Runtime,
+        RuntimeOptions, WebOptions,
+    };
+
+    fn kv_runtime(backend: Arc<MemoryKvBackend>, permissions: AllowlistWebPermissions) -> Runtime {
+        Runtime::new(RuntimeOptions {
+            extension_options: ExtensionOptions {
+                kv_store: KvStore::new_custom(backend, KvConfig::default()),
+                web: WebOptions {
+                    permissions: Arc::new(permissions),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .expect("Could not create the runtime")
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        let backend = Arc::new(MemoryKvBackend::default());
+        let permissions = AllowlistWebPermissions::new();
+        permissions.allow_open("app", true, true);
+
+        let mut runtime = kv_runtime(backend.clone(), permissions);
+        let module = Module::new(
+            "test.js",
+            "
+            export async function write() {
+                const kv = await Deno.openKv('app');
+                await kv.set(['users', 1], { name: 'a' });
+                await kv.set(['users', 2], { name: 'b' });
+
+                const first = await kv.get(['users', 1]);
+                const stale = await kv.atomic()
+                    .check({ key: ['users', 1], versionstamp: null })
+                    .set(['users', 1], { name: 'c' })
+                    .commit();
+                const fresh = await kv.atomic()
+                    .check(first)
+                    .sum(['count'], new Deno.KvU64(5n))
+                    .sum(['count'], new Deno.KvU64(2n))
+                    .delete(['users', 2])
+                    .commit();
+                return [stale.ok, fresh.ok];
+            }
+
+            export async function read() {
+                const kv = await Deno.openKv('app');
+                const users = [];
+                for await (const entry of kv.list({ prefix: ['users'] })) {
+                    users.push(entry.value.name);
+                }
+                const count = await kv.get(['count']);
+                const last = [];
+                for await (const entry of kv.list({ prefix: [] }, { reverse: true, limit: 1 })) {
+                    last.push(entry.key[0]);
+                }
+                return [users, Number(count.value.value), last];
+            }
+
+            export async function denied() {
+                try {
+                    await Deno.openKv('other');
+                    return 'opened';
+                } catch (e) {
+                    return e.name;
+                }
+            }
+            ",
+        );
+
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let written: (bool, bool) = runtime
+            .call_function(Some(&handle), "write", &())
+            .expect("Could not call function");
+        assert_eq!(written, (false, true));
+
+        let denied: String = runtime
+            .call_function(Some(&handle), "denied", &())
+            .expect("Could not call function");
+        assert_eq!(denied, "NotCapable");
+
+        // A second runtime sees the same entries
+        let permissions = AllowlistWebPermissions::new();
+        permissions.allow_open("app", true, true);
+        let mut runtime = kv_runtime(backend, permissions);
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let read: (Vec<String>, u64, Vec<String>) = runtime
+            .call_function(Some(&handle), "read", &())
+            .expect("Could not call function");
+        assert_eq!(read, (vec!["a".to_string()], 7, vec!["users".to_string()]));
+    }
+
+    #[test]
+    fn test_shared_backend_commits() {
+        use deno_core::futures::executor::block_on;
+        use denokv_proto::Mutation;
+
+        let backend: Arc<dyn KvBackend> = Arc::new(MemoryKvBackend::default());
+        let sum = |value| AtomicWrite {
+            checks: vec![],
+            mutations: vec![Mutation {
+                key: b"count".to_vec(),
+                kind: MutationKind::Sum {
+                    value,
+                    min_v8: vec![],
+                    max_v8: vec![],
+                    clamp: false,
+                },
+                expire_at: None,
+            }],
+            enqueues: vec![],
+        };
+
+        // Two stores sharing a backend, committing at the same time
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let db = BackendDb {
+                    backend: backend.clone(),
+                };
+                std::thread::spawn(move || {
+                    (0..50)
+                        .map(|_| {
+                            block_on(db.atomic_write(sum(KvValue::U64(1))))
+                                .expect("commit should not fail")
+                                .expect("commit has no checks")
+                                .versionstamp
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut versionstamps: Vec<_> = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().expect("thread should not panic"))
+            .collect();
+        versionstamps.sort_unstable();
+        versionstamps.dedup();
+        assert_eq!(versionstamps.len(), 100);
+
+        let db = BackendDb { backend };
+        let count = db
+            .read_range(
+                ReadRange {
+                    start: b"count".to_vec(),
+                    end: b"count\0".to_vec(),
+                    limit: NonZeroU32::MIN,
+                    reverse: false,
+                },
+                now_ms(),
+            )
+            .expect("read should not fail");
+        assert!(matches!(count.entries[0].value, KvValue::U64(100)));
+
+        // Bounds cannot be used with `Deno.KvU64` sums
+        let mut bounded = sum(KvValue::U64(1));
+        if let MutationKind::Sum { min_v8, .. } = &mut bounded.mutations[0].kind {
+            min_v8.push(0);
+        }
+        assert!(block_on(db.atomic_write(bounded)).is_err());
+    }
+}
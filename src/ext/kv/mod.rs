@@ -1,10 +1,13 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use deno_core::{extension, Extension};
 use deno_kv::{dynamic::MultiBackendDbHandler, remote::RemoteDbHandler, sqlite::SqliteDbHandler};
 
 use super::ExtensionTrait;
 
+mod backend;
+pub use backend::{KvBackend, MemoryKvBackend};
+
 extension!(
     init_kv,
     deps = [rustyscript],
@@ -39,6 +42,10 @@ enum KvStoreBuilder {
     Remote {
         http_options: deno_kv::remote::HttpOptions,
     },
+
+    Custom {
+        backend: Arc<dyn KvBackend>,
+    },
 }
 
 /// Configuration for the key-value store
@@ -115,9 +122,9 @@ impl Default for KvConfig {
     }
 }
 
-/// Bi-modal key-value store for deno
+/// Key-value store for deno
 ///
-/// Wraps the deno sqlite (local) and remote implementations
+/// Wraps the deno sqlite (local) and remote implementations, or a [`KvBackend`] implemented in rust
 #[derive(Clone)]
 pub struct KvStore(KvStoreBuilder, KvConfig);
 impl KvStore {
@@ -137,6 +144,17 @@ impl KvStore {
         Self(KvStoreBuilder::Remote { http_options }, config)
     }
 
+    /// Create a new key-value store backed by rust code
+    ///
+    /// Every call to `Deno.openKv` opens the same store, once the path passes `WebPermissions::check_open`
+    /// (`:default` if no path was given)
+    ///
+    /// Clones of the store, and runtimes created with them, share the same entries
+    #[must_use]
+    pub fn new_custom(backend: Arc<dyn KvBackend>, config: KvConfig) -> Self {
+        Self(KvStoreBuilder::Custom { backend }, config)
+    }
+
     /// Get the handler for the key-value store
     ///
     /// This is used to create the extension
@@ -152,6 +170,11 @@ impl KvStore {
                 let db = RemoteDbHandler::new(http_options.clone());
                 MultiBackendDbHandler::new(vec![(&["https://", "http://"], Box::new(db))])
             }
+
+            KvStoreBuilder::Custom { backend } => {
+                let db = backend::BackendDbHandler::new(backend.clone());
+                MultiBackendDbHandler::new(vec![(&[""], Box::new(db))])
+            }
         }
     }
 
@@ -8,6 +8,9 @@ use deno_core::{
 
 pub mod rustyscript;
 
+mod extension_set;
+pub use extension_set::{BuiltinExtension, ExtensionSet};
+
 trait ExtensionTrait<A> {
     fn init(options: A) -> Extension;
 
@@ -91,6 +94,12 @@ pub mod runtime;
 
 /// Options for configuring extensions
 pub struct ExtensionOptions {
+    /// A set of built-in APIs to remove from the runtime
+    ///
+    /// Unlike crate features, this can be decided per-runtime  
+    /// Disabled APIs are absent from the global scope, not merely denied
+    pub disabled: ExtensionSet,
+
     /// Options specific to the `deno_web`, `deno_fetch` and `deno_net` extensions
     ///
     /// Requires the `web` feature to be enabled
@@ -112,6 +121,31 @@ pub struct ExtensionOptions {
     #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
     pub io_pipes: Option<deno_io::Stdio>,
 
+    /// Optional reader to use as `Deno.stdin`, instead of the process stdin
+    ///
+    /// Takes precedence over the stdin of `io_pipes`  
+    /// Requires the `io` feature to be enabled
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    pub stdin: Option<Box<dyn std::io::Read + Send>>,
+
+    /// Optional writer to receive `Deno.stdout`, instead of the process stdout
+    ///
+    /// Output is forwarded from a background thread, so it can arrive shortly after the write completes in javascript  
+    /// Takes precedence over the stdout of `io_pipes`  
+    /// Requires the `io` feature to be enabled
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    pub stdout: Option<Box<dyn std::io::Write + Send>>,
+
+    /// Optional writer to receive `Deno.stderr`, instead of the process stderr
+    ///
+    /// Forwarded the same way as `stdout`  
+    /// Requires the `io` feature to be enabled
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    pub stderr: Option<Box<dyn std::io::Write + Send>>,
+
     /// Optional path to the directory where the webstorage extension will store its data
     ///
     /// Requires the `webstorage` feature to be enabled
@@ -165,6 +199,8 @@ impl Default for ExtensionOptions {
         let broadcast_channel = deno_web::InMemoryBroadcastChannel::default();
 
         Self {
+            disabled: ExtensionSet::default(),
+
             #[cfg(feature = "web")]
             web: {
                 let mut web_options = web::WebOptions::default();
@@ -181,6 +217,15 @@ impl Default for ExtensionOptions {
             #[cfg(feature = "io")]
             io_pipes: Some(deno_io::Stdio::default()),
 
+            #[cfg(feature = "io")]
+            stdin: None,
+
+            #[cfg(feature = "io")]
+            stdout: None,
+
+            #[cfg(feature = "io")]
+            stderr: None,
+
             #[cfg(feature = "webstorage")]
             webstorage_origin_storage_dir: None,
 
@@ -202,13 +247,30 @@ impl Default for ExtensionOptions {
     }
 }
 
+#[cfg_attr(not(feature = "io"), allow(clippy::unnecessary_wraps))]
 pub(crate) fn all_extensions(
     user_extensions: Vec<Extension>,
-    options: ExtensionOptions,
+    #[cfg_attr(not(feature = "io"), allow(unused_mut))] mut options: ExtensionOptions,
     shared_array_buffer_store: Option<CrossIsolateStore<SharedRef<BackingStore>>>,
+    core_only: bool,
     is_snapshot: bool,
-) -> Vec<Extension> {
+) -> Result<Vec<Extension>, crate::Error> {
     let mut extensions = rustyscript::extensions(is_snapshot);
+    if core_only {
+        extensions.extend(user_extensions);
+        return Ok(extensions);
+    }
+
+    #[cfg(feature = "io")]
+    {
+        options.io_pipes = io::redirect_stdio(
+            options.io_pipes.take(),
+            options.stdin.take(),
+            options.stdout.take(),
+            options.stderr.take(),
+        )
+        .map_err(|e| crate::Error::Runtime(format!("could not redirect stdio: {e}")))?;
+    }
 
     #[cfg(feature = "webidl")]
     extensions.extend(webidl::extensions(is_snapshot));
@@ -281,5 +343,5 @@ pub(crate) fn all_extensions(
     }
 
     extensions.extend(user_extensions);
-    extensions
+    Ok(extensions)
 }
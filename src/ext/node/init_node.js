@@ -1,2 +1,15 @@
 import { initializeDebugEnv } from "ext:deno_node/internal/util/debuglog.ts";
-initializeDebugEnv("rustyscript");
\ No newline at end of file
+import { core } from "ext:core/mod.js";
+import { Buffer } from "node:buffer";
+
+initializeDebugEnv("rustyscript");
+
+// Loads a native addon through the napi extension, the same way `require` does for `.node` files
+// Kept in the op state rather than on `globalThis`, so scripts cannot replace it
+// Used by `Runtime::load_napi_addon`, and by imports of `.node` files
+core.ops.op_register_napi_addon_loader((path) => {
+    if (path.endsWith("cpufeatures.node")) {
+        throw new Error("Using cpu-features module is currently not supported");
+    }
+    return core.ops.op_napi_open(path, globalThis, Buffer.from, reportError);
+});
@@ -1,19 +1,42 @@
 use std::sync::Arc;
 
-use deno_core::{extension, Extension};
+use deno_core::{extension, op2, v8, Extension, OpState};
 use deno_resolver::npm::DenoInNpmPackageChecker;
 use resolvers::{RustyNpmPackageFolderResolver, RustyResolver};
 use sys_traits::impls::RealSys;
 
 use super::ExtensionTrait;
+use crate::Error;
 
 mod cjs_translator;
 pub mod resolvers;
 pub use cjs_translator::NodeCodeTranslator;
 
+/// The function registered by `init_node.js`, which loads a `.node` addon and returns its exports
+pub(crate) struct NapiAddonLoader(pub v8::Global<v8::Function>);
+
+/// Registers the napi addon loader - only the first call, from `init_node.js`, has any effect
+#[op2]
+fn op_register_napi_addon_loader(state: &mut OpState, #[global] loader: v8::Global<v8::Function>) {
+    if !state.has::<NapiAddonLoader>() {
+        state.put(NapiAddonLoader(loader));
+    }
+}
+
+/// Returns the napi addon loader, for the modules generated for imports of `.node` files
+#[op2]
+#[global]
+fn op_napi_addon_loader(state: &mut OpState) -> Result<v8::Global<v8::Function>, Error> {
+    state
+        .try_borrow::<NapiAddonLoader>()
+        .map(|loader| loader.0.clone())
+        .ok_or_else(|| Error::Runtime("The napi addon loader is not registered".to_string()))
+}
+
 extension!(
     init_node,
     deps = [rustyscript],
+    ops = [op_register_napi_addon_loader, op_napi_addon_loader],
     esm_entry_point = "ext:init_node/init_node.js",
     esm = [ dir "src/ext/node", "init_node.js" ],
 );
@@ -13,7 +13,6 @@ import * as version from "ext:runtime/01_version.ts";
 import * as signals from "ext:deno_os/40_signals.js";
 import * as tty from "ext:runtime/40_tty.js";
 
-const opArgs = scopeWindow.memoizeLazy(() => core.ops.op_bootstrap_args());
 const opPid = scopeWindow.memoizeLazy(() => core.ops.op_bootstrap_pid());
 
 import { core } from "ext:core/mod.js";
@@ -27,7 +26,6 @@ applyToDeno({
     // https://github.com/denoland/deno/issues/23004
     ppid: getterOnly(() => core.ops.op_ppid()),
     noColor: getterOnly(() => core.ops.op_bootstrap_no_color()),
-    args: getterOnly(opArgs),
     mainModule: getterOnly(() => core.ops.op_main_module()),
     exitCode: {
         __proto__: null,
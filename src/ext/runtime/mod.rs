@@ -6,32 +6,14 @@ use deno_core::{
     v8::{BackingStore, SharedRef},
     CrossIsolateStore, Extension, ExtensionFileSource,
 };
-use deno_runtime::deno_permissions::Permissions;
-use deno_runtime::permissions::RuntimePermissionDescriptorParser;
 use deno_telemetry::OtelConfig;
-use sys_traits::impls::RealSys;
 
 use super::{
-    node::resolvers::RustyResolver, web::to_permissions_options, web::PermissionsContainer,
+    node::resolvers::RustyResolver, web::build_deno_permissions, web::PermissionsContainer,
     ExtensionOptions, ExtensionTrait,
 };
 use crate::module_loader::{LoaderOptions, RustyLoader};
 
-fn build_permissions(
-    permissions_container: &PermissionsContainer,
-) -> ::deno_permissions::PermissionsContainer {
-    let parser = Arc::new(RuntimePermissionDescriptorParser::<RealSys>::new(RealSys));
-    let opts = to_permissions_options(permissions_container.0.as_ref());
-
-    match Permissions::from_options(&*parser, &opts) {
-        Ok(perms) => ::deno_permissions::PermissionsContainer::new(parser, perms),
-        Err(_) => {
-            // Fallback for backward compatibility
-            ::deno_permissions::PermissionsContainer::new(parser, Permissions::allow_all())
-        }
-    }
-}
-
 // Some of the polyfills reference the denoland/deno runtime directly
 // So we need to include a subset of the real thing
 //
@@ -51,7 +33,7 @@ extension!(
         state.put(options);
 
         let container = state.borrow::<PermissionsContainer>();
-        let permissions = build_permissions(container);
+        let permissions = build_deno_permissions(container);
         state.put(permissions);
     },
     customizer = |e: &mut Extension| {
@@ -0,0 +1,81 @@
+//! Deterministic replacements for `Date.now`, `performance.now` and `Math.random`,
+//! see `RuntimeOptions::deterministic_time` and `RuntimeOptions::deterministic_random_seed`
+
+/// Pins the clock seen by `Date` and `performance` to `time`, in milliseconds since the unix epoch
+const TIME_SCRIPT: &str = "((time) => {
+    const RealDate = globalThis.Date;
+    function Date(...args) {
+        if (!new.target) {
+            return new RealDate(time).toString();
+        }
+        return Reflect.construct(RealDate, args.length ? args : [time], new.target);
+    }
+    Object.setPrototypeOf(Date, RealDate);
+    Object.defineProperty(Date, 'length', { value: RealDate.length });
+    Date.prototype = RealDate.prototype;
+    Date.now = () => time;
+    Object.defineProperty(RealDate.prototype, 'constructor', {
+        value: Date,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+    });
+    Object.defineProperty(globalThis, 'Date', {
+        value: Date,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+    });
+
+    if (globalThis.performance) {
+        globalThis.performance.now = () => 0;
+        Object.defineProperty(globalThis.performance, 'timeOrigin', { value: time });
+    }
+})";
+
+/// Replaces `Math.random` with a xoshiro128** generator, seeded from the two halves of a 64 bit seed
+const RANDOM_SCRIPT: &str = "((lo, hi) => {
+    // splitmix32, to expand the seed into the generator's state
+    let seed = lo ^ Math.imul(hi, 0x9e3779b9);
+    const next_seed = () => {
+        seed = (seed + 0x9e3779b9) | 0;
+        let z = seed;
+        z = Math.imul(z ^ (z >>> 16), 0x85ebca6b);
+        z = Math.imul(z ^ (z >>> 13), 0xc2b2ae35);
+        return (z ^ (z >>> 16)) >>> 0;
+    };
+    const s = [next_seed(), next_seed(), next_seed(), next_seed()];
+
+    const rotl = (x, k) => (x << k) | (x >>> (32 - k));
+    const next = () => {
+        const result = Math.imul(rotl(Math.imul(s[1], 5), 7), 9) >>> 0;
+        const t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = rotl(s[3], 11);
+        return result;
+    };
+
+    // 53 random bits, as a float in [0, 1)
+    Math.random = () => ((next() >>> 5) * 67108864 + (next() >>> 6)) / 9007199254740992;
+})";
+
+/// Returns the script installing the requested overrides, or `None` if neither is set
+pub(crate) fn install_script(time: Option<u64>, random_seed: Option<u64>) -> Option<String> {
+    let mut scripts = Vec::new();
+
+    if let Some(time) = time {
+        scripts.push(format!("{TIME_SCRIPT}({time});"));
+    }
+
+    if let Some(seed) = random_seed {
+        let lo = seed & 0xffff_ffff;
+        let hi = seed >> 32;
+        scripts.push(format!("{RANDOM_SCRIPT}({lo}, {hi});"));
+    }
+
+    (!scripts.is_empty()).then(|| scripts.join("\n"))
+}
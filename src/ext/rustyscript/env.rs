@@ -0,0 +1,160 @@
+//! A sandboxed `Deno.env`, see `RuntimeOptions::env`
+use std::collections::HashMap;
+
+use deno_core::{op2, OpState};
+use deno_error::JsErrorBox;
+
+/// The environment variables seen by scripts, in place of the process environment
+///
+/// Only present in the op state when `RuntimeOptions::env` is set
+pub(crate) struct EnvVars(pub HashMap<String, String>);
+
+/// Replaces the methods of `Deno.env` with ones backed by [`EnvVars`], or defines it if missing
+///
+/// Node's `process.env`, when present, is replaced by a proxy over the same ops, so the two always agree
+pub(crate) const INSTALL_SCRIPT: &str = "(() => {
+    const ops = Deno.core.ops;
+    const env = {
+        get: (key) => ops.op_sandbox_env_get(String(key)) ?? undefined,
+        set: (key, value) => ops.op_sandbox_env_set(String(key), String(value)),
+        delete: (key) => ops.op_sandbox_env_delete(String(key)),
+        has: (key) => ops.op_sandbox_env_get(String(key)) !== null,
+        toObject: () => ops.op_sandbox_env_to_object(),
+    };
+
+    if (globalThis.Deno.env) {
+        Object.assign(globalThis.Deno.env, env);
+    } else {
+        Object.defineProperty(globalThis.Deno, 'env', {
+            value: env,
+            writable: true,
+            enumerable: false,
+            configurable: true,
+        });
+    }
+
+    const process = globalThis.process;
+    if (!process) return;
+
+    // As in node, invalid keys read as missing instead of throwing
+    const lookup = (key) => {
+        try {
+            return env.get(key);
+        } catch (e) {
+            if (e instanceof TypeError) return undefined;
+            throw e;
+        }
+    };
+    process.env = new Proxy({}, {
+        get: (target, key) => {
+            if (typeof key === 'symbol') return target[key];
+            return lookup(key) ?? (key in Object.prototype ? target[key] : undefined);
+        },
+        set: (target, key, value) => {
+            if (typeof key === 'symbol') target[key] = value;
+            else env.set(key, value);
+            return true;
+        },
+        has: (target, key) => typeof key === 'symbol' ? key in target : lookup(key) !== undefined,
+        deleteProperty: (target, key) => {
+            if (typeof key === 'symbol') delete target[key];
+            else env.delete(key);
+            return true;
+        },
+        ownKeys: () => Reflect.ownKeys(env.toObject()),
+        getOwnPropertyDescriptor: (_target, key) => {
+            const value = lookup(String(key));
+            if (value !== undefined) return { value, enumerable: true, configurable: true, writable: true };
+        },
+        defineProperty: (target, key, descriptor) => {
+            if (typeof key === 'symbol') return Reflect.defineProperty(target, key, descriptor);
+            env.set(key, descriptor.value);
+            return true;
+        },
+    });
+})();";
+
+/// Validates a key the same way `Deno.env` does
+fn check_key(key: &str) -> Result<(), JsErrorBox> {
+    if key.is_empty() {
+        Err(JsErrorBox::type_error("Key is an empty string."))
+    } else if key.contains(['=', '\0']) {
+        Err(JsErrorBox::type_error(format!(
+            "Key contains invalid characters: {key:?}"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks access to a variable against `WebPermissions::check_env`, failing like deno does
+#[cfg_attr(not(feature = "web"), allow(unused_variables))]
+fn check_access(state: &OpState, key: &str) -> Result<(), JsErrorBox> {
+    check_key(key)?;
+
+    #[cfg(feature = "web")]
+    if let Some(permissions) = state.try_borrow::<crate::ext::web::PermissionsContainer>() {
+        if permissions.0.check_env(key).is_err() {
+            return Err(JsErrorBox::new(
+                "NotCapable",
+                format!("Requires env access to \"{key}\", run again with the --allow-env flag"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn env_vars(state: &mut OpState) -> Result<&mut HashMap<String, String>, JsErrorBox> {
+    state
+        .try_borrow_mut::<EnvVars>()
+        .map(|vars| &mut vars.0)
+        .ok_or_else(|| JsErrorBox::generic("The sandboxed environment is not enabled"))
+}
+
+#[op2]
+#[string]
+pub fn op_sandbox_env_get(
+    state: &mut OpState,
+    #[string] key: &str,
+) -> Result<Option<String>, JsErrorBox> {
+    check_access(state, key)?;
+    Ok(env_vars(state)?.get(key).cloned())
+}
+
+#[op2(fast)]
+pub fn op_sandbox_env_set(
+    state: &mut OpState,
+    #[string] key: &str,
+    #[string] value: &str,
+) -> Result<(), JsErrorBox> {
+    check_access(state, key)?;
+    if value.contains('\0') {
+        return Err(JsErrorBox::type_error(format!(
+            "Value contains invalid characters: {value:?}"
+        )));
+    }
+
+    env_vars(state)?.insert(key.to_string(), value.to_string());
+    Ok(())
+}
+
+#[op2(fast)]
+pub fn op_sandbox_env_delete(state: &mut OpState, #[string] key: &str) -> Result<(), JsErrorBox> {
+    check_access(state, key)?;
+    env_vars(state)?.remove(key);
+    Ok(())
+}
+
+/// Returns every variable, failing if any of them cannot be accessed
+#[op2]
+#[serde]
+pub fn op_sandbox_env_to_object(
+    state: &mut OpState,
+) -> Result<HashMap<String, String>, JsErrorBox> {
+    let vars = env_vars(state)?.clone();
+    for key in vars.keys() {
+        check_access(state, key)?;
+    }
+    Ok(vars)
+}
@@ -0,0 +1,67 @@
+//! Depth and size limits for `JSON.parse`, see `RuntimeOptions::json_limits`
+
+/// Replaces `JSON.parse`, and the `json()` methods of `Request` and `Response` that bypass it,
+/// with versions rejecting input over the limits before parsing it
+const LIMITS_SCRIPT: &str = "((maxDepth, maxLength) => {
+    const originalParse = JSON.parse;
+
+    const check = (text) => {
+        if (text.length > maxLength) {
+            throw new RangeError(`JSON input of ${text.length} characters exceeds the limit of ${maxLength}`);
+        }
+
+        // Count the nesting of objects and arrays, skipping over strings
+        let depth = 0;
+        let inString = false;
+        for (let i = 0; i < text.length; i++) {
+            const c = text.charCodeAt(i);
+            if (inString) {
+                if (c === 92) i++;
+                else if (c === 34) inString = false;
+            } else if (c === 34) {
+                inString = true;
+            } else if (c === 123 || c === 91) {
+                if (++depth > maxDepth) {
+                    throw new RangeError(`JSON input exceeds the maximum nesting depth of ${maxDepth}`);
+                }
+            } else if (c === 125 || c === 93) {
+                depth--;
+            }
+        }
+    };
+
+    const { parse } = {
+        parse(text, reviver) {
+            text = String(text);
+            check(text);
+            return originalParse(text, reviver);
+        },
+    };
+    Object.defineProperty(JSON, 'parse', {
+        value: parse,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+    });
+
+    for (const Body of [globalThis.Request, globalThis.Response]) {
+        if (typeof Body?.prototype?.text === 'function') {
+            const { json } = {
+                async json() {
+                    return JSON.parse(await this.text());
+                },
+            };
+            Object.defineProperty(Body.prototype, 'json', {
+                value: json,
+                writable: true,
+                enumerable: true,
+                configurable: true,
+            });
+        }
+    }
+})";
+
+/// Returns the script enforcing the given limits on `JSON.parse`
+pub(crate) fn install_script(max_depth: usize, max_length: usize) -> String {
+    format!("{LIMITS_SCRIPT}({max_depth}, {max_length});")
+}
@@ -0,0 +1,113 @@
+//! Structured console output, see `RuntimeOptions::console_handler`
+use std::{sync::Arc, time::SystemTime};
+
+use deno_core::{op2, v8, JsRuntime};
+
+/// The level a console message was logged at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// `console.debug`
+    Debug,
+
+    /// `console.log`, `console.info`, and most other console methods
+    Info,
+
+    /// `console.warn`
+    Warn,
+
+    /// `console.error`, `console.trace`, and failed `console.assert` calls
+    Error,
+}
+
+impl LogLevel {
+    /// Converts the level used by `deno_web`'s console
+    fn from_console_level(level: u32) -> Self {
+        match level {
+            0 => Self::Debug,
+            1 => Self::Info,
+            2 => Self::Warn,
+            _ => Self::Error,
+        }
+    }
+}
+
+/// A single message printed with the javascript console
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The level the message was logged at
+    pub level: LogLevel,
+
+    /// The formatted message, without colors or the trailing newline
+    pub message: String,
+
+    /// When the console method was called
+    pub timestamp: SystemTime,
+
+    /// The file and 1-based line number of the console call, if it was made from a script
+    ///
+    /// Line numbers refer to the code as executed - after transpilation, for typescript
+    pub origin: Option<(String, u32)>,
+}
+
+/// Receives console output, see `RuntimeOptions::console_handler`
+///
+/// Only present in the op state when a handler is set
+#[derive(Clone)]
+pub(crate) struct ConsoleHandler(pub Arc<dyn Fn(LogRecord) + Send + Sync>);
+impl ConsoleHandler {
+    /// Wraps a handler, so that the file of each record's origin is formatted with `RuntimeOptions::specifier_display`
+    pub fn new(
+        handler: Arc<dyn Fn(LogRecord) + Send + Sync>,
+        display: Option<crate::module_loader::SpecifierDisplay>,
+    ) -> Self {
+        let Some(display) = display else {
+            return Self(handler);
+        };
+
+        Self(Arc::new(move |mut record: LogRecord| {
+            if let Some((file, _)) = &mut record.origin {
+                *file = crate::utilities::display_specifiers(file, display.as_ref());
+            }
+            handler(record);
+        }))
+    }
+}
+
+/// Delivers a console message to the handler, if one is set
+/// Returns false if there is no handler, and the message should be printed instead
+#[op2(fast)]
+pub fn op_console_record(
+    scope: &mut v8::PinScope<'_, '_>,
+    #[string] message: String,
+    #[smi] level: u32,
+) -> bool {
+    let state = JsRuntime::op_state_from(scope);
+    let Some(handler) = state.borrow().try_borrow::<ConsoleHandler>().cloned() else {
+        return false;
+    };
+
+    let record = LogRecord {
+        level: LogLevel::from_console_level(level),
+        message,
+        timestamp: SystemTime::now(),
+        origin: console_origin(scope),
+    };
+    (handler.0)(record);
+    true
+}
+
+/// Finds the first stack frame outside of the extensions - the script that called the console
+fn console_origin(scope: &mut v8::PinScope<'_, '_>) -> Option<(String, u32)> {
+    let trace = v8::StackTrace::current_stack_trace(scope, 32)?;
+    (0..trace.get_frame_count())
+        .filter_map(|i| trace.get_frame(scope, i))
+        .find_map(|frame| {
+            let file = frame.get_script_name(scope)?.to_rust_string_lossy(scope);
+            if file.is_empty() || file.starts_with("ext:") {
+                return None;
+            }
+
+            let line = u32::try_from(frame.get_line_number()).ok()?;
+            Some((file, line))
+        })
+}
@@ -8,8 +8,28 @@ use crate::{error::Error, RsAsyncFunction, RsFunction};
 type FnCache = HashMap<String, Box<dyn RsFunction>>;
 type AsyncFnCache = HashMap<String, Box<dyn RsAsyncFunction>>;
 
+/// Namespaces of modules defined with `Runtime::define_builtin_module`, by specifier
+pub(crate) type BuiltinModules = HashMap<String, v8::Global<v8::Value>>;
+
 mod callbacks;
 
+mod deterministic;
+pub(crate) use deterministic::install_script as deterministic_install_script;
+
+mod json_limits;
+pub(crate) use json_limits::install_script as json_limits_install_script;
+
+mod env;
+use env::{
+    op_sandbox_env_delete, op_sandbox_env_get, op_sandbox_env_set, op_sandbox_env_to_object,
+};
+pub(crate) use env::{EnvVars, INSTALL_SCRIPT as ENV_INSTALL_SCRIPT};
+
+mod log_record;
+use log_record::op_console_record;
+pub(crate) use log_record::ConsoleHandler;
+pub use log_record::{LogLevel, LogRecord};
+
 /// Registers a JS function with the runtime as being the entrypoint for the module
 ///
 /// # Arguments
@@ -55,6 +75,60 @@ fn call_registered_function_async(
     Box::pin(std::future::ready(Err(Error::ValueNotCallable(name))))
 }
 
+/// Returns the namespace object backing a builtin module
+#[op2]
+#[global]
+fn op_builtin_module(
+    state: &mut OpState,
+    #[string] specifier: &str,
+) -> Result<v8::Global<v8::Value>, Error> {
+    state
+        .try_borrow::<BuiltinModules>()
+        .and_then(|modules| modules.get(specifier))
+        .cloned()
+        .ok_or_else(|| Error::ModuleNotFound(format!("builtin module {specifier} is not defined")))
+}
+
+/// Error-level console output and uncaught errors, see `RuntimeOptions::fail_on_console_error`
+///
+/// Only present in the op state when reporting is enabled
+#[derive(Default)]
+pub(crate) struct ReportedErrors(pub Vec<String>);
+
+/// Records a message printed with `console.error`, if error reporting is enabled
+#[op2(fast)]
+fn op_report_error(state: &mut OpState, #[string] message: &str) {
+    if let Some(errors) = state.try_borrow_mut::<ReportedErrors>() {
+        errors.0.push(message.to_string());
+    }
+}
+
+/// The arguments scripts see as `Deno.args`, see `Runtime::set_args`
+///
+/// The version is bumped each time they are set, so scripts only rebuild `Deno.args` when it changes
+pub(crate) struct ScriptArgs {
+    pub args: Vec<String>,
+    pub version: u32,
+}
+
+/// Returns the arguments set with `Runtime::set_args`, if any
+#[op2]
+#[serde]
+fn op_script_args(state: &OpState) -> Vec<String> {
+    state
+        .try_borrow::<ScriptArgs>()
+        .map(|args| args.args.clone())
+        .unwrap_or_default()
+}
+
+/// Returns the version of the arguments set with `Runtime::set_args`, or 0 if they were never set
+#[op2(fast)]
+fn op_script_args_version(state: &OpState) -> u32 {
+    state
+        .try_borrow::<ScriptArgs>()
+        .map_or(0, |args| args.version)
+}
+
 #[op2(fast)]
 fn op_panic2(#[string] msg: &str) -> Result<(), Error> {
     Err(Error::Runtime(msg.to_string()))
@@ -62,7 +136,11 @@ fn op_panic2(#[string] msg: &str) -> Result<(), Error> {
 
 extension!(
     rustyscript,
-    ops = [op_register_entrypoint, call_registered_function, call_registered_function_async],
+    ops = [
+        op_register_entrypoint, call_registered_function, call_registered_function_async, op_builtin_module, op_report_error, op_console_record,
+        op_sandbox_env_get, op_sandbox_env_set, op_sandbox_env_delete, op_sandbox_env_to_object,
+        op_script_args, op_script_args_version,
+    ],
     esm_entry_point = "ext:rustyscript/rustyscript.js",
     esm = [ dir "src/ext/rustyscript", "rustyscript.js" ],
     middleware = |op| match op.name {
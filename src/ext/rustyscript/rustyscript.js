@@ -25,6 +25,30 @@ const getterOnly = (getter) => {
 const applyToGlobal = (properties) => Object.defineProperties(globalThis, properties);
 const applyToDeno = (properties) => Object.defineProperties(globalThis.Deno, properties);
 
+// A `HostError` from a registered function arrives as a plain error, carrying its class name
+// It is rebuilt here as an instance of a class with that name, created on first use
+const hostErrorClasses = new Map();
+const toHostError = (e) => {
+    if (!(e instanceof Error) || !Object.hasOwn(e, '__rustyscript_host_error')) {
+        return e;
+    }
+
+    const name = e.__rustyscript_host_error;
+    let HostError = hostErrorClasses.get(name);
+    if (HostError === undefined) {
+        HostError = ({ [name]: class extends Error {} })[name];
+        Object.defineProperty(HostError.prototype, 'name', nonEnumerable(name));
+        hostErrorClasses.set(name, HostError);
+    }
+
+    const error = new HostError(e.message);
+    Error.captureStackTrace(error, toHostError);
+    if (Object.hasOwn(e, 'code')) {
+        error.code = e.code;
+    }
+    return error;
+};
+
 // Populate the global object
 globalThis.rustyscript = {
     'register_entrypoint': (f) => Deno.core.ops.op_register_entrypoint(f),
@@ -32,18 +56,41 @@ globalThis.rustyscript = {
     
     'functions': new Proxy({}, {
         get: function(_target, name) {
-            return (...args) => Deno.core.ops.call_registered_function(name, args);
+            return (...args) => {
+                try {
+                    return Deno.core.ops.call_registered_function(name, args);
+                } catch (e) {
+                    throw toHostError(e);
+                }
+            };
         }
     }),
 
     'async_functions': new Proxy({}, {
         get: function(_target, name) {
-            return (...args) => Deno.core.ops.call_registered_function_async(name, args);
+            return (...args) => Deno.core.ops.call_registered_function_async(name, args)
+                .catch((e) => { throw toHostError(e); });
         }
     })
 };
 Object.freeze(globalThis.rustyscript);
 
+// Command line arguments, see `Runtime::set_args`
+// As in Deno they are a frozen array, only rebuilt when the arguments are changed
+const freeze = Object.freeze;
+let scriptArgs = freeze([]);
+let scriptArgsVersion = 0;
+applyToDeno({
+    args: getterOnly(() => {
+        const version = Deno.core.ops.op_script_args_version();
+        if (version !== scriptArgsVersion) {
+            scriptArgs = freeze(Deno.core.ops.op_script_args());
+            scriptArgsVersion = version;
+        }
+        return scriptArgs;
+    })
+});
+
 export {
     nonEnumerable, readOnly, writeable, getterOnly, applyToGlobal, applyToDeno
-};
\ No newline at end of file
+};
\ No newline at end of file
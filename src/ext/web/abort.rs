@@ -0,0 +1,212 @@
+//! Rust-side control over javascript `AbortController`s
+use deno_core::{serde_json, v8};
+
+use crate::{js_value::Value, Error, Runtime};
+
+/// A handle to a javascript `AbortController`, created and triggered from rust
+///
+/// The controller's `AbortSignal` is exposed to scripts as a global, under the given name,
+/// so it can be passed to `fetch` or any other API accepting a signal
+///
+/// Aborting goes through the `deno_web` abort machinery, so an in-flight `fetch` using the signal
+/// will reject with an `AbortError`
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{AbortControllerWrapper, Module, Runtime};
+///
+/// # fn main() -> Result<(), rustyscript::Error> {
+/// let mut runtime = Runtime::new(Default::default())?;
+/// let controller = AbortControllerWrapper::new(&mut runtime, "requestSignal")?;
+///
+/// let module = Module::new("test.js", "
+///     export const request = fetch('https://example.com', { signal: requestSignal });
+/// ");
+/// runtime.load_module(&module)?;
+///
+/// // Later, when the request is no longer needed
+/// controller.abort(&mut runtime, None)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AbortControllerWrapper {
+    controller: Value,
+    signal: Value,
+    name: String,
+}
+
+impl AbortControllerWrapper {
+    /// Create a new `AbortController` in the runtime, and expose its signal as `globalThis[name]`
+    ///
+    /// # Errors
+    /// Will return an error if the controller cannot be created
+    pub fn new(runtime: &mut Runtime, name: impl ToString) -> Result<Self, Error> {
+        let name = name.to_string();
+        let key = serde_json::to_string(&name)?;
+        let (controller, signal): (Value, Value) = runtime.eval(format!(
+            "(() => {{
+                const controller = new AbortController();
+                Object.defineProperty(globalThis, {key}, {{
+                    value: controller.signal,
+                    writable: true,
+                    configurable: true,
+                }});
+                return [controller, controller.signal];
+            }})()"
+        ))?;
+
+        Ok(Self {
+            controller,
+            signal,
+            name,
+        })
+    }
+
+    /// Get the name of the global the signal is exposed as
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the controller's `AbortSignal`
+    #[must_use]
+    pub fn signal(&self) -> &Value {
+        &self.signal
+    }
+
+    /// Abort the controller, signalling every consumer of its `AbortSignal`
+    ///
+    /// With no `reason`, pending operations such as `fetch` reject with a `DOMException` named `AbortError`
+    /// Otherwise they reject with the given reason
+    ///
+    /// Aborting an already-aborted controller has no effect
+    /// The rejections will be delivered the next time the event loop runs
+    ///
+    /// # Errors
+    /// Will return an error if the controller cannot be aborted
+    pub fn abort(&self, runtime: &mut Runtime, reason: Option<&str>) -> Result<(), Error> {
+        runtime.with_scope(|scope| {
+            let controller = v8::Local::new(scope, self.controller.as_v8());
+            let controller = v8::Local::<v8::Object>::try_from(controller)
+                .map_err(|_| Error::Runtime("AbortController is not an object".to_string()))?;
+
+            let key = v8::String::new(scope, "abort")
+                .ok_or_else(|| Error::V8Encoding("abort".to_string()))?;
+            let abort = controller
+                .get(scope, key.into())
+                .and_then(|f| v8::Local::<v8::Function>::try_from(f).ok())
+                .ok_or_else(|| Error::ValueNotCallable("AbortController.abort".to_string()))?;
+
+            let args: Vec<v8::Local<v8::Value>> = match reason {
+                Some(reason) => vec![v8::String::new(scope, reason)
+                    .ok_or_else(|| Error::V8Encoding(reason.to_string()))?
+                    .into()],
+                None => vec![],
+            };
+
+            abort
+                .call(scope, controller.into(), &args)
+                .ok_or_else(|| Error::Runtime("Could not abort the controller".to_string()))?;
+            Ok(())
+        })
+    }
+
+    /// Returns true if the controller has been aborted
+    ///
+    /// # Errors
+    /// Will return an error if the signal's state cannot be read
+    pub fn is_aborted(&self, runtime: &mut Runtime) -> Result<bool, Error> {
+        runtime.with_scope(|scope| {
+            let signal = v8::Local::new(scope, self.signal.as_v8());
+            let signal = v8::Local::<v8::Object>::try_from(signal)
+                .map_err(|_| Error::Runtime("AbortSignal is not an object".to_string()))?;
+
+            let key = v8::String::new(scope, "aborted")
+                .ok_or_else(|| Error::V8Encoding("aborted".to_string()))?;
+            let aborted = signal
+                .get(scope, key.into())
+                .ok_or_else(|| Error::ValueNotFound("AbortSignal.aborted".to_string()))?;
+            Ok(aborted.is_true())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{js_value::Promise, json_args, Module, RuntimeOptions};
+
+    #[test]
+    fn test_abort_signal() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let controller = AbortControllerWrapper::new(&mut runtime, "signal")
+            .expect("Could not create the controller");
+        assert!(!controller
+            .is_aborted(&mut runtime)
+            .expect("Could not read the signal"));
+
+        let aborted: bool = runtime.eval("signal.aborted").expect("Could not eval");
+        assert!(!aborted);
+
+        controller
+            .abort(&mut runtime, Some("stop"))
+            .expect("Could not abort");
+        assert!(controller
+            .is_aborted(&mut runtime)
+            .expect("Could not read the signal"));
+
+        let reason: String = runtime.eval("signal.reason").expect("Could not eval");
+        assert_eq!(reason, "stop");
+    }
+
+    #[test]
+    fn test_abort_fetch() {
+        // A server that accepts connections, but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Could not get address")
+        );
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function request(url) {
+                try {
+                    await fetch(url, { signal });
+                    return 'completed';
+                } catch (e) {
+                    return e.name;
+                }
+            }
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let controller = AbortControllerWrapper::new(&mut runtime, "signal")
+            .expect("Could not create the controller");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let result: Promise<String> = runtime
+            .call_function_immediate(Some(&handle), "request", json_args!(url))
+            .expect("Could not call function");
+
+        // Let the request get in-flight
+        for _ in 0..10 {
+            runtime
+                .advance_event_loop(deno_core::PollEventLoopOptions::default())
+                .expect("Could not run the event loop");
+        }
+        assert!(result.is_pending(&mut runtime));
+
+        controller
+            .abort(&mut runtime, None)
+            .expect("Could not abort");
+        let result = result
+            .into_value(&mut runtime)
+            .expect("Could not resolve the promise");
+        assert_eq!(result, "AbortError");
+    }
+}
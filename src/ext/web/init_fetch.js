@@ -1,4 +1,5 @@
 import * as headers from "ext:deno_fetch/20_headers.js";
+import { InnerBody } from "ext:deno_fetch/22_body.js";
 import * as formData from "ext:deno_fetch/21_formdata.js";
 import * as httpClient from "ext:deno_fetch/22_http_client.js";
 import * as request from "ext:deno_fetch/23_request.js";
@@ -10,8 +11,161 @@ Deno.core.setWasmStreamingCallback(fetch.handleWasmStreaming);
 
 import {applyToGlobal, writeable, nonEnumerable} from 'ext:rustyscript/rustyscript.js';
 
+// Bounds a response body to `WebOptions::max_response_bytes`, counting bytes as they are read
+function limitResponse(res, limit) {
+    const tooLarge = () => new RangeError(`Response body exceeds the limit of ${limit} bytes`);
+    if (Number(res.headers.get("content-length")) > limit) {
+        res.body?.cancel();
+        throw tooLarge();
+    }
+    if (res.body === null) {
+        return res;
+    }
+
+    let received = 0;
+    const body = res.body.pipeThrough(new TransformStream({
+        transform(chunk, controller) {
+            received += chunk.byteLength;
+            if (received > limit) {
+                controller.error(tooLarge());
+            } else {
+                controller.enqueue(chunk);
+            }
+        },
+    }));
+
+    return withBody(res, body);
+}
+
+// Returns a copy of a response reading from a different body
+// The copy shares the header list and url list of the original, and its headers keep their guard
+function withBody(res, body) {
+    const inner = response.toInnerResponse(res);
+    return response.fromInnerResponse(
+        { ...inner, body: new InnerBody(body) },
+        headers.guardFromHeaders(res.headers),
+    );
+}
+
+// Queues fetches beyond `WebOptions::max_concurrent_fetches`, handing each freed slot to the oldest waiting one
+let fetchesInFlight = 0;
+const waitingFetches = [];
+function acquireFetchSlot(limit, signal) {
+    if (fetchesInFlight < limit) {
+        fetchesInFlight++;
+        return null;
+    }
+
+    return new Promise((resolve, reject) => {
+        const waiter = { resolve, reject };
+        waitingFetches.push(waiter);
+        signal?.addEventListener("abort", () => {
+            const index = waitingFetches.indexOf(waiter);
+            if (index !== -1) {
+                waitingFetches.splice(index, 1);
+                reject(signal.reason);
+            }
+        }, { once: true });
+    });
+}
+
+function releaseFetchSlot() {
+    const next = waitingFetches.shift();
+    if (next) {
+        next.resolve();
+    } else {
+        fetchesInFlight--;
+    }
+}
+
+// Keeps the slot of a fetch until its body is read to the end, errors, is cancelled or is garbage collected,
+// since the connection stays open until then
+const fetchSlotRegistry = new FinalizationRegistry((release) => release());
+function holdFetchSlot(res) {
+    let held = true;
+    const release = () => {
+        if (held) {
+            held = false;
+            releaseFetchSlot();
+        }
+    };
+    if (res.body === null) {
+        release();
+        return res;
+    }
+
+    const reader = res.body.getReader();
+    const body = new ReadableStream({
+        async pull(controller) {
+            let chunk;
+            try {
+                chunk = await reader.read();
+            } catch (e) {
+                release();
+                controller.error(e);
+                return;
+            }
+
+            if (chunk.done) {
+                release();
+                controller.close();
+            } else {
+                controller.enqueue(chunk.value);
+            }
+        },
+        cancel(reason) {
+            release();
+            return reader.cancel(reason);
+        },
+    });
+    fetchSlotRegistry.register(body, release);
+    return withBody(res, body);
+}
+
+// Adds `WebOptions::default_headers` to a request, unless it already sets them
+function withDefaultHeaders(input, init) {
+    const defaults = Deno.core.ops.op_fetch_default_headers();
+    if (defaults.length === 0) {
+        return init;
+    }
+
+    const fallback = input instanceof request.Request ? input.headers : undefined;
+    const merged = new headers.Headers(init?.headers ?? fallback);
+    for (const [name, value] of defaults) {
+        if (!merged.has(name)) {
+            merged.set(name, value);
+        }
+    }
+    return { ...init, headers: merged };
+}
+
+async function limitedFetch(input, init) {
+    init = withDefaultHeaders(input, init);
+
+    const maxConcurrent = Deno.core.ops.op_fetch_max_concurrent();
+    let res;
+    if (maxConcurrent === null) {
+        res = await fetch.fetch(input, init);
+    } else {
+        const signal = init?.signal ?? (input instanceof request.Request ? input.signal : undefined);
+        signal?.throwIfAborted();
+        await acquireFetchSlot(maxConcurrent, signal);
+        try {
+            res = await fetch.fetch(input, init);
+        } catch (e) {
+            releaseFetchSlot();
+            throw e;
+        }
+        res = holdFetchSlot(res);
+    }
+
+    const limit = Deno.core.ops.op_fetch_max_response_bytes();
+    return limit === null ? res : limitResponse(res, limit);
+}
+Object.defineProperty(limitedFetch, "name", { value: "fetch" });
+
 applyToGlobal({
-    fetch: writeable(fetch.fetch),
+    fetch: writeable(limitedFetch),
     Request: nonEnumerable(request.Request),
     Response: nonEnumerable(response.Response),
     Headers: nonEnumerable(headers.Headers),
@@ -20,4 +174,4 @@ applyToGlobal({
 });
 
 globalThis.Deno.HttpClient = httpClient.HttpClient;
-globalThis.Deno.createHttpClient = httpClient.createHttpClient;
\ No newline at end of file
+globalThis.Deno.createHttpClient = httpClient.createHttpClient;
\ No newline at end of file
@@ -1,18 +1,23 @@
 use std::sync::Arc;
 
-use deno_core::{extension, Extension};
+use deno_core::{extension, Extension, OpState};
 
 use super::ExtensionTrait;
 
+mod abort;
+pub use abort::AbortControllerWrapper;
+
 mod options;
 pub use options::WebOptions;
 
 mod permissions;
-pub(crate) use permissions::PermissionsContainer;
+pub(crate) use permissions::{
+    build_deno_permissions, push_call_permissions, CallPermissionsGuard, PermissionsContainer,
+};
 pub use permissions::{
-    to_permissions_options, AllowlistWebPermissions, CheckedPath, DefaultWebPermissions,
-    PermissionCheckError, PermissionDeniedError, PermissionsOptions, SystemsPermissionKind,
-    WebPermissions,
+    to_permissions_options, AllowlistWebPermissions, CheckedPath, ClosureWebPermissions,
+    DefaultWebPermissions, PermissionCheckError, PermissionDeniedError, PermissionsOptions,
+    SystemsPermissionKind, WebPermissions,
 };
 
 /// Stub for a node op deno_net expects to find
@@ -26,15 +31,58 @@ pub fn op_tls_peer_certificate(
     None
 }
 
+/// The limit from `WebOptions::max_response_bytes`, applied to fetched responses
+struct MaxResponseBytes(Option<usize>);
+
+#[deno_core::op2]
+#[serde]
+fn op_fetch_max_response_bytes(state: &OpState) -> Option<usize> {
+    state.borrow::<MaxResponseBytes>().0
+}
+
+/// The limit from `WebOptions::max_concurrent_fetches`, applied to fetches in flight
+struct MaxConcurrentFetches(Option<usize>);
+
+#[deno_core::op2]
+#[serde]
+fn op_fetch_max_concurrent(state: &OpState) -> Option<usize> {
+    state.borrow::<MaxConcurrentFetches>().0
+}
+
+/// The headers from `WebOptions::default_headers`, added to fetches and websocket handshakes
+struct DefaultHeaders(Vec<(String, String)>);
+
+#[deno_core::op2]
+#[serde]
+fn op_fetch_default_headers(state: &OpState) -> Vec<(String, String)> {
+    state.borrow::<DefaultHeaders>().0.clone()
+}
+
 extension!(
     init_fetch,
     deps = [rustyscript],
+    ops = [op_fetch_max_response_bytes, op_fetch_max_concurrent, op_fetch_default_headers],
     esm_entry_point = "ext:init_fetch/init_fetch.js",
     esm = [ dir "src/ext/web", "init_fetch.js" ],
+    options = {
+        max_response_bytes: Option<usize>,
+        max_concurrent_fetches: Option<usize>,
+        default_headers: Vec<(String, String)>
+    },
+    state = |state, config| {
+        state.put(MaxResponseBytes(config.max_response_bytes));
+        state.put(DefaultHeaders(config.default_headers));
+        // A cap of 0 could never be satisfied, so it allows a single fetch
+        state.put(MaxConcurrentFetches(config.max_concurrent_fetches.map(|n| n.max(1))));
+    },
 );
 impl ExtensionTrait<WebOptions> for init_fetch {
     fn init(options: WebOptions) -> Extension {
-        init_fetch::init()
+        init_fetch::init(
+            options.max_response_bytes,
+            options.max_concurrent_fetches,
+            options.default_headers,
+        )
     }
 }
 impl ExtensionTrait<WebOptions> for deno_fetch::deno_fetch {
@@ -115,16 +163,11 @@ extension!(
         permissions: Arc<dyn WebPermissions>
     },
     state = |state, config| {
-        state.put(PermissionsContainer(config.permissions.clone()));
+        let container = PermissionsContainer(config.permissions.clone());
         if !state.has::<deno_permissions::PermissionsContainer>() {
-            let parser = Arc::new(deno_permissions::RuntimePermissionDescriptorParser::new(sys_traits::impls::RealSys));
-            let opts = permissions::to_permissions_options(config.permissions.as_ref());
-            let permissions = match deno_permissions::Permissions::from_options(&*parser, &opts) {
-                Ok(p) => deno_permissions::PermissionsContainer::new(parser, p),
-                Err(_) => deno_permissions::PermissionsContainer::allow_all(parser),
-            };
-            state.put(permissions);
+            state.put(build_deno_permissions(&container));
         }
+        state.put(container);
     },
 );
 impl ExtensionTrait<WebOptions> for init_web {
@@ -162,3 +205,352 @@ pub fn extensions(options: WebOptions, is_snapshot: bool) -> Vec<Extension> {
         init_fetch::build(options, is_snapshot),
     ]
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use crate::{js_value::Promise, json_args, Module, Runtime, RuntimeOptions};
+
+    /// A module exporting `add(i32, i32) -> i32`
+    const ADD_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f, // type section
+        0x03, 0x02, 0x01, 0x00, // function section
+        0x07, 0x07, 0x01, 0x03, 0x61, 0x64, 0x64, 0x00, 0x00, // export section
+        0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b, // code section
+    ];
+
+    #[test]
+    fn test_wasm_instantiate_streaming() {
+        // A server that answers a single request with the wasm module
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+        let url = format!(
+            "http://{}/add.wasm",
+            listener.local_addr().expect("Could not get address")
+        );
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Could not accept connection");
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).expect("Could not read request");
+
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/wasm\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                ADD_WASM.len()
+            );
+            stream
+                .write_all(header.as_bytes())
+                .expect("Could not write response");
+            stream
+                .write_all(ADD_WASM)
+                .expect("Could not write response");
+        });
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function add(url) {
+                const { instance } = await WebAssembly.instantiateStreaming(fetch(url));
+                return instance.exports.add(2, 3);
+            }
+
+            export async function compile(bytes) {
+                const response = new Response(new Uint8Array(bytes), {
+                    headers: { 'Content-Type': 'application/wasm' },
+                });
+                const module = await WebAssembly.compileStreaming(response);
+                return WebAssembly.Module.exports(module).map(e => e.name);
+            }
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let sum: Promise<i32> = runtime
+            .call_function_immediate(Some(&handle), "add", json_args!(url))
+            .expect("Could not call function");
+        assert_eq!(
+            sum.into_value(&mut runtime)
+                .expect("Could not resolve the promise"),
+            5
+        );
+
+        let exports: Vec<String> = runtime
+            .call_function(Some(&handle), "compile", json_args!(ADD_WASM))
+            .expect("Could not call function");
+        assert_eq!(exports, vec!["add".to_string()]);
+    }
+
+    #[test]
+    fn test_max_response_bytes() {
+        // A server answering each request with 64 bytes, announcing the length only if asked to
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Could not get address")
+        );
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("Could not accept connection");
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).expect("Could not read request");
+                let length = if String::from_utf8_lossy(&buf[..n]).contains("GET /sized") {
+                    "Content-Length: 64\r\n"
+                } else {
+                    ""
+                };
+
+                let header = format!("HTTP/1.1 200 OK\r\n{length}Connection: close\r\n\r\n");
+                stream
+                    .write_all(header.as_bytes())
+                    .expect("Could not write response");
+                stream
+                    .write_all(&[b'a'; 64])
+                    .expect("Could not write response");
+            }
+        });
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function text(url) {
+                try {
+                    return (await (await fetch(url)).text()).length;
+                } catch (e) {
+                    return e.name;
+                }
+            }
+
+            export async function metadata(url) {
+                const res = await fetch(url);
+                let immutable = false;
+                try { res.headers.set('x-test', '1'); } catch { immutable = true; }
+                return [res.url, immutable, res.headers.get('connection'), (await res.text()).length];
+            }
+
+            export async function stream(url) {
+                const reader = (await fetch(url)).body.getReader();
+                let total = 0;
+                try {
+                    for (;;) {
+                        const { done, value } = await reader.read();
+                        if (done) return total;
+                        total += value.length;
+                    }
+                } catch (e) {
+                    return e.name;
+                }
+            }
+        ",
+        );
+
+        let mut options = RuntimeOptions::default();
+        options.extension_options.web.max_response_bytes = Some(16);
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        for (function, path) in [("text", "/sized"), ("text", "/"), ("stream", "/")] {
+            let result: deno_core::serde_json::Value = runtime
+                .call_function(Some(&handle), function, json_args!(format!("{url}{path}")))
+                .expect("Could not call function");
+            assert_eq!(result, "RangeError", "{function} {path}");
+        }
+
+        // A response within the limit keeps its url and immutable headers
+        let mut options = RuntimeOptions::default();
+        options.extension_options.web.max_response_bytes = Some(64);
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let result: deno_core::serde_json::Value = runtime
+            .call_function(
+                Some(&handle),
+                "metadata",
+                json_args!(format!("{url}/sized")),
+            )
+            .expect("Could not call function");
+        assert_eq!(
+            result,
+            deno_core::serde_json::json!([format!("{url}/sized"), true, "close", 64])
+        );
+
+        // Unlimited by default
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let length: usize = runtime
+            .call_function(Some(&handle), "text", json_args!(format!("{url}/")))
+            .expect("Could not call function");
+        assert_eq!(length, 64);
+    }
+
+    #[test]
+    fn test_default_headers() {
+        // A server answering each request with the headers it was sent
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Could not get address")
+        );
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("Could not accept connection");
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).expect("Could not read request");
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let body = request
+                    .lines()
+                    .filter(|line| line.starts_with("user-agent:") || line.starts_with("x-"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream
+                    .write_all(header.as_bytes())
+                    .expect("Could not write response");
+                stream
+                    .write_all(body.as_bytes())
+                    .expect("Could not write response");
+            }
+        });
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function plain(url) {
+                return (await fetch(url)).text();
+            }
+
+            export async function overridden(url) {
+                const request = new Request(url, { headers: { 'X-Api-Key': 'mine' } });
+                return (await fetch(request)).text();
+            }
+        ",
+        );
+
+        let mut options = RuntimeOptions::default();
+        options.extension_options.web.user_agent = "my-agent/1.0".to_string();
+        options.extension_options.web.default_headers = vec![
+            ("X-Api-Key".to_string(), "secret".to_string()),
+            ("X-Client".to_string(), "rustyscript".to_string()),
+        ];
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let headers: String = runtime
+            .call_function(Some(&handle), "plain", json_args!(url.clone()))
+            .expect("Could not call function");
+        assert!(headers.contains("user-agent: my-agent/1.0"), "{headers}");
+        assert!(headers.contains("x-api-key: secret"), "{headers}");
+        assert!(headers.contains("x-client: rustyscript"), "{headers}");
+
+        let headers: String = runtime
+            .call_function(Some(&handle), "overridden", json_args!(url))
+            .expect("Could not call function");
+        assert!(headers.contains("x-api-key: mine"), "{headers}");
+        assert!(!headers.contains("x-api-key: secret"), "{headers}");
+        assert!(headers.contains("x-client: rustyscript"), "{headers}");
+    }
+
+    #[test]
+    fn test_max_concurrent_fetches() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // A slow server recording the most connections it has had open at once
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Could not get address")
+        );
+        let peak = Arc::new(AtomicUsize::new(0));
+        let server_peak = peak.clone();
+        std::thread::spawn(move || {
+            let open = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("Could not accept connection");
+                let open = open.clone();
+                let peak = server_peak.clone();
+                std::thread::spawn(move || {
+                    let now = open.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+
+                    let mut buf = [0; 1024];
+                    let _ = stream.read(&mut buf).expect("Could not read request");
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+
+                    let response =
+                        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+                    stream
+                        .write_all(response.as_bytes())
+                        .expect("Could not write response");
+
+                    // The connection is open until the client closes it
+                    while stream.read(&mut buf).is_ok_and(|n| n > 0) {}
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function fetchAll(url, n) {
+                const requests = Array.from({ length: n }, () => fetch(url).then((r) => r.text()));
+                return (await Promise.all(requests)).filter((text) => text === 'ok').length;
+            }
+
+            export async function abortWaiting(url) {
+                const first = Array.from({ length: 5 }, () => fetch(url).then((r) => r.text()));
+                const controller = new AbortController();
+                const waiting = fetch(url, { signal: controller.signal });
+                controller.abort();
+                const result = await waiting.then(() => 'sent', (e) => e.name);
+                await Promise.all(first);
+                return result;
+            }
+
+            export async function unreadBodies(url) {
+                let arrived = 0;
+                const fetches = Array.from({ length: 6 }, () => fetch(url).then((r) => (arrived++, r)));
+                await new Promise((resolve) => setTimeout(resolve, 200));
+                const before = arrived;
+
+                const first = await Promise.all(fetches.slice(0, 5));
+                await Promise.all(first.map((r) => r.text()));
+                const last = await (await fetches[5]).text();
+                return [before, last];
+            }
+        ",
+        );
+
+        let mut options = RuntimeOptions::default();
+        options.extension_options.web.max_concurrent_fetches = Some(5);
+        let mut runtime = Runtime::new(options).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let completed: usize = runtime
+            .call_function(Some(&handle), "fetchAll", json_args!(&url, 100))
+            .expect("Could not call function");
+        assert_eq!(completed, 100);
+        assert!(peak.load(Ordering::SeqCst) <= 5);
+
+        let result: String = runtime
+            .call_function(Some(&handle), "abortWaiting", json_args!(&url))
+            .expect("Could not call function");
+        assert_eq!(result, "AbortError");
+
+        // A response whose body has not been read still holds its slot
+        let (arrived, last): (usize, String) = runtime
+            .call_function(Some(&handle), "unreadBodies", json_args!(&url))
+            .expect("unread bodies should not fail the fetches");
+        assert_eq!(arrived, 5);
+        assert_eq!(last, "ok");
+    }
+}
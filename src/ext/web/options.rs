@@ -33,9 +33,17 @@ pub struct WebOptions {
     /// Base URL for some `deno_web` OPs
     pub base_url: Option<deno_core::ModuleSpecifier>,
 
-    /// User agent to use for fetch
+    /// User agent sent with fetches and websocket handshakes
+    ///
+    /// A `User-Agent` header set by the script takes precedence
     pub user_agent: String,
 
+    /// Headers added to every fetch and websocket handshake, such as an API key
+    ///
+    /// Headers set by the script for a request take precedence over these - names are case-insensitive  
+    /// An invalid name or value fails each request with a `TypeError`
+    pub default_headers: Vec<(String, String)>,
+
     /// Root certificate store for TLS connections for fetches and network OPs
     pub root_cert_store_provider: Option<std::sync::Arc<dyn deno_tls::RootCertStoreProvider>>,
 
@@ -77,6 +85,25 @@ pub struct WebOptions {
 
     /// OpenTelemetry configuration for the `deno_telemetry` extension
     pub telemetry_config: deno_telemetry::OtelConfig,
+
+    /// Optional cap, in bytes, on the size of a response body that `fetch` will deliver to scripts
+    ///
+    /// Bytes are counted as they are read, so buffering methods such as `text()` or `arrayBuffer()`,
+    /// and streaming reads of `response.body`, all reject with a `RangeError` once the cap is exceeded  
+    /// A response whose `Content-Length` is already over the cap rejects from `fetch` itself
+    ///
+    /// Defaults to `None` - unlimited - for compatibility; set it to bound the memory a script can use through `fetch`
+    pub max_response_bytes: Option<usize>,
+
+    /// Optional cap on the number of `fetch` calls a runtime can have in flight at once
+    ///
+    /// Fetches beyond the cap wait in order, rather than failing, until an earlier one frees its slot  
+    /// A slot is held until the response body is read to the end, cancelled or garbage collected, or the fetch fails -
+    /// a response whose body is never read keeps its connection, and its slot, open  
+    /// A waiting fetch whose `AbortSignal` is aborted rejects without being sent
+    ///
+    /// Defaults to `None` - unlimited - for compatibility; set it to bound the outbound connections a script can open
+    pub max_concurrent_fetches: Option<usize>,
 }
 
 impl Default for WebOptions {
@@ -84,6 +111,7 @@ impl Default for WebOptions {
         Self {
             base_url: None,
             user_agent: String::new(),
+            default_headers: Vec::new(),
             root_cert_store_provider: None,
             proxy: None,
             request_builder_hook: None,
@@ -96,6 +124,8 @@ impl Default for WebOptions {
             client_builder_hook: None,
             resolver: Resolver::default(),
             telemetry_config: deno_telemetry::OtelConfig::default(),
+            max_response_bytes: None,
+            max_concurrent_fetches: None,
         }
     }
 }
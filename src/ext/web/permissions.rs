@@ -1,10 +1,16 @@
 use std::{
     borrow::Cow,
+    cell::RefCell,
     collections::HashSet,
-    path::Path,
+    path::{Path, PathBuf},
+    rc::Rc,
     sync::{Arc, RwLock},
 };
 
+use deno_core::OpState;
+use deno_permissions::RuntimePermissionDescriptorParser;
+use sys_traits::impls::RealSys;
+
 pub use deno_permissions::{
     CheckedPath, PermissionCheckError, PermissionDeniedError, PermissionState, PermissionsOptions,
 };
@@ -18,6 +24,21 @@ pub fn oops(msg: impl std::fmt::Display) -> PermissionCheckError {
     })
 }
 
+/// Serializes a URL the way `check_url` receives it, or returns it unchanged if it cannot be parsed
+fn normalize_url(url: &str) -> String {
+    deno_core::url::Url::parse(url).map_or_else(|_| url.to_string(), String::from)
+}
+
+/// Resolves a path against the current directory and collapses `.` and `..` components
+fn normalize_path(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    deno_core::normalize_path(Cow::Owned(path)).into_owned()
+}
+
 /// The default permissions manager for the web related extensions
 ///
 /// Allows all operations
@@ -210,13 +231,15 @@ impl AllowlistWebPermissions {
     }
 
     /// Whitelist a URL
+    ///
+    /// The URL is normalized the way requests are, so `https://example.com` also matches `https://example.com/`
     pub fn allow_url(&self, url: &str) {
-        self.borrow_mut().url.insert(url.to_string());
+        self.borrow_mut().url.insert(normalize_url(url));
     }
 
     /// Blacklist a URL
     pub fn deny_url(&self, url: &str) {
-        self.borrow_mut().url.remove(url);
+        self.borrow_mut().url.remove(&normalize_url(url));
     }
 
     /// Whitelist a path for reading
@@ -438,6 +461,312 @@ impl WebPermissions for AllowlistWebPermissions {
     }
 }
 
+type UrlCheck = Arc<dyn Fn(&deno_core::url::Url) -> bool + Send + Sync>;
+type HostCheck = Arc<dyn Fn(&str, Option<u16>) -> bool + Send + Sync>;
+type VsockCheck = Arc<dyn Fn(u32, u32) -> bool + Send + Sync>;
+type PathCheck = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+type NameCheck = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+type SysCheck = Arc<dyn Fn(&SystemsPermissionKind) -> bool + Send + Sync>;
+
+/// Permissions manager for the web related extensions
+///
+/// Delegates each check to a user-supplied closure, for policies that an allowlist cannot
+/// express - such as allowing only HTTPS urls, or only hosts under a specific domain
+///
+/// Any check without a closure is denied  
+/// Checks that cover every path (`read_all`, `write_all`) ask the closure about the filesystem root
+///
+/// Path closures are given an absolute path with `.` and `..` already resolved, so a prefix check
+/// cannot be escaped with `..` - the normalized path is also the one handed back to the caller
+///
+/// # Example
+/// ```rust
+/// use std::{borrow::Cow, path::Path};
+/// use rustyscript::{ClosureWebPermissions, WebPermissions};
+///
+/// let permissions = ClosureWebPermissions::new()
+///     .with_net(|url| url.scheme() == "https")
+///     .with_read(|path| path.starts_with("/srv/assets"));
+///
+/// let allowed = Cow::Borrowed(Path::new("/srv/assets/logo.png"));
+/// assert!(permissions.check_read(allowed, None).is_ok());
+///
+/// let escaped = Cow::Borrowed(Path::new("/srv/assets/../../etc/passwd"));
+/// assert!(permissions.check_read(escaped, None).is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct ClosureWebPermissions {
+    hrtime: bool,
+    exec: bool,
+    net: Option<UrlCheck>,
+    host: Option<HostCheck>,
+    vsock: Option<VsockCheck>,
+    read: Option<PathCheck>,
+    write: Option<PathCheck>,
+    env: Option<NameCheck>,
+    sys: Option<SysCheck>,
+}
+impl ClosureWebPermissions {
+    /// Create a new instance with nothing allowed by default
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `hrtime` permission
+    ///
+    /// If true, timers will be allowed to use high resolution time
+    #[must_use]
+    pub fn with_hrtime(mut self, value: bool) -> Self {
+        self.hrtime = value;
+        self
+    }
+
+    /// Set the `exec` permission
+    ///
+    /// If true, FFI execution will be allowed
+    #[must_use]
+    pub fn with_exec(mut self, value: bool) -> Self {
+        self.exec = value;
+        self
+    }
+
+    /// Decide which URLs can be used by fetch or websocket
+    #[must_use]
+    pub fn with_net(
+        mut self,
+        check: impl Fn(&deno_core::url::Url) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.net = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which hosts, and optionally ports, can be connected to by net
+    #[must_use]
+    pub fn with_host(
+        mut self,
+        check: impl Fn(&str, Option<u16>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.host = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which virtual sockets, by cid and port, can be connected to by net
+    #[must_use]
+    pub fn with_vsock(mut self, check: impl Fn(u32, u32) -> bool + Send + Sync + 'static) -> Self {
+        self.vsock = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which paths can be read, or opened for reading
+    #[must_use]
+    pub fn with_read(mut self, check: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.read = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which paths can be written to, or opened for writing
+    #[must_use]
+    pub fn with_write(mut self, check: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.write = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which environment variables can be accessed
+    #[must_use]
+    pub fn with_env(mut self, check: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.env = Some(Arc::new(check));
+        self
+    }
+
+    /// Decide which system operations are allowed
+    #[must_use]
+    pub fn with_sys(
+        mut self,
+        check: impl Fn(&SystemsPermissionKind) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.sys = Some(Arc::new(check));
+        self
+    }
+
+    fn allows_read(&self, path: &Path) -> bool {
+        self.read.as_ref().is_some_and(|check| check(path))
+    }
+
+    fn allows_write(&self, path: &Path) -> bool {
+        self.write.as_ref().is_some_and(|check| check(path))
+    }
+}
+impl std::fmt::Debug for ClosureWebPermissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureWebPermissions")
+            .field("hrtime", &self.hrtime)
+            .field("exec", &self.exec)
+            .field("net", &self.net.is_some())
+            .field("host", &self.host.is_some())
+            .field("vsock", &self.vsock.is_some())
+            .field("read", &self.read.is_some())
+            .field("write", &self.write.is_some())
+            .field("env", &self.env.is_some())
+            .field("sys", &self.sys.is_some())
+            .finish()
+    }
+}
+impl WebPermissions for ClosureWebPermissions {
+    fn allow_hrtime(&self) -> bool {
+        self.hrtime
+    }
+
+    fn check_url(
+        &self,
+        url: &deno_core::url::Url,
+        api_name: &str,
+    ) -> Result<(), PermissionCheckError> {
+        if self.net.as_ref().is_some_and(|check| check(url)) {
+            Ok(())
+        } else {
+            Err(oops(url))
+        }
+    }
+
+    fn check_host(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        api_name: &str,
+    ) -> Result<(), PermissionCheckError> {
+        if self.host.as_ref().is_some_and(|check| check(host, port)) {
+            Ok(())
+        } else {
+            Err(oops(host))
+        }
+    }
+
+    fn check_vsock(&self, cid: u32, port: u32, api_name: &str) -> Result<(), PermissionCheckError> {
+        if self.vsock.as_ref().is_some_and(|check| check(cid, port)) {
+            Ok(())
+        } else {
+            Err(oops(format!("vsock: {cid}:{port}")))
+        }
+    }
+
+    fn check_open<'a>(
+        &self,
+        resolved: bool,
+        read: bool,
+        write: bool,
+        path: Cow<'a, Path>,
+        api_name: &str,
+    ) -> Option<std::borrow::Cow<'a, Path>> {
+        let path = normalize_path(&path);
+        if read && !self.allows_read(&path) {
+            return None;
+        }
+        if write && !self.allows_write(&path) {
+            return None;
+        }
+        Some(Cow::Owned(path))
+    }
+
+    fn check_read<'a>(
+        &self,
+        p: Cow<'a, Path>,
+        api_name: Option<&str>,
+    ) -> Result<Cow<'a, Path>, PermissionCheckError> {
+        let p = normalize_path(&p);
+        if self.allows_read(&p) {
+            Ok(Cow::Owned(p))
+        } else {
+            Err(oops(p.display()))
+        }
+    }
+
+    fn check_read_all(&self, api_name: Option<&str>) -> Result<(), PermissionCheckError> {
+        if self.allows_read(Path::new("/")) {
+            Ok(())
+        } else {
+            Err(oops("read_all"))
+        }
+    }
+
+    fn check_read_blind(
+        &self,
+        p: &Path,
+        display: &str,
+        api_name: &str,
+    ) -> Result<(), PermissionCheckError> {
+        self.check_read(Cow::Borrowed(p), Some(api_name))?;
+        Ok(())
+    }
+
+    fn check_write<'a>(
+        &self,
+        p: Cow<'a, Path>,
+        api_name: Option<&str>,
+    ) -> Result<Cow<'a, Path>, PermissionCheckError> {
+        let p = normalize_path(&p);
+        if self.allows_write(&p) {
+            Ok(Cow::Owned(p))
+        } else {
+            Err(oops(p.display()))
+        }
+    }
+
+    fn check_write_all(&self, api_name: &str) -> Result<(), PermissionCheckError> {
+        if self.allows_write(Path::new("/")) {
+            Ok(())
+        } else {
+            Err(oops("write_all"))
+        }
+    }
+
+    fn check_write_blind(
+        &self,
+        path: &Path,
+        display: &str,
+        api_name: &str,
+    ) -> Result<(), PermissionCheckError> {
+        self.check_write(Cow::Borrowed(path), Some(api_name))?;
+        Ok(())
+    }
+
+    fn check_write_partial<'a>(
+        &self,
+        path: Cow<'a, Path>,
+        api_name: &str,
+    ) -> Result<Cow<'a, Path>, PermissionCheckError> {
+        self.check_write(path, Some(api_name))
+    }
+
+    fn check_sys(
+        &self,
+        kind: SystemsPermissionKind,
+        api_name: &str,
+    ) -> Result<(), PermissionCheckError> {
+        if self.sys.as_ref().is_some_and(|check| check(&kind)) {
+            Ok(())
+        } else {
+            Err(oops(kind.as_str()))
+        }
+    }
+
+    fn check_env(&self, var: &str) -> Result<(), PermissionCheckError> {
+        if self.env.as_ref().is_some_and(|check| check(var)) {
+            Ok(())
+        } else {
+            Err(oops(var))
+        }
+    }
+
+    fn check_exec(&self) -> Result<(), PermissionCheckError> {
+        if self.exec {
+            Ok(())
+        } else {
+            Err(oops("ffi"))
+        }
+    }
+}
+
 /// Trait managing the permissions for the web related extensions
 ///
 /// See [`DefaultWebPermissions`] for a default implementation that allows-all
@@ -636,10 +965,106 @@ impl_sys_permission_kinds!(
     Inspector("inspector"),
 );
 
-#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct PermissionsContainer(pub Arc<dyn WebPermissions>);
 
+/// Permissions applied for the duration of a single call, innermost last,
+/// see `Runtime::call_function_with_permissions`
+///
+/// Only present in the op state while such a call is in progress, alongside the deno permissions it replaced
+struct CallPermissions {
+    calls: Vec<Arc<dyn WebPermissions>>,
+    replaced: deno_permissions::PermissionsContainer,
+}
+
+/// Applies permissions on top of the runtime's until the returned guard is dropped
+///
+/// If they deny network access, the deno permissions are swapped for a copy of the runtime's without it,
+/// so every network op is denied - including those the extensions make on their own, such as `EventSource` reconnects
+pub(crate) fn push_call_permissions(
+    state: Rc<RefCell<OpState>>,
+    permissions: Arc<dyn WebPermissions>,
+) -> CallPermissionsGuard {
+    {
+        let mut op_state = state.borrow_mut();
+        if !op_state.has::<CallPermissions>() {
+            let replaced = op_state
+                .borrow::<deno_permissions::PermissionsContainer>()
+                .clone();
+            op_state.put(CallPermissions {
+                calls: Vec::new(),
+                replaced,
+            });
+        }
+        op_state
+            .borrow_mut::<CallPermissions>()
+            .calls
+            .push(permissions);
+        apply_call_permissions(&mut op_state);
+    }
+    CallPermissionsGuard(state)
+}
+
+/// Puts the deno permissions matching the calls in progress into the op state
+fn apply_call_permissions(state: &mut OpState) {
+    let Some(calls) = state.try_borrow::<CallPermissions>() else {
+        return;
+    };
+    if calls.calls.is_empty() {
+        let calls = state.take::<CallPermissions>();
+        state.put(calls.replaced);
+        return;
+    }
+
+    // Probed the same way as `to_permissions_options`
+    let deny_net = calls
+        .calls
+        .iter()
+        .any(|permissions| permissions.check_host("0.0.0.0", Some(0), "probe").is_err());
+    let permissions = if deny_net {
+        let mut options = to_permissions_options(state.borrow::<PermissionsContainer>().0.as_ref());
+        options.allow_net = None;
+        options.deny_net = None;
+        deno_permissions_from_options(&options)
+    } else {
+        calls.replaced.clone()
+    };
+    state.put(permissions);
+}
+
+/// Removes the innermost call permissions when dropped, so they are lifted even if the call fails or is cancelled
+pub(crate) struct CallPermissionsGuard(Rc<RefCell<OpState>>);
+impl Drop for CallPermissionsGuard {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.0.try_borrow_mut() {
+            if let Some(calls) = state.try_borrow_mut::<CallPermissions>() {
+                calls.calls.pop();
+            }
+            apply_call_permissions(&mut state);
+        }
+    }
+}
+
+/// Builds the permissions the deno extensions check, from the runtime's `WebPermissions`
+pub(crate) fn build_deno_permissions(
+    permissions: &PermissionsContainer,
+) -> deno_permissions::PermissionsContainer {
+    deno_permissions_from_options(&to_permissions_options(permissions.0.as_ref()))
+}
+
+fn deno_permissions_from_options(
+    options: &PermissionsOptions,
+) -> deno_permissions::PermissionsContainer {
+    let parser = Arc::new(RuntimePermissionDescriptorParser::new(RealSys));
+    match deno_permissions::Permissions::from_options(&*parser, options) {
+        Ok(p) => deno_permissions::PermissionsContainer::new(parser, p),
+        Err(_) => {
+            // Fallback for backward compatibility
+            deno_permissions::PermissionsContainer::allow_all(parser)
+        }
+    }
+}
+
 /// Convert WebPermissions to deno_permissions::PermissionsOptions
 ///
 /// This function probes the WebPermissions trait methods to determine
@@ -1,9 +1,44 @@
 import * as websocket from "ext:deno_websocket/01_websocket.js";
 import * as websocketStream from "ext:deno_websocket/02_websocketstream.js";
+import { Headers } from "ext:deno_fetch/20_headers.js";
 
 import { applyToGlobal, nonEnumerable } from 'ext:rustyscript/rustyscript.js';
 
+// Adds `WebOptions::default_headers` to the options of a handshake, unless they already set them
+function withDefaultHeaders(init) {
+    const defaults = Deno.core.ops.op_fetch_default_headers();
+    if (defaults.length === 0) {
+        return init;
+    }
+
+    if (typeof init === "string") {
+        init = { protocols: [init] };
+    } else if (Array.isArray(init)) {
+        init = { protocols: init };
+    }
+
+    const merged = new Headers(init?.headers);
+    for (const [name, value] of defaults) {
+        if (!merged.has(name)) {
+            merged.set(name, value);
+        }
+    }
+    return { ...init, headers: merged };
+}
+
+// Applies `WebOptions::default_headers` to the handshake of each new connection
+function withHandshakeHeaders(constructor) {
+    return new Proxy(constructor, {
+        construct(target, args, newTarget) {
+            if (args.length > 0) {
+                args = [args[0], withDefaultHeaders(args[1]), ...args.slice(2)];
+            }
+            return Reflect.construct(target, args, newTarget);
+        },
+    });
+}
+
 applyToGlobal({
-    WebSocket: nonEnumerable(websocket.WebSocket),
-    WebSocketStream: nonEnumerable(websocketStream.WebSocketStream)
-});
\ No newline at end of file
+    WebSocket: nonEnumerable(withHandshakeHeaders(websocket.WebSocket)),
+    WebSocketStream: nonEnumerable(withHandshakeHeaders(websocketStream.WebSocketStream))
+});
\ No newline at end of file
@@ -2,6 +2,9 @@ use deno_core::{extension, Extension};
 
 use super::ExtensionTrait;
 
+mod wrapper;
+pub use wrapper::{WebSocketClose, WebSocketEvent, WebSocketWrapper};
+
 extension!(
     init_websocket,
     deps = [rustyscript],
@@ -0,0 +1,462 @@
+//! Rust-side control over javascript `WebSocket`s
+use std::time::Duration;
+
+use deno_core::serde_json;
+use serde::Deserialize;
+
+use crate::{
+    js_value::{Function, Value},
+    json_args, Error, Runtime,
+};
+
+/// The details of a closed websocket connection
+///
+/// Mirrors the fields of the WHATWG `CloseEvent`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketClose {
+    /// The close status code sent by the server
+    pub code: u16,
+
+    /// The close reason sent by the server
+    pub reason: String,
+
+    /// True if the connection was closed with a completed closing handshake
+    pub was_clean: bool,
+}
+
+impl WebSocketClose {
+    /// The close status code for a normal closure
+    pub const NORMAL: u16 = 1000;
+
+    /// The close status code reported when a connection closes without a close frame
+    pub const ABNORMAL: u16 = 1006;
+
+    /// Returns true if the connection was closed cleanly, with code 1000
+    ///
+    /// Any other closure, including a clean one with a different code, is abnormal
+    #[must_use]
+    pub fn is_normal(&self) -> bool {
+        self.was_clean && self.code == Self::NORMAL
+    }
+}
+
+/// An event received on a [`WebSocketWrapper`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketEvent {
+    /// The connection was established
+    Open,
+
+    /// A text message was received
+    Text(String),
+
+    /// A binary message was received
+    Binary(Vec<u8>),
+
+    /// The connection failed, or an error occurred
+    Error(String),
+
+    /// The connection was closed
+    /// No further events will be received
+    Close(WebSocketClose),
+}
+
+/// The shape of the events queued by the javascript side
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RawEvent {
+    Open,
+    Text { data: String },
+    Binary { data: Vec<u8> },
+    Error { message: String },
+    Close(WebSocketClose),
+}
+
+impl From<RawEvent> for WebSocketEvent {
+    fn from(event: RawEvent) -> Self {
+        match event {
+            RawEvent::Open => Self::Open,
+            RawEvent::Text { data } => Self::Text(data),
+            RawEvent::Binary { data } => Self::Binary(data),
+            RawEvent::Error { message } => Self::Error(message),
+            RawEvent::Close(close) => Self::Close(close),
+        }
+    }
+}
+
+/// A handle to a javascript `WebSocket`, created and driven from rust
+///
+/// Events are queued as they arrive, and can be read in order with [`WebSocketWrapper::recv`] or [`WebSocketWrapper::recv_sync`]
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{Runtime, WebSocketEvent, WebSocketWrapper};
+///
+/// # fn main() -> Result<(), rustyscript::Error> {
+/// let mut runtime = Runtime::new(Default::default())?;
+/// let socket = WebSocketWrapper::connect(&mut runtime, "wss://echo.websocket.org")?;
+///
+/// while let Some(event) = socket.recv_sync(&mut runtime, None)? {
+///     match event {
+///         WebSocketEvent::Open => socket.send(&mut runtime, "hello")?,
+///         WebSocketEvent::Text(text) => {
+///             println!("Received: {text}");
+///             socket.close(&mut runtime, None, None)?;
+///         }
+///         WebSocketEvent::Close(close) if !close.is_normal() => {
+///             println!("Closed abnormally: {} {}", close.code, close.reason);
+///         }
+///         _ => {}
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct WebSocketWrapper {
+    socket: Value,
+    next: Function,
+    send_text: Function,
+    send_binary: Function,
+    close: Function,
+}
+
+impl WebSocketWrapper {
+    /// Open a new `WebSocket` connection to the given url
+    ///
+    /// The connection is established in the background; wait for [`WebSocketEvent::Open`]
+    /// before sending messages
+    ///
+    /// # Errors
+    /// Will return an error if the url is invalid, or the socket cannot be created
+    pub fn connect(runtime: &mut Runtime, url: &str) -> Result<Self, Error> {
+        let url = serde_json::to_string(url)?;
+        let (socket, next, send_text, send_binary, close): (
+            Value,
+            Function,
+            Function,
+            Function,
+            Function,
+        ) = runtime.eval(format!(
+            "(() => {{
+                const socket = new WebSocket({url});
+                socket.binaryType = 'arraybuffer';
+
+                const events = [];
+                let pending = null;
+                let resolve = null;
+                const push = (event) => {{
+                    if (resolve) {{
+                        const r = resolve;
+                        pending = resolve = null;
+                        r(event);
+                    }} else {{
+                        events.push(event);
+                    }}
+                }};
+
+                socket.addEventListener('open', () => push({{ type: 'open' }}));
+                socket.addEventListener('message', (e) => push(
+                    typeof e.data === 'string'
+                        ? {{ type: 'text', data: e.data }}
+                        : {{ type: 'binary', data: Array.from(new Uint8Array(e.data)) }}
+                ));
+                socket.addEventListener('error', (e) => push({{
+                    type: 'error',
+                    message: e.message || e.error?.message || 'WebSocket error',
+                }}));
+                socket.addEventListener('close', (e) => push({{
+                    type: 'close',
+                    code: e.code,
+                    reason: e.reason,
+                    wasClean: e.wasClean,
+                }}));
+
+                // An abandoned wait is resumed by the next call, so no event is lost
+                const next = () => {{
+                    if (events.length) return events.shift();
+                    pending ??= new Promise((r) => resolve = r);
+                    return pending;
+                }};
+
+                return [
+                    socket,
+                    next,
+                    (data) => socket.send(data),
+                    (data) => socket.send(new Uint8Array(data)),
+                    (code, reason) => socket.close(code ?? undefined, reason ?? undefined),
+                ];
+            }})()"
+        ))?;
+
+        Ok(Self {
+            socket,
+            next,
+            send_text,
+            send_binary,
+            close,
+        })
+    }
+
+    /// Get the underlying javascript `WebSocket`
+    #[must_use]
+    pub fn socket(&self) -> &Value {
+        &self.socket
+    }
+
+    /// Send a text message
+    ///
+    /// # Errors
+    /// Will return an error if the socket is not open
+    pub fn send(&self, runtime: &mut Runtime, text: &str) -> Result<(), Error> {
+        self.send_text
+            .call_immediate(runtime, None, &json_args!(text))
+    }
+
+    /// Send a binary message
+    ///
+    /// # Errors
+    /// Will return an error if the socket is not open
+    pub fn send_binary(&self, runtime: &mut Runtime, data: &[u8]) -> Result<(), Error> {
+        self.send_binary
+            .call_immediate(runtime, None, &json_args!(data))
+    }
+
+    /// Begin closing the connection, with an optional status code and reason
+    ///
+    /// The closure completes in the background, and is reported as a [`WebSocketEvent::Close`]
+    ///
+    /// # Errors
+    /// Will return an error if the code or reason is rejected by the `WebSocket` API
+    pub fn close(
+        &self,
+        runtime: &mut Runtime,
+        code: Option<u16>,
+        reason: Option<&str>,
+    ) -> Result<(), Error> {
+        self.close
+            .call_immediate(runtime, None, &json_args!(code, reason))
+    }
+
+    /// Wait for the next event on the socket, blocking and running the event loop as needed
+    ///
+    /// Returns `None` if no event arrives within `timeout`
+    /// Once a [`WebSocketEvent::Close`] has been received, further calls will wait until the timeout
+    ///
+    /// # Errors
+    /// Will return an error if the event cannot be read
+    pub fn recv_sync(
+        &self,
+        runtime: &mut Runtime,
+        timeout: Option<Duration>,
+    ) -> Result<Option<WebSocketEvent>, Error> {
+        runtime
+            .tokio_runtime()
+            .block_on(self.recv(runtime, timeout))
+    }
+
+    /// Wait for the next event on the socket, running the event loop as needed
+    ///
+    /// Returns `None` if no event arrives within `timeout`
+    /// Once a [`WebSocketEvent::Close`] has been received, further calls will wait until the timeout
+    ///
+    /// # Errors
+    /// Will return an error if the event cannot be read
+    pub async fn recv(
+        &self,
+        runtime: &mut Runtime,
+        timeout: Option<Duration>,
+    ) -> Result<Option<WebSocketEvent>, Error> {
+        let next = self
+            .next
+            .call_async::<RawEvent>(runtime, None, &json_args!());
+        let event = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, next).await {
+                Ok(event) => event?,
+                Err(_) => return Ok(None),
+            },
+            None => next.await?,
+        };
+        Ok(Some(event.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RuntimeOptions;
+
+    #[test]
+    fn test_close_event() {
+        let close: WebSocketClose =
+            serde_json::from_str(r#"{ "code": 1000, "reason": "done", "wasClean": true }"#)
+                .unwrap();
+        assert!(close.is_normal());
+
+        let close = WebSocketClose {
+            code: 4000,
+            ..close
+        };
+        assert!(!close.is_normal());
+    }
+
+    /// Serves a single websocket connection, echoing each message until it is closed
+    ///
+    /// Frames are assumed to be short and unfragmented, which is all the tests send
+    fn spawn_echo_server() -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // The client does not check the accept key, so the handshake can skip computing it
+            let mut request = Vec::new();
+            let mut byte = [0; 1];
+            while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                request.push(byte[0]);
+            }
+            stream
+                .write_all(
+                    b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                    Connection: Upgrade\r\nSec-WebSocket-Accept: unchecked\r\n\r\n",
+                )
+                .unwrap();
+
+            loop {
+                let mut header = [0; 2];
+                if stream.read_exact(&mut header).is_err() {
+                    return;
+                }
+                let opcode = header[0] & 0x0f;
+
+                // Client frames are always masked
+                let mut mask = [0; 4];
+                stream.read_exact(&mut mask).unwrap();
+                let mut payload = vec![0; usize::from(header[1] & 0x7f)];
+                stream.read_exact(&mut payload).unwrap();
+                for (i, byte) in payload.iter_mut().enumerate() {
+                    *byte ^= mask[i % 4];
+                }
+
+                let mut frame = vec![0x80 | opcode, u8::try_from(payload.len()).unwrap()];
+                frame.extend(payload);
+                stream.write_all(&frame).unwrap();
+                if opcode == 0x8 {
+                    return;
+                }
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_websocket_stream() {
+        let module = crate::Module::new(
+            "test.js",
+            "
+            export async function echo(url, message) {
+                const stream = new WebSocketStream(url);
+                const { readable, writable } = await stream.opened;
+
+                await writable.getWriter().write(message);
+                const { value } = await readable.getReader().read();
+
+                stream.close({ closeCode: 1000, reason: 'done' });
+                const { closeCode } = await stream.closed;
+                return [value, closeCode];
+            }
+        ",
+        );
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            timeout: Duration::from_secs(10),
+            ..Default::default()
+        })
+        .unwrap();
+        let handle = runtime.load_module(&module).unwrap();
+
+        let url = spawn_echo_server();
+        let (value, code): (String, u16) = runtime
+            .call_function(Some(&handle), "echo", json_args!(url, "ping"))
+            .unwrap();
+        assert_eq!(value, "ping");
+        assert_eq!(code, WebSocketClose::NORMAL);
+    }
+
+    #[test]
+    fn test_default_headers() {
+        use std::io::Read;
+
+        // Captures the handshake of a single connection, then drops it
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut byte = [0; 1];
+            while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                request.push(byte[0]);
+            }
+            tx.send(String::from_utf8_lossy(&request).to_lowercase())
+                .unwrap();
+        });
+
+        let module = crate::Module::new(
+            "test.js",
+            "
+            export function connect(url) {
+                return new Promise((resolve) => {
+                    const socket = new WebSocket(url, 'chat');
+                    socket.onclose = () => resolve();
+                });
+            }
+        ",
+        );
+
+        let mut options = RuntimeOptions {
+            timeout: Duration::from_secs(10),
+            ..Default::default()
+        };
+        options.extension_options.web.user_agent = "my-agent/1.0".to_string();
+        options.extension_options.web.default_headers =
+            vec![("X-Api-Key".to_string(), "secret".to_string())];
+        let mut runtime = Runtime::new(options).unwrap();
+        let handle = runtime.load_module(&module).unwrap();
+        runtime
+            .call_function::<()>(Some(&handle), "connect", json_args!(url))
+            .unwrap();
+
+        let request = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(request.contains("user-agent: my-agent/1.0"), "{request}");
+        assert!(request.contains("x-api-key: secret"), "{request}");
+        assert!(
+            request.contains("sec-websocket-protocol: chat"),
+            "{request}"
+        );
+    }
+
+    #[test]
+    fn test_connection_refused() {
+        // Bind then drop a listener, so the port refuses connections
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+        let socket = WebSocketWrapper::connect(&mut runtime, &url).unwrap();
+
+        let timeout = Some(Duration::from_secs(5));
+        let event = socket.recv_sync(&mut runtime, timeout).unwrap();
+        assert!(matches!(event, Some(WebSocketEvent::Error(_))));
+
+        let event = socket.recv_sync(&mut runtime, timeout).unwrap();
+        let Some(WebSocketEvent::Close(close)) = event else {
+            panic!("Expected a close event, got {event:?}");
+        };
+        assert_eq!(close.code, WebSocketClose::ABNORMAL);
+        assert!(!close.was_clean);
+        assert!(!close.is_normal());
+    }
+}
@@ -17,11 +17,19 @@ use serde::de::DeserializeOwned;
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    ext,
-    module_loader::{LoaderOptions, RustyLoader},
+    ext::{
+        self,
+        rustyscript::{
+            deterministic_install_script, json_limits_install_script, BuiltinModules,
+            ConsoleHandler, EnvVars, ReportedErrors, ScriptArgs, ENV_INSTALL_SCRIPT,
+        },
+    },
+    interrupt_handle::Watchdog,
+    module_loader::{LoaderEvent, LoaderOptions, RustyLoader},
+    string_cache::StringCache,
     traits::{ToDefinedValue, ToModuleSpecifier},
-    transpiler::transpile,
-    utilities, Error, ExtensionOptions, Module, ModuleHandle,
+    transpiler::{static_imports, transpile, transpile_with_diagnostics, Diagnostic},
+    utilities, Error, ExtensionOptions, InterruptHandle, Module, ModuleHandle,
 };
 
 fn safe_prepare_stack_trace_callback<'s>(
@@ -37,6 +45,351 @@ fn safe_prepare_stack_trace_callback<'s>(
     v8::String::new(scope, &message).map_or_else(|| v8::undefined(scope).into(), Into::into)
 }
 
+/// The stack size applied to V8, see `RuntimeOptions::stack_size`
+///
+/// V8 reads its stack limit from a process-wide flag, which cannot be changed once V8 is initialized,
+/// so this is fixed by the first runtime created in the process
+static V8_STACK_SIZE: std::sync::OnceLock<Option<usize>> = std::sync::OnceLock::new();
+
+/// If a heap size is provided, set the isolate params (preserving any user-provided params otherwise)
+fn isolate_params(
+    params: Option<v8::CreateParams>,
+    max_heap_size: Option<usize>,
+) -> Option<v8::CreateParams> {
+    match max_heap_size {
+        Some(max_heap_size) => Some(
+            params
+                .unwrap_or_else(v8::Isolate::create_params)
+                .heap_limits(0, max_heap_size),
+        ),
+        None => params,
+    }
+}
+
+/// Runs `f` in a handle scope entered into the runtime's main context
+pub(crate) fn with_scope<R>(
+    runtime: &mut JsRuntime,
+    f: impl FnOnce(&mut v8::PinScope<'_, '_>) -> R,
+) -> R {
+    let context = runtime.main_context();
+    let isolate = runtime.v8_isolate();
+
+    let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
+    let mut scope = pinned.init();
+    let context_local = v8::Local::new(&scope, context);
+    let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
+    f(&mut context_scope)
+}
+
+/// Replaces the globals overridden by `RuntimeOptions` - the sandboxed `Deno.env`,
+/// the deterministic clock and random number generator, and the limited `JSON.parse`
+///
+/// Code generation from strings is disabled last, once the overrides are in place
+fn install_global_overrides(
+    runtime: &mut JsRuntime,
+    env: Option<HashMap<String, String>>,
+    deterministic_time: Option<u64>,
+    deterministic_random_seed: Option<u64>,
+    json_limits: Option<JsonLimits>,
+    allow_code_generation: bool,
+) -> Result<(), Error> {
+    if let Some(env) = env {
+        runtime.op_state().borrow_mut().put(EnvVars(env));
+        runtime.execute_script("<sandbox_env>", ENV_INSTALL_SCRIPT)?;
+    }
+
+    if let Some(script) =
+        deterministic_install_script(deterministic_time, deterministic_random_seed)
+    {
+        runtime.execute_script("<deterministic>", script)?;
+    }
+
+    if let Some(limits) = json_limits {
+        let script = json_limits_install_script(limits.max_depth, limits.max_length);
+        runtime.execute_script("<json_limits>", script)?;
+    }
+
+    if !allow_code_generation {
+        with_scope(runtime, |scope| {
+            scope
+                .get_current_context()
+                .set_allow_generation_from_strings(false);
+        });
+
+        // Without a callback, v8 would block WebAssembly compilation along with `eval`
+        runtime
+            .v8_isolate()
+            .set_allow_wasm_code_generation_callback(allow_wasm_code_generation);
+    }
+
+    Ok(())
+}
+
+/// Lets WebAssembly compile while code generation from strings is disallowed
+unsafe extern "C" fn allow_wasm_code_generation(
+    _context: v8::Local<v8::Context>,
+    _source: v8::Local<v8::String>,
+) -> bool {
+    true
+}
+
+/// Registers the near-heap-limit callback, which terminates the runtime
+/// unless `on_memory_pressure` raises the limit instead
+fn watch_heap_limit(
+    runtime: &mut JsRuntime,
+    on_memory_pressure: Option<Arc<dyn Fn(HeapStats) -> MemoryPressureAction + Send + Sync>>,
+    heap_exhausted_token: CancellationToken,
+) {
+    let isolate_handle = runtime.v8_isolate().thread_safe_handle();
+    runtime.add_near_heap_limit_callback(move |current_value, initial_value| {
+        let stats = HeapStats {
+            current_heap_limit: current_value,
+            initial_heap_limit: initial_value,
+        };
+        if let Some(MemoryPressureAction::IncreaseLimit(limit)) =
+            on_memory_pressure.as_ref().map(|callback| callback(stats))
+        {
+            if limit > current_value {
+                return limit;
+            }
+        }
+
+        isolate_handle.terminate_execution();
+
+        // Signal the outer runtime to cancel block_on future (avoid hanging) and return friendly error
+        heap_exhausted_token.cancel();
+
+        // Spike the heap limit while terminating to avoid segfaulting
+        // Callback may fire multiple times if memory usage increases quicker then termination finalizes
+        5 * current_value
+    });
+}
+
+/// The delegate for V8's structured serialization
+/// Host objects, shared memory, and wasm modules are not supported
+struct StructuredClone;
+impl v8::ValueSerializerImpl for StructuredClone {
+    fn throw_data_clone_error<'s>(
+        &self,
+        scope: &mut v8::PinScope<'s, '_>,
+        message: v8::Local<'s, v8::String>,
+    ) {
+        let error = v8::Exception::type_error(scope, message);
+        scope.throw_exception(error);
+    }
+}
+impl v8::ValueDeserializerImpl for StructuredClone {}
+
+/// Returns the message of the exception caught during a failed structured (de)serialization
+fn structured_clone_error(scope: &mut v8::PinnedRef<v8::TryCatch<v8::HandleScope>>) -> String {
+    scope.exception().map_or_else(
+        || "unsupported value".to_string(),
+        |exception| exception.to_rust_string_lossy(scope),
+    )
+}
+
+/// Converts function arguments into one value per argument, with `serde_v8`
+/// Arguments are spread if they serialized to an array, and `()` (serialized as `null`) is no arguments
+/// The buffers of any [`crate::js_value::Bytes`] are moved into `Uint8Array`s, rather than copied
+/// Strings equal to one in `strings` reuse it, instead of being created again
+fn encode_args<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: &impl serde::ser::Serialize,
+    sorted_keys: bool,
+    strings: &StringCache,
+) -> Result<Vec<v8::Local<'s, v8::Value>>, Error> {
+    let mut value = if strings.len() == 0 {
+        deno_core::serde_v8::to_v8(scope, args)?
+    } else {
+        deno_core::serde_v8::to_v8(scope, strings.wrap(args))?
+    };
+    if sorted_keys {
+        value = sort_keys(scope, value)
+            .ok_or_else(|| Error::Runtime("Failed to sort argument keys".to_string()))?;
+    }
+
+    if value.is_null() {
+        Ok(vec![])
+    } else if let Ok(array) = v8::Local::<v8::Array>::try_from(value) {
+        (0..array.length())
+            .map(|i| array.get_index(scope, i))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::Runtime("Failed to encode arguments".to_string()))
+    } else {
+        Ok(vec![value])
+    }
+}
+
+/// Rebuilds the plain objects in a value with their keys in sorted order, for `RuntimeOptions::sorted_keys`
+/// Typed arrays and other non-plain objects are left as they are
+fn sort_keys<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    value: v8::Local<'s, v8::Value>,
+) -> Option<v8::Local<'s, v8::Value>> {
+    if let Ok(array) = v8::Local::<v8::Array>::try_from(value) {
+        for i in 0..array.length() {
+            let item = array.get_index(scope, i)?;
+            let item = sort_keys(scope, item)?;
+            array.set_index(scope, i, item)?;
+        }
+        return Some(value);
+    }
+
+    let Ok(object) = v8::Local::<v8::Object>::try_from(value) else {
+        return Some(value);
+    };
+    if value.is_array_buffer_view() || value.is_array_buffer() {
+        return Some(value);
+    }
+
+    let keys = object.get_own_property_names(scope, v8::GetPropertyNamesArgs::default())?;
+    let mut entries = Vec::with_capacity(keys.length() as usize);
+    for i in 0..keys.length() {
+        let key = keys.get_index(scope, i)?;
+        let item = object.get(scope, key)?;
+        entries.push((key.to_rust_string_lossy(scope), key, item));
+    }
+    entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+    let sorted = v8::Object::new(scope);
+    for (_, key, item) in entries {
+        let item = sort_keys(scope, item)?;
+        sorted.set(scope, key, item)?;
+    }
+    Some(sorted.into())
+}
+
+/// Applies `RuntimeOptions::stack_size`
+/// The stack limit can only be set before V8 is initialized by the first runtime
+fn apply_stack_size(stack_size: Option<usize>) -> Result<(), Error> {
+    let applied = *V8_STACK_SIZE.get_or_init(|| {
+        if let Some(stack_size) = stack_size {
+            v8::V8::set_flags_from_string(&format!("--stack-size={}", stack_size / 1024));
+        }
+        stack_size
+    });
+
+    if stack_size.is_none() || stack_size == applied {
+        return Ok(());
+    }
+
+    match applied {
+        None => Err(Error::Runtime(
+            "The stack size can only be set before V8 is initialized, by the first runtime created"
+                .to_string(),
+        )),
+        Some(_) => Err(Error::Runtime(
+            "The stack size is process-wide, and was already set by the first runtime created"
+                .to_string(),
+        )),
+    }
+}
+
+/// Records that V8 is being initialized without a stack size, since its flags cannot be changed afterwards  
+/// Must be called before anything other than a runtime initializes the platform
+pub(crate) fn lock_stack_size() {
+    V8_STACK_SIZE.get_or_init(|| None);
+}
+
+/// Number of promises created but not yet settled, see `InnerRuntime::pending_promise_count`
+/// Stored in an isolate slot, and maintained by `count_pending_promises`
+#[derive(Default)]
+struct PendingPromises(std::cell::Cell<usize>);
+
+/// Native promise hook, tracking promises as they are created and settled
+unsafe extern "C" fn count_pending_promises(
+    hook_type: v8::PromiseHookType,
+    promise: v8::Local<v8::Promise>,
+    _parent: v8::Local<v8::Value>,
+) {
+    v8::callback_scope!(unsafe scope, promise);
+    let Some(pending) = scope.get_slot::<PendingPromises>() else {
+        return;
+    };
+
+    let count = pending.0.get();
+    match hook_type {
+        v8::PromiseHookType::Init => pending.0.set(count + 1),
+        v8::PromiseHookType::Resolve => pending.0.set(count.saturating_sub(1)),
+        _ => {}
+    }
+}
+
+/// Script used by `InnerRuntime::define_global_getter`
+///
+/// Installs an accessor on `globalThis` which calls a registered function on first access,
+/// then replaces itself with the result
+const GLOBAL_GETTER_SCRIPT: &str = "(name, callback) => {
+    const define = (value) => Object.defineProperty(globalThis, name, {
+        value,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+    });
+    Object.defineProperty(globalThis, name, {
+        get() {
+            const value = rustyscript.functions[callback]();
+            define(value);
+            return value;
+        },
+        set: define,
+        enumerable: false,
+        configurable: true,
+    });
+}";
+
+/// Script used by `InnerRuntime::freeze_intrinsics`
+///
+/// Deep-freezes the standard built-ins, their prototypes, and the hidden intrinsics
+/// reachable only through syntax (iterators, generators, async functions, etc.)
+/// then locks the global bindings for them so they cannot be replaced
+const FREEZE_INTRINSICS_SCRIPT: &str = r"(() => {
+    const names = [
+        'Object', 'Function', 'Array', 'String', 'Number', 'Boolean', 'Symbol', 'BigInt',
+        'Error', 'AggregateError', 'EvalError', 'RangeError', 'ReferenceError', 'SyntaxError',
+        'TypeError', 'URIError', 'Promise', 'RegExp', 'Date', 'Map', 'Set', 'WeakMap', 'WeakSet',
+        'WeakRef', 'FinalizationRegistry', 'Proxy', 'Reflect', 'JSON', 'Math', 'Atomics', 'Intl',
+        'ArrayBuffer', 'SharedArrayBuffer', 'DataView', 'Int8Array', 'Uint8Array',
+        'Uint8ClampedArray', 'Int16Array', 'Uint16Array', 'Int32Array', 'Uint32Array',
+        'Float32Array', 'Float64Array', 'BigInt64Array', 'BigUint64Array',
+        'eval', 'isFinite', 'isNaN', 'parseFloat', 'parseInt',
+        'decodeURI', 'decodeURIComponent', 'encodeURI', 'encodeURIComponent',
+    ].filter((name) => name in globalThis);
+
+    const hidden = [
+        Object.getPrototypeOf(Object.getPrototypeOf([][Symbol.iterator]())),
+        Object.getPrototypeOf(function* () {}),
+        Object.getPrototypeOf(async function () {}),
+        Object.getPrototypeOf(async function* () {}),
+        Object.getPrototypeOf(Uint8Array),
+    ];
+
+    const seen = new Set();
+    const freeze = (value) => {
+        if (value === null || (typeof value !== 'object' && typeof value !== 'function')) return;
+        if (seen.has(value)) return;
+        seen.add(value);
+
+        Object.freeze(value);
+        for (const key of Reflect.ownKeys(value)) {
+            const descriptor = Reflect.getOwnPropertyDescriptor(value, key);
+            if ('value' in descriptor) {
+                freeze(descriptor.value);
+            } else {
+                freeze(descriptor.get);
+                freeze(descriptor.set);
+            }
+        }
+        freeze(Reflect.getPrototypeOf(value));
+    };
+
+    for (const name of names) {
+        freeze(globalThis[name]);
+        Object.defineProperty(globalThis, name, { writable: false, configurable: false });
+    }
+    hidden.forEach(freeze);
+})();";
+
 /// Wrapper trait to make the `InnerRuntime` generic over the runtime types
 pub trait RuntimeTrait {
     fn try_new(options: deno_core::RuntimeOptions) -> Result<Self, Error>
@@ -112,47 +465,345 @@ fn decode_args_to_globals(
     );
     let args = runtime.execute_script("<decode_args>", script)?;
 
-    let context = runtime.main_context();
-    let isolate = runtime.v8_isolate();
-    let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-    let mut scope = pinned.init();
-    let context_local = v8::Local::new(&scope, context);
-    let context_scope = v8::ContextScope::new(&mut scope, context_local);
-    let args_local = v8::Local::new(&context_scope, &args);
-
-    // Check if it's an array
-    if let Ok(arr) = v8::Local::<v8::Array>::try_from(args_local) {
-        let len = arr.length();
-        let mut result = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            let index = v8::Integer::new(&context_scope, i as i32);
-            if let Some(arg) = arr.get(&context_scope, index.into()) {
-                let isolate: &v8::Isolate = &context_scope;
-                result.push(v8::Global::new(isolate, arg));
+    with_scope(runtime, |scope| {
+        let args_local = v8::Local::new(scope, &args);
+
+        // Check if it's an array
+        if let Ok(arr) = v8::Local::<v8::Array>::try_from(args_local) {
+            let len = arr.length();
+            let mut result = Vec::with_capacity(len as usize);
+            for i in 0..len {
+                let index = v8::Integer::new(scope, i as i32);
+                if let Some(arg) = arr.get(scope, index.into()) {
+                    let isolate: &v8::Isolate = scope;
+                    result.push(v8::Global::new(isolate, arg));
+                }
             }
+            Ok(result)
+        } else if args_local.is_undefined() || args_local.is_null() {
+            Ok(vec![])
+        } else {
+            Ok(vec![args])
         }
-        Ok(result)
-    } else if args_local.is_undefined() || args_local.is_null() {
-        Ok(vec![])
+    })
+}
+
+/// Extensions of the files in a package that can be imported
+const PACKAGE_MODULE_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "ts", "mts", "cts", "json"];
+
+/// Finds the entry point of a package directory, without the node resolver
+///
+/// Uses the `exports`, `module` or `main` fields of `package.json`, in that order,
+/// falling back to an `index` module if there is no `package.json`, or it names no entry point
+fn package_entrypoint(dir: &Path) -> Result<PathBuf, Error> {
+    let package_json = dir.join("package.json");
+    let entry = if package_json.exists() {
+        let package: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(package_json)?)?;
+        package_export(&package["exports"])
+            .or_else(|| package["module"].as_str())
+            .or_else(|| package["main"].as_str())
+            .map(str::to_string)
     } else {
-        Ok(vec![args])
+        None
+    };
+
+    let candidates = match entry {
+        Some(entry) => vec![
+            entry.clone(),
+            format!("{entry}.js"),
+            format!("{entry}/index.js"),
+        ],
+        None => ["index.js", "index.mjs", "index.ts"]
+            .map(str::to_string)
+            .to_vec(),
+    };
+
+    candidates
+        .into_iter()
+        .map(|candidate| dir.join(candidate))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            Error::ModuleNotFound(format!(
+                "Could not find the entry point of the package at {}",
+                dir.display()
+            ))
+        })
+}
+
+/// Gets the main entry from a package's `exports` field, preferring ESM conditions
+///
+/// Only the root export is understood - subpath patterns and other conditions need the node resolver
+fn package_export(exports: &serde_json::Value) -> Option<&str> {
+    match exports {
+        serde_json::Value::String(entry) => Some(entry),
+        serde_json::Value::Object(map) => match map.get(".") {
+            Some(main) => package_export(main),
+            None => ["import", "default", "require"]
+                .iter()
+                .find_map(|condition| map.get(*condition).and_then(package_export)),
+        },
+        _ => None,
+    }
+}
+
+/// Lists the importable files in a package, excluding its dependencies
+///
+/// Symlinked directories are not followed, so a link back into the package cannot loop
+fn package_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_symlink() && path.is_dir() {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                if path.file_name().is_some_and(|name| name != "node_modules") {
+                    pending.push(path);
+                }
+            } else if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PACKAGE_MODULE_EXTENSIONS.contains(&ext))
+            {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The outcome of a bounded run of the event loop, see [`crate::Runtime::run_until_idle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleResult {
+    /// The event loop ran to completion - there is no pending work left
+    Idle,
+
+    /// The turn or time budget ran out while work was still pending
+    BudgetExceeded,
+}
+
+/// What was still pending when a runtime was shut down, see [`crate::Runtime::terminate_and_drain`]
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// True if the event loop became idle before the deadline
+    pub idle: bool,
+
+    /// True if a script was still running at the deadline, and was terminated
+    pub terminated: bool,
+
+    /// The time spent draining the event loop
+    pub elapsed: Duration,
+
+    /// The number of intervals that were cancelled before draining
+    pub cancelled_intervals: usize,
+
+    /// The number of timers that had not yet fired at the deadline
+    pub pending_timers: usize,
+
+    /// The names of the async ops still in flight at the deadline, such as `op_fetch_send`
+    pub pending_ops: Vec<String>,
+
+    /// The names of the resources that were still open after draining, and were closed
+    pub closed_resources: Vec<String>,
+
+    /// Errors thrown by scripts while draining
+    pub errors: Vec<Error>,
+}
+
+/// The modules fetched ahead of time by [`crate::Runtime::prefetch_graph`]
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchReport {
+    /// The modules that were fetched and transpiled, in the order they were reached
+    pub prefetched: Vec<deno_core::ModuleSpecifier>,
+
+    /// The imports that could not be resolved or fetched, and why
+    /// Unresolved imports are listed as written, and the rest by their resolved URL
+    pub failed: Vec<(String, Error)>,
+}
+
+/// The state of the heap as it nears its limit, see [`RuntimeOptions::on_memory_pressure`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    /// The heap limit that is about to be reached, in bytes
+    pub current_heap_limit: usize,
+
+    /// The heap limit the isolate was created with, in bytes
+    pub initial_heap_limit: usize,
+}
+
+/// How to respond to memory pressure, see [`RuntimeOptions::on_memory_pressure`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryPressureAction {
+    /// Terminate execution, which fails with [`Error::HeapExhausted`]
+    Terminate,
+
+    /// Raise the heap limit to the given number of bytes, and keep running  
+    /// A limit that is not above the current one terminates instead
+    IncreaseLimit(usize),
+}
+
+/// When V8 runs queued microtasks - promise continuations and `queueMicrotask` callbacks,
+/// see [`RuntimeOptions::microtask_policy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MicrotaskPolicy {
+    /// Microtasks run whenever a call into javascript returns, as well as on each tick of the event loop
+    #[default]
+    Auto,
+
+    /// Microtasks only run when flushed with `Runtime::run_microtasks`, or on a tick of the event loop
+    Explicit,
+}
+
+impl From<MicrotaskPolicy> for v8::MicrotasksPolicy {
+    fn from(policy: MicrotaskPolicy) -> Self {
+        match policy {
+            MicrotaskPolicy::Auto => v8::MicrotasksPolicy::Auto,
+            MicrotaskPolicy::Explicit => v8::MicrotasksPolicy::Explicit,
+        }
+    }
+}
+
+/// Limits on the input accepted by `JSON.parse`, see [`RuntimeOptions::json_limits`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLimits {
+    /// The deepest nesting of objects and arrays allowed - defaults to 5,000
+    pub max_depth: usize,
+
+    /// The longest input allowed, in UTF-16 code units - defaults to 128Mi
+    pub max_length: usize,
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 5_000,
+            max_length: 128 * 1024 * 1024,
+        }
+    }
+}
+
+/// The effective configuration of a runtime, from [`crate::Runtime::config_summary`]
+///
+/// A read-only snapshot of the settings the runtime was created with, for diagnosing misconfiguration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RuntimeConfigSummary {
+    /// The timeout for each call into the runtime, see [`RuntimeOptions::timeout`]
+    pub timeout: Duration,
+
+    /// The timeout for evaluating modules, see [`RuntimeOptions::module_eval_timeout`]
+    pub module_eval_timeout: Option<Duration>,
+
+    /// The heap limit, in bytes, see [`RuntimeOptions::max_heap_size`]
+    pub max_heap_size: Option<usize>,
+
+    /// The stack size, in bytes, see [`RuntimeOptions::stack_size`]
+    pub stack_size: Option<usize>,
+
+    /// The entrypoint used when a module does not register one, see [`RuntimeOptions::default_entrypoint`]
+    pub default_entrypoint: Option<String>,
+
+    /// Names of the `deno_core` extensions installed in the runtime, in load order  
+    /// Includes the built-in extensions, followed by those from [`RuntimeOptions::extensions`]
+    pub extensions: Vec<String>,
+
+    /// Built-in APIs removed from the global scope, see [`crate::ExtensionOptions::disabled`]
+    pub disabled: crate::ExtensionSet,
+
+    /// True if the runtime was created from a startup snapshot
+    pub from_snapshot: bool,
+
+    /// True if only the core extensions were loaded, see [`RuntimeOptions::core_only`]
+    pub core_only: bool,
+
+    /// See [`RuntimeOptions::allow_dynamic_import`]
+    pub allow_dynamic_import: bool,
+
+    /// See [`RuntimeOptions::allow_code_generation`]
+    pub allow_code_generation: bool,
+
+    /// See [`RuntimeOptions::allow_http_entrypoint`]
+    pub allow_http_entrypoint: bool,
+
+    /// Debug representation of the permissions used by the web extensions  
+    /// `None` if the `web` feature is not enabled
+    pub permissions: Option<String>,
+}
+
+impl RuntimeConfigSummary {
+    /// Records the settings from `options`, before the extensions are built
+    fn new(options: &RuntimeOptions) -> Self {
+        #[cfg(feature = "web")]
+        let permissions = Some(format!("{:?}", options.extension_options.web.permissions));
+        #[cfg(not(feature = "web"))]
+        let permissions = None;
+
+        Self {
+            timeout: options.timeout,
+            module_eval_timeout: options.module_eval_timeout,
+            max_heap_size: options.max_heap_size,
+            stack_size: options.stack_size,
+            default_entrypoint: options.default_entrypoint.clone(),
+            extensions: Vec::new(),
+            disabled: options.extension_options.disabled.clone(),
+            from_snapshot: options.startup_snapshot.is_some(),
+            core_only: options.core_only,
+            allow_dynamic_import: options.allow_dynamic_import,
+            allow_code_generation: options.allow_code_generation,
+            allow_http_entrypoint: options.allow_http_entrypoint,
+            permissions,
+        }
     }
 }
 
 /// Represents the set of options accepted by the runtime constructor
+#[allow(clippy::struct_excessive_bools)]
 pub struct RuntimeOptions {
     /// A set of `deno_core` extensions to add to the runtime
+    ///
+    /// These are appended after the built-in extensions, so they may depend on them  
+    /// Their ops are callable from javascript as `Deno.core.ops.<name>`, with full `#[op2]` support
+    /// (fast calls, zero-copy buffers, async ops)
     pub extensions: Vec<deno_core::Extension>,
 
     /// Additional options for the built-in extensions
     pub extension_options: ext::ExtensionOptions,
 
+    /// If true, none of the built-in extensions enabled by crate features are loaded;
+    /// only the core `rustyscript` extension, and any in `extensions`
+    ///
+    /// Scripts can still load modules and call functions, but APIs such as `fetch`, `URL`, or the deno console are absent  
+    /// See [`RuntimeOptions::minimal`]
+    pub core_only: bool,
+
     /// Function to use as entrypoint if the module does not provide one
     pub default_entrypoint: Option<String>,
 
     /// Amount of time to run for before killing the thread
     pub timeout: Duration,
 
+    /// Optional time limit for evaluating a module's top-level code, separate from `timeout`
+    ///
+    /// Applies only while loading modules - such as with [`crate::Runtime::load_module`] or
+    /// [`crate::Runtime::evaluate_module`] - and not while
+    /// calling their exports later, so a module doing expensive work at import time fails fast
+    /// with [`Error::Timeout`], even if its functions are allowed to run much longer  
+    /// Synchronous top-level code, such as a busy loop, is terminated as well
+    pub module_eval_timeout: Option<Duration>,
+
+    /// Optional handle to an existing tokio runtime, used to drive the event loop
+    ///
+    /// By default, each runtime creates its own current-thread tokio runtime;
+    /// with a handle, the runtime is borrowed instead: no threads are spawned, and timers and IO run on the provided executor  
+    /// This is equivalent to [`crate::Runtime::with_tokio_runtime_handle`]
+    pub tokio_handle: Option<tokio::runtime::Handle>,
+
     /// Optional maximum heap size for the runtime
     ///
     /// If the heap size is exceeded, the runtime will return a `HeapExhausted` error.
@@ -161,6 +812,20 @@ pub struct RuntimeOptions {
     /// (~5mb with default features)
     pub max_heap_size: Option<usize>,
 
+    /// Optional stack size, in bytes, that V8 may use before throwing a `RangeError` for stack overflow
+    ///
+    /// V8 defaults to just under 1MiB, which limits recursion to roughly 10,000 frames
+    ///
+    /// **WARNING** this only raises the limit V8 enforces; the thread running the runtime must actually have
+    /// that much stack available, or deep recursion will crash the process instead of throwing.  
+    /// Spawned threads default to 2MiB - use `std::thread::Builder::stack_size` to run the runtime on a larger one,
+    /// and leave some headroom above this value for native frames
+    ///
+    /// This is a process-wide V8 setting, and is fixed by the first runtime created in the process.  
+    /// Creating a later runtime with a different value will fail, as will setting it once V8 has been initialized
+    /// without it - for example by [`crate::init_platform`], or by a worker pool
+    pub stack_size: Option<usize>,
+
     /// Optional cache provider for the module loader
     #[allow(deprecated)]
     pub module_cache: Option<Box<dyn crate::module_loader::ModuleCacheProvider>>,
@@ -168,6 +833,24 @@ pub struct RuntimeOptions {
     /// Optional import provider for the module loader
     pub import_provider: Option<Box<dyn crate::module_loader::ImportProvider>>,
 
+    /// Optional provider to load source maps on demand, when an error needs them
+    ///
+    /// By default the source map of every transpiled module is kept in memory;
+    /// with a provider set those maps are discarded instead, which saves memory for large applications
+    pub source_map_provider: Option<Box<dyn crate::module_loader::SourceMapProvider>>,
+
+    /// Optional `tsconfig.json` path mapping for the module loader
+    ///
+    /// When set, bare specifiers matching the tsconfig's `paths` (or found under its `baseUrl`)
+    /// resolve to the same files `tsc` would resolve them to  
+    /// See [`crate::module_loader::TsConfigPaths`]
+    pub tsconfig_paths: Option<crate::module_loader::TsConfigPaths>,
+
+    /// What to do when a module is loaded again, with a specifier that was already loaded
+    ///
+    /// Defaults to [`crate::module_loader::ReloadPolicy::AlwaysReload`], evaluating the module again
+    pub reload_policy: crate::module_loader::ReloadPolicy,
+
     /// Optional snapshot to load into the runtime
     ///
     /// This will reduce load times, but requires the same extensions to be loaded as when the snapshot was created  
@@ -191,6 +874,182 @@ pub struct RuntimeOptions {
     ///
     /// By default only `http`/`https` (`url_import` crate feature), and `file` (`fs_import` crate feature) are allowed
     pub schema_whlist: HashSet<String>,
+
+    /// If true, object keys are sorted when serializing rust values into JS,
+    /// and when producing JSON with [`crate::Runtime::to_json_string`]
+    ///
+    /// Useful to get stable output for golden-file tests, where map ordering would otherwise vary
+    pub sorted_keys: bool,
+
+    /// If true, messages printed with `console.error`, and uncaught javascript errors returned by the runtime,
+    /// are recorded so they can be checked after a run
+    ///
+    /// See [`crate::Runtime::take_reported_errors`] and [`crate::Runtime::check_reported_errors`]  
+    /// Useful when using rustyscript as a test runner, so that error output fails the run instead of being missed
+    ///
+    /// Console output is only recorded with the `console` feature enabled
+    pub fail_on_console_error: bool,
+
+    /// If true, [`crate::Runtime::load_module_from_url`] will accept an insecure `http://` entry point
+    ///
+    /// By default only `https://` entry points are allowed
+    pub allow_http_entrypoint: bool,
+
+    /// If false, scripts cannot use dynamic `import()`, which rejects with a `PermissionDenied` error
+    ///
+    /// Static imports are unaffected, so modules can still import their dependencies -
+    /// but code can no longer be loaded from specifiers computed at runtime, such as from untrusted input  
+    /// Defaults to true
+    pub allow_dynamic_import: bool,
+
+    /// If false, scripts cannot generate code from strings, matching a strict content security policy
+    ///
+    /// `eval(...)` and `new Function(...)` throw an `EvalError`  
+    /// Code loaded from rust, such as by `Runtime::eval` or as a module, and WebAssembly compilation, are unaffected  
+    /// Defaults to true
+    pub allow_code_generation: bool,
+
+    /// Optional hook to rewrite errors before they are returned to the caller
+    ///
+    /// Can be used to redact paths, or map internal specifiers to friendly names
+    ///
+    /// Applied to the errors returned by calls that run javascript or load modules:
+    /// - `eval`, `call_function`, `call_method`, `call_constructor` and their variants
+    /// - `load_module`, `load_modules`, `instantiate_module`, `evaluate_module` and the other module loads
+    /// - Runs of the event loop, such as `block_on_event_loop` and `run_until_idle`, and `run_microtasks`
+    ///
+    /// Not applied to errors raised outside of javascript: creating the runtime, reading a value with `get_value`,
+    /// converting arguments or return values to and from rust, or a worker's thread having stopped
+    ///
+    /// Note that javascript errors are [`Error::JsError`], whose fields (message, frames, etc.) can be rewritten directly
+    pub error_transform: Option<Arc<dyn Fn(Error) -> Error + Send + Sync>>,
+
+    /// Optional hook to control how module specifiers are displayed, such as `file:///long/abs/path/src/foo.ts` as `src/foo.ts`
+    ///
+    /// Applied to:
+    /// - The file names of the stack frames of javascript errors
+    /// - Specifiers of loaded modules in the messages and stacks of javascript errors - other URLs a script
+    ///   writes into a message are left as they are
+    /// - Every specifier in the messages of the crate's own errors, such as [`crate::Error::ModuleNotFound`]
+    /// - The failures of [`PrefetchReport`], and the origin of console [`crate::LogRecord`]s
+    ///
+    /// Errors are rewritten where they leave the runtime's calls - evaluating code, calling functions,
+    /// loading modules and running the event loop - but not errors from creating the runtime  
+    /// Runs before `error_transform`, which sees the rewritten error
+    pub specifier_display: Option<crate::module_loader::SpecifierDisplay>,
+
+    /// Optional handler to receive console output as structured records, instead of it being printed
+    ///
+    /// Each call to a console method is delivered as it happens, with its level, a timestamp,
+    /// and the file and line the call was made from, when it came from a script  
+    /// Error-level messages are still recorded for `fail_on_console_error`
+    ///
+    /// Requires the `console` feature
+    pub console_handler: Option<Arc<dyn Fn(crate::LogRecord) + Send + Sync>>,
+
+    /// Optional sandboxed environment for scripts
+    ///
+    /// If set, `Deno.env` reads, writes, and deletes variables in this map instead of the real process environment,
+    /// and is defined even if no extension provides it  
+    /// With the `node_experimental` feature, `process.env` is backed by the same map  
+    /// Each access is checked with `WebPermissions::check_env`, and a denied key throws a `NotCapable` error, as in deno
+    pub env: Option<HashMap<String, String>>,
+
+    /// Functions that rewrite the code of every module after it is transpiled, applied in order
+    ///
+    /// Each receives the module's specifier and the output of the previous preprocessor, and can be used to
+    /// inject instrumentation, prepend a banner, or rewrite globals, without a custom `ImportProvider`  
+    /// Applies to javascript and typescript modules, whether loaded from rust or imported; JSON modules are left as they are  
+    /// An error from a preprocessor fails the load of the module
+    ///
+    /// Note that adding lines shifts the line numbers reported in errors for that module
+    pub module_preprocessors: Vec<crate::module_loader::ModulePreprocessor>,
+
+    /// Parsers for custom module types, such as `toml` or `yaml`, keyed by type name
+    ///
+    /// A module imported with a matching import attribute (`with { type: "toml" }`), or failing that
+    /// with a matching file extension (`./config.toml`), is parsed by the handler instead of being transpiled,
+    /// and its default export is the resulting object  
+    /// Errors from the handler fail the import, so they should describe the position of the problem
+    pub module_type_handlers: HashMap<String, crate::module_loader::ModuleTypeHandler>,
+
+    /// Handlers for modules imported with a custom URL scheme, such as `s3://`, keyed by scheme (`s3`)
+    ///
+    /// Each handler resolves and fetches the modules of its scheme, as a lighter alternative to an `ImportProvider`  
+    /// The import provider, if any, is consulted first; schemes without a handler fall through to the default handling
+    pub scheme_handlers: HashMap<String, Arc<dyn crate::module_loader::SchemeHandler>>,
+
+    /// Optional callback invoked when the heap nears its limit, before the runtime is terminated
+    ///
+    /// V8 calls it as usage approaches the current limit - `max_heap_size`, or V8's default if unset -
+    /// and it decides whether to raise the limit, or to terminate with [`Error::HeapExhausted`]  
+    /// To get a warning at a soft threshold, set `max_heap_size` to the threshold and return
+    /// [`MemoryPressureAction::IncreaseLimit`] with the real budget, then recycle the runtime;
+    /// the callback is called again if the raised limit is approached as well
+    ///
+    /// The callback runs during garbage collection, so it must not call back into the runtime
+    pub on_memory_pressure: Option<Arc<dyn Fn(HeapStats) -> MemoryPressureAction + Send + Sync>>,
+
+    /// Optional fixed time for scripts, in milliseconds since the unix epoch
+    ///
+    /// If set, `Date.now()` and `new Date()` always return this time, and `performance.now()` always returns 0,
+    /// so that scripts produce the same output on every run  
+    /// Intended for reproducible tests; leave unset in production to use the real clock
+    pub deterministic_time: Option<u64>,
+
+    /// Optional seed for `Math.random`
+    ///
+    /// If set, `Math.random()` is replaced by a seeded pseudo-random generator, so two runtimes with the same seed
+    /// produce the same sequence of numbers  
+    /// The generator is not cryptographically secure, and does not affect `crypto.getRandomValues` - see `ExtensionOptions::crypto_seed`
+    pub deterministic_random_seed: Option<u64>,
+
+    /// Optional observer of the module loader's progress, such as for a loading indicator
+    ///
+    /// Called as each module is resolved, fetched, and transpiled, or when it is found in the cache provider instead  
+    /// Purely observational - the sink cannot affect how modules are loaded, and runs on the runtime's thread,
+    /// so it should return quickly
+    pub loader_event_sink: Option<crate::module_loader::LoaderEventSink>,
+
+    /// Optional callback notified once each module has loaded, for instrumentation or auditing
+    ///
+    /// For a module loaded from rust, called once its top-level code has run - with `Ok(())` for it and each of its
+    /// static imports, or with the error it failed with  
+    /// Modules loaded with a dynamic `import()` are reported once fetched, since their evaluation settles the `import()` instead  
+    /// Any module that cannot be fetched or transpiled is reported with the error  
+    /// Runs on the runtime's thread, so it should return quickly
+    pub on_module_loaded: Option<crate::module_loader::ModuleLoadedCallback>,
+
+    /// If true, keep the non-fatal diagnostics the transpiler reports for modules that still load,
+    /// until they are taken with [`crate::Runtime::take_diagnostics`]
+    ///
+    /// Does not change whether a module loads - syntax errors that stop a module from loading are still returned as errors  
+    /// Defaults to false
+    pub collect_diagnostics: bool,
+
+    /// When queued microtasks are run - defaults to [`MicrotaskPolicy::Auto`]
+    ///
+    /// With [`MicrotaskPolicy::Explicit`], a resolved promise's `then` callbacks do not run when the call that
+    /// resolved it returns, but only once flushed with `Runtime::run_microtasks`, for testing promise ordering precisely  
+    /// Calls that run the event loop, such as `Runtime::call_function`, still flush the queue on each tick;
+    /// use the `_immediate` variants to avoid this
+    pub microtask_policy: MicrotaskPolicy,
+
+    /// If true, count promises as they are created and settled, for [`crate::Runtime::pending_promise_count`]
+    ///
+    /// Installs a promise hook that runs for every promise the runtime creates, so it is off unless needed  
+    /// Defaults to false
+    pub track_pending_promises: bool,
+
+    /// Limits on the input accepted by `JSON.parse`, for sandboxes parsing untrusted payloads
+    ///
+    /// Input over a limit throws a catchable `RangeError` before it is parsed, instead of exhausting the stack or heap  
+    /// Also applies to the `json()` methods of `Request` and `Response`, when the `web` feature is enabled
+    ///
+    /// Arguments passed from rust to javascript are parsed internally, and are not subject to the limits
+    ///
+    /// Defaults to `None`, for no limits - [`JsonLimits::default`] gives limits no reasonable payload reaches
+    pub json_limits: Option<JsonLimits>,
 }
 
 impl Default for RuntimeOptions {
@@ -199,19 +1058,154 @@ impl Default for RuntimeOptions {
             extensions: Vec::default(),
             default_entrypoint: None,
             timeout: Duration::MAX,
+            module_eval_timeout: None,
+            tokio_handle: None,
             max_heap_size: None,
+            stack_size: None,
             module_cache: None,
             import_provider: None,
+            tsconfig_paths: None,
+            source_map_provider: None,
+            reload_policy: crate::module_loader::ReloadPolicy::default(),
             startup_snapshot: None,
             isolate_params: None,
             shared_array_buffer_store: None,
             schema_whlist: HashSet::default(),
+            sorted_keys: false,
+            core_only: false,
+            fail_on_console_error: false,
+            allow_http_entrypoint: false,
+            allow_dynamic_import: true,
+            allow_code_generation: true,
+            error_transform: None,
+            specifier_display: None,
+            console_handler: None,
+            env: None,
+            module_preprocessors: Vec::new(),
+            module_type_handlers: HashMap::new(),
+            scheme_handlers: HashMap::new(),
+            on_memory_pressure: None,
+            deterministic_time: None,
+            deterministic_random_seed: None,
+            loader_event_sink: None,
+            on_module_loaded: None,
+            collect_diagnostics: false,
+            microtask_policy: MicrotaskPolicy::default(),
+            track_pending_promises: false,
+            json_limits: None,
 
             extension_options: ExtensionOptions::default(),
         }
     }
 }
 
+impl RuntimeOptions {
+    /// Options for a minimal runtime, with only the core `rustyscript` extension  
+    /// Regardless of crate features, no web, node, or other built-in APIs are loaded - only pure javascript,
+    /// module loading, and function calls are available
+    ///
+    /// This reduces startup time and memory use; to also reduce binary size, disable the crate's default features
+    ///
+    /// Equivalent to `RuntimeOptions { core_only: true, ..Default::default() }`
+    ///
+    /// ```no_run
+    /// use rustyscript::{ Runtime, RuntimeOptions };
+    ///
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions::minimal())?;
+    /// let value: i64 = runtime.eval("[1, 2, 3].reduce((a, b) => a + b)")?;
+    /// assert_eq!(value, 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn minimal() -> Self {
+        Self {
+            core_only: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Installs the isolate-level hooks the runtime depends on
+fn configure_isolate(
+    isolate: &mut v8::OwnedIsolate,
+    microtask_policy: MicrotaskPolicy,
+    track_pending_promises: bool,
+) {
+    isolate.set_prepare_stack_trace_callback(safe_prepare_stack_trace_callback);
+    if track_pending_promises {
+        isolate.set_slot(PendingPromises::default());
+        isolate.set_promise_hook(count_pending_promises);
+    }
+    isolate.set_microtasks_policy(microtask_policy.into());
+}
+
+/// Stores the state the runtime's own ops depend on
+fn configure_op_state(
+    runtime: &mut JsRuntime,
+    fail_on_console_error: bool,
+    console_handler: Option<ConsoleHandler>,
+) {
+    let op_state = runtime.op_state();
+    let mut op_state = op_state.borrow_mut();
+    if fail_on_console_error {
+        op_state.put(ReportedErrors::default());
+    }
+    if let Some(handler) = console_handler {
+        op_state.put(handler);
+    }
+
+    let mut feature_checker = FeatureChecker::default();
+    feature_checker.set_exit_cb(Box::new(|_, _| {}));
+    op_state.put(Arc::new(feature_checker));
+}
+
+/// Returns the own string keys of `globalThis`, enumerable or not, in the order they were defined
+fn global_keys(scope: &mut v8::PinScope<'_, '_>) -> Vec<String> {
+    let global = scope.get_current_context().global(scope);
+    crate::js_value::own_properties(scope, global, v8::PropertyFilter::ALL_PROPERTIES, false)
+        .map(|properties| properties.into_iter().map(|(key, _)| key).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the value, getter and setter of each own string-keyed property of `globalThis`
+///
+/// Reading the descriptors does not run getters, and tells a replaced builtin apart from the original
+fn global_slots<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+) -> Vec<(String, [v8::Local<'s, v8::Value>; 3])> {
+    let global = scope.get_current_context().global(scope);
+    let mut slots = Vec::new();
+    for key in global_keys(scope) {
+        let slot = v8::String::new(scope, &key)
+            .and_then(|name| global.get_own_property_descriptor(scope, name.into()))
+            .and_then(|descriptor| v8::Local::<v8::Object>::try_from(descriptor).ok())
+            .and_then(|descriptor| {
+                let mut slot = [v8::undefined(scope).into(); 3];
+                for (field, name) in slot.iter_mut().zip(["value", "get", "set"]) {
+                    let name = v8::String::new(scope, name)?;
+                    *field = descriptor.get(scope, name.into())?;
+                }
+                Some(slot)
+            });
+        if let Some(slot) = slot {
+            slots.push((key, slot));
+        }
+    }
+    slots
+}
+
+/// Records the properties of `globalThis`, to tell the globals defined later apart
+fn builtin_globals(runtime: &mut JsRuntime) -> HashMap<String, [v8::Global<v8::Value>; 3]> {
+    with_scope(runtime, |scope| {
+        global_slots(scope)
+            .into_iter()
+            .map(|(key, slot)| (key, slot.map(|value| v8::Global::new(scope, value))))
+            .collect()
+    })
+}
+
 /// Deno `JsRuntime` wrapper providing helper functions needed
 /// by the public-facing Runtime API
 ///
@@ -224,17 +1218,57 @@ pub struct InnerRuntime<RT: RuntimeTrait> {
 
     pub cwd: PathBuf,
     pub default_entrypoint: Option<String>,
+    pub sorted_keys: bool,
+    pub allow_http_entrypoint: bool,
+    pub error_transform: Option<Arc<dyn Fn(Error) -> Error + Send + Sync>>,
+    pub specifier_display: Option<crate::module_loader::SpecifierDisplay>,
+    pub interrupt: InterruptHandle,
+    pub module_eval_timeout: Option<Duration>,
+
+    /// Modules instantiated by `InnerRuntime::instantiate_module` that have not yet been evaluated
+    pub unevaluated_modules: HashSet<deno_core::ModuleId>,
+
+    /// Stack of the most recent javascript error, see `InnerRuntime::take_error_stack`
+    pub last_error_stack: Option<String>,
+
+    /// Properties of `globalThis` once the runtime was ready, see `InnerRuntime::user_global_keys`
+    pub builtin_globals: HashMap<String, [v8::Global<v8::Value>; 3]>,
+
+    pub microtask_policy: MicrotaskPolicy,
+
+    /// The configuration the runtime was created with, see `InnerRuntime::config_summary`
+    pub config: RuntimeConfigSummary,
+
+    /// Helper functions compiled from scripts, see `InnerRuntime::with_helper_function`
+    pub helper_functions: HashMap<&'static str, v8::Global<v8::Function>>,
+
+    /// Set once `InnerRuntime::terminate_and_drain` starts, after which no new calls are accepted
+    pub drained: bool,
+
+    /// Strings reused when passing arguments, see `InnerRuntime::intern_string`
+    pub string_cache: StringCache,
 }
 impl<RT: RuntimeTrait> InnerRuntime<RT> {
     pub fn new(
         options: RuntimeOptions,
         heap_exhausted_token: CancellationToken,
     ) -> Result<Self, Error> {
+        let mut config = RuntimeConfigSummary::new(&options);
         let cwd = std::env::current_dir()?;
         let module_loader = Rc::new(RustyLoader::new(LoaderOptions {
             cache_provider: options.module_cache,
             import_provider: options.import_provider,
+            source_map_provider: options.source_map_provider,
             schema_whlist: options.schema_whlist,
+            reload: options.reload_policy,
+            tsconfig_paths: options.tsconfig_paths,
+            deny_dynamic_import: !options.allow_dynamic_import,
+            preprocessors: options.module_preprocessors,
+            module_type_handlers: options.module_type_handlers,
+            scheme_handlers: options.scheme_handlers,
+            event_sink: options.loader_event_sink,
+            on_module_loaded: options.on_module_loaded.clone(),
+            collect_diagnostics: options.collect_diagnostics,
             cwd: cwd.clone(),
 
             #[cfg(feature = "node_experimental")]
@@ -243,38 +1277,28 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
             ..Default::default()
         }));
 
+        // Built-in APIs to strip from the global scope once the runtime is ready
+        let disabled_extensions = options.extension_options.disabled.removal_script();
+
         // If a snapshot is provided, do not reload ESM for extensions
         let is_snapshot = options.startup_snapshot.is_some();
         let extensions = ext::all_extensions(
             options.extensions,
             options.extension_options,
             options.shared_array_buffer_store.clone(),
+            options.core_only,
             is_snapshot,
-        );
+        )?;
+        config.extensions = extensions.iter().map(|e| e.name.to_string()).collect();
 
-        // If a heap size is provided, set the isolate params (preserving any user-provided params otherwise)
-        let isolate_params = match options.isolate_params {
-            Some(params) => {
-                if let Some(max_heap_size) = options.max_heap_size {
-                    Some(params.heap_limits(0, max_heap_size))
-                } else {
-                    Some(params)
-                }
-            }
-            None => {
-                if let Some(max_heap_size) = options.max_heap_size {
-                    let params = v8::Isolate::create_params().heap_limits(0, max_heap_size);
-                    Some(params)
-                } else {
-                    None
-                }
-            }
-        };
+        let isolate_params = isolate_params(options.isolate_params, options.max_heap_size);
+        apply_stack_size(options.stack_size)?;
 
         let mut deno_runtime = RT::try_new(deno_core::RuntimeOptions {
             module_loader: Some(module_loader.clone()),
 
             extension_transpiler: Some(module_loader.as_extension_transpiler()),
+            custom_module_evaluation_cb: Some(Box::new(module_loader.custom_module_evaluator())),
             create_params: isolate_params,
             shared_array_buffer_store: options.shared_array_buffer_store.clone(),
 
@@ -284,46 +1308,76 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
             ..Default::default()
         })?;
 
-        deno_runtime
-            .rt_mut()
-            .v8_isolate()
-            .set_prepare_stack_trace_callback(safe_prepare_stack_trace_callback);
-
-        let mut feature_checker = FeatureChecker::default();
-        feature_checker.set_exit_cb(Box::new(|_, _| {}));
-        deno_runtime
-            .rt_mut()
-            .op_state()
-            .borrow_mut()
-            .put(Arc::new(feature_checker));
-
-        // Add a callback to terminate the runtime if the max_heap_size limit is approached
-        if options.max_heap_size.is_some() {
-            let isolate_handle = deno_runtime.rt_mut().v8_isolate().thread_safe_handle();
+        configure_isolate(
+            deno_runtime.rt_mut().v8_isolate(),
+            options.microtask_policy,
+            options.track_pending_promises,
+        );
 
+        if !disabled_extensions.is_empty() {
             deno_runtime
                 .rt_mut()
-                .add_near_heap_limit_callback(move |current_value, _| {
-                    isolate_handle.terminate_execution();
+                .execute_script("<disable_extensions>", disabled_extensions)?;
+        }
 
-                    // Signal the outer runtime to cancel block_on future (avoid hanging) and return friendly error
-                    heap_exhausted_token.cancel();
+        let interrupt =
+            InterruptHandle::new(deno_runtime.rt_mut().v8_isolate().thread_safe_handle());
 
-                    // Spike the heap limit while terminating to avoid segfaulting
-                    // Callback may fire multiple times if memory usage increases quicker then termination finalizes
-                    5 * current_value
-                });
+        let console_handler = options
+            .console_handler
+            .map(|handler| ConsoleHandler::new(handler, options.specifier_display.clone()));
+        configure_op_state(
+            deno_runtime.rt_mut(),
+            options.fail_on_console_error,
+            console_handler,
+        );
+
+        install_global_overrides(
+            deno_runtime.rt_mut(),
+            options.env,
+            options.deterministic_time,
+            options.deterministic_random_seed,
+            options.json_limits,
+            options.allow_code_generation,
+        )?;
+
+        // Add a callback to terminate the runtime if the heap limit is approached
+        if options.max_heap_size.is_some() || options.on_memory_pressure.is_some() {
+            watch_heap_limit(
+                deno_runtime.rt_mut(),
+                options.on_memory_pressure,
+                heap_exhausted_token,
+            );
         }
 
-        let default_entrypoint = options.default_entrypoint;
+        let builtin_globals = builtin_globals(deno_runtime.rt_mut());
         Ok(Self {
             module_loader,
             deno_runtime,
             cwd,
-            default_entrypoint,
+            default_entrypoint: options.default_entrypoint,
+            sorted_keys: options.sorted_keys,
+            allow_http_entrypoint: options.allow_http_entrypoint,
+            error_transform: options.error_transform,
+            specifier_display: options.specifier_display,
+            interrupt,
+            module_eval_timeout: options.module_eval_timeout,
+            unevaluated_modules: HashSet::new(),
+            last_error_stack: None,
+            builtin_globals,
+            microtask_policy: options.microtask_policy,
+            config,
+            helper_functions: HashMap::new(),
+            drained: false,
+            string_cache: StringCache::default(),
         })
     }
 
+    /// Returns the configuration the runtime was created with
+    pub fn config_summary(&self) -> &RuntimeConfigSummary {
+        &self.config
+    }
+
     /// Destroy the `RustyScript` runtime, returning the deno RT instance
     #[allow(dead_code)]
     pub fn into_inner(self) -> RT {
@@ -335,6 +1389,64 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         self.deno_runtime.rt_mut()
     }
 
+    /// Sets the arguments scripts see as `Deno.args`, and after the first two entries of `process.argv`
+    #[cfg_attr(not(feature = "node_experimental"), allow(clippy::unnecessary_wraps))]
+    pub fn set_args(&mut self, args: Vec<String>) -> Result<(), Error> {
+        {
+            let op_state = self.deno_runtime().op_state();
+            let mut op_state = op_state.borrow_mut();
+            let version = op_state
+                .try_borrow::<ScriptArgs>()
+                .map_or(0, |args| args.version);
+            op_state.put(ScriptArgs {
+                args,
+                version: version.wrapping_add(1).max(1),
+            });
+        }
+
+        #[cfg(feature = "node_experimental")]
+        self.deno_runtime().execute_script(
+            "<script_args>",
+            "(() => {
+                const argv = globalThis.process?.argv;
+                if (Array.isArray(argv)) argv.splice(2, argv.length - 2, ...Deno.args);
+            })()",
+        )?;
+
+        Ok(())
+    }
+
+    /// Runs `f` in a handle scope entered into the main context
+    pub fn with_scope<R>(&mut self, f: impl FnOnce(&mut v8::PinScope<'_, '_>) -> R) -> R {
+        with_scope(self.deno_runtime(), f)
+    }
+
+    /// Runs `f` in a handle scope entered into the main context, with the function `source` evaluates to
+    ///
+    /// The function is compiled on first use, then cached for later calls
+    pub fn with_helper_function<R>(
+        &mut self,
+        source: &'static str,
+        f: impl FnOnce(&mut v8::PinScope<'_, '_>, v8::Local<'_, v8::Function>) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            if let Some(function) = self.helper_functions.get(source) {
+                let function = v8::Local::new(scope, function);
+                return f(scope, function);
+            }
+
+            let code = v8::String::new(scope, source)
+                .ok_or_else(|| Error::V8Encoding(source.to_string()))?;
+            let function = v8::Script::compile(scope, code, None)
+                .and_then(|script| script.run(scope))
+                .and_then(|function| v8::Local::<v8::Function>::try_from(function).ok())
+                .ok_or_else(|| Error::Runtime("Could not compile a helper function".to_string()))?;
+            self.helper_functions
+                .insert(source, v8::Global::new(scope, function));
+            f(scope, function)
+        })
+    }
+
     /// Set the current working directory for the runtime
     /// This is used to resolve relative paths in the module loader
     pub fn set_current_dir(&mut self, path: impl AsRef<Path>) -> Result<&Path, Error> {
@@ -348,10 +1460,88 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         Ok(&self.cwd)
     }
 
+    /// Returns the transpiler diagnostics collected since the last call, see [`RuntimeOptions::collect_diagnostics`]
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.module_loader.take_diagnostics()
+    }
+
     pub fn current_dir(&self) -> &Path {
         &self.cwd
     }
 
+    /// Formats a specifier with `RuntimeOptions::specifier_display`, if set
+    fn display_specifier(&self, specifier: &deno_core::ModuleSpecifier) -> String {
+        match &self.specifier_display {
+            Some(display) => display(specifier),
+            None => specifier.to_string(),
+        }
+    }
+
+    /// Rewrites the specifiers in an error with `RuntimeOptions::specifier_display`, if set
+    fn display_error(&self, error: Error) -> Error {
+        match &self.specifier_display {
+            Some(display) => error.display_specifiers(display.as_ref(), &|specifier| {
+                self.module_loader.has_module_source(specifier)
+            }),
+            None => error,
+        }
+    }
+
+    /// Handles interrupts, and applies `RuntimeOptions::error_transform` to an error result,
+    /// then records it for `take_error_stack` and `take_reported_errors`
+    fn track_error<T>(&mut self, result: Result<T, Error>) -> Result<T, Error> {
+        // Execution was stopped by an `InterruptHandle` - allow the runtime to resume
+        let result = match result {
+            Err(_) if self.interrupt.clear() => Err(Error::Interrupted),
+            result => result,
+        };
+
+        let result = result.map_err(|e| self.display_error(e));
+        let result = match &self.error_transform {
+            Some(transform) => result.map_err(|e| transform(e)),
+            None => result,
+        };
+
+        if let Err(e) = &result {
+            if let Some(stack) = e.stack() {
+                self.last_error_stack = Some(stack);
+            }
+
+            if let Error::JsError(e) = e {
+                let state = self.deno_runtime().op_state();
+                let mut state = state.borrow_mut();
+                if let Some(errors) = state.try_borrow_mut::<ReportedErrors>() {
+                    errors.0.push(e.exception_message.clone());
+                }
+            }
+        }
+        result
+    }
+
+    /// Remove and return the error-level output and uncaught errors recorded so far
+    pub fn take_reported_errors(&mut self) -> Vec<String> {
+        let state = self.deno_runtime().op_state();
+        let mut state = state.borrow_mut();
+        state
+            .try_borrow_mut::<ReportedErrors>()
+            .map(|errors| std::mem::take(&mut errors.0))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of promises created but not yet resolved or rejected,
+    /// or 0 unless `RuntimeOptions::track_pending_promises` is set
+    pub fn pending_promise_count(&mut self) -> usize {
+        let isolate = self.deno_runtime().v8_isolate();
+        isolate
+            .get_slot::<PendingPromises>()
+            .map_or(0, |pending| pending.0.get())
+    }
+
+    /// Remove and return the stack of the most recent javascript error
+    pub fn take_error_stack(&mut self) -> Option<String> {
+        self.last_error_stack.take()
+    }
+
     /// Remove and return a value from the state
     pub fn take<T>(&mut self) -> Option<T>
     where
@@ -430,13 +1620,33 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         options: PollEventLoopOptions,
         timeout: Option<Duration>,
     ) -> Result<(), Error> {
-        if let Some(timeout) = timeout {
-            Ok(tokio::select! {
+        self.enter_js()?;
+
+        let result = if let Some(timeout) = timeout {
+            tokio::select! {
                 r = self.deno_runtime().run_event_loop(options) => r,
                 () = tokio::time::sleep(timeout) => Ok(()),
-            }?)
+            }
         } else {
-            Ok(self.deno_runtime().run_event_loop(options).await?)
+            self.deno_runtime().run_event_loop(options).await
+        };
+        self.track_error(result.map_err(Into::into))
+    }
+
+    /// Polls the JS event loop once, using the given context
+    /// Ready once the event loop has no pending work, or on error
+    pub fn poll_event_loop(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        options: PollEventLoopOptions,
+    ) -> Poll<Result<(), Error>> {
+        if let Err(e) = self.enter_js() {
+            return Poll::Ready(Err(e));
+        }
+
+        match self.deno_runtime().poll_event_loop(cx, options) {
+            Poll::Ready(result) => Poll::Ready(self.track_error(result.map_err(Into::into))),
+            Poll::Pending => Poll::Pending,
         }
     }
 
@@ -446,33 +1656,416 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         &mut self,
         options: PollEventLoopOptions,
     ) -> Result<bool, Error> {
+        self.enter_js()?;
+
         let result = std::future::poll_fn(|cx| {
             Poll::Ready(match self.deno_runtime().poll_event_loop(cx, options) {
                 Poll::Ready(t) => t.map(|()| false),
                 Poll::Pending => Ok(true),
             })
         })
-        .await?;
+        .await;
+
+        self.track_error(result.map_err(Into::into))
+    }
+
+    /// Runs the JS event loop until it has no pending work, or until the timeout elapses
+    /// Returns true if the event loop became idle, or false if work was still pending at the timeout
+    pub async fn wait_until_idle(
+        &mut self,
+        options: PollEventLoopOptions,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        self.enter_js()?;
+
+        match tokio::time::timeout(timeout, self.deno_runtime().run_event_loop(options)).await {
+            Ok(result) => {
+                self.track_error(result.map_err(Into::into))?;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Runs the JS event loop until it has no pending work, or until `max_turns` polls of the loop
+    /// have been made, or the timeout elapses - whichever comes first
+    pub async fn run_until_idle(
+        &mut self,
+        options: PollEventLoopOptions,
+        max_turns: usize,
+        timeout: Duration,
+    ) -> Result<IdleResult, Error> {
+        use deno_core::stats::RuntimeActivityStatsFilter;
+
+        self.enter_js()?;
+
+        let mut turns = 0;
+        let drain = std::future::poll_fn(|cx| {
+            if turns >= max_turns {
+                // A loop with no pending ops or timers is idle, even if no turns of the budget remain
+                let filter = RuntimeActivityStatsFilter::default()
+                    .with_ops()
+                    .with_timers();
+                let stats = self.deno_runtime().runtime_activity_stats_factory();
+                return Poll::Ready(Ok(if stats.capture(&filter).dump().active.is_empty() {
+                    IdleResult::Idle
+                } else {
+                    IdleResult::BudgetExceeded
+                }));
+            }
+
+            turns += 1;
+            self.deno_runtime()
+                .poll_event_loop(cx, options)
+                .map(|result| result.map(|()| IdleResult::Idle))
+        });
+
+        match tokio::time::timeout(timeout, drain).await {
+            Ok(result) => self.track_error(result.map_err(Into::into)),
+            Err(_) => Ok(IdleResult::BudgetExceeded),
+        }
+    }
+
+    /// Prepares for a call into javascript
+    ///
+    /// Returns an error once the runtime has been shut down by `InnerRuntime::terminate_and_drain`,
+    /// and re-applies the microtask policy, which `deno_core` resets to automatic whenever it converts an exception
+    fn enter_js(&mut self) -> Result<(), Error> {
+        if self.drained {
+            return Err(Error::Runtime(
+                "The runtime has been shut down by terminate_and_drain".to_string(),
+            ));
+        }
+
+        let policy = self.microtask_policy.into();
+        self.deno_runtime()
+            .v8_isolate()
+            .set_microtasks_policy(policy);
+        Ok(())
+    }
+
+    /// Cancels intervals, runs the event loop until idle or `deadline`, then closes the resources left open
+    ///
+    /// A watchdog thread terminates scripts still running at the deadline  
+    /// Calls made once the drain has started are rejected
+    pub async fn terminate_and_drain(&mut self, deadline: Duration) -> ShutdownReport {
+        use deno_core::stats::{RuntimeActivity, RuntimeActivityStatsFilter};
+
+        self.drained = true;
+
+        let started = std::time::Instant::now();
+        let mut report = ShutdownReport::default();
+
+        // Intervals never finish on their own, so no more of their callbacks are run
+        let filter = RuntimeActivityStatsFilter::default().with_timers();
+        let intervals: Vec<usize> = self
+            .deno_runtime()
+            .runtime_activity_stats_factory()
+            .capture(&filter)
+            .dump()
+            .active
+            .into_iter()
+            .filter_map(|activity| match activity {
+                RuntimeActivity::Interval(id, _) => Some(id),
+                _ => None,
+            })
+            .collect();
+        if !intervals.is_empty() {
+            let script =
+                format!("for (const id of {intervals:?}) globalThis.Deno.core.cancelTimer(id);");
+            match self.deno_runtime().execute_script("<shutdown>", script) {
+                Ok(_) => report.cancelled_intervals = intervals.len(),
+                Err(e) => report.errors.push(e.into()),
+            }
+        }
+
+        let watchdog = Watchdog::start(&self.interrupt, deadline);
+        let result = tokio::time::timeout(
+            deadline,
+            self.deno_runtime()
+                .run_event_loop(PollEventLoopOptions::default()),
+        )
+        .await;
+        let fired = watchdog.stop();
+        report.elapsed = started.elapsed();
+
+        match result {
+            Ok(Ok(())) => report.idle = true,
+            Ok(Err(_)) if fired => report.terminated = true,
+            Ok(Err(e)) => {
+                if let Err(e) = self.track_error::<()>(Err(e.into())) {
+                    report.errors.push(e);
+                }
+            }
+            Err(_) => {}
+        }
+
+        let filter = RuntimeActivityStatsFilter::default()
+            .with_ops()
+            .with_timers();
+        let snapshot = self
+            .deno_runtime()
+            .runtime_activity_stats_factory()
+            .capture(&filter)
+            .dump();
+        for activity in snapshot.active {
+            match activity {
+                RuntimeActivity::AsyncOp(_, _, name) => report.pending_ops.push(name.to_string()),
+                RuntimeActivity::Timer(..) | RuntimeActivity::Interval(..) => {
+                    report.pending_timers += 1;
+                }
+                RuntimeActivity::Resource(..) => {}
+            }
+        }
+
+        // Sockets, files and the like are closed, leaving the process's standard streams alone
+        let resources: Vec<_> = {
+            let state = self.deno_runtime().op_state();
+            let mut state = state.borrow_mut();
+            let rids: Vec<_> = state
+                .resource_table
+                .names()
+                .filter(|(_, name)| !matches!(name.as_ref(), "stdin" | "stdout" | "stderr"))
+                .map(|(rid, name)| (rid, name.into_owned()))
+                .collect();
+            rids.into_iter()
+                .filter_map(|(rid, name)| Some((state.resource_table.take_any(rid).ok()?, name)))
+                .collect()
+        };
+        for (resource, name) in resources {
+            resource.close();
+            report.closed_resources.push(name);
+        }
+
+        report
+    }
+
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code
+    /// The expression is evaluated in the global context, so changes persist
+    ///
+    /// Async because some expressions may require a tokio runtime
+    ///
+    /// # Arguments
+    /// * `expr` - A string representing the JavaScript expression to evaluate
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the expression (`T`)
+    /// or an error (`Error`) if the expression cannot be evaluated or if the
+    /// result cannot be deserialized.
+    #[allow(clippy::unused_async, reason = "Prevent panic on sleep calls")]
+    pub async fn eval(&mut self, expr: impl ToString) -> Result<v8::Global<v8::Value>, Error> {
+        self.enter_js()?;
+
+        let result = self.deno_runtime().execute_script("", expr.to_string());
+        self.track_error(result.map_err(Into::into))
+    }
+
+    /// Freeze the JS built-ins and their prototypes, so that code run afterwards
+    /// cannot tamper with them
+    pub fn freeze_intrinsics(&mut self) -> Result<(), Error> {
+        self.deno_runtime()
+            .execute_script("<freeze_intrinsics>", FREEZE_INTRINSICS_SCRIPT)?;
+        Ok(())
+    }
+
+    /// Define a property of `globalThis` whose value is computed by `getter` the first time it is read
+    pub fn define_global_getter<F>(&mut self, name: &str, getter: F) -> Result<(), Error>
+    where
+        F: Fn() -> Result<serde_json::Value, Error> + 'static,
+    {
+        let callback = format!("__rustyscript_global_getter:{name}");
+        self.register_function(&callback, move |_| getter())?;
+
+        let script = format!(
+            "({GLOBAL_GETTER_SCRIPT})({}, {})",
+            serde_json::to_string(name)?,
+            serde_json::to_string(&callback)?
+        );
+        self.deno_runtime()
+            .execute_script("<define_global_getter>", script)?;
+        Ok(())
+    }
+
+    /// Run the microtask queue (promise continuations, `queueMicrotask` callbacks) to completion
+    /// Does not run timers, or poll any pending ops
+    pub fn run_microtasks(&mut self) -> Result<(), Error> {
+        self.enter_js()?;
+
+        let result = self.with_scope(|scope| {
+            v8::tc_scope!(let tc_scope, scope);
+            tc_scope.perform_microtask_checkpoint();
+            match tc_scope.exception() {
+                Some(exception) if !tc_scope.is_execution_terminating() => {
+                    Err(deno_core::error::JsError::from_v8_exception(tc_scope, exception).into())
+                }
+                _ => Ok(()),
+            }
+        });
+
+        self.track_error(result)
+    }
+
+    /// Returns the code compiled for a module loaded from a handle, after transpilation
+    pub fn module_source(&self, module_context: &ModuleHandle) -> Option<String> {
+        let specifier = module_context
+            .module()
+            .filename()
+            .to_module_specifier(&self.cwd)
+            .ok()?;
+        self.module_loader.module_source(&specifier)
+    }
+
+    /// Resolve an import the way the module loader would, without loading anything
+    ///
+    /// The referrer is a URL or path, relative to the current directory, and defaults to the current directory
+    pub fn resolve_specifier(
+        &self,
+        specifier: &str,
+        referrer: Option<&str>,
+    ) -> Result<deno_core::ModuleSpecifier, Error> {
+        let referrer = match referrer {
+            Some(referrer) if deno_core::specifier_has_uri_scheme(referrer) => {
+                deno_core::resolve_url(referrer)?
+            }
+            Some(referrer) => referrer.to_module_specifier(&self.cwd)?,
+            None => deno_core::ModuleSpecifier::from_directory_path(&self.cwd).map_err(|()| {
+                Error::Runtime(format!("invalid current directory: {}", self.cwd.display()))
+            })?,
+        };
+
+        deno_core::ModuleLoader::resolve(
+            self.module_loader.as_ref(),
+            specifier,
+            referrer.as_str(),
+            deno_core::ResolutionKind::Import,
+        )
+        .map_err(|e| Error::ModuleNotFound(e.to_string()))
+    }
+
+    /// Returns the own string keys of `globalThis`, enumerable or not, in property order
+    pub fn global_keys(&mut self) -> Vec<String> {
+        self.with_scope(global_keys)
+    }
+
+    /// Creates an internalized string, reused by function calls passing an equal string
+    pub fn intern_string(&mut self, value: &str) -> Result<(), Error> {
+        let strings = &mut self.string_cache;
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            strings.insert(scope, value)
+        })
+    }
+
+    /// Returns the keys of `globalThis` that were not present once the runtime was ready,
+    /// or whose value, getter or setter has been replaced since
+    pub fn user_global_keys(&mut self) -> Vec<String> {
+        let builtins = &self.builtin_globals;
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            global_slots(scope)
+                .into_iter()
+                .filter(|(key, slot)| {
+                    builtins.get(key).is_none_or(|builtin| {
+                        builtin.iter().zip(slot).any(|(builtin, value)| {
+                            !v8::Local::new(scope, builtin).strict_equals(*value)
+                        })
+                    })
+                })
+                .map(|(key, _)| key)
+                .collect()
+        })
+    }
+
+    /// Applies network permissions to everything that runs until the returned guard is dropped,
+    /// on top of the runtime's
+    #[cfg(feature = "web")]
+    pub fn push_call_permissions(
+        &mut self,
+        permissions: Arc<dyn crate::WebPermissions>,
+    ) -> Result<ext::web::CallPermissionsGuard, Error> {
+        let state = self.deno_runtime().op_state();
+        {
+            let state = state.try_borrow_mut()?;
+            if !state.has::<ext::web::PermissionsContainer>()
+                || !state.has::<deno_permissions::PermissionsContainer>()
+            {
+                return Err(Error::Runtime(
+                    "Call permissions require the web extension".to_string(),
+                ));
+            }
+        }
+        Ok(ext::web::push_call_permissions(state, permissions))
+    }
+
+    /// Define a module that can be imported by specifier, backed by the properties of an object
+    pub fn define_builtin_module(
+        &mut self,
+        specifier: &str,
+        exports: &v8::Global<v8::Value>,
+    ) -> Result<(), Error> {
+        let specifier = deno_core::resolve_url(specifier)?;
+
+        // Snapshot the export names
+        let names = self.with_scope(|scope| {
+            let exports = v8::Local::new(scope, exports);
+            let exports = v8::Local::<v8::Object>::try_from(exports)
+                .map_err(|_| Error::Runtime(format!("exports of {specifier} must be an object")))?;
+            let keys = exports
+                .get_own_property_names(scope, v8::GetPropertyNamesArgs::default())
+                .ok_or_else(|| Error::Runtime(format!("could not read exports of {specifier}")))?;
+
+            Ok::<_, Error>(
+                (0..keys.length())
+                    .filter_map(|i| keys.get_index(scope, i))
+                    .map(|key| key.to_rust_string_lossy(scope))
+                    .collect::<Vec<_>>(),
+            )
+        })?;
+
+        // The module reads the namespace back out of the op state when it is evaluated
+        let key = serde_json::to_string(specifier.as_str())?;
+        let mut source = vec![format!(
+            "const namespace = Deno.core.ops.op_builtin_module({key});"
+        )];
+        for (i, name) in names.iter().enumerate() {
+            let name = serde_json::to_string(name)?;
+            source.push(format!(
+                "const export{i} = namespace[{name}]; export {{ export{i} as {name} }};"
+            ));
+        }
+        if !names.iter().any(|name| name == "default") {
+            source.push("export default namespace;".to_string());
+        }
+
+        {
+            let state = self.deno_runtime().op_state();
+            let mut state = state.try_borrow_mut()?;
+            if !state.has::<BuiltinModules>() {
+                state.put(BuiltinModules::new());
+            }
+            state
+                .borrow_mut::<BuiltinModules>()
+                .insert(specifier.to_string(), exports.clone());
+        }
 
-        Ok(result)
+        self.module_loader
+            .define_builtin_module(specifier, source.join("\n"));
+        Ok(())
     }
 
-    /// Evaluate a piece of non-ECMAScript-module JavaScript code
-    /// The expression is evaluated in the global context, so changes persist
-    ///
-    /// Async because some expressions may require a tokio runtime
-    ///
-    /// # Arguments
-    /// * `expr` - A string representing the JavaScript expression to evaluate
-    ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the expression (`T`)
-    /// or an error (`Error`) if the expression cannot be evaluated or if the
-    /// result cannot be deserialized.
-    #[allow(clippy::unused_async, reason = "Prevent panic on sleep calls")]
-    pub async fn eval(&mut self, expr: impl ToString) -> Result<v8::Global<v8::Value>, Error> {
-        let result = self.deno_runtime().execute_script("", expr.to_string())?;
-        Ok(result)
+    /// Get the namespace object of a module, as a generic value
+    pub fn get_module_namespace(
+        &mut self,
+        module_context: &ModuleHandle,
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        self.ensure_evaluated(module_context)?;
+        let namespace = self
+            .deno_runtime()
+            .get_module_namespace(module_context.id())?;
+
+        self.with_scope(|scope| {
+            let local: v8::Local<v8::Value> = v8::Local::new(scope, namespace).into();
+            let isolate: &v8::Isolate = scope;
+            Ok(v8::Global::new(isolate, local))
+        })
     }
 
     /// Attempt to get a value out of the global context (globalThis.name)
@@ -489,15 +2082,14 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
             .execute_script("<get_global_value>", name.to_string())?;
 
         // Check if the value is undefined
-        let isolate = self.deno_runtime().v8_isolate();
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let scope = pinned.init();
-        let local = v8::Local::new(&scope, &result);
-        if local.is_undefined() {
-            return Err(Error::ValueNotFound(name.to_string()));
-        }
+        self.with_scope(|scope| {
+            let local = v8::Local::new(scope, &result);
+            if local.is_undefined() {
+                return Err(Error::ValueNotFound(name.to_string()));
+            }
 
-        Ok(result)
+            Ok(result)
+        })
     }
 
     /// Attempt to get a value out of a module context
@@ -513,61 +2105,98 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         module_context: &ModuleHandle,
         name: &str,
     ) -> Result<v8::Global<v8::Value>, Error> {
+        self.ensure_evaluated(module_context)?;
         let module_namespace = self
             .deno_runtime()
             .get_module_namespace(module_context.id())?;
 
-        let context = self.deno_runtime().main_context();
-        let isolate = self.deno_runtime().v8_isolate();
-
-        // Use std::pin::pin! pattern for proper scope management
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = pinned.init();
-        let context_local = v8::Local::new(&scope, context);
-        let context_scope = v8::ContextScope::new(&mut scope, context_local);
-
-        let module_namespace_local = v8::Local::new(&context_scope, module_namespace);
-        assert!(module_namespace_local.is_module_namespace_object());
+        self.with_scope(|scope| {
+            let module_namespace_local = v8::Local::new(scope, module_namespace);
+            assert!(module_namespace_local.is_module_namespace_object());
 
-        // Create key string directly using context_scope
-        let key = v8::String::new(&context_scope, name)
-            .ok_or_else(|| Error::V8Encoding(name.to_string()))?;
-        let value = module_namespace_local.get(&context_scope, key.into());
+            // Create key string directly using scope
+            let key =
+                v8::String::new(scope, name).ok_or_else(|| Error::V8Encoding(name.to_string()))?;
+            let value = module_namespace_local.get(scope, key.into());
 
-        match value.if_defined() {
-            Some(v) => {
-                let isolate: &v8::Isolate = &context_scope;
-                Ok(v8::Global::<v8::Value>::new(isolate, v))
+            match value.if_defined() {
+                Some(v) => {
+                    let isolate: &v8::Isolate = scope;
+                    Ok(v8::Global::<v8::Value>::new(isolate, v))
+                }
+                _ => Err(Error::ValueNotFound(name.to_string())),
             }
-            _ => Err(Error::ValueNotFound(name.to_string())),
-        }
+        })
     }
 
     pub async fn resolve_with_event_loop(
         &mut self,
         value: v8::Global<v8::Value>,
     ) -> Result<v8::Global<v8::Value>, Error> {
+        self.enter_js()?;
+
         let future = self.deno_runtime().resolve(value);
         let result = self
             .deno_runtime()
             .with_event_loop_future(future, PollEventLoopOptions::default())
-            .await?;
-        Ok(result)
+            .await;
+        self.track_error(result.map_err(Into::into))
     }
 
     pub fn decode_value<T>(&mut self, value: v8::Global<v8::Value>) -> Result<T, Error>
     where
         T: DeserializeOwned,
     {
-        let context = self.deno_runtime().main_context();
-        let isolate = self.deno_runtime().v8_isolate();
+        self.with_scope(|scope| {
+            let result = v8::Local::<v8::Value>::new(scope, value);
+            Ok(from_v8(scope, result)?)
+        })
+    }
+
+    /// Copies the bytes of an `ArrayBuffer`, `SharedArrayBuffer`, typed array or `DataView`
+    ///
+    /// Views only yield the range of their buffer that they cover
+    pub fn bytes_from_value(&mut self, value: &v8::Global<v8::Value>) -> Result<Vec<u8>, Error> {
+        self.with_scope(|scope| {
+            let value = v8::Local::<v8::Value>::new(scope, value);
+
+            if let Ok(view) = v8::Local::<v8::ArrayBufferView>::try_from(value) {
+                let mut bytes = vec![0; view.byte_length()];
+                view.copy_contents(&mut bytes);
+                Ok(bytes)
+            } else if let Ok(buffer) = v8::Local::<v8::ArrayBuffer>::try_from(value) {
+                let store = buffer.get_backing_store();
+                Ok(store[..buffer.byte_length()]
+                    .iter()
+                    .map(std::cell::Cell::get)
+                    .collect())
+            } else if let Ok(buffer) = v8::Local::<v8::SharedArrayBuffer>::try_from(value) {
+                let store = buffer.get_backing_store();
+                Ok(store[..buffer.byte_length()]
+                    .iter()
+                    .map(std::cell::Cell::get)
+                    .collect())
+            } else {
+                Err(Error::Runtime(format!(
+                    "expected an ArrayBuffer or typed array, found {}",
+                    value.type_repr()
+                )))
+            }
+        })
+    }
 
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = pinned.init();
-        let context_local = v8::Local::new(&scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
-        let result = v8::Local::<v8::Value>::new(&context_scope, value);
-        Ok(from_v8(&mut context_scope, result)?)
+    /// Decodes a value as untyped JSON, mapping `undefined` to `null`
+    pub fn decode_json(
+        &mut self,
+        value: v8::Global<v8::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        self.with_scope(|scope| {
+            let result = v8::Local::<v8::Value>::new(scope, value);
+            if result.is_undefined() {
+                return Ok(serde_json::Value::Null);
+            }
+            Ok(from_v8(scope, result)?)
+        })
     }
 
     pub fn get_value_ref(
@@ -575,6 +2204,10 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         module_context: Option<&ModuleHandle>,
         name: &str,
     ) -> Result<v8::Global<v8::Value>, Error> {
+        if let Some(module_context) = module_context {
+            self.ensure_evaluated(module_context)?;
+        }
+
         // Try to get the value from the module context first
         let result = module_context
             .and_then(|module_context| self.get_module_export_value(module_context, name).ok());
@@ -607,18 +2240,28 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         let value = self.get_value_ref(module_context, name)?;
 
         // Convert it into a function
-        let isolate = self.deno_runtime().v8_isolate();
-
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let scope = pinned.init();
-        let local_value = v8::Local::<v8::Value>::new(&scope, value);
-        let f: v8::Local<v8::Function> = local_value
-            .try_into()
-            .or::<Error>(Err(Error::ValueNotCallable(name.to_string())))?;
+        self.with_scope(|scope| {
+            let local_value = v8::Local::<v8::Value>::new(scope, value);
+            let f: v8::Local<v8::Function> = local_value
+                .try_into()
+                .or::<Error>(Err(Error::ValueNotCallable(name.to_string())))?;
+
+            // Return it as a global
+            let isolate: &v8::Isolate = scope;
+            Ok(v8::Global::<v8::Function>::new(isolate, f))
+        })
+    }
 
-        // Return it as a global
-        let isolate: &v8::Isolate = &scope;
-        Ok(v8::Global::<v8::Function>::new(isolate, f))
+    /// Serialize a value to a JSON string
+    /// Object keys are sorted if the runtime was configured with `sorted_keys`
+    pub fn serialize_json(&self, value: &impl serde::ser::Serialize) -> Result<String, Error> {
+        if self.sorted_keys {
+            let mut value = serde_json::to_value(value)?;
+            value.sort_all_objects();
+            Ok(serde_json::to_string(&value)?)
+        } else {
+            Ok(serde_json::to_string(value)?)
+        }
     }
 
     pub fn call_function_by_ref(
@@ -626,9 +2269,22 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         module_context: Option<&ModuleHandle>,
         function: &v8::Global<v8::Function>,
         args: &impl serde::ser::Serialize,
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        self.enter_js()?;
+
+        let result = self.call_function_by_ref_inner(module_context, function, args);
+        self.track_error(result)
+    }
+
+    fn call_function_by_ref_inner(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        function: &v8::Global<v8::Function>,
+        args: &impl serde::ser::Serialize,
     ) -> Result<v8::Global<v8::Value>, Error> {
         // Get the receiver (namespace object or undefined)
         let receiver = if let Some(module_context) = module_context {
+            self.ensure_evaluated(module_context)?;
             Some(
                 self.deno_runtime()
                     .get_module_namespace(module_context.id())?,
@@ -637,72 +2293,202 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
             None
         };
 
-        // Get the main context and create scopes
-        let context = self.deno_runtime().main_context();
-        let isolate = self.deno_runtime().v8_isolate();
+        let sorted_keys = self.sorted_keys;
+        let strings = &self.string_cache;
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            // Create local handle for the function
+            let function_local = v8::Local::new(scope, function);
+
+            // Create the receiver (undefined or module namespace)
+            let recv: v8::Local<v8::Value> = match &receiver {
+                Some(ns) => v8::Local::new(scope, ns).into(),
+                None => v8::undefined(scope).into(),
+            };
+
+            // Args should serialize to an array (tuples become arrays)
+            let args_locals = encode_args(scope, args, sorted_keys, strings)?;
+
+            // Call the function, catching any exception it throws
+            v8::tc_scope!(let tc_scope, scope);
+            let result = function_local.call(tc_scope, recv, &args_locals);
+
+            // Handle the result
+            match result {
+                Some(value) => {
+                    let isolate: &v8::Isolate = tc_scope;
+                    Ok(v8::Global::new(isolate, value))
+                }
+                None => match tc_scope.exception() {
+                    // Convert the exception into a proper JS error, with a source-mapped stack
+                    Some(exception) if !tc_scope.is_execution_terminating() => Err(
+                        deno_core::error::JsError::from_v8_exception(tc_scope, exception).into(),
+                    ),
+
+                    // Function call failed without an exception (likely terminated)
+                    _ => Err(Error::Runtime("Function call failed".to_string())),
+                },
+            }
+        })
+    }
 
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = pinned.init();
-        let context_local = v8::Local::new(&scope, &context);
-        let context_scope = v8::ContextScope::new(&mut scope, context_local);
+    /// Calls a method of an object
+    pub fn call_method(
+        &mut self,
+        object: &v8::Global<v8::Value>,
+        name: &str,
+        args: &[v8::Global<v8::Value>],
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        self.enter_js()?;
 
-        // Create local handle for the function
-        let function_local = v8::Local::new(&context_scope, function);
+        let result = self.call_method_inner(object, name, args);
+        self.track_error(result)
+    }
 
-        // Create the receiver (undefined or module namespace)
-        let recv: v8::Local<v8::Value> = match &receiver {
-            Some(ns) => v8::Local::new(&context_scope, ns).into(),
-            None => v8::undefined(&context_scope).into(),
-        };
+    fn call_method_inner(
+        &mut self,
+        object: &v8::Global<v8::Value>,
+        name: &str,
+        args: &[v8::Global<v8::Value>],
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        self.with_scope(|scope| {
+            let receiver = v8::Local::new(scope, object);
+            let object = v8::Local::<v8::Object>::try_from(receiver)
+                .map_err(|_| Error::ValueNotFound(name.to_string()))?;
+            let key =
+                v8::String::new(scope, name).ok_or_else(|| Error::V8Encoding(name.to_string()))?;
+            let method = object
+                .get(scope, key.into())
+                .and_then(|f| v8::Local::<v8::Function>::try_from(f).ok())
+                .ok_or_else(|| Error::ValueNotCallable(name.to_string()))?;
+
+            let args: Vec<v8::Local<v8::Value>> =
+                args.iter().map(|arg| v8::Local::new(scope, arg)).collect();
+
+            // Call the method, catching any exception it throws
+            v8::tc_scope!(let tc_scope, scope);
+            match method.call(tc_scope, receiver, &args) {
+                Some(value) => {
+                    let isolate: &v8::Isolate = tc_scope;
+                    Ok(v8::Global::new(isolate, value))
+                }
+                None => match tc_scope.exception() {
+                    Some(exception) if !tc_scope.is_execution_terminating() => Err(
+                        deno_core::error::JsError::from_v8_exception(tc_scope, exception).into(),
+                    ),
+                    _ => Err(Error::Runtime(format!("Call to {name} failed"))),
+                },
+            }
+        })
+    }
 
-        // Serialize arguments to JSON and parse in JS
-        // Args should serialize to an array (tuples become arrays in serde_json)
-        let json_str = deno_core::serde_json::to_string(args)?;
+    /// Converts serializable arguments into values, as they would be passed to a function call
+    pub fn encode_args(
+        &mut self,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<Vec<v8::Global<v8::Value>>, Error> {
+        let sorted_keys = self.sorted_keys;
+        let strings = &self.string_cache;
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            let args = encode_args(scope, args, sorted_keys, strings)?;
+            let isolate: &v8::Isolate = scope;
+            Ok(args
+                .into_iter()
+                .map(|arg| v8::Global::new(isolate, arg))
+                .collect())
+        })
+    }
 
-        // Handle special case of empty tuple () which serializes to "null"
-        let args_locals: Vec<v8::Local<v8::Value>> = if json_str == "null" || json_str == "[]" {
-            vec![]
-        } else {
-            let escaped = json_str.replace('\\', "\\\\").replace('\'', "\\'");
-            let script_source = format!("JSON.parse('{escaped}')");
-            let source = v8::String::new(&context_scope, &script_source)
-                .ok_or_else(|| Error::V8Encoding("Failed to create script source".to_string()))?;
-            let script = v8::Script::compile(&context_scope, source, None)
-                .ok_or_else(|| Error::Runtime("Failed to compile JSON parse script".to_string()))?;
-            let args_value = script
-                .run(&context_scope)
-                .ok_or_else(|| Error::Runtime("Failed to parse arguments".to_string()))?;
-
-            // Check if it's an array
-            if args_value.is_array() {
-                let args_array: v8::Local<v8::Array> = args_value.try_into().unwrap();
-                let mut locals = Vec::with_capacity(args_array.length() as usize);
-                for i in 0..args_array.length() {
-                    if let Some(arg) = args_array.get_index(&context_scope, i) {
-                        locals.push(arg);
-                    }
+    /// Calls a class or constructor function with `new`, returning the instance
+    pub fn call_constructor(
+        &mut self,
+        class: &v8::Global<v8::Value>,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        self.enter_js()?;
+
+        let result = self.call_constructor_inner(class, args);
+        self.track_error(result)
+    }
+
+    fn call_constructor_inner(
+        &mut self,
+        class: &v8::Global<v8::Value>,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<v8::Global<v8::Value>, Error> {
+        let sorted_keys = self.sorted_keys;
+        let strings = &self.string_cache;
+        with_scope(self.deno_runtime.rt_mut(), |scope| {
+            let class = v8::Local::new(scope, class);
+            let class = v8::Local::<v8::Function>::try_from(class)
+                .map_err(|_| Error::ValueNotCallable("class".to_string()))?;
+            let args = encode_args(scope, args, sorted_keys, strings)?;
+
+            // Construct the instance, catching any exception - including calling a non-constructor with `new`
+            v8::tc_scope!(let tc_scope, scope);
+            match class.new_instance(tc_scope, &args) {
+                Some(instance) => {
+                    let isolate: &v8::Isolate = tc_scope;
+                    Ok(v8::Global::new(
+                        isolate,
+                        v8::Local::<v8::Value>::from(instance),
+                    ))
                 }
-                locals
-            } else {
-                // Single argument
-                vec![args_value]
+                None => match tc_scope.exception() {
+                    Some(exception) if !tc_scope.is_execution_terminating() => Err(
+                        deno_core::error::JsError::from_v8_exception(tc_scope, exception).into(),
+                    ),
+                    _ => Err(Error::Runtime("Constructor call failed".to_string())),
+                },
             }
-        };
+        })
+    }
 
-        // Call the function
-        let result = function_local.call(&context_scope, recv, &args_locals);
+    /// Serializes a value with V8's structured serialization, the format used by `structuredClone`
+    pub fn serialize_value(&mut self, value: &v8::Global<v8::Value>) -> Result<Vec<u8>, Error> {
+        use v8::ValueSerializerHelper;
+        self.with_scope(|scope| {
+            let context = scope.get_current_context();
+            v8::tc_scope!(let tc_scope, scope);
+            let value = v8::Local::new(tc_scope, value);
+            let serializer = v8::ValueSerializer::new(tc_scope, Box::new(StructuredClone));
+            serializer.write_header();
+
+            match serializer.write_value(context, value) {
+                Some(true) => Ok(serializer.release()),
+                _ => Err(Error::Runtime(format!(
+                    "Value could not be serialized: {}",
+                    structured_clone_error(tc_scope)
+                ))),
+            }
+        })
+    }
 
-        // Handle the result
-        match result {
-            Some(value) => {
-                let isolate: &v8::Isolate = &context_scope;
-                Ok(v8::Global::new(isolate, value))
+    /// Deserializes a value written by `InnerRuntime::serialize_value`
+    pub fn deserialize_value(&mut self, data: &[u8]) -> Result<v8::Global<v8::Value>, Error> {
+        use v8::ValueDeserializerHelper;
+        self.with_scope(|scope| {
+            let context = scope.get_current_context();
+            v8::tc_scope!(let tc_scope, scope);
+            let deserializer =
+                v8::ValueDeserializer::new(tc_scope, Box::new(StructuredClone), data);
+            if deserializer.read_header(context) != Some(true) {
+                return Err(Error::Runtime(format!(
+                    "Value could not be deserialized: {}",
+                    structured_clone_error(tc_scope)
+                )));
             }
-            None => {
-                // Function call failed (likely an exception was thrown)
-                Err(Error::Runtime("Function call failed".to_string()))
+
+            match deserializer.read_value(context) {
+                Some(value) => {
+                    let isolate: &v8::Isolate = tc_scope;
+                    Ok(v8::Global::new(isolate, value))
+                }
+                None => Err(Error::Runtime(format!(
+                    "Value could not be deserialized: {}",
+                    structured_clone_error(tc_scope)
+                ))),
             }
-        }
+        })
     }
 
     /// A utility function that run provided future concurrently with the event loop.
@@ -720,6 +2506,8 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         deno_core::error::AnyError: From<E>,
         Error: std::convert::From<E>,
     {
+        self.enter_js()?;
+
         // Manually implement tokio::select
         std::future::poll_fn(|cx| {
             let evt_status = self.deno_runtime().poll_event_loop(cx, poll_options);
@@ -770,16 +2558,14 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
 
         // Try to get an entrypoint from the default export next
         if let Ok(default_export) = self.get_module_export_value(module_context, "default") {
-            let isolate = self.deno_runtime().v8_isolate();
-
-            let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-            let scope = pinned.init();
-            let default_export = v8::Local::new(&scope, default_export);
-            if default_export.is_function() {
-                if let Ok(f) = v8::Local::<v8::Function>::try_from(default_export) {
-                    let isolate: &v8::Isolate = &scope;
-                    return Ok(Some(v8::Global::new(isolate, f)));
-                }
+            let entrypoint = self.with_scope(|scope| {
+                let default_export = v8::Local::new(scope, default_export);
+                let f = v8::Local::<v8::Function>::try_from(default_export).ok()?;
+                let isolate: &v8::Isolate = scope;
+                Some(v8::Global::new(isolate, f))
+            });
+            if entrypoint.is_some() {
+                return Ok(entrypoint);
             }
         }
 
@@ -793,6 +2579,149 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         Ok(None)
     }
 
+    /// Load a directory as a package, using the entry point from its `package.json`
+    ///
+    /// Every module within the package is allowed to be imported, so that internal relative imports
+    /// will resolve without the `fs_import` feature. Dependencies in `node_modules` are not included
+    pub async fn load_package(&mut self, dir: &Path) -> Result<ModuleHandle, Error> {
+        let dir = self.cwd.join(dir);
+
+        // The node resolver applies the full `exports` rules, when it is available
+        #[cfg(feature = "node_experimental")]
+        let entrypoint = if dir.join("package.json").is_file() {
+            self.module_loader.resolve_package_entrypoint(&dir)?
+        } else {
+            package_entrypoint(&dir)?
+        };
+        #[cfg(not(feature = "node_experimental"))]
+        let entrypoint = package_entrypoint(&dir)?;
+
+        for path in package_files(&dir)? {
+            let specifier = path.to_module_specifier(&self.cwd)?;
+            self.module_loader.whitelist_add(&specifier);
+        }
+
+        let module = Module::load(&entrypoint)?;
+        self.load_modules(None, vec![&module]).await
+    }
+
+    /// Resolves, fetches and transpiles every module statically imported by `module`, without
+    /// evaluating any of them - modules end up in the module cache, if there is one
+    ///
+    /// Failures are recorded in the report, and stop only that branch of the graph
+    pub async fn prefetch_graph(&mut self, module: &Module) -> Result<PrefetchReport, Error> {
+        use deno_core::{
+            ModuleLoadOptions, ModuleLoadReferrer, ModuleLoadResponse, ModuleLoader, ModuleType,
+            RequestedModuleType, ResolutionKind,
+        };
+
+        let entry = module.filename().to_module_specifier(&self.cwd)?;
+        self.module_loader.whitelist_add(&entry);
+        let (code, _) = transpile(&entry, module.contents())?;
+
+        let mut report = PrefetchReport::default();
+        let mut seen = HashSet::new();
+        let mut pending: std::collections::VecDeque<_> = static_imports(&entry, &code)
+            .into_iter()
+            .map(|import| (import, entry.clone()))
+            .collect();
+        while let Some((import, referrer)) = pending.pop_front() {
+            let specifier =
+                match self
+                    .module_loader
+                    .resolve(&import, referrer.as_str(), ResolutionKind::Import)
+                {
+                    Ok(specifier) => specifier,
+                    Err(e) => {
+                        let error = Error::Runtime(e.to_string());
+                        report.failed.push((import, self.display_error(error)));
+                        continue;
+                    }
+                };
+
+            // Modules provided by the runtime itself are never fetched
+            if matches!(specifier.scheme(), "ext" | "node") || !seen.insert(specifier.clone()) {
+                continue;
+            }
+
+            let referrer = ModuleLoadReferrer {
+                specifier: referrer,
+                line_number: 1,
+                column_number: 1,
+            };
+            let options = ModuleLoadOptions {
+                is_dynamic_import: false,
+                is_synchronous: false,
+                requested_module_type: RequestedModuleType::None,
+            };
+            let result = match self
+                .module_loader
+                .load(&specifier, Some(&referrer), options)
+            {
+                ModuleLoadResponse::Sync(result) => result,
+                ModuleLoadResponse::Async(future) => future.await,
+            };
+
+            match result {
+                Ok(source) => {
+                    if source.module_type == ModuleType::JavaScript {
+                        let code = String::from_utf8_lossy(source.code.as_bytes());
+                        pending.extend(
+                            static_imports(&specifier, &code)
+                                .into_iter()
+                                .map(|import| (import, specifier.clone())),
+                        );
+                    }
+                    report.prefetched.push(specifier);
+                }
+                Err(e) => {
+                    let error = self.display_error(Error::Runtime(e.to_string()));
+                    report
+                        .failed
+                        .push((self.display_specifier(&specifier), error));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Load a remote module as the main module, fetching it through the module loader
+    /// Relative imports in the module are resolved against its URL
+    pub async fn load_main_module_from_url(&mut self, url: &str) -> Result<ModuleHandle, Error> {
+        self.enter_js()?;
+
+        let result = self.load_main_module_from_url_inner(url).await;
+        self.track_error(result)
+    }
+
+    async fn load_main_module_from_url_inner(&mut self, url: &str) -> Result<ModuleHandle, Error> {
+        let specifier = deno_core::resolve_url(url)?;
+        match specifier.scheme() {
+            "https" => {}
+            "http" if self.allow_http_entrypoint => {}
+            "http" => {
+                return Err(Error::Runtime(format!(
+                    "insecure entry point {specifier} is not allowed; set `allow_http_entrypoint` to enable it"
+                )))
+            }
+            scheme => {
+                return Err(Error::Runtime(format!(
+                    "unsupported scheme for a remote entry point: {scheme}"
+                )))
+            }
+        }
+
+        let module_id = self.deno_runtime().load_main_es_module(&specifier).await?;
+        self.evaluate_loaded_module(module_id, &specifier).await?;
+
+        // The source is owned by the loader, so the handle only records the URL
+        let module = Module::new(specifier.as_str(), "");
+        let mut module_handle_stub = ModuleHandle::new(&module, module_id, None);
+        let entrypoint = self.get_module_entrypoint(&mut module_handle_stub)?;
+        Ok(ModuleHandle::new(&module, module_id, entrypoint))
+    }
+
     /// Load one or more modules
     /// Returns a future that resolves to a handle to the main module, or the last
     /// side-module
@@ -803,6 +2732,71 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
         &mut self,
         main_module: Option<&Module>,
         side_modules: Vec<&Module>,
+    ) -> Result<ModuleHandle, Error> {
+        let side_modules = side_modules.into_iter().map(|m| (m, None)).collect();
+        self.load_keyed_modules(main_module, side_modules).await
+    }
+
+    /// Load and evaluate a side module like `InnerRuntime::load_modules`, storing its transpiled code
+    /// and source map in the cache provider under `cache_key` rather than under its specifier
+    pub async fn load_module_with_cache_key(
+        &mut self,
+        module: &Module,
+        cache_key: &str,
+    ) -> Result<ModuleHandle, Error> {
+        let cache_key = match RustyLoader::cache_key_specifier(cache_key) {
+            Ok(cache_key) => cache_key,
+            Err(e) => return self.track_error(Err(e)),
+        };
+        self.load_keyed_modules(None, vec![(module, Some(cache_key))])
+            .await
+    }
+
+    /// Loads modules, consulting the cache provider under the key given with any side module
+    async fn load_keyed_modules(
+        &mut self,
+        main_module: Option<&Module>,
+        side_modules: Vec<(&Module, Option<deno_core::ModuleSpecifier>)>,
+    ) -> Result<ModuleHandle, Error> {
+        self.enter_js()?;
+
+        let result = Self::within_eval_timeout(
+            self.interrupt.clone(),
+            self.module_eval_timeout,
+            self.load_modules_inner(main_module, side_modules),
+        )
+        .await;
+        self.track_error(result)
+    }
+
+    /// Runs module evaluation under `RuntimeOptions::module_eval_timeout`, if one is set
+    ///
+    /// Async work is bounded by the future's timeout, and a watchdog thread terminates
+    /// synchronous top-level code that never yields back to the event loop
+    async fn within_eval_timeout<T>(
+        interrupt: InterruptHandle,
+        timeout: Option<Duration>,
+        future: impl std::future::Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let Some(timeout) = timeout else {
+            return future.await;
+        };
+
+        let watchdog = Watchdog::start(&interrupt, timeout);
+        let result = tokio::time::timeout(timeout, future).await;
+        if watchdog.stop() || result.is_err() {
+            return Err(Error::Timeout(format!(
+                "module evaluation exceeded {}ms",
+                timeout.as_millis()
+            )));
+        }
+        result?
+    }
+
+    async fn load_modules_inner(
+        &mut self,
+        main_module: Option<&Module>,
+        side_modules: Vec<(&Module, Option<deno_core::ModuleSpecifier>)>,
     ) -> Result<ModuleHandle, Error> {
         if main_module.is_none() && side_modules.is_empty() {
             return Err(Error::Runtime(
@@ -812,81 +2806,275 @@ impl<RT: RuntimeTrait> InnerRuntime<RT> {
 
         let mut module_handle_stub = ModuleHandle::default();
 
+        // Set if the last module is an existing instance, see `ReloadPolicy`
+        let mut reused = false;
+
         // Get additional modules first
-        for side_module in side_modules {
+        for (side_module, cache_key) in side_modules {
             let module_specifier = side_module.filename().to_module_specifier(&self.cwd)?;
             self.module_loader.whitelist_add(&module_specifier);
-            let (code, sourcemap) = transpile(&module_specifier, side_module.contents())?;
-
-            // Now CJS translation, for node
-            #[cfg(feature = "node_experimental")]
-            let code = self
+            if let Some(handle) = self
                 .module_loader
-                .translate_cjs(&module_specifier, &code)
-                .await?;
-
-            let fast_code = deno_core::FastString::from(code.clone());
+                .loaded_module(&module_specifier, side_module.contents())
+            {
+                module_handle_stub = handle;
+                reused = true;
+                continue;
+            }
 
             let s_modid = self
-                .deno_runtime()
-                .load_side_es_module_from_code(&module_specifier, fast_code)
+                .load_side_module(&module_specifier, side_module, cache_key.as_ref())
                 .await?;
-
-            // Update source map cache
-            self.module_loader.insert_source_map(
-                module_specifier.as_str(),
-                code,
-                sourcemap.map(|s| s.to_vec()),
-            );
-
-            let mod_load = self.deno_runtime().mod_evaluate(s_modid);
-            self.with_event_loop_future(mod_load, PollEventLoopOptions::default())
+            self.evaluate_loaded_module(s_modid, &module_specifier)
                 .await?;
             module_handle_stub = ModuleHandle::new(side_module, s_modid, None);
+            self.module_loader.set_loaded_module(
+                &module_specifier,
+                side_module.contents(),
+                module_handle_stub.clone(),
+            );
+            reused = false;
         }
 
         // Load main module
         if let Some(module) = main_module {
             let module_specifier = module.filename().to_module_specifier(&self.cwd)?;
             self.module_loader.whitelist_add(&module_specifier);
-            let (code, sourcemap) = transpile(&module_specifier, module.contents())?;
-
-            // Now CJS translation, for node
-            #[cfg(feature = "node_experimental")]
-            let code = self
-                .module_loader
-                .translate_cjs(&module_specifier, &code)
-                .await?;
-
-            let fast_code = deno_core::FastString::from(code.clone());
-
-            let module_id = self
-                .deno_runtime()
-                .load_main_es_module_from_code(&module_specifier, fast_code)
-                .await?;
-
-            // Update source map cache
-            self.module_loader.insert_source_map(
-                module_specifier.as_str(),
-                code,
-                sourcemap.map(|s| s.to_vec()),
-            );
+            let result = async {
+                self.module_loader
+                    .notify(LoaderEvent::Transpiling, &module_specifier);
+                let ((code, sourcemap), diagnostics) =
+                    transpile_with_diagnostics(&module_specifier, module.contents())?;
+                self.module_loader.add_diagnostics(diagnostics);
+                let code = self.module_loader.preprocess(&module_specifier, code)?;
+
+                // Now CJS translation, for node
+                #[cfg(feature = "node_experimental")]
+                let code = self
+                    .module_loader
+                    .translate_cjs(&module_specifier, &code)
+                    .await?;
+
+                let fast_code = deno_core::FastString::from(code.clone());
+
+                let module_id = self
+                    .deno_runtime()
+                    .load_main_es_module_from_code(&module_specifier, fast_code)
+                    .await?;
+
+                // Update source map cache
+                self.module_loader.insert_source_map(
+                    module_specifier.as_str(),
+                    code,
+                    sourcemap.map(|s| s.to_vec()),
+                );
+                Ok::<_, Error>(module_id)
+            }
+            .await;
+            let module_id = self.report_load_failure(&module_specifier, result)?;
 
             // Finish execution
-            let mod_load = self.deno_runtime().mod_evaluate(module_id);
-            self.with_event_loop_future(mod_load, PollEventLoopOptions::default())
+            self.evaluate_loaded_module(module_id, &module_specifier)
                 .await?;
             module_handle_stub = ModuleHandle::new(module, module_id, None);
+            reused = false;
+        }
+
+        // An existing instance already has its entrypoint
+        if reused {
+            return Ok(module_handle_stub);
         }
 
         // Try to get the default entrypoint
         let entrypoint = self.get_module_entrypoint(&mut module_handle_stub)?;
-
-        Ok(ModuleHandle::new(
+        let handle = ModuleHandle::new(
             module_handle_stub.module(),
             module_handle_stub.id(),
             entrypoint,
-        ))
+        );
+
+        // Record the entrypoint of the last side module, for when it is reused
+        if main_module.is_none() {
+            let module_specifier = handle.module().filename().to_module_specifier(&self.cwd)?;
+            self.module_loader.set_loaded_module(
+                &module_specifier,
+                handle.module().contents(),
+                handle.clone(),
+            );
+        }
+
+        Ok(handle)
+    }
+
+    /// Transpiles and instantiates a side module, without evaluating it
+    ///
+    /// With a `cache_key`, the cache provider is consulted under that key before transpiling
+    async fn load_side_module(
+        &mut self,
+        module_specifier: &deno_core::ModuleSpecifier,
+        module: &Module,
+        cache_key: Option<&deno_core::ModuleSpecifier>,
+    ) -> Result<deno_core::ModuleId, Error> {
+        let result = self
+            .load_side_module_inner(module_specifier, module, cache_key)
+            .await;
+        self.report_load_failure(module_specifier, result)
+    }
+
+    async fn load_side_module_inner(
+        &mut self,
+        module_specifier: &deno_core::ModuleSpecifier,
+        module: &Module,
+        cache_key: Option<&deno_core::ModuleSpecifier>,
+    ) -> Result<deno_core::ModuleId, Error> {
+        let cached = cache_key.and_then(|key| self.module_loader.cached_code(key));
+        let (code, sourcemap) = if let Some((code, sourcemap)) = cached {
+            self.module_loader
+                .notify(LoaderEvent::Cached, module_specifier);
+            (code, sourcemap)
+        } else {
+            self.module_loader
+                .notify(LoaderEvent::Transpiling, module_specifier);
+            let ((code, sourcemap), diagnostics) =
+                transpile_with_diagnostics(module_specifier, module.contents())?;
+            self.module_loader.add_diagnostics(diagnostics);
+            let code = self.module_loader.preprocess(module_specifier, code)?;
+            let sourcemap = sourcemap.map(|s| s.to_vec());
+            if let Some(key) = cache_key {
+                self.module_loader
+                    .cache_code(key, &code, sourcemap.as_deref());
+            }
+            (code, sourcemap)
+        };
+
+        // Now CJS translation, for node
+        #[cfg(feature = "node_experimental")]
+        let code = self
+            .module_loader
+            .translate_cjs(module_specifier, &code)
+            .await?;
+
+        let fast_code = deno_core::FastString::from(code.clone());
+
+        let module_id = self
+            .deno_runtime()
+            .load_side_es_module_from_code(module_specifier, fast_code)
+            .await?;
+
+        // Update source map cache
+        self.module_loader
+            .insert_source_map(module_specifier.as_str(), code, sourcemap);
+
+        Ok(module_id)
+    }
+
+    /// Runs the top-level code of a loaded module, along with any imports not yet evaluated
+    ///
+    /// The outcome is reported to `RuntimeOptions::on_module_loaded`, along with the static imports loaded for it
+    async fn evaluate_loaded_module(
+        &mut self,
+        module_id: deno_core::ModuleId,
+        module_specifier: &deno_core::ModuleSpecifier,
+    ) -> Result<(), Error> {
+        let mod_load = self.deno_runtime().mod_evaluate(module_id);
+        let result = self
+            .with_event_loop_future(mod_load, PollEventLoopOptions::default())
+            .await;
+        self.module_loader
+            .report_evaluated(module_specifier, &result);
+        result
+    }
+
+    /// Reports an error loading a module to `RuntimeOptions::on_module_loaded`, if one is set
+    fn report_load_failure<T>(
+        &self,
+        module_specifier: &deno_core::ModuleSpecifier,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        if let Err(e) = &result {
+            self.module_loader
+                .report_evaluated(module_specifier, &Err(e.clone()));
+        }
+        result
+    }
+
+    /// Resolve and link a module and its imports, without running any top-level code
+    ///
+    /// The module cannot be used until it has been passed to `InnerRuntime::evaluate_module`
+    pub async fn instantiate_module(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
+        self.enter_js()?;
+
+        let result = self.instantiate_module_inner(module).await;
+        self.track_error(result)
+    }
+
+    async fn instantiate_module_inner(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
+        let module_specifier = module.filename().to_module_specifier(&self.cwd)?;
+        self.module_loader.whitelist_add(&module_specifier);
+        let module_id = self
+            .load_side_module(&module_specifier, module, None)
+            .await?;
+        self.unevaluated_modules.insert(module_id);
+        Ok(ModuleHandle::new(module, module_id, None))
+    }
+
+    /// Run the top-level code of a module from `InnerRuntime::instantiate_module`
+    ///
+    /// Returns a new handle for the module, with its entrypoint
+    pub async fn evaluate_module(
+        &mut self,
+        module_context: &ModuleHandle,
+    ) -> Result<ModuleHandle, Error> {
+        self.enter_js()?;
+
+        let result = self.evaluate_module_inner(module_context).await;
+        self.track_error(result)
+    }
+
+    async fn evaluate_module_inner(
+        &mut self,
+        module_context: &ModuleHandle,
+    ) -> Result<ModuleHandle, Error> {
+        if !self.unevaluated_modules.remove(&module_context.id()) {
+            return Err(Error::Runtime(format!(
+                "Module {} was not instantiated, or has already been evaluated",
+                module_context.module().filename().display()
+            )));
+        }
+
+        let module_specifier = module_context
+            .module()
+            .filename()
+            .to_module_specifier(&self.cwd)?;
+        Self::within_eval_timeout(
+            self.interrupt.clone(),
+            self.module_eval_timeout,
+            self.evaluate_loaded_module(module_context.id(), &module_specifier),
+        )
+        .await?;
+
+        let mut module_handle_stub = module_context.clone();
+        let entrypoint = self.get_module_entrypoint(&mut module_handle_stub)?;
+        let handle = ModuleHandle::new(module_context.module(), module_context.id(), entrypoint);
+
+        // Recorded like any other loaded module, see `ReloadPolicy`
+        self.module_loader.set_loaded_module(
+            &module_specifier,
+            handle.module().contents(),
+            handle.clone(),
+        );
+        Ok(handle)
+    }
+
+    /// Returns an error if the module was instantiated, but has not yet been evaluated
+    fn ensure_evaluated(&self, module_context: &ModuleHandle) -> Result<(), Error> {
+        if self.unevaluated_modules.contains(&module_context.id()) {
+            return Err(Error::Runtime(format!(
+                "Module {} has not been evaluated; call `evaluate_module` first",
+                module_context.module().filename().display()
+            )));
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,123 @@
+//! A thread-safe handle used to interrupt a runtime's execution
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use deno_core::v8;
+
+/// A `Send`-able handle to a runtime, which can interrupt javascript execution from any thread
+///
+/// Obtained with [`crate::Runtime::interrupt_handle`]. Useful for watchdogs enforcing a global
+/// deadline that spans many calls, independent of the runtime's own timeout
+///
+/// Terminating stops the currently running script, and the call in progress returns [`crate::Error::Interrupted`]  
+/// If no script is running, the next one to run will be interrupted instead
+///
+/// The runtime remains usable afterwards
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{ Runtime, Error };
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Error> {
+/// let mut runtime = Runtime::new(Default::default())?;
+/// let handle = runtime.interrupt_handle();
+///
+/// std::thread::spawn(move || {
+///     std::thread::sleep(Duration::from_secs(1));
+///     handle.terminate();
+/// });
+///
+/// let result = runtime.eval::<()>("while (true) {}");
+/// assert!(matches!(result, Err(Error::Interrupted)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct InterruptHandle {
+    isolate: v8::IsolateHandle,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    pub(crate) fn new(isolate: v8::IsolateHandle) -> Self {
+        Self {
+            isolate,
+            interrupted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Terminate the runtime's javascript execution
+    /// Has no effect if the runtime has been dropped
+    pub fn terminate(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.isolate.terminate_execution();
+    }
+
+    /// Returns true if an interrupt has been requested, and not yet handled by the runtime
+    #[must_use]
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Clears a pending interrupt, allowing the runtime to resume execution
+    /// Returns true if there was an interrupt to clear
+    pub(crate) fn clear(&self) -> bool {
+        if self.interrupted.swap(false, Ordering::SeqCst) {
+            self.isolate.cancel_terminate_execution();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Terminates a runtime's javascript execution if it is still running at a deadline
+///
+/// The thread is joined by [`Watchdog::stop`], so it can never terminate a later call
+pub(crate) struct Watchdog {
+    interrupt: InterruptHandle,
+    cancel: mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<bool>,
+}
+
+impl Watchdog {
+    /// Starts a thread that terminates the runtime once `deadline` has passed, unless stopped first
+    pub(crate) fn start(interrupt: &InterruptHandle, deadline: Duration) -> Self {
+        let (cancel, cancelled) = mpsc::channel::<()>();
+        let thread = {
+            let interrupt = interrupt.clone();
+            std::thread::spawn(move || {
+                let expired = cancelled.recv_timeout(deadline) == Err(RecvTimeoutError::Timeout);
+                if expired {
+                    interrupt.terminate();
+                }
+                expired
+            })
+        };
+
+        Self {
+            interrupt: interrupt.clone(),
+            cancel,
+            thread,
+        }
+    }
+
+    /// Stops the watchdog and waits for its thread to exit, returning true if it terminated the runtime
+    ///
+    /// If it did, the interrupt is cleared so that the runtime can run scripts again
+    pub(crate) fn stop(self) -> bool {
+        drop(self.cancel);
+        let fired = self.thread.join().unwrap_or(false);
+        if fired {
+            self.interrupt.clear();
+        }
+        fired
+    }
+}
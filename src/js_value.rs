@@ -222,14 +222,408 @@ impl Value {
     where
         T: serde::de::DeserializeOwned,
     {
-        let context = runtime.deno_runtime().main_context();
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = scope.init();
-        let context_local = v8::Local::new(&scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
-        let local = self.0.as_local(&context_scope);
-        Ok(deno_core::serde_v8::from_v8(&mut context_scope, local)?)
+        runtime.with_scope(|scope| {
+            let local = self.0.as_local(scope);
+            Ok(deno_core::serde_v8::from_v8(scope, local)?)
+        })
+    }
+
+    /// Calls this value as a function, with no `this` binding  
+    /// A shortcut for converting to a [`Function`] and using [`Function::call`]
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// # Errors
+    /// Will return [`crate::Error::ValueNotCallable`] if this value is not a function,
+    /// or an error if the function throws, or its result cannot be deserialized into the given type
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{js_value::Value, json_args, Runtime, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let value: Value = runtime.eval("(a, b) => a + b")?;
+    ///
+    /// let sum: u32 = value.call(&mut runtime, json_args!(1, 2))?;
+    /// assert_eq!(sum, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call<T>(
+        &self,
+        runtime: &mut crate::Runtime,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, crate::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let function: Function = self.as_v8().clone().try_into()?;
+        function.call(runtime, None, args)
+    }
+
+    /// Creates a live javascript value from JSON  
+    /// The counterpart to `try_into::<serde_json::Value>`
+    ///
+    /// # Errors
+    /// Will return an error if the value cannot be converted
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{js_value::Value, Runtime, Error};
+    /// use rustyscript::deno_core::serde_json::json;
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let value = Value::from_json(&mut runtime, &json!({ "id": 1, "tags": ["a", "b"] }))?;
+    ///
+    /// let json: rustyscript::deno_core::serde_json::Value = value.try_into(&mut runtime)?;
+    /// assert_eq!(json["tags"][1], "b");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json(
+        runtime: &mut crate::Runtime,
+        json: &deno_core::serde_json::Value,
+    ) -> Result<Self, crate::Error> {
+        runtime.with_scope(|scope| {
+            let local = deno_core::serde_v8::to_v8(scope, json)?;
+            Ok(Self::from_v8(v8::Global::new(scope, local)))
+        })
+    }
+
+    /// Reads a single property of this value, without deserializing the rest of the object
+    ///
+    /// Returns `None` if the property does not exist, or is `undefined`
+    ///
+    /// # Errors
+    /// Will return an error if this value is not an object, if the getter throws,
+    /// or if the property cannot be deserialized into the given type
+    pub fn get_property<T>(
+        &self,
+        runtime: &mut crate::Runtime,
+        key: &str,
+    ) -> Result<Option<T>, crate::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        runtime.with_scope(|scope| {
+            let object = self.0.as_local(scope);
+            let object = v8::Local::<v8::Object>::try_from(object).map_err(|_| {
+                crate::Error::Runtime(format!(
+                    "Expected an object, found `{}`",
+                    object.type_repr()
+                ))
+            })?;
+            let key = v8::String::new(scope, key)
+                .ok_or_else(|| crate::Error::V8Encoding(key.to_string()))?;
+
+            v8::tc_scope!(let tc_scope, scope);
+            let Some(value) = object.get(tc_scope, key.into()) else {
+                return Err(property_exception(tc_scope));
+            };
+
+            if value.is_undefined() {
+                Ok(None)
+            } else {
+                Ok(Some(deno_core::serde_v8::from_v8(tc_scope, value)?))
+            }
+        })
+    }
+
+    /// Sets a single property of this value, without deserializing the rest of the object
+    ///
+    /// The assignment has strict-mode semantics, so setting a property of a frozen object,
+    /// or a read-only property, is an error rather than being silently ignored
+    ///
+    /// # Errors
+    /// Will return an error if this value is not an object, if the value cannot be serialized,
+    /// or if the assignment fails
+    pub fn set_property<T>(
+        &self,
+        runtime: &mut crate::Runtime,
+        key: &str,
+        value: &T,
+    ) -> Result<(), crate::Error>
+    where
+        T: serde::Serialize,
+    {
+        // v8's own `Object::set` is sloppy, and ignores failed assignments
+        runtime.with_helper_function(STRICT_SET_SCRIPT, |scope, setter| {
+            let object = self.0.as_local(scope);
+            if !object.is_object() {
+                return Err(crate::Error::Runtime(format!(
+                    "Expected an object, found `{}`",
+                    object.type_repr()
+                )));
+            }
+            let key: v8::Local<v8::Value> = v8::String::new(scope, key)
+                .ok_or_else(|| crate::Error::V8Encoding(key.to_string()))?
+                .into();
+            let value = deno_core::serde_v8::to_v8(scope, value)?;
+
+            v8::tc_scope!(let tc_scope, scope);
+            let recv = v8::undefined(tc_scope).into();
+            match setter.call(tc_scope, recv, &[object, key, value]) {
+                Some(_) => Ok(()),
+                None => Err(property_exception(tc_scope)),
+            }
+        })
+    }
+
+    /// Freezes this value, as `Object.freeze` would, so that it can be handed to untrusted code read-only
+    ///
+    /// If `deep` is true, every object reachable through this value's own data properties is frozen as well  
+    /// Accessor properties are not invoked, so values returned by getters are left as-is
+    ///
+    /// The object graph is walked with v8's own operations rather than the javascript built-ins,
+    /// so scripts that replaced `Object.freeze` or `Reflect` cannot interfere with it
+    ///
+    /// Writes to a frozen object are ignored in sloppy-mode code, and throw in strict-mode code  
+    /// Freezing a primitive value does nothing
+    ///
+    /// # Errors
+    /// Will return an error if an object cannot be frozen, such as a non-empty typed array, or if a proxy trap throws
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{js_value::Value, Runtime, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let config: Value = runtime.eval("({ plugins: { enabled: true } })")?;
+    /// config.freeze(&mut runtime, true)?;
+    ///
+    /// let plugins: Value = config.get_property(&mut runtime, "plugins")?.unwrap();
+    /// assert!(plugins.set_property(&mut runtime, "enabled", &false).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn freeze(&self, runtime: &mut crate::Runtime, deep: bool) -> Result<(), crate::Error> {
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            let value_key: v8::Local<v8::Name> = v8::String::new(scope, "value")
+                .ok_or_else(|| crate::Error::V8Encoding("value".to_string()))?
+                .into();
+            v8::tc_scope!(let tc_scope, scope);
+
+            // Objects already frozen by this call, bucketed by identity hash
+            let mut seen: std::collections::HashMap<i32, Vec<v8::Local<v8::Object>>> =
+                std::collections::HashMap::new();
+            let mut stack = vec![value];
+            while let Some(value) = stack.pop() {
+                let Ok(object) = v8::Local::<v8::Object>::try_from(value) else {
+                    continue;
+                };
+                let bucket = seen.entry(object.get_identity_hash().get()).or_default();
+                if bucket.contains(&object) {
+                    continue;
+                }
+                bucket.push(object);
+
+                match object.set_integrity_level(tc_scope, v8::IntegrityLevel::Frozen) {
+                    Some(true) => {}
+                    Some(false) => {
+                        return Err(crate::Error::Runtime(
+                            "Value could not be frozen".to_string(),
+                        ))
+                    }
+                    None => return Err(property_exception(tc_scope)),
+                }
+                if !deep {
+                    break;
+                }
+
+                let args = v8::GetPropertyNamesArgs {
+                    mode: v8::KeyCollectionMode::OwnOnly,
+                    property_filter: v8::PropertyFilter::ALL_PROPERTIES,
+                    index_filter: v8::IndexFilter::IncludeIndices,
+                    key_conversion: v8::KeyConversionMode::ConvertToString,
+                };
+                let Some(keys) = object.get_own_property_names(tc_scope, args) else {
+                    return Err(property_exception(tc_scope));
+                };
+                for i in 0..keys.length() {
+                    let Some(key) = keys.get_index(tc_scope, i) else {
+                        return Err(property_exception(tc_scope));
+                    };
+                    let Ok(key) = v8::Local::<v8::Name>::try_from(key) else {
+                        continue;
+                    };
+                    let Some(descriptor) = object.get_own_property_descriptor(tc_scope, key) else {
+                        return Err(property_exception(tc_scope));
+                    };
+
+                    // Only data descriptors have a `value` - accessors are left alone
+                    let Ok(descriptor) = v8::Local::<v8::Object>::try_from(descriptor) else {
+                        continue;
+                    };
+                    if descriptor.has_own_property(tc_scope, value_key) == Some(true) {
+                        if let Some(value) = descriptor.get(tc_scope, value_key.into()) {
+                            stack.push(value);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the names of this object's own enumerable string-keyed properties, in the order of `Object.keys`
+    ///
+    /// Symbol keys are excluded, and array indices are returned as strings
+    ///
+    /// # Errors
+    /// Will return an error if this value is not an object, or if a proxy trap throws
+    pub fn keys(
+        &self,
+        runtime: &mut crate::Runtime,
+    ) -> Result<Vec<std::string::String>, crate::Error> {
+        Ok(self
+            .own_properties(runtime, false)?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Returns the own enumerable string-keyed properties of this object, with their values, in the order of `Object.entries`
+    ///
+    /// Values are not deserialized, so they can be of any type, and can be reflected over in turn
+    ///
+    /// # Errors
+    /// Will return an error if this value is not an object, or if a getter or proxy trap throws
+    pub fn entries(
+        &self,
+        runtime: &mut crate::Runtime,
+    ) -> Result<Vec<(std::string::String, Value)>, crate::Error> {
+        Ok(self
+            .own_properties(runtime, true)?
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect())
+    }
+
+    /// Collects the own enumerable string keys of this object, and optionally their values
+    fn own_properties(
+        &self,
+        runtime: &mut crate::Runtime,
+        with_values: bool,
+    ) -> Result<Vec<(std::string::String, Option<Value>)>, crate::Error> {
+        runtime.with_scope(|scope| {
+            let object = self.0.as_local(scope);
+            let object = v8::Local::<v8::Object>::try_from(object).map_err(|_| {
+                crate::Error::Runtime(format!(
+                    "Expected an object, found `{}`",
+                    object.type_repr()
+                ))
+            })?;
+
+            let properties = own_properties(
+                scope,
+                object,
+                v8::PropertyFilter::ONLY_ENUMERABLE,
+                with_values,
+            )?;
+            Ok(properties
+                .into_iter()
+                .map(|(key, value)| (key, value.map(Value::from_v8)))
+                .collect())
+        })
+    }
+
+    /// Formats this value for display, the same way `console.log` would
+    /// Uses the console extension's `Deno.inspect`, so it is not available if that extension is disabled
+    ///
+    /// # Errors
+    /// Will return an error if `Deno.inspect` is not available, or if a custom inspect function throws
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{js_value::{InspectOptions, Value}, Runtime, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let value: Value = runtime.eval("({ a: [1, 2, 3], b: new Map([['c', 4]]) })")?;
+    ///
+    /// let text = value.inspect(&mut runtime, InspectOptions::default())?;
+    /// println!("{text}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inspect(
+        &self,
+        runtime: &mut crate::Runtime,
+        options: InspectOptions,
+    ) -> Result<std::string::String, crate::Error> {
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            let options = deno_core::serde_v8::to_v8(scope, options)?;
+
+            let global = scope.get_current_context().global(scope);
+            let inspect = ["Deno", "inspect"].iter().try_fold(global, |object, key| {
+                let key = v8::String::new(scope, key)?;
+                object
+                    .get(scope, key.into())
+                    .and_then(|value| v8::Local::<v8::Object>::try_from(value).ok())
+            });
+            let inspect = inspect
+                .and_then(|inspect| v8::Local::<v8::Function>::try_from(inspect).ok())
+                .ok_or_else(|| crate::Error::ValueNotFound("Deno.inspect".to_string()))?;
+
+            v8::tc_scope!(let tc_scope, scope);
+            let recv = v8::undefined(tc_scope).into();
+            match inspect.call(tc_scope, recv, &[value, options]) {
+                Some(text) => Ok(text.to_rust_string_lossy(tc_scope)),
+                None => Err(property_exception(tc_scope)),
+            }
+        })
+    }
+
+    /// Reads this value as a number, without going through serde  
+    /// Returns `None` if the value is not a number
+    #[must_use]
+    pub fn as_f64(&self, runtime: &mut crate::Runtime) -> Option<f64> {
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            value
+                .is_number()
+                .then(|| value.number_value(scope))
+                .flatten()
+        })
+    }
+
+    /// Reads this value as an integer, without going through serde  
+    /// Returns `None` if the value is not a number or `BigInt`, or is not an integer that fits in an `i64`
+    #[must_use]
+    pub fn as_i64(&self, runtime: &mut crate::Runtime) -> Option<i64> {
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            if let Ok(bigint) = v8::Local::<v8::BigInt>::try_from(value) {
+                let (n, lossless) = bigint.i64_value();
+                return lossless.then_some(n);
+            }
+
+            let n = value
+                .is_number()
+                .then(|| value.number_value(scope))
+                .flatten()?;
+            #[allow(clippy::cast_precision_loss)]
+            let in_range = n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64;
+            #[allow(clippy::cast_possible_truncation)]
+            in_range.then_some(n as i64)
+        })
+    }
+
+    /// Reads this value as a boolean, without going through serde  
+    /// Returns `None` if the value is not a boolean - no truthiness conversion is done
+    #[must_use]
+    pub fn as_bool(&self, runtime: &mut crate::Runtime) -> Option<bool> {
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            value.is_boolean().then(|| value.boolean_value(scope))
+        })
     }
 
     /// Contructs a new Value from a `v8::Value` global
@@ -239,6 +633,96 @@ impl Value {
     }
 }
 
+/// Options for [`Value::inspect`], mirroring those of `Deno.inspect`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectOptions {
+    /// How many levels of nested objects to render before abbreviating them
+    /// Defaults to 4
+    pub depth: u32,
+
+    /// Use ANSI colors in the output
+    pub colors: bool,
+
+    /// Sort the keys of objects, maps and sets
+    pub sorted: bool,
+
+    /// Include non-enumerable properties
+    pub show_hidden: bool,
+
+    /// The maximum number of entries of an array or other iterable to render
+    /// Defaults to 100
+    pub iterable_limit: usize,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            colors: false,
+            sorted: false,
+            show_hidden: false,
+            iterable_limit: 100,
+        }
+    }
+}
+
+/// Assigns a property with strict-mode semantics, used by [`Value::set_property`]
+const STRICT_SET_SCRIPT: &str = "(function (o, k, v) { 'use strict'; o[k] = v; })";
+
+/// Keys of an object, with their values if requested, see [`own_properties`]
+pub(crate) type OwnProperties = Vec<(std::string::String, Option<v8::Global<v8::Value>>)>;
+
+/// Collects the own string keys of `object` that pass `property_filter`, and optionally their values
+///
+/// Symbol keys are always excluded, and array indices are returned as strings
+pub(crate) fn own_properties(
+    scope: &mut v8::PinScope<'_, '_>,
+    object: v8::Local<v8::Object>,
+    property_filter: v8::PropertyFilter,
+    with_values: bool,
+) -> Result<OwnProperties, crate::Error> {
+    v8::tc_scope!(let tc_scope, scope);
+    let args = v8::GetPropertyNamesArgs {
+        mode: v8::KeyCollectionMode::OwnOnly,
+        property_filter: property_filter | v8::PropertyFilter::SKIP_SYMBOLS,
+        index_filter: v8::IndexFilter::IncludeIndices,
+        key_conversion: v8::KeyConversionMode::ConvertToString,
+    };
+    let Some(names) = object.get_own_property_names(tc_scope, args) else {
+        return Err(property_exception(tc_scope));
+    };
+
+    let mut properties = Vec::with_capacity(names.length() as usize);
+    for i in 0..names.length() {
+        let Some(key) = names.get_index(tc_scope, i) else {
+            return Err(property_exception(tc_scope));
+        };
+        let value = if with_values {
+            let Some(value) = object.get(tc_scope, key) else {
+                return Err(property_exception(tc_scope));
+            };
+            Some(v8::Global::new(tc_scope, value))
+        } else {
+            None
+        };
+        properties.push((key.to_rust_string_lossy(tc_scope), value));
+    }
+    Ok(properties)
+}
+
+/// Converts the exception caught while accessing a property, or calling into javascript, into an error
+fn property_exception(
+    scope: &mut v8::PinnedRef<'_, v8::TryCatch<v8::HandleScope>>,
+) -> crate::Error {
+    match scope.exception() {
+        Some(exception) if !scope.is_execution_terminating() => {
+            deno_core::error::JsError::from_v8_exception(scope, exception).into()
+        }
+        _ => crate::Error::Runtime("Property access failed".to_string()),
+    }
+}
+
 mod function;
 pub use function::*;
 
@@ -251,10 +735,13 @@ pub use string::*;
 mod map;
 pub use map::*;
 
+mod bytes;
+pub use bytes::*;
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{Module, Runtime, RuntimeOptions};
+    use crate::{json_args, Module, Runtime, RuntimeOptions};
 
     #[test]
     fn test_value() {
@@ -279,20 +766,225 @@ mod test {
         let global = g.into_v8();
 
         // Test try_from_v8 with proper context scope
-        let context = runtime.deno_runtime().main_context();
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let pinned = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = pinned.init();
-        let context_local = v8::Local::new(&scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
+        runtime.with_scope(|scope| {
+            // Test that we can create a Function from the global
+            let _f = Function::try_from_v8(scope, global.clone()).unwrap();
+
+            // Test from_v8_unchecked
+            let f = unsafe { Function::from_v8_unchecked(global.clone()) };
+
+            // Test as_local with context scope
+            let _local = f.into_inner().as_local(scope);
+        });
+    }
+
+    #[test]
+    fn test_value_inspect() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let value: Value = runtime
+            .eval("({ b: 'text', a: [1, 2, 3], nested: { deeper: { deepest: {} } } })")
+            .unwrap();
+
+        let text = value
+            .inspect(&mut runtime, InspectOptions::default())
+            .unwrap();
+        assert_eq!(
+            text,
+            "{ b: \"text\", a: [ 1, 2, 3 ], nested: { deeper: { deepest: {} } } }"
+        );
+
+        let text = value
+            .inspect(
+                &mut runtime,
+                InspectOptions {
+                    depth: 1,
+                    sorted: true,
+                    iterable_limit: 2,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            text,
+            "{ a: [ 1, 2, ... 1 more item ], b: \"text\", nested: { deeper: [Object] } }"
+        );
+
+        let text = value
+            .inspect(
+                &mut runtime,
+                InspectOptions {
+                    colors: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(text.contains("\x1b["));
+    }
+
+    #[test]
+    fn test_value_from_json() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let json = deno_core::serde_json::json!({
+            "name": "msg",
+            "tags": ["a", "b"],
+            "nested": { "ok": true },
+        });
+        let value = Value::from_json(&mut runtime, &json).unwrap();
+
+        // The value is a live object
+        let tags: Option<Vec<std::string::String>> =
+            value.get_property(&mut runtime, "tags").unwrap();
+        assert_eq!(tags.unwrap(), vec!["a", "b"]);
+        value
+            .set_property(&mut runtime, "name", &"changed")
+            .unwrap();
+
+        let roundtrip: deno_core::serde_json::Value = value.try_into(&mut runtime).unwrap();
+        assert_eq!(roundtrip["name"], "changed");
+        assert_eq!(roundtrip["nested"], json["nested"]);
+    }
+
+    #[test]
+    fn test_value_properties() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let config: Value = runtime
+            .eval("({ name: 'test', nested: { a: 1 } })")
+            .unwrap();
+        let name: Option<std::string::String> = config.get_property(&mut runtime, "name").unwrap();
+        assert_eq!(name.as_deref(), Some("test"));
+
+        let missing: Option<std::string::String> =
+            config.get_property(&mut runtime, "missing").unwrap();
+        assert!(missing.is_none());
+
+        config.set_property(&mut runtime, "count", &5).unwrap();
+        let count: Option<u32> = config.get_property(&mut runtime, "count").unwrap();
+        assert_eq!(count, Some(5));
+
+        let frozen: Value = runtime.eval("Object.freeze({ a: 1 })").unwrap();
+        frozen.set_property(&mut runtime, "a", &2).unwrap_err();
+
+        let number: Value = runtime.eval("5").unwrap();
+        number.get_property::<u32>(&mut runtime, "a").unwrap_err();
+    }
+
+    #[test]
+    fn test_value_freeze() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let shallow: Value = runtime.eval("({ a: 1, nested: { b: 2 } })").unwrap();
+        shallow.freeze(&mut runtime, false).unwrap();
+        shallow.set_property(&mut runtime, "a", &2).unwrap_err();
+        let nested: Value = shallow
+            .get_property(&mut runtime, "nested")
+            .unwrap()
+            .unwrap();
+        nested.set_property(&mut runtime, "b", &3).unwrap();
+
+        // Replacing the built-ins does not affect freezing
+        runtime
+            .eval::<()>(
+                "Object.freeze = (v) => v; Reflect.ownKeys = () => []; globalThis.Set = null;",
+            )
+            .unwrap();
+
+        let deep: Value = runtime
+            .eval("globalThis.frozen = { list: [{ c: 1 }] }; frozen.self = frozen; frozen")
+            .unwrap();
+        deep.freeze(&mut runtime, true).unwrap();
+        let mutated: bool = runtime
+            .eval(
+                "(() => {
+                    frozen.list[0].c = 2;
+                    return frozen.list[0].c !== 1 || !Object.isFrozen(frozen.list);
+                })()",
+            )
+            .unwrap();
+        assert!(!mutated);
+
+        let rejected: bool = runtime
+            .eval("(() => { 'use strict'; try { frozen.list.push(1); return false; } catch { return true; } })()")
+            .unwrap();
+        assert!(rejected);
+
+        let number: Value = runtime.eval("5").unwrap();
+        number.freeze(&mut runtime, true).unwrap();
+    }
+
+    #[test]
+    fn test_value_keys_and_entries() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let value: Value = runtime
+            .eval(
+                "(() => {
+                    const object = { b: 1, a: 'two', 3: [3], [Symbol('hidden')]: 4 };
+                    Object.defineProperty(object, 'secret', { value: 5, enumerable: false });
+                    return Object.create({ inherited: 6 }, Object.getOwnPropertyDescriptors(object));
+                })()",
+            )
+            .unwrap();
+        assert_eq!(value.keys(&mut runtime).unwrap(), vec!["3", "b", "a"]);
+
+        let entries = value.entries(&mut runtime).unwrap();
+        let keys: Vec<_> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["3", "b", "a"]);
+        assert_eq!(entries[1].1.as_i64(&mut runtime), Some(1));
+        let nested: Vec<u32> = entries[0].1.clone().try_into(&mut runtime).unwrap();
+        assert_eq!(nested, vec![3]);
+
+        let array: Value = runtime.eval("['x', 'y']").unwrap();
+        assert_eq!(array.keys(&mut runtime).unwrap(), vec!["0", "1"]);
+
+        let number: Value = runtime.eval("5").unwrap();
+        number.keys(&mut runtime).unwrap_err();
+        number.entries(&mut runtime).unwrap_err();
+    }
+
+    #[test]
+    fn test_value_call() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let value: Value = runtime.eval("(a, b) => a + b").unwrap();
+        let sum: u32 = value.call(&mut runtime, json_args!(1, 2)).unwrap();
+        assert_eq!(sum, 3);
+
+        let value: Value = runtime.eval("async () => 'later'").unwrap();
+        let result: std::string::String = value.call(&mut runtime, json_args!()).unwrap();
+        assert_eq!(result, "later");
+
+        let value: Value = runtime.eval("({ a: 1 })").unwrap();
+        let error = value.call::<u32>(&mut runtime, json_args!()).unwrap_err();
+        assert!(matches!(error, crate::Error::ValueNotCallable(_)));
+    }
+
+    #[test]
+    fn test_value_scalars() {
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+
+        let number: Value = runtime.eval("2.5").unwrap();
+        assert_eq!(number.as_f64(&mut runtime), Some(2.5));
+        assert_eq!(number.as_i64(&mut runtime), None);
+        assert_eq!(number.as_bool(&mut runtime), None);
+
+        let integer: Value = runtime.eval("-42").unwrap();
+        assert_eq!(integer.as_i64(&mut runtime), Some(-42));
+        assert_eq!(integer.as_f64(&mut runtime), Some(-42.0));
 
-        // Test that we can create a Function from the global
-        let _f = Function::try_from_v8(&mut context_scope, global.clone()).unwrap();
+        let bigint: Value = runtime.eval("9007199254740993n").unwrap();
+        assert_eq!(bigint.as_i64(&mut runtime), Some(9_007_199_254_740_993));
+        assert_eq!(bigint.as_f64(&mut runtime), None);
 
-        // Test from_v8_unchecked
-        let f = unsafe { Function::from_v8_unchecked(global.clone()) };
+        let boolean: Value = runtime.eval("true").unwrap();
+        assert_eq!(boolean.as_bool(&mut runtime), Some(true));
+        assert_eq!(boolean.as_f64(&mut runtime), None);
 
-        // Test as_local with context scope
-        let _local = f.into_inner().as_local(&context_scope);
+        let string: Value = runtime.eval("'1'").unwrap();
+        assert_eq!(string.as_f64(&mut runtime), None);
+        assert_eq!(string.as_i64(&mut runtime), None);
+        assert_eq!(string.as_bool(&mut runtime), None);
     }
 }
@@ -0,0 +1,133 @@
+use std::cell::Cell;
+
+use deno_core::serde_v8::ToJsBuffer;
+use serde::{Serialize, Serializer};
+
+/// Binary data for a function argument, which javascript receives as a `Uint8Array`
+///
+/// A plain `Vec<u8>` argument arrives as an array of numbers instead
+/// The buffer is moved into the backing store of the array rather than copied, so it is consumed by the call
+///
+/// Can be used anywhere in the arguments, including inside of structs and collections
+/// It only has a meaningful representation when passed to javascript - other serializers such as `serde_json` cannot represent it
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{ Runtime, Module, Error, js_value::Bytes, json_args };
+///
+/// # fn main() -> Result<(), Error> {
+/// let module = Module::new("test.js", "
+///     export function invert(pixels) {
+///         return pixels.map(p => 255 - p).length;
+///     }
+/// ");
+///
+/// let mut runtime = Runtime::new(Default::default())?;
+/// let handle = runtime.load_module(&module)?;
+///
+/// let pixels = vec![0u8; 1024 * 1024];
+/// let len: usize = runtime.call_function(Some(&handle), "invert", json_args!(Bytes::new(pixels)))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Bytes(Cell<Vec<u8>>);
+
+impl Bytes {
+    /// Wrap binary data to be passed to javascript as a `Uint8Array`
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self(Cell::new(data.into()))
+    }
+
+    /// Returns the data, which is empty if it has already been passed to a function
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0.into_inner()
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(data: &[u8]) -> Self {
+        Self::new(data)
+    }
+}
+
+impl std::fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let data = self.0.take();
+        let result = f.debug_tuple("Bytes").field(&data.len()).finish();
+        self.0.set(data);
+        result
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // serde_v8 moves the buffer into a `Uint8Array` during this call
+        ToJsBuffer::from(self.0.take()).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{json_args, Module, Runtime, RuntimeOptions};
+
+    #[test]
+    fn test_bytes() {
+        #[derive(Serialize)]
+        struct Nested {
+            data: Bytes,
+            list: Vec<u8>,
+        }
+
+        let module = Module::new(
+            "test.js",
+            "
+            export function describe(bytes, nested) {
+                return [
+                    bytes instanceof Uint8Array,
+                    bytes.length,
+                    bytes[1],
+                    nested.data instanceof Uint8Array,
+                    nested.data.length,
+                    Array.isArray(nested.list),
+                ];
+            }
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let bytes = Bytes::new(vec![1, 2, 3]);
+        let nested = Nested {
+            data: Bytes::new(vec![9; 4]),
+            list: vec![1, 2],
+        };
+        let result: deno_core::serde_json::Value = runtime
+            .call_function(Some(&handle), "describe", json_args!(&bytes, &nested))
+            .expect("Could not call function");
+        assert_eq!(
+            result,
+            deno_core::serde_json::json!([true, 3, 2, true, 4, true])
+        );
+
+        // The buffer was moved into javascript, so passing it again sends an empty array
+        let result: deno_core::serde_json::Value = runtime
+            .call_function(Some(&handle), "describe", json_args!(&bytes, &nested))
+            .expect("Could not call function");
+        assert_eq!(
+            result,
+            deno_core::serde_json::json!([true, 0, null, true, 0, true])
+        );
+        assert!(bytes.into_inner().is_empty());
+    }
+}
@@ -25,6 +25,67 @@ impl Function {
         unsafe_f.is_async_function()
     }
 
+    /// Returns the function's `name` property  
+    /// Anonymous functions have an empty name, as in javascript
+    #[must_use]
+    pub fn name(&self, runtime: &mut crate::Runtime) -> String {
+        runtime.with_scope(|scope| {
+            self.property(scope, "name")
+                .filter(|v| v.is_string())
+                .map(|v| v.to_rust_string_lossy(scope))
+                .unwrap_or_default()
+        })
+    }
+
+    /// Returns the number of declared parameters, the function's `length` property  
+    /// Rest parameters, and parameters after the first one with a default value, are not counted
+    #[must_use]
+    pub fn arity(&self, runtime: &mut crate::Runtime) -> usize {
+        runtime.with_scope(|scope| {
+            self.property(scope, "length")
+                .filter(|v| v.is_number())
+                .and_then(|v| v.uint32_value(scope))
+                .and_then(|length| usize::try_from(length).ok())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Returns the function's source text, as `Function.prototype.toString` would  
+    /// Native functions, such as built-ins or functions registered from rust, return the `[native code]` form
+    #[must_use]
+    pub fn to_string(&self, runtime: &mut crate::Runtime) -> String {
+        runtime.with_scope(|scope| {
+            let global: v8::Local<v8::Value> = scope.get_current_context().global(scope).into();
+            v8::tc_scope!(let tc_scope, scope);
+
+            let function = v8::Local::new(tc_scope, &self.0 .0);
+            ["Function", "prototype", "toString"]
+                .into_iter()
+                .try_fold(global, |object, name| {
+                    let object = v8::Local::<v8::Object>::try_from(object).ok()?;
+                    let key = v8::String::new(tc_scope, name)?;
+                    object.get(tc_scope, key.into())
+                })
+                .and_then(|f| v8::Local::<v8::Function>::try_from(f).ok())
+                .and_then(|to_string| to_string.call(tc_scope, function, &[]))
+                .filter(|source| source.is_string())
+                .map(|source| source.to_rust_string_lossy(tc_scope))
+                .unwrap_or_default()
+        })
+    }
+
+    /// Reads a property of the function
+    fn property<'s>(
+        &self,
+        scope: &v8::PinScope<'s, '_>,
+        name: &str,
+    ) -> Option<v8::Local<'s, v8::Value>> {
+        let function = v8::Local::new(scope, &self.0 .0);
+        let function = v8::Local::<v8::Object>::try_from(function).ok()?;
+        let key = v8::String::new(scope, name)?;
+        function.get(scope, key.into())
+    }
+
     /// Calls this function. See [`crate::Runtime::call_stored_function`]
     /// Blocks until:
     /// - The event loop is resolved, and
@@ -115,4 +176,61 @@ mod test {
         let value = value.into_value(&mut runtime).unwrap();
         assert_eq!(value, 42);
     }
+
+    #[test]
+    fn test_function_introspection() {
+        let module = Module::new(
+            "test.js",
+            "
+            export function add(a, b) { return a + b; }
+            export const rest = (a, ...others) => a;
+            export const defaults = (a, b = 1, c) => a;
+            export const anonymous = [function() {}][0];
+        ",
+        );
+
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+        let handle = runtime.load_module(&module).unwrap();
+
+        let add: Function = runtime.get_value(Some(&handle), "add").unwrap();
+        assert_eq!(add.name(&mut runtime), "add");
+        assert_eq!(add.arity(&mut runtime), 2);
+
+        let rest: Function = runtime.get_value(Some(&handle), "rest").unwrap();
+        assert_eq!(rest.name(&mut runtime), "rest");
+        assert_eq!(rest.arity(&mut runtime), 1);
+
+        let defaults: Function = runtime.get_value(Some(&handle), "defaults").unwrap();
+        assert_eq!(defaults.arity(&mut runtime), 1);
+
+        let anonymous: Function = runtime.get_value(Some(&handle), "anonymous").unwrap();
+        assert_eq!(anonymous.name(&mut runtime), "");
+        assert_eq!(anonymous.arity(&mut runtime), 0);
+    }
+
+    #[test]
+    fn test_function_to_string() {
+        let module = Module::new(
+            "test.js",
+            "
+            export function add(a, b) { return a + b; }
+            export const native = Math.max;
+        ",
+        );
+
+        let mut runtime = Runtime::new(RuntimeOptions::default()).unwrap();
+        let handle = runtime.load_module(&module).unwrap();
+
+        let add: Function = runtime.get_value(Some(&handle), "add").unwrap();
+        assert_eq!(
+            add.to_string(&mut runtime),
+            "function add(a, b) { return a + b; }"
+        );
+
+        let native: Function = runtime.get_value(Some(&handle), "native").unwrap();
+        assert_eq!(
+            native.to_string(&mut runtime),
+            "function max() { [native code] }"
+        );
+    }
 }
@@ -21,14 +21,7 @@ impl Map {
     /// Gets a value from the map
     /// Warning: If a key is not valid UTF-8, the value may be inaccessible
     pub fn get(&self, key: &str, runtime: &mut crate::Runtime) -> Option<crate::js_value::Value> {
-        let deno_runtime = runtime.deno_runtime();
-        let context = deno_runtime.main_context();
-        let isolate = deno_runtime.v8_isolate();
-        let handle_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut handle_scope = handle_scope.init();
-        let context_local = v8::Local::new(&handle_scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut handle_scope, context_local);
-        self.get_property_by_name_impl(&mut context_scope, key)
+        runtime.with_scope(|scope| self.get_property_by_name_impl(scope, key))
     }
 
     /// Converts the map to a hashmap
@@ -37,45 +30,24 @@ impl Map {
         &self,
         runtime: &mut crate::Runtime,
     ) -> std::collections::HashMap<String, crate::js_value::Value> {
-        let deno_runtime = runtime.deno_runtime();
-        let context = deno_runtime.main_context();
-        let isolate = deno_runtime.v8_isolate();
-        let handle_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut handle_scope = handle_scope.init();
-        let context_local = v8::Local::new(&handle_scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut handle_scope, context_local);
-        self.to_rust_hashmap_impl(&mut context_scope)
+        runtime.with_scope(|scope| self.to_rust_hashmap_impl(scope))
     }
 
     /// Returns the keys of the map
     /// Warning: If a key is not valid UTF-8, the value may be inaccessible
     pub fn keys(&self, runtime: &mut crate::Runtime) -> Vec<String> {
-        let deno_runtime = runtime.deno_runtime();
-        let context = deno_runtime.main_context();
-        let isolate = deno_runtime.v8_isolate();
-        let handle_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut handle_scope = handle_scope.init();
-        let context_local = v8::Local::new(&handle_scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut handle_scope, context_local);
-        self.get_string_keys_impl(&mut context_scope)
+        runtime.with_scope(|scope| self.get_string_keys_impl(scope))
     }
 
     /// Returns the number of keys in the map
     /// Skips any keys that are not valid UTF-8
     pub fn len(&self, runtime: &mut crate::Runtime) -> usize {
-        let deno_runtime = runtime.deno_runtime();
-        let context = deno_runtime.main_context();
-        let isolate = deno_runtime.v8_isolate();
-        let handle_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut handle_scope = handle_scope.init();
-        let context_local = v8::Local::new(&handle_scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut handle_scope, context_local);
-        self.get_string_keys_impl(&mut context_scope).len()
+        runtime.with_scope(|scope| self.get_string_keys_impl(scope).len())
     }
 
     fn to_rust_hashmap_impl(
         &self,
-        scope: &mut v8::ContextScope<v8::HandleScope>,
+        scope: &mut v8::PinScope<'_, '_>,
     ) -> std::collections::HashMap<String, crate::js_value::Value> {
         let keys = self.get_string_keys_impl(scope);
         let mut map = std::collections::HashMap::new();
@@ -92,7 +64,7 @@ impl Map {
 
     fn get_property_by_name_impl(
         &self,
-        scope: &mut v8::ContextScope<v8::HandleScope>,
+        scope: &mut v8::PinScope<'_, '_>,
         name: &str,
     ) -> Option<crate::js_value::Value> {
         let local = self.0.as_local(scope);
@@ -104,7 +76,7 @@ impl Map {
         Some(crate::js_value::Value::from_v8(value))
     }
 
-    fn get_string_keys_impl(&self, scope: &mut v8::ContextScope<v8::HandleScope>) -> Vec<String> {
+    fn get_string_keys_impl(&self, scope: &mut v8::PinScope<'_, '_>) -> Vec<String> {
         let local = self.0.as_local(scope);
         let mut keys = vec![];
 
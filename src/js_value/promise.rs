@@ -33,14 +33,10 @@ where
         let result = runtime
             .with_event_loop_future(future, PollEventLoopOptions::default())
             .await?;
-        let context = runtime.main_context();
-        let isolate = runtime.v8_isolate();
-        let scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = scope.init();
-        let context_local = v8::Local::new(&scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
-        let local = v8::Local::new(&context_scope, &result);
-        Ok(deno_core::serde_v8::from_v8(&mut context_scope, local)?)
+        crate::inner_runtime::with_scope(runtime, |scope| {
+            let local = v8::Local::new(scope, &result);
+            Ok(deno_core::serde_v8::from_v8(scope, local)?)
+        })
     }
 
     /// Returns a future that resolves the promise
@@ -63,43 +59,35 @@ where
 
     /// Checks if the promise is pending or already resolved
     pub fn is_pending(&self, runtime: &mut crate::Runtime) -> bool {
-        let context = runtime.deno_runtime().main_context();
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = scope.init();
-        let context_local = v8::Local::new(&scope, context);
-        let context_scope = v8::ContextScope::new(&mut scope, context_local);
-        let value = self.0.as_local(&context_scope);
-        value.state() == v8::PromiseState::Pending
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
+            value.state() == v8::PromiseState::Pending
+        })
     }
 
     /// Polls the promise, returning `Poll::Pending` if the promise is still pending
     /// or `Poll::Ready(Ok(T))` if the promise is resolved
     /// or `Poll::Ready(Err(Error))` if the promise is rejected
     pub fn poll_promise(&self, runtime: &mut crate::Runtime) -> std::task::Poll<Result<T, Error>> {
-        let context = runtime.deno_runtime().main_context();
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let mut scope = scope.init();
-        let context_local = v8::Local::new(&scope, context);
-        let mut context_scope = v8::ContextScope::new(&mut scope, context_local);
-        let value = self.0.as_local(&context_scope);
+        runtime.with_scope(|scope| {
+            let value = self.0.as_local(scope);
 
-        match value.state() {
-            PromiseState::Pending => std::task::Poll::Pending,
-            PromiseState::Rejected => {
-                let error = value.result(&context_scope);
-                let error = deno_core::error::JsError::from_v8_exception(&mut context_scope, error);
-                std::task::Poll::Ready(Err(error.into()))
-            }
-            PromiseState::Fulfilled => {
-                let result = value.result(&context_scope);
-                match deno_core::serde_v8::from_v8::<T>(&mut context_scope, result) {
-                    Ok(value) => std::task::Poll::Ready(Ok(value)),
-                    Err(e) => std::task::Poll::Ready(Err(e.into())),
+            match value.state() {
+                PromiseState::Pending => std::task::Poll::Pending,
+                PromiseState::Rejected => {
+                    let error = value.result(scope);
+                    let error = deno_core::error::JsError::from_v8_exception(scope, error);
+                    std::task::Poll::Ready(Err(error.into()))
+                }
+                PromiseState::Fulfilled => {
+                    let result = value.result(scope);
+                    match deno_core::serde_v8::from_v8::<T>(scope, result) {
+                        Ok(value) => std::task::Poll::Ready(Ok(value)),
+                        Err(e) => std::task::Poll::Ready(Err(e.into())),
+                    }
                 }
             }
-        }
+        })
     }
 }
 
@@ -16,10 +16,7 @@ impl String {
     /// Converts the string to a rust string
     /// Potentially lossy, if the string contains orphan UTF-16 surrogates
     pub fn to_string_lossy(&self, runtime: &mut crate::Runtime) -> std::string::String {
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let pinned_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let scope = pinned_scope.init();
-        self.to_rust_string_lossy(&scope)
+        runtime.with_scope(|scope| self.to_rust_string_lossy(scope))
     }
 
     /// Converts the string to a rust string
@@ -33,19 +30,13 @@ impl String {
     /// Converts the string to a UTF-8 character buffer in the form of a `Vec<u8>`
     /// Excludes the null terminator
     pub fn to_utf8_bytes(&self, runtime: &mut crate::Runtime) -> Vec<u8> {
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let pinned_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let scope = pinned_scope.init();
-        self.to_utf8_buffer(&scope)
+        runtime.with_scope(|scope| self.to_utf8_buffer(scope))
     }
 
     /// Converts the string to a UTF-16 character buffer in the form of a `Vec<u16>`
     /// Excludes the null terminator
     pub fn to_utf16_bytes(&self, runtime: &mut crate::Runtime) -> Vec<u16> {
-        let isolate = runtime.deno_runtime().v8_isolate();
-        let pinned_scope = std::pin::pin!(v8::HandleScope::new(isolate));
-        let scope = pinned_scope.init();
-        self.to_utf16_buffer(&scope)
+        runtime.with_scope(|scope| self.to_utf16_buffer(scope))
     }
 
     pub(crate) fn to_rust_string_lossy<C>(
@@ -22,6 +22,8 @@
 //! The runtime is designed to be as flexible as possible, allowing you to modify capabilities, the module loader, and more.  
 //! - Asynchronous JS is fully supported, and the runtime can be configured to run in a multithreaded environment.  
 //! - Typescript is supported, and will be transpired into JS for execution.
+//!   Types are only stripped, never checked - type errors do not prevent a module from running,
+//!   and are not reported. Run `tsc` or `deno check` ahead of time to catch them.
 //! - Node JS is supported experimentally, but is not yet fully compatible ([See the `NodeJS` Compatibility section](https://rscarson.github.io/rustyscript-book/advanced/nodejs_compatibility.md))
 //!
 //! **Unopinionated**  
@@ -309,12 +311,16 @@ pub mod module_loader;
 pub mod static_runtime;
 
 mod async_bridge;
+mod cpu_profile;
 mod ext;
 mod inner_runtime;
+mod interrupt_handle;
 mod module;
 mod module_handle;
 mod module_wrapper;
+mod repl;
 mod runtime;
+mod string_cache;
 mod traits;
 mod transpiler;
 mod utilities;
@@ -393,7 +399,7 @@ pub mod extensions {
 
 #[cfg(feature = "kv")]
 #[cfg_attr(docsrs, doc(cfg(feature = "kv")))]
-pub use ext::kv::{KvConfig, KvStore};
+pub use ext::kv::{KvBackend, KvConfig, KvStore, MemoryKvBackend};
 
 //#[cfg(feature = "cache")]
 //#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
@@ -403,23 +409,36 @@ pub use ext::kv::{KvConfig, KvStore};
 #[cfg_attr(docsrs, doc(cfg(feature = "node_experimental")))]
 pub use ext::node::resolvers::RustyResolver;
 
+pub use ext::rustyscript::{LogLevel, LogRecord};
 #[cfg(feature = "web")]
 #[cfg_attr(docsrs, doc(cfg(feature = "web")))]
 pub use ext::web::{
-    to_permissions_options, AllowlistWebPermissions, CheckedPath, DefaultWebPermissions,
-    PermissionCheckError, PermissionDeniedError, PermissionsOptions, SystemsPermissionKind,
-    WebOptions, WebPermissions,
+    to_permissions_options, AbortControllerWrapper, AllowlistWebPermissions, CheckedPath,
+    ClosureWebPermissions, DefaultWebPermissions, PermissionCheckError, PermissionDeniedError,
+    PermissionsOptions, SystemsPermissionKind, WebOptions, WebPermissions,
 };
-pub use ext::ExtensionOptions;
+
+#[cfg(feature = "websocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+pub use ext::websocket::{WebSocketClose, WebSocketEvent, WebSocketWrapper};
+
+pub use ext::{BuiltinExtension, ExtensionOptions, ExtensionSet};
 
 // Expose some important stuff from us
 pub use async_bridge::TokioRuntime;
-pub use error::Error;
+pub use cpu_profile::CpuProfile;
+pub use error::{Error, HostError};
 pub use inner_runtime::{RsAsyncFunction, RsFunction};
+pub use interrupt_handle::InterruptHandle;
 pub use module::Module;
 pub use module_handle::ModuleHandle;
 pub use module_wrapper::ModuleWrapper;
-pub use runtime::{Runtime, RuntimeOptions, Undefined};
+pub use repl::Repl;
+pub use runtime::{
+    HeapStats, IdleResult, JsonLimits, MemoryPressureAction, MicrotaskPolicy, PrefetchReport,
+    Runtime, RuntimeConfigSummary, RuntimeOptions, ShutdownReport, Undefined,
+};
+pub use transpiler::Diagnostic;
 pub use utilities::{evaluate, import, init_platform, resolve_path, validate};
 
 // Deprecated traits for backward compatibility
@@ -430,7 +449,8 @@ pub use traits::ToV8String;
 #[cfg(feature = "broadcast_channel")]
 #[cfg_attr(docsrs, doc(cfg(feature = "broadcast_channel")))]
 pub use ext::broadcast_channel::{
-    BroadcastChannelWrapper, IsolatedBroadcastChannel, IsolatedBroadcastChannelWrapper,
+    BridgeDirection, BroadcastChannelWrapper, IsolatedBroadcastChannel,
+    IsolatedBroadcastChannelWrapper,
 };
 
 #[cfg(feature = "web")]
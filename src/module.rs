@@ -9,6 +9,8 @@ use std::{
 use maybe_path::MaybePathBuf;
 use serde::{Deserialize, Serialize};
 
+use crate::{traits::ToModuleSpecifier, Error};
+
 /// Creates a static module
 ///
 /// This is just a macro around [`Module::new_static`]
@@ -248,6 +250,30 @@ impl Module {
     pub fn contents(&self) -> &str {
         &self.contents
     }
+
+    /// Checks the module for syntax errors, without a runtime and without executing it
+    ///
+    /// JS and TS are both parsed, but types are not checked  
+    /// JSON modules are not validated
+    ///
+    /// # Errors
+    /// Will return [`Error::Runtime`] describing the first syntax error, with its line and column
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyscript::Module;
+    ///
+    /// let module = Module::new("module.ts", "const x: number = ;");
+    /// assert!(module.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let specifier = self
+            .filename()
+            .to_module_specifier(&std::env::current_dir()?)?;
+        crate::transpiler::validate(&specifier, self.contents())
+            .map_err(|e| Error::Runtime(e.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -271,6 +297,27 @@ mod test_module {
         );
     }
 
+    #[test]
+    fn test_validate() {
+        Module::new("module.js", "export const x = 1;")
+            .validate()
+            .unwrap();
+        Module::new("module.ts", "export const x: number = 1;")
+            .validate()
+            .unwrap();
+
+        let e = Module::new("module.js", "const a = 1;\nconst x = ;")
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("module.js:2:11"), "{e}");
+
+        // Type annotations are only valid in typescript
+        Module::new("module.js", "const x: number = 1;")
+            .validate()
+            .unwrap_err();
+    }
+
     #[test]
     fn test_load_dir() {
         let modules =
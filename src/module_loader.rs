@@ -2,9 +2,13 @@
 //! This module provides tools for caching module data, resolving module specifiers, and loading modules
 #![allow(deprecated)]
 
-use std::{borrow::Cow, cell::RefCell, path::PathBuf, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
 
-use deno_core::{error::ModuleLoaderError, ModuleLoader, ModuleSpecifier};
+use deno_core::{
+    error::ModuleLoaderError, v8, CustomModuleEvaluationKind, FastString, ModuleLoader,
+    ModuleSourceCode, ModuleSpecifier,
+};
+use deno_error::JsErrorBox;
 
 mod inner_loader;
 use inner_loader::InnerRustyLoader;
@@ -16,7 +20,60 @@ pub use cache_provider::{ClonableSource, ModuleCacheProvider};
 mod import_provider;
 pub use import_provider::ImportProvider;
 
-use crate::transpiler::ExtensionTranspiler;
+mod source_map_provider;
+pub use source_map_provider::SourceMapProvider;
+
+mod reload_policy;
+pub use reload_policy::ReloadPolicy;
+
+mod tsconfig_paths;
+pub use tsconfig_paths::TsConfigPaths;
+
+mod scheme_handler;
+pub use scheme_handler::SchemeHandler;
+
+use crate::transpiler::{Diagnostic, ExtensionTranspiler};
+
+/// A function rewriting the source of a module after it has been transpiled,
+/// see `RuntimeOptions::module_preprocessors`
+///
+/// Receives the module's specifier and its transpiled code, and returns the new code
+pub type ModulePreprocessor =
+    Arc<dyn Fn(&ModuleSpecifier, String) -> Result<String, crate::Error> + Send + Sync>;
+
+/// A parser for a custom module type, such as `toml` or `yaml`, see `RuntimeOptions::module_type_handlers`
+///
+/// Receives the contents of the module, and returns the object it represents
+pub type ModuleTypeHandler =
+    Arc<dyn Fn(&[u8]) -> Result<deno_core::serde_json::Value, crate::Error> + Send + Sync>;
+
+/// A stage in the loading of a module, reported to a [`LoaderEventSink`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoaderEvent {
+    /// An import has been resolved to the specifier, and is about to be checked against the loader's permissions
+    Resolving,
+
+    /// The module's code is being fetched from the filesystem, the network, or an import provider
+    Fetching,
+
+    /// The module's code is being transpiled, or parsed if it is a data module
+    Transpiling,
+
+    /// The module was found in the cache provider, and will not be fetched or transpiled
+    Cached,
+}
+
+/// An observer of the module loader's progress, see `RuntimeOptions::loader_event_sink`
+///
+/// Receives each stage of loading, along with the specifier of the module it applies to
+pub type LoaderEventSink = Arc<dyn Fn(LoaderEvent, &ModuleSpecifier) + Send + Sync>;
+
+/// Called once a module has been evaluated or fetched, or has failed to load, see `RuntimeOptions::on_module_loaded`
+pub type ModuleLoadedCallback =
+    Arc<dyn Fn(&ModuleSpecifier, &Result<(), crate::Error>) + Send + Sync>;
+
+/// Formats a module specifier for display in errors and logs, see `RuntimeOptions::specifier_display`
+pub type SpecifierDisplay = Arc<dyn Fn(&ModuleSpecifier) -> String + Send + Sync>;
 
 /// The primary module loader implementation for rustyscript
 /// This structure manages fetching module code, transpilation, and caching
@@ -54,12 +111,159 @@ impl RustyLoader {
         self.inner_mut().add_source_map(file_name, code, source_map);
     }
 
+    /// Resolves the entry point of a package directory with the node resolver
+    #[cfg(feature = "node_experimental")]
+    pub fn resolve_package_entrypoint(
+        &self,
+        dir: &std::path::Path,
+    ) -> Result<std::path::PathBuf, crate::Error> {
+        self.inner().resolve_package_entrypoint(dir)
+    }
+
+    /// Returns the source code the loader holds for a module, used for source mapping
+    pub fn module_source(&self, specifier: &ModuleSpecifier) -> Option<String> {
+        let inner = self.inner();
+        let (source, _) = inner.get_source_map(specifier.as_str())?;
+        Some(source.clone())
+    }
+
+    /// Returns true if the loader holds the source of a module, meaning it was loaded
+    pub fn has_module_source(&self, specifier: &ModuleSpecifier) -> bool {
+        self.inner().get_source_map(specifier.as_str()).is_some()
+    }
+
+    /// Returns the already-loaded instance of a module, if the reload policy allows reusing it
+    pub fn loaded_module(
+        &self,
+        specifier: &ModuleSpecifier,
+        code: &str,
+    ) -> Option<crate::ModuleHandle> {
+        self.inner().loaded_module(specifier, code)
+    }
+
+    /// Records a loaded module, so that it can be reused according to the reload policy
+    pub fn set_loaded_module(
+        &self,
+        specifier: &ModuleSpecifier,
+        code: &str,
+        handle: crate::ModuleHandle,
+    ) {
+        self.inner_mut().set_loaded_module(specifier, code, handle);
+    }
+
+    /// Converts an explicit cache key into the specifier it is stored under in the cache provider
+    ///
+    /// Keys live under their own scheme, so they can never collide with the specifier of a module
+    pub fn cache_key_specifier(key: &str) -> Result<ModuleSpecifier, crate::Error> {
+        let mut specifier = ModuleSpecifier::parse("rustyscript-cache:/")
+            .map_err(|e| crate::Error::Runtime(e.to_string()))?;
+        specifier.set_path(key);
+        Ok(specifier)
+    }
+
+    /// Returns the transpiled code, and its source map, cached under an explicit key if the cache provider has it
+    pub fn cached_code(&self, key: &ModuleSpecifier) -> Option<(String, Option<Vec<u8>>)> {
+        self.inner().cached_code(key)
+    }
+
+    /// Caches transpiled code and its source map under an explicit key, if a cache provider is set
+    pub fn cache_code(&self, key: &ModuleSpecifier, code: &str, source_map: Option<&[u8]>) {
+        self.inner_mut().cache_code(key, code, source_map);
+    }
+
+    /// Reports a stage of loading a module to the event sink, if one is set
+    pub fn notify(&self, event: LoaderEvent, specifier: &ModuleSpecifier) {
+        self.inner().notify(event, specifier);
+    }
+
+    /// Reports the outcome of evaluating a module loaded from rust, along with its static imports,
+    /// to the `on_module_loaded` callback, if one is set
+    pub fn report_evaluated(&self, specifier: &ModuleSpecifier, result: &Result<(), crate::Error>) {
+        self.inner_mut().report_evaluated(specifier, result);
+    }
+
+    /// Applies the module preprocessors to transpiled code, in order
+    pub fn preprocess(
+        &self,
+        specifier: &ModuleSpecifier,
+        code: String,
+    ) -> Result<String, crate::Error> {
+        self.inner().preprocess(specifier, code)
+    }
+
+    /// Keeps non-fatal transpiler diagnostics, if they are being collected
+    pub fn add_diagnostics(&self, diagnostics: Vec<Diagnostic>) {
+        self.inner_mut().add_diagnostics(diagnostics);
+    }
+
+    /// Returns the diagnostics collected so far, clearing them
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        self.inner_mut().take_diagnostics()
+    }
+
+    /// Registers the generated source for a builtin module
+    pub fn define_builtin_module(&self, specifier: ModuleSpecifier, source: String) {
+        self.inner_mut().define_builtin_module(specifier, source);
+    }
+
     /// Get an extension transpiler that can be injected into a `deno_core::JsRuntime`
     pub fn as_extension_transpiler(self: &Rc<Self>) -> ExtensionTranspiler {
         let loader = self.clone();
         Rc::new(move |specifier, code| loader.inner().transpile_extension(&specifier, &code))
     }
 
+    /// Returns a callback evaluating the module types that `deno_core` does not support natively  
+    /// Can be injected into a `deno_core::JsRuntime` as its `custom_module_evaluation_cb`
+    ///
+    /// These are `json5`, and the types with a registered `ModuleTypeHandler`, which the loader
+    /// has already converted to plain JSON
+    pub fn custom_module_evaluator(
+        &self,
+    ) -> impl Fn(
+        &mut v8::PinScope<'_, '_>,
+        Cow<'_, str>,
+        &FastString,
+        ModuleSourceCode,
+    ) -> Result<CustomModuleEvaluationKind, JsErrorBox>
+           + 'static {
+        let module_types = self.inner().custom_module_types();
+        move |scope: &mut v8::PinScope<'_, '_>,
+              module_type: Cow<'_, str>,
+              specifier: &FastString,
+              code: ModuleSourceCode| {
+            if !module_types.iter().any(|t| *t == module_type) {
+                return Err(JsErrorBox::generic(format!(
+                    "Unsupported module type `{module_type}` for {}",
+                    specifier.as_str()
+                )));
+            }
+            Self::evaluate_custom_module(scope, specifier, code)
+        }
+    }
+
+    /// Evaluates a custom module, whose code the loader has already converted to plain JSON
+    fn evaluate_custom_module(
+        scope: &mut v8::PinScope<'_, '_>,
+        specifier: &FastString,
+        code: ModuleSourceCode,
+    ) -> Result<CustomModuleEvaluationKind, JsErrorBox> {
+        let ModuleSourceCode::String(code) = code else {
+            return Err(JsErrorBox::generic(format!(
+                "{} is not a text module",
+                specifier.as_str()
+            )));
+        };
+
+        let value = v8::String::new(scope, code.as_str())
+            .and_then(|json| v8::json::parse(scope, json))
+            .ok_or_else(|| {
+                JsErrorBox::generic(format!("{} could not be parsed", specifier.as_str()))
+            })?;
+        Ok(CustomModuleEvaluationKind::Synthetic(v8::Global::new(
+            scope, value,
+        )))
+    }
+
     /// Transpile a module from CJS to ESM
     #[allow(dead_code)]
     pub async fn translate_cjs(
@@ -102,13 +306,35 @@ impl ModuleLoader for RustyLoader {
     }
 
     fn get_source_map(&self, file_name: &str) -> Option<Cow<'_, [u8]>> {
-        let inner = self.inner();
-        let map = inner.get_source_map(file_name)?.1.as_deref()?;
-        Some(Cow::Owned(map.to_vec()))
+        if let Some(map) = self
+            .inner()
+            .get_source_map(file_name)
+            .and_then(|(_, map)| map.as_deref())
+        {
+            return Some(Cow::Owned(map.to_vec()));
+        }
+
+        // Fall back to loading the map on demand
+        let map = self.inner().source_map_provider()?.source_map(file_name)?;
+        self.inner_mut().add_original_sources(file_name, &map);
+        Some(Cow::Owned(map))
+    }
+
+    /// Lets error locations be rewritten to the relative sources of a map, when it embeds their content
+    fn source_map_source_exists(&self, source_url: &str) -> Option<bool> {
+        self.inner()
+            .original_source(source_url)
+            .is_some()
+            .then_some(true)
     }
 
     fn get_source_mapped_source_line(&self, file_name: &str, line_number: usize) -> Option<String> {
+        // Errors are reported against the mapped source, which a map may embed under its own name
         let inner = self.inner();
+        if let Some(source) = inner.original_source(file_name) {
+            return source.split('\n').nth(line_number).map(ToString::to_string);
+        }
+
         let lines: Vec<_> = inner.get_source_map(file_name)?.0.split('\n').collect();
         if line_number >= lines.len() {
             return None;
@@ -117,6 +343,50 @@ impl ModuleLoader for RustyLoader {
     }
 }
 
+/// Returns the sources embedded in the `sourcesContent` of a module's source map, by URL
+///
+/// Each source is resolved as `deno_core` does when mapping an error: as a URL, or failing that
+/// relative to the module, after the map's `sourceRoot`
+fn sources_content(file_name: &str, map: &[u8]) -> Vec<(String, String)> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RawSourceMap {
+        #[serde(default)]
+        source_root: Option<String>,
+        #[serde(default)]
+        sources: Vec<Option<String>>,
+        #[serde(default)]
+        sources_content: Vec<Option<String>>,
+    }
+
+    let Ok(map) = deno_core::serde_json::from_slice::<RawSourceMap>(map) else {
+        return Vec::new();
+    };
+    let base = ModuleSpecifier::parse(file_name).ok();
+    let root = map
+        .source_root
+        .filter(|root| !root.is_empty())
+        .map(|root| format!("{}/", root.trim_end_matches('/')));
+
+    map.sources
+        .into_iter()
+        .zip(map.sources_content)
+        .filter_map(|(source, content)| {
+            let source = source?;
+            let content = content?;
+            let source = match &root {
+                Some(root) => format!("{root}{source}"),
+                None => source,
+            };
+            let url = match ModuleSpecifier::parse(&source) {
+                Ok(url) => url.to_string(),
+                Err(_) => base.as_ref()?.join(&source).ok()?.to_string(),
+            };
+            Some((url, content))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use deno_core::{
@@ -269,6 +539,120 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_loader_events() {
+        let events: Arc<std::sync::Mutex<Vec<(LoaderEvent, String)>>> = Arc::default();
+        let sink_events = events.clone();
+        let loader = RustyLoader::new(LoaderOptions {
+            cache_provider: Some(Box::new(MemoryModuleCacheProvider::default())),
+            import_provider: Some(Box::new(TestImportProvider::new())),
+            event_sink: Some(Arc::new(move |event, specifier| {
+                sink_events
+                    .lock()
+                    .unwrap()
+                    .push((event, specifier.to_string()));
+            })),
+            cwd: std::env::current_dir().unwrap(),
+            ..LoaderOptions::default()
+        });
+
+        let specifier = loader
+            .resolve("test://anything", "", ResolutionKind::Import)
+            .unwrap();
+        let options = || deno_core::ModuleLoadOptions {
+            is_dynamic_import: false,
+            is_synchronous: false,
+            requested_module_type: deno_core::RequestedModuleType::None,
+        };
+
+        let ModuleLoadResponse::Async(future) = loader.load(&specifier, None, options()) else {
+            panic!("Unexpected response");
+        };
+        future.await.expect("Expected to get source");
+
+        // The second load is served from the cache
+        let ModuleLoadResponse::Sync(result) = loader.load(&specifier, None, options()) else {
+            panic!("Unexpected response");
+        };
+        result.expect("Expected to get source");
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (LoaderEvent::Resolving, "test://anything".to_string()),
+                (LoaderEvent::Fetching, "test://1".to_string()),
+                (LoaderEvent::Transpiling, "test://1".to_string()),
+                (LoaderEvent::Cached, "test://1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_on_module_loaded() {
+        struct MemoryProvider;
+        impl ImportProvider for MemoryProvider {
+            fn resolve(
+                &mut self,
+                specifier: &ModuleSpecifier,
+                _referrer: &str,
+                _kind: deno_core::ResolutionKind,
+            ) -> Option<Result<ModuleSpecifier, ModuleLoaderError>> {
+                (specifier.scheme() == "mem").then(|| Ok(specifier.clone()))
+            }
+            fn import(
+                &mut self,
+                specifier: &ModuleSpecifier,
+                _referrer: Option<&ModuleSpecifier>,
+                _is_dyn_import: bool,
+            ) -> Option<Result<String, ModuleLoaderError>> {
+                match specifier.path() {
+                    "dynamic.js" => Some(Ok("export const value = 1;".to_string())),
+                    "static.js" => Some(Ok("export const value = 2;".to_string())),
+                    _ => Some(Err(deno_error::JsErrorBox::generic("not found"))),
+                }
+            }
+        }
+
+        let loaded: Arc<std::sync::Mutex<Vec<(String, bool)>>> = Arc::default();
+        let callback_loaded = loaded.clone();
+        let mut runtime = crate::Runtime::new(crate::RuntimeOptions {
+            import_provider: Some(Box::new(MemoryProvider)),
+            on_module_loaded: Some(Arc::new(move |specifier, result| {
+                let name = specifier.as_str().rsplit('/').next().unwrap_or_default();
+                callback_loaded
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), result.is_ok()));
+            })),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let module = crate::Module::new(
+            "main.js",
+            "import 'mem:static.js'; export const value = (await import('mem:dynamic.js')).value;",
+        );
+        runtime.load_module(&module).unwrap();
+
+        let module = crate::Module::new("broken.js", "throw new Error('oops');");
+        runtime.load_module(&module).unwrap_err();
+
+        let module = crate::Module::new("missing.js", "import 'mem:missing.js';");
+        runtime.load_module(&module).unwrap_err();
+
+        assert_eq!(
+            *loaded.lock().unwrap(),
+            vec![
+                ("mem:dynamic.js".to_string(), true),
+                ("mem:static.js".to_string(), true),
+                ("main.js".to_string(), true),
+                ("broken.js".to_string(), false),
+                ("mem:missing.js".to_string(), false),
+                ("missing.js".to_string(), false),
+            ]
+        );
+    }
+
     /// Test backward compatibility for ImportProvider trait
     #[test]
     fn test_import_provider_backward_compat() {
@@ -326,4 +710,85 @@ mod test {
         assert!(result.is_some());
         assert_eq!(result.unwrap().unwrap(), "console.log('new style')");
     }
+
+    #[test]
+    fn test_source_map_provider() {
+        let loader = RustyLoader::new(LoaderOptions {
+            source_map_provider: Some(Box::new(|filename: &str| {
+                (filename == "file:///lazy.ts").then(|| b"lazy map".to_vec())
+            })),
+            ..LoaderOptions::default()
+        });
+
+        // Maps are not stored eagerly when a provider is set
+        loader.insert_source_map(
+            "file:///eager.ts",
+            String::new(),
+            Some(b"eager map".to_vec()),
+        );
+        assert!(loader.get_source_map("file:///eager.ts").is_none());
+
+        let map = loader.get_source_map("file:///lazy.ts").unwrap();
+        assert_eq!(map.as_ref(), b"lazy map");
+    }
+
+    #[test]
+    fn test_source_lines_from_sources_content() {
+        let loader = RustyLoader::new(LoaderOptions::default());
+
+        // A prebuilt bundle, mapping back to typescript sources named differently from the bundle
+        let map = deno_core::serde_json::json!({
+            "version": 3,
+            "sourceRoot": "src",
+            "sources": ["main.ts", "other.ts", "file:///abs/lib.ts"],
+            "sourcesContent": ["const x: number = 1;\nthrow new Error(`${x}`);", null, "export {};"],
+            "names": [],
+            "mappings": "",
+        });
+        loader.insert_source_map(
+            "file:///dist/bundle.js",
+            "const x = 1;\nthrow new Error(`${x}`);".to_string(),
+            Some(map.to_string().into_bytes()),
+        );
+        assert_eq!(
+            loader
+                .get_source_mapped_source_line("file:///dist/src/main.ts", 0)
+                .as_deref(),
+            Some("const x: number = 1;")
+        );
+        assert_eq!(
+            loader
+                .get_source_mapped_source_line("file:///abs/lib.ts", 0)
+                .as_deref(),
+            Some("export {};")
+        );
+        assert_eq!(
+            loader.source_map_source_exists("file:///dist/src/main.ts"),
+            Some(true)
+        );
+        assert_eq!(
+            loader.source_map_source_exists("file:///dist/src/other.ts"),
+            None
+        );
+
+        // The bundle itself still shows its own code
+        assert_eq!(
+            loader
+                .get_source_mapped_source_line("file:///dist/bundle.js", 0)
+                .as_deref(),
+            Some("const x = 1;")
+        );
+
+        // Without embedded sources, the stored code is used
+        loader.insert_source_map("file:///plain.js", "first\nsecond".to_string(), None);
+        assert_eq!(
+            loader
+                .get_source_mapped_source_line("file:///plain.js", 1)
+                .as_deref(),
+            Some("second")
+        );
+        assert!(loader
+            .get_source_mapped_source_line("file:///plain.js", 2)
+            .is_none());
+    }
 }
@@ -14,16 +14,19 @@ use std::{
 use deno_core::{
     error::{AnyError, ModuleLoaderError},
     futures::FutureExt,
+    serde_json,
     url::ParseError,
     FastString, ModuleLoadResponse, ModuleResolutionError, ModuleSource, ModuleSourceCode,
-    ModuleSpecifier, ModuleType,
+    ModuleSpecifier, ModuleType, RequestedModuleType,
 };
 use deno_error::JsErrorBox;
 
 use crate::{
     module_loader::{ClonableSource, ModuleCacheProvider},
     traits::ToModuleSpecifier,
-    transpiler::{transpile, transpile_extension, ExtensionTranspilation},
+    transpiler::{
+        transpile_extension, transpile_with_diagnostics, Diagnostic, ExtensionTranspilation,
+    },
     Error,
 };
 
@@ -38,11 +41,18 @@ use node_resolver::InNpmPackageChecker;
 #[cfg(feature = "node_experimental")]
 use node_resolver::{NodeResolutionKind, ResolutionMode};
 
-use super::ImportProvider;
+use super::{
+    reload_policy::code_hash, ImportProvider, LoaderEvent, LoaderEventSink, ModuleLoadedCallback,
+    ModulePreprocessor, ModuleTypeHandler, ReloadPolicy, SchemeHandler, SourceMapProvider,
+    TsConfigPaths,
+};
 
 /// Stores the source code and source ma#![allow(deprecated)]p for loaded modules
 type SourceMapCache = HashMap<String, (String, Option<Vec<u8>>)>;
 
+/// Module type for `import x from "./file.json5" with { type: "json5" }`
+pub const JSON5_MODULE_TYPE: &str = "json5";
+
 /// Options for the `RustyLoader` struct
 /// Not for public use
 #[derive(Default)]
@@ -64,9 +74,39 @@ pub struct LoaderOptions {
     /// An optional import provider to manage module resolution
     pub import_provider: Option<Box<dyn ImportProvider>>,
 
+    /// An optional provider to load source maps on demand, instead of storing them
+    pub source_map_provider: Option<Box<dyn SourceMapProvider>>,
+
     /// A whitelist of custom schema prefixes that are allowed to be loaded
     pub schema_whlist: HashSet<String>,
 
+    /// What to do when a module is loaded more than once
+    pub reload: ReloadPolicy,
+
+    /// Optional `tsconfig.json` path mapping, applied to bare specifiers
+    pub tsconfig_paths: Option<TsConfigPaths>,
+
+    /// If true, dynamic `import()` is rejected
+    pub deny_dynamic_import: bool,
+
+    /// Functions applied, in order, to the code of every module after transpiling
+    pub preprocessors: Vec<ModulePreprocessor>,
+
+    /// Parsers for custom module types, by import attribute type or file extension
+    pub module_type_handlers: HashMap<String, ModuleTypeHandler>,
+
+    /// Handlers resolving and fetching the modules of custom URL schemes, by scheme
+    pub scheme_handlers: HashMap<String, Arc<dyn SchemeHandler>>,
+
+    /// An optional observer notified at each stage of loading a module
+    pub event_sink: Option<LoaderEventSink>,
+
+    /// An optional callback notified once each module is evaluated, or fails to load
+    pub on_module_loaded: Option<ModuleLoadedCallback>,
+
+    /// If true, non-fatal transpiler diagnostics are kept until taken
+    pub collect_diagnostics: bool,
+
     /// The current working directory for the loader
     pub cwd: PathBuf,
 }
@@ -105,8 +145,22 @@ pub struct InnerRustyLoader {
     cache_provider: Option<Box<dyn ModuleCacheProvider>>,
     fs_whlist: HashSet<String>,
     source_map_cache: SourceMapCache,
+    original_sources: HashMap<String, String>,
     import_provider: Option<Box<dyn ImportProvider>>,
+    source_map_provider: Option<Box<dyn SourceMapProvider>>,
     schema_whlist: HashSet<String>,
+    reload: ReloadPolicy,
+    tsconfig_paths: Option<TsConfigPaths>,
+    deny_dynamic_import: bool,
+    preprocessors: Vec<ModulePreprocessor>,
+    module_type_handlers: HashMap<String, ModuleTypeHandler>,
+    scheme_handlers: HashMap<String, Arc<dyn SchemeHandler>>,
+    event_sink: Option<LoaderEventSink>,
+    on_module_loaded: Option<ModuleLoadedCallback>,
+    awaiting_evaluation: Vec<ModuleSpecifier>,
+    diagnostics: Option<Vec<Diagnostic>>,
+    loaded_modules: HashMap<ModuleSpecifier, (u64, crate::ModuleHandle)>,
+    builtin_modules: HashMap<ModuleSpecifier, String>,
     cwd: PathBuf,
 
     #[cfg(feature = "node_experimental")]
@@ -121,8 +175,30 @@ impl InnerRustyLoader {
             cache_provider: options.cache_provider,
             fs_whlist: options.fs_whitelist,
             source_map_cache: options.source_map_cache,
+            original_sources: HashMap::new(),
             import_provider: options.import_provider,
+            source_map_provider: options.source_map_provider,
             schema_whlist: options.schema_whlist,
+            reload: options.reload,
+            tsconfig_paths: options.tsconfig_paths,
+            deny_dynamic_import: options.deny_dynamic_import,
+            preprocessors: options.preprocessors,
+            module_type_handlers: options.module_type_handlers,
+            scheme_handlers: options
+                .scheme_handlers
+                .into_iter()
+                .map(|(scheme, handler)| {
+                    // Accept `s3`, `s3:` or `s3://`
+                    let scheme = scheme.trim_end_matches("//").trim_end_matches(':');
+                    (scheme.to_ascii_lowercase(), handler)
+                })
+                .collect(),
+            event_sink: options.event_sink,
+            on_module_loaded: options.on_module_loaded,
+            awaiting_evaluation: Vec::new(),
+            diagnostics: options.collect_diagnostics.then(Vec::new),
+            loaded_modules: HashMap::new(),
+            builtin_modules: HashMap::new(),
             cwd: options.cwd,
 
             #[cfg(feature = "node_experimental")]
@@ -169,6 +245,13 @@ impl InnerRustyLoader {
         referrer: &str,
         kind: deno_core::ResolutionKind,
     ) -> Result<ModuleSpecifier, ModuleLoaderError> {
+        if self.deny_dynamic_import && kind == deno_core::ResolutionKind::DynamicImport {
+            return Err(JsErrorBox::new(
+                "PermissionDenied",
+                format!("dynamic import of {specifier} is not allowed"),
+            ));
+        }
+
         #[cfg(feature = "node_experimental")]
         let referrer_specifier = if deno_core::specifier_has_uri_scheme(referrer) {
             deno_core::resolve_url(referrer).map_err(JsErrorBox::from_err)?
@@ -202,8 +285,23 @@ impl InnerRustyLoader {
         }
 
         // Resolve the module specifier to an absolute URL
-        let url =
-            deno_core::resolve_import(specifier, referrer).map_err(ModuleLoaderError::from_err)?;
+        // Bare specifiers may first be mapped by the tsconfig's `paths`
+        let mapped = self
+            .tsconfig_paths
+            .as_ref()
+            .and_then(|paths| paths.resolve(specifier));
+        let url = match mapped {
+            Some(url) => url,
+            None => deno_core::resolve_import(specifier, referrer)
+                .map_err(ModuleLoaderError::from_err)?,
+        };
+
+        self.notify(LoaderEvent::Resolving, &url);
+
+        // Builtin modules are always allowed
+        if self.builtin_modules.contains_key(&url) {
+            return Ok(url);
+        }
 
         // Check if the module is in the cache
         if self
@@ -222,6 +320,11 @@ impl InnerRustyLoader {
             }
         }
 
+        // Custom schemes are resolved by their handler
+        if let Some(handler) = self.scheme_handlers.get(url.scheme()) {
+            return handler.resolve(&url, referrer);
+        }
+
         if referrer == "." {
             // Added from rust, add to the whitelist
             // so we can load it from the filesystem
@@ -276,14 +379,88 @@ impl InnerRustyLoader {
         module_specifier: &ModuleSpecifier,
         maybe_referrer: Option<&deno_core::ModuleLoadReferrer>,
         options: deno_core::ModuleLoadOptions,
+    ) -> deno_core::ModuleLoadResponse {
+        let is_dynamic_import = options.is_dynamic_import;
+        let response = Self::load_source(inner.clone(), module_specifier, maybe_referrer, options);
+        if inner.borrow().on_module_loaded.is_none() {
+            return response;
+        }
+
+        let specifier = module_specifier.clone();
+        match response {
+            ModuleLoadResponse::Sync(result) => ModuleLoadResponse::Sync(Self::track_source(
+                &inner,
+                &specifier,
+                is_dynamic_import,
+                result,
+            )),
+            ModuleLoadResponse::Async(future) => ModuleLoadResponse::Async(
+                async move {
+                    let result = future.await;
+                    Self::track_source(&inner, &specifier, is_dynamic_import, result)
+                }
+                .boxed_local(),
+            ),
+        }
+    }
+
+    /// Reports the outcome of fetching a module to the `on_module_loaded` callback
+    ///
+    /// Static imports are held until the module loaded from rust has been evaluated, see `report_evaluated`,
+    /// while dynamic imports are reported as soon as they are fetched
+    fn track_source(
+        inner: &RefCell<Self>,
+        specifier: &ModuleSpecifier,
+        is_dynamic_import: bool,
+        result: Result<ModuleSource, ModuleLoaderError>,
+    ) -> Result<ModuleSource, ModuleLoaderError> {
+        match result {
+            Ok(source) => {
+                if is_dynamic_import {
+                    inner.borrow().report_module_loaded(specifier, &Ok(()));
+                } else {
+                    inner
+                        .borrow_mut()
+                        .awaiting_evaluation
+                        .push(specifier.clone());
+                }
+                Ok(source)
+            }
+            Err(e) => {
+                let error = Error::Runtime(e.to_string());
+                inner.borrow().report_module_loaded(specifier, &Err(error));
+                Err(e)
+            }
+        }
+    }
+
+    fn load_source(
+        inner: Rc<RefCell<Self>>,
+        module_specifier: &ModuleSpecifier,
+        maybe_referrer: Option<&deno_core::ModuleLoadReferrer>,
+        options: deno_core::ModuleLoadOptions,
     ) -> deno_core::ModuleLoadResponse {
         let module_specifier = module_specifier.clone();
         let maybe_referrer_url = maybe_referrer.map(|r| r.specifier.clone());
         let is_dyn_import = options.is_dynamic_import;
+        let requested_module_type = options.requested_module_type;
+
+        // Builtin modules are generated, and never transpiled or cached
+        if let Some(source) = inner.borrow().builtin_modules.get(&module_specifier) {
+            return ModuleLoadResponse::Sync(Ok(ModuleSource::new(
+                ModuleType::JavaScript,
+                ModuleSourceCode::String(source.clone().into()),
+                &module_specifier,
+                None,
+            )));
+        }
 
         // Check if the module is in the cache first
         if let Some(cache) = &inner.borrow().cache_provider {
             if let Some(source) = cache.get(&module_specifier) {
+                inner
+                    .borrow()
+                    .notify(LoaderEvent::Cached, &module_specifier);
                 return deno_core::ModuleLoadResponse::Sync(Ok(source));
             }
         }
@@ -299,7 +476,36 @@ impl InnerRustyLoader {
         if let Some(result) = provider_result {
             return ModuleLoadResponse::Async(
                 async move {
-                    Self::handle_load(inner, module_specifier, |_, _| async move { result }).await
+                    Self::handle_load(
+                        inner,
+                        module_specifier,
+                        requested_module_type,
+                        |_, _, _| async move { result.map(String::into_bytes) },
+                    )
+                    .await
+                }
+                .boxed_local(),
+            );
+        }
+
+        // Then the handler for a custom scheme
+        let scheme_handler = inner
+            .borrow()
+            .scheme_handlers
+            .get(module_specifier.scheme())
+            .cloned();
+        if let Some(handler) = scheme_handler {
+            return ModuleLoadResponse::Async(
+                async move {
+                    Self::handle_load(
+                        inner,
+                        module_specifier,
+                        requested_module_type,
+                        move |_, specifier, _| async move {
+                            handler.load(&specifier).await.map(String::into_bytes)
+                        },
+                    )
+                    .await
                 }
                 .boxed_local(),
             );
@@ -310,14 +516,30 @@ impl InnerRustyLoader {
             // Remote fetch imports
             #[cfg(feature = "url_import")]
             "https" | "http" => ModuleLoadResponse::Async(
-                async move { Self::handle_load(inner, module_specifier, Self::load_remote).await }
-                    .boxed_local(),
+                async move {
+                    Self::handle_load(
+                        inner,
+                        module_specifier,
+                        requested_module_type,
+                        Self::load_remote,
+                    )
+                    .await
+                }
+                .boxed_local(),
             ),
 
             // FS imports
             "file" => ModuleLoadResponse::Async(
-                async move { Self::handle_load(inner, module_specifier, Self::load_file).await }
-                    .boxed_local(),
+                async move {
+                    Self::handle_load(
+                        inner,
+                        module_specifier,
+                        requested_module_type,
+                        Self::load_file,
+                    )
+                    .await
+                }
+                .boxed_local(),
             ),
 
             // Default deny-all
@@ -451,16 +673,39 @@ impl InnerRustyLoader {
         Ok(url)
     }
 
+    /// Reads a module from disk - `raw` modules are returned as they are, the rest are translated from CJS if needed
     #[allow(unused_variables)]
     async fn load_file(
         inner: Rc<RefCell<Self>>,
         module_specifier: ModuleSpecifier,
-    ) -> Result<String, ModuleLoaderError> {
+        raw: bool,
+    ) -> Result<Vec<u8>, ModuleLoaderError> {
         let path = module_specifier.to_file_path().map_err(|()| {
             JsErrorBox::from_err(Error::Runtime(format!(
                 "{module_specifier} is not a file path"
             )))
         })?;
+
+        // Native addons are loaded through the napi extension, with their exports as the default export
+        #[cfg(feature = "node_experimental")]
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("node"))
+        {
+            let path = serde_json::to_string(&path.to_string_lossy())
+                .map_err(|e| ModuleLoaderError::generic(e.to_string()))?;
+            return Ok(
+                format!("export default Deno.core.ops.op_napi_addon_loader()({path});")
+                    .into_bytes(),
+            );
+        }
+
+        if raw {
+            return tokio::fs::read(path)
+                .await
+                .map_err(ModuleLoaderError::from_err);
+        }
+
         let content = tokio::fs::read_to_string(path)
             .await
             .map_err(ModuleLoaderError::from_err)?;
@@ -468,33 +713,81 @@ impl InnerRustyLoader {
             .await
             .map_err(ModuleLoaderError::from_err)?;
 
-        Ok(content)
+        Ok(content.into_bytes())
     }
 
     #[cfg(feature = "url_import")]
     async fn load_remote(
         _: Rc<RefCell<Self>>,
         module_specifier: ModuleSpecifier,
-    ) -> Result<String, ModuleLoaderError> {
+        _raw: bool,
+    ) -> Result<Vec<u8>, ModuleLoaderError> {
         let response = reqwest::get(module_specifier)
             .await
             .map_err(|e| ModuleLoaderError::generic(e.to_string()))?;
         let response = response
-            .text()
+            .bytes()
             .await
             .map_err(|e| ModuleLoaderError::generic(e.to_string()))?;
-        Ok(response)
+        Ok(response.to_vec())
+    }
+
+    /// Parses a JSON5 module, returning its contents as plain JSON
+    fn parse_json5(
+        module_specifier: &ModuleSpecifier,
+        code: &str,
+    ) -> Result<String, ModuleLoaderError> {
+        let value: serde_json::Value = json5::from_str(code)
+            .map_err(|e| Error::JsonDecode(format!("{module_specifier}: {e}")))
+            .map_err(JsErrorBox::from_err)?;
+        serde_json::to_string(&value).map_err(JsErrorBox::from_err)
+    }
+
+    /// Parses a module with a custom module type handler, returning its contents as plain JSON
+    fn parse_with_handler(
+        type_handler: &ModuleTypeHandler,
+        module_specifier: &ModuleSpecifier,
+        code: &[u8],
+    ) -> Result<String, ModuleLoaderError> {
+        let value = type_handler(code).map_err(|e| {
+            JsErrorBox::from_err(Error::JsonDecode(format!("{module_specifier}: {e}")))
+        })?;
+        serde_json::to_string(&value).map_err(JsErrorBox::from_err)
+    }
+
+    /// Returns the module types evaluated by `RustyLoader::custom_module_evaluator`
+    pub fn custom_module_types(&self) -> Vec<String> {
+        std::iter::once(JSON5_MODULE_TYPE.to_string())
+            .chain(self.module_type_handlers.keys().cloned())
+            .collect()
+    }
+
+    /// Returns the handler for a module's import attribute type, or failing that, for its file extension
+    fn module_type_handler(
+        &self,
+        requested_module_type: &RequestedModuleType,
+        extension: &std::ffi::OsStr,
+    ) -> Option<ModuleTypeHandler> {
+        let name = match requested_module_type {
+            RequestedModuleType::Other(t) => t.to_string(),
+            RequestedModuleType::None => extension.to_string_lossy().to_ascii_lowercase(),
+            _ => return None,
+        };
+        self.module_type_handlers.get(&name).cloned()
     }
 
     /// Loads a module's source code from the cache or from the provided handler
+    ///
+    /// The handler is told to return the contents unaltered when a custom module type handler will parse them
     async fn handle_load<F, Fut>(
         inner: Rc<RefCell<Self>>,
         module_specifier: ModuleSpecifier,
+        requested_module_type: RequestedModuleType,
         handler: F,
     ) -> Result<ModuleSource, ModuleLoaderError>
     where
-        F: FnOnce(Rc<RefCell<Self>>, ModuleSpecifier) -> Fut,
-        Fut: std::future::Future<Output = Result<String, ModuleLoaderError>>,
+        F: FnOnce(Rc<RefCell<Self>>, ModuleSpecifier, bool) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<u8>, ModuleLoaderError>>,
     {
         // Check if the module is in the cache first
         if let Some(Some(source)) = inner
@@ -503,6 +796,9 @@ impl InnerRustyLoader {
             .as_ref()
             .map(|p| p.get(&module_specifier))
         {
+            inner
+                .borrow()
+                .notify(LoaderEvent::Cached, &module_specifier);
             return Ok(source);
         }
 
@@ -514,16 +810,67 @@ impl InnerRustyLoader {
         let extension = Path::new(module_specifier.path())
             .extension()
             .unwrap_or_default();
-        let module_type = if extension.eq_ignore_ascii_case("json") {
-            ModuleType::Json
+        let type_handler = inner
+            .borrow()
+            .module_type_handler(&requested_module_type, extension);
+        let module_type = match requested_module_type {
+            RequestedModuleType::Other(t) if t == JSON5_MODULE_TYPE || type_handler.is_some() => {
+                ModuleType::Other(t)
+            }
+            _ if extension.eq_ignore_ascii_case("json") => ModuleType::Json,
+            _ => ModuleType::JavaScript,
+        };
+
+        // Load the module code, and transpile or parse it if necessary
+        inner
+            .borrow()
+            .notify(LoaderEvent::Fetching, &module_specifier);
+        let bytes = handler(
+            inner.clone(),
+            module_specifier.clone(),
+            type_handler.is_some(),
+        )
+        .await?;
+        inner
+            .borrow()
+            .notify(LoaderEvent::Transpiling, &module_specifier);
+        let (code, tcode, source_map) = if let Some(type_handler) = &type_handler {
+            let json = Self::parse_with_handler(type_handler, &module_specifier, &bytes)?;
+            let code = String::from_utf8_lossy(&bytes).into_owned();
+            if module_type == ModuleType::JavaScript {
+                // Imported without an attribute, so the object becomes the default export
+                // Parsed rather than inlined as a literal, in which `__proto__` keys would set the prototype
+                let literal = serde_json::to_string(&json).map_err(JsErrorBox::from_err)?;
+                (code, format!("export default JSON.parse({literal});"), None)
+            } else {
+                (code, json, None)
+            }
         } else {
-            ModuleType::JavaScript
+            let code = String::from_utf8(bytes).map_err(|e| {
+                JsErrorBox::from_err(Error::Runtime(format!(
+                    "{module_specifier} is not valid UTF-8: {e}"
+                )))
+            })?;
+            let (tcode, source_map) = if let ModuleType::Other(_) = module_type {
+                (Self::parse_json5(&module_specifier, &code)?, None)
+            } else {
+                let (contents, diagnostics) = transpile_with_diagnostics(&module_specifier, &code)
+                    .map_err(ModuleLoaderError::from_err)?;
+                inner.borrow_mut().add_diagnostics(diagnostics);
+                contents
+            };
+            (code, tcode, source_map)
         };
 
-        // Load the module code, and transpile it if necessary
-        let code = handler(inner.clone(), module_specifier.clone()).await?;
-        let (tcode, source_map) =
-            transpile(&module_specifier, &code).map_err(ModuleLoaderError::from_err)?;
+        // Module preprocessors only apply to code
+        let tcode = if module_type == ModuleType::JavaScript && type_handler.is_none() {
+            inner
+                .borrow()
+                .preprocess(&module_specifier, tcode)
+                .map_err(ModuleLoaderError::from_err)?
+        } else {
+            tcode
+        };
 
         // Create the module source
         let mut source = ModuleSource::new(
@@ -559,11 +906,197 @@ impl InnerRustyLoader {
         self.source_map_cache.get(filename)
     }
 
+    /// Returns the already-loaded instance of a module, if the reload policy allows reusing it
+    pub fn loaded_module(
+        &self,
+        specifier: &ModuleSpecifier,
+        code: &str,
+    ) -> Option<crate::ModuleHandle> {
+        let (hash, handle) = self.loaded_modules.get(specifier)?;
+        if self.reload.should_reload(*hash, code) {
+            None
+        } else {
+            Some(handle.clone())
+        }
+    }
+
+    /// Records a loaded module, so that it can be reused according to the reload policy
+    pub fn set_loaded_module(
+        &mut self,
+        specifier: &ModuleSpecifier,
+        code: &str,
+        handle: crate::ModuleHandle,
+    ) {
+        // Nothing is ever reused
+        if self.reload == ReloadPolicy::AlwaysReload {
+            return;
+        }
+
+        self.loaded_modules
+            .insert(specifier.clone(), (code_hash(code), handle));
+    }
+
+    /// Reports a stage of loading a module to the event sink, if one is set
+    pub fn notify(&self, event: LoaderEvent, specifier: &ModuleSpecifier) {
+        if let Some(sink) = &self.event_sink {
+            sink(event, specifier);
+        }
+    }
+
+    /// Reports the outcome of loading a module to the `on_module_loaded` callback, if one is set
+    pub fn report_module_loaded(&self, specifier: &ModuleSpecifier, result: &Result<(), Error>) {
+        if let Some(callback) = &self.on_module_loaded {
+            callback(specifier, result);
+        }
+    }
+
+    /// Reports the outcome of evaluating a module loaded from rust to the `on_module_loaded` callback
+    ///
+    /// On success, the static imports fetched for it are reported first, since they were evaluated before it  
+    /// On failure only the module itself is reported, since it is not known which of its imports threw
+    pub fn report_evaluated(&mut self, specifier: &ModuleSpecifier, result: &Result<(), Error>) {
+        let imports = std::mem::take(&mut self.awaiting_evaluation);
+        let Some(callback) = &self.on_module_loaded else {
+            return;
+        };
+
+        if result.is_ok() {
+            for import in imports.iter().filter(|import| *import != specifier) {
+                callback(import, &Ok(()));
+            }
+        }
+        callback(specifier, result);
+    }
+
+    /// Applies the module preprocessors to transpiled code, in order
+    pub fn preprocess(&self, specifier: &ModuleSpecifier, code: String) -> Result<String, Error> {
+        self.preprocessors
+            .iter()
+            .try_fold(code, |code, preprocessor| preprocessor(specifier, code))
+    }
+
+    /// Keeps non-fatal transpiler diagnostics, if they are being collected
+    pub fn add_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
+        if let Some(collected) = &mut self.diagnostics {
+            collected.extend(diagnostics);
+        }
+    }
+
+    /// Returns the diagnostics collected so far, clearing them
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Registers the generated source for a builtin module
+    /// Imports of the specifier will resolve to it, regardless of scheme
+    pub fn define_builtin_module(&mut self, specifier: ModuleSpecifier, source: String) {
+        self.builtin_modules.insert(specifier, source);
+    }
+
+    /// Returns the code the cache provider holds under an explicit cache key, if any
+    pub fn cached_code(&self, key: &ModuleSpecifier) -> Option<(String, Option<Vec<u8>>)> {
+        let provider = self.cache_provider.as_ref()?;
+        let code = match provider.get(key)?.code {
+            ModuleSourceCode::String(code) => code.as_str().to_string(),
+            ModuleSourceCode::Bytes(bytes) => String::from_utf8(bytes.to_vec()).ok()?,
+        };
+        let source_map = provider
+            .get(&Self::source_map_key(key))
+            .map(|source| match source.code {
+                ModuleSourceCode::String(map) => map.as_bytes().to_vec(),
+                ModuleSourceCode::Bytes(map) => map.to_vec(),
+            });
+        Some((code, source_map))
+    }
+
+    /// Stores transpiled code in the cache provider under an explicit cache key
+    pub fn cache_code(&mut self, key: &ModuleSpecifier, code: &str, source_map: Option<&[u8]>) {
+        if let Some(provider) = &mut self.cache_provider {
+            let source = ModuleSource::new(
+                ModuleType::JavaScript,
+                ModuleSourceCode::String(code.to_string().into()),
+                key,
+                None,
+            );
+            provider.set(key, source);
+
+            // Source maps are JSON, stored beside the code so that a cache hit keeps its stack traces mapped
+            if let Some(map) = source_map.and_then(|map| std::str::from_utf8(map).ok()) {
+                let key = Self::source_map_key(key);
+                let source = ModuleSource::new(
+                    ModuleType::Json,
+                    ModuleSourceCode::String(map.to_string().into()),
+                    &key,
+                    None,
+                );
+                provider.set(&key, source);
+            }
+        }
+    }
+
+    /// The key a source map is cached under, beside the code cached under `key`
+    ///
+    /// Keys from `RustyLoader::cache_key_specifier` never have a fragment, so this cannot collide with one
+    fn source_map_key(key: &ModuleSpecifier) -> ModuleSpecifier {
+        let mut key = key.clone();
+        key.set_fragment(Some("sourcemap"));
+        key
+    }
+
     /// Adds a source map to the cache
+    /// The map itself is discarded if a source map provider will supply it on demand
     pub fn add_source_map(&mut self, filename: &str, source: String, source_map: Option<Vec<u8>>) {
+        if let Some(map) = &source_map {
+            self.add_original_sources(filename, map);
+        }
+
+        let source_map = source_map.filter(|_| self.source_map_provider.is_none());
         self.source_map_cache
             .insert(filename.to_string(), (source, source_map));
     }
+
+    /// Records the `sourcesContent` of a module's source map, by the URL of each source
+    pub fn add_original_sources(&mut self, filename: &str, source_map: &[u8]) {
+        self.original_sources
+            .extend(super::sources_content(filename, source_map));
+    }
+
+    /// Returns the original source of a file named in a source map, if the map embedded it
+    pub fn original_source(&self, source_url: &str) -> Option<&str> {
+        self.original_sources.get(source_url).map(String::as_str)
+    }
+
+    /// Returns the provider used to load source maps on demand, if any
+    pub fn source_map_provider(&self) -> Option<&dyn SourceMapProvider> {
+        self.source_map_provider.as_deref()
+    }
+
+    /// Resolves the entry point of a package directory with the node resolver,
+    /// applying the conditions and patterns of its `exports` field as node would
+    #[cfg(feature = "node_experimental")]
+    pub fn resolve_package_entrypoint(&self, dir: &Path) -> Result<PathBuf, crate::Error> {
+        let resolved = self
+            .node
+            .node_resolver
+            .resolve_package_subpath_from_deno_module(
+                dir,
+                None,
+                None,
+                ResolutionMode::Import,
+                NodeResolutionKind::Execution,
+            )
+            .map_err(|e| crate::Error::ModuleNotFound(e.to_string()))?;
+
+        match resolved {
+            node_resolver::UrlOrPath::Path(path) => Ok(path),
+            node_resolver::UrlOrPath::Url(url) => url.to_file_path().map_err(|()| {
+                crate::Error::ModuleNotFound(format!("The entry point {url} is not a local file"))
+            }),
+        }
+    }
 }
 
 #[cfg(feature = "node_experimental")]
@@ -0,0 +1,42 @@
+//! This module provides the policy applied when the same module is loaded more than once
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Controls what happens when a module is loaded again, using a specifier that was already loaded
+///
+/// Applies to modules loaded from code, such as with [`crate::Runtime::load_module`]; a reloaded module
+/// replaces the old one for any later imports of its specifier, but modules that already imported it keep the old instance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReloadPolicy {
+    /// A module is evaluated again each time it is loaded
+    #[default]
+    AlwaysReload,
+
+    /// The first instance of a module is kept, and loading it again returns that instance
+    /// even if the module's code has changed
+    CacheForever,
+
+    /// A module is evaluated again only if its code has changed since it was last loaded
+    /// Otherwise, the existing instance is returned
+    ReloadIfChanged,
+}
+
+impl ReloadPolicy {
+    /// Returns true if a module last loaded with code matching `previous_hash` should be evaluated again
+    pub(crate) fn should_reload(self, previous_hash: u64, code: &str) -> bool {
+        match self {
+            Self::AlwaysReload => true,
+            Self::CacheForever => false,
+            Self::ReloadIfChanged => previous_hash != code_hash(code),
+        }
+    }
+}
+
+/// Hashes a module's code, to detect changes between loads
+pub(crate) fn code_hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
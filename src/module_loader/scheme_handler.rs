@@ -0,0 +1,67 @@
+//! This module provides handlers for modules imported with a custom URL scheme, such as `s3://`
+use std::future::Future;
+
+use deno_core::{error::ModuleLoaderError, futures::future::LocalBoxFuture, ModuleSpecifier};
+
+/// Resolves and fetches the modules of a single custom URL scheme, see `RuntimeOptions::scheme_handlers`
+///
+/// A lighter alternative to [`super::ImportProvider`] for the common case of loading modules from
+/// somewhere other than the filesystem or the network, such as object storage or a database
+/// Loaded code is transpiled, preprocessed and cached like any other module
+///
+/// Implemented for closures taking the specifier and returning a future of the code;
+/// the future must own anything it needs, so clone the specifier before moving it in
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use rustyscript::{ deno_core::ModuleSpecifier, module_loader::SchemeHandler };
+///
+/// let handler: Arc<dyn SchemeHandler> = Arc::new(|specifier: &ModuleSpecifier| {
+///     let path = specifier.path().to_string();
+///     async move { Ok(format!("export const path = {path:?};")) }
+/// });
+/// ```
+#[allow(unused_variables)]
+pub trait SchemeHandler: Send + Sync {
+    /// Resolve an import of this scheme to the specifier that will be loaded
+    /// This can be used to normalize the specifier, or to deny the import
+    ///
+    /// The default behavior is to return the specifier unchanged
+    ///
+    /// # Arguments
+    /// - `specifier`: The module specifier to resolve, as an absolute URL
+    /// - `referrer`: The URL of the module that is importing the specifier
+    ///
+    /// # Errors
+    /// An error is returned to the caller, denying the import
+    fn resolve(
+        &self,
+        specifier: &ModuleSpecifier,
+        referrer: &str,
+    ) -> Result<ModuleSpecifier, ModuleLoaderError> {
+        Ok(specifier.clone())
+    }
+
+    /// Fetch the code of a module of this scheme
+    ///
+    /// # Arguments
+    /// - `specifier`: The module specifier to load, as returned by `resolve`
+    fn load(
+        &self,
+        specifier: &ModuleSpecifier,
+    ) -> LocalBoxFuture<'static, Result<String, ModuleLoaderError>>;
+}
+
+impl<F, Fut> SchemeHandler for F
+where
+    F: Fn(&ModuleSpecifier) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, ModuleLoaderError>> + 'static,
+{
+    fn load(
+        &self,
+        specifier: &ModuleSpecifier,
+    ) -> LocalBoxFuture<'static, Result<String, ModuleLoaderError>> {
+        Box::pin(self(specifier))
+    }
+}
@@ -0,0 +1,28 @@
+//! This module provides a trait for loading source maps on demand
+/// Source map provider trait
+/// Implement this trait to provide source maps lazily, only when they are needed to map an error's stack trace
+///
+/// By default the loader keeps the source map of every module it transpiles in memory.  
+/// When a provider is set, those maps are discarded, and the provider is queried instead
+///
+/// Any `Fn(&str) -> Option<Vec<u8>>` closure can be used as a provider
+pub trait SourceMapProvider {
+    /// Get the source map for a given file
+    ///
+    /// # Arguments
+    /// - `filename`: The URL of the file, as it appears in stack traces
+    ///
+    /// # Returns
+    /// - Some(Vec<u8>): The source map for the file
+    /// - None: No source map is available; locations will refer to the code as executed
+    fn source_map(&self, filename: &str) -> Option<Vec<u8>>;
+}
+
+impl<F> SourceMapProvider for F
+where
+    F: Fn(&str) -> Option<Vec<u8>>,
+{
+    fn source_map(&self, filename: &str) -> Option<Vec<u8>> {
+        self(filename)
+    }
+}
@@ -0,0 +1,228 @@
+//! This module provides `tsconfig.json` path mapping for the module loader
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use deno_core::ModuleSpecifier;
+use serde::Deserialize;
+
+use crate::Error;
+
+/// Extensions tried, in order, when a mapped path does not name a file directly
+const EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "js", "jsx", "mjs"];
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptions {
+    base_url: Option<String>,
+    #[serde(default)]
+    paths: HashMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsConfig {
+    #[serde(default)]
+    compiler_options: CompilerOptions,
+}
+
+/// The `baseUrl` and `paths` compiler options of a `tsconfig.json`, applied during module resolution
+///
+/// Bare specifiers matching a `paths` pattern (such as `@lib/*`) are mapped the same way `tsc` maps them:
+/// - An exact pattern wins over a wildcard one, and the wildcard pattern with the longest prefix wins over the rest
+/// - Each target of the pattern is tried in order, and the first that names an existing file is used
+/// - Targets may omit the extension, name a directory with an `index` file, or use `.js` for a `.ts` file
+///
+/// With a `baseUrl`, other bare specifiers are also tried relative to it
+/// Specifiers that are not mapped to an existing file are resolved as usual
+///
+/// Only the file itself is read - `extends` is not followed
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{module_loader::TsConfigPaths, Runtime, RuntimeOptions};
+///
+/// # fn main() -> Result<(), rustyscript::Error> {
+/// let mut runtime = Runtime::new(RuntimeOptions {
+///     tsconfig_paths: Some(TsConfigPaths::from_file("tsconfig.json")?),
+///     ..Default::default()
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TsConfigPaths {
+    base_url: Option<PathBuf>,
+    root: PathBuf,
+    patterns: Vec<(String, Vec<String>)>,
+}
+
+impl TsConfigPaths {
+    /// Reads the path mapping options from a `tsconfig.json` file
+    ///
+    /// # Errors
+    /// Will return an error if the file cannot be read, or is not a valid `tsconfig.json`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = std::path::absolute(path.as_ref())?;
+        let json = std::fs::read_to_string(&path)?;
+        let dir = path.parent().unwrap_or(&path);
+        Self::from_json(dir, &json)
+    }
+
+    /// Parses the path mapping options from the contents of a `tsconfig.json`
+    /// Relative paths are resolved from `dir`, the directory the file would be in
+    ///
+    /// Comments and trailing commas are allowed, as they are by `tsc`
+    ///
+    /// # Errors
+    /// Will return an error if the contents are not a valid `tsconfig.json`
+    pub fn from_json(dir: impl AsRef<Path>, json: &str) -> Result<Self, Error> {
+        let root = std::path::absolute(dir.as_ref())?;
+
+        let options = jsonc_parser::ParseOptions::default();
+        let value = jsonc_parser::parse_to_serde_value(json, &options)
+            .map_err(|e| Error::JsonDecode(format!("tsconfig.json: {e}")))?
+            .ok_or_else(|| Error::JsonDecode("tsconfig.json is empty".to_string()))?;
+        let config = TsConfig::deserialize(value)
+            .map_err(|e| Error::JsonDecode(format!("tsconfig.json: {e}")))?;
+
+        let options = config.compiler_options;
+        let base_url = options.base_url.map(|base_url| root.join(base_url));
+        let mut patterns: Vec<_> = options.paths.into_iter().collect();
+
+        // Exact patterns first, then wildcards by descending prefix length
+        patterns.sort_by_key(|(pattern, _)| match pattern.split_once('*') {
+            Some((prefix, _)) => (true, std::cmp::Reverse(prefix.len())),
+            None => (false, std::cmp::Reverse(pattern.len())),
+        });
+
+        Ok(Self {
+            base_url,
+            root,
+            patterns,
+        })
+    }
+
+    /// Maps a bare specifier to the file it refers to, if any
+    ///
+    /// Returns `None` for relative, absolute, and URL specifiers, and for any that do not map to an existing file
+    #[must_use]
+    pub fn resolve(&self, specifier: &str) -> Option<ModuleSpecifier> {
+        if specifier.starts_with("./")
+            || specifier.starts_with("../")
+            || specifier.starts_with('/')
+            || deno_core::specifier_has_uri_scheme(specifier)
+        {
+            return None;
+        }
+
+        // `paths` targets are relative to `baseUrl` if set, or to the `tsconfig.json` otherwise
+        let paths_root = self.base_url.as_deref().unwrap_or(&self.root);
+
+        let mapped = self.patterns.iter().find_map(|(pattern, targets)| {
+            let matched = match pattern.split_once('*') {
+                Some((prefix, suffix)) => specifier
+                    .strip_prefix(prefix)
+                    .and_then(|s| s.strip_suffix(suffix)),
+                None => (pattern == specifier).then_some(""),
+            }?;
+
+            Some(targets.iter().find_map(|target| {
+                let target = target.replacen('*', matched, 1);
+                find_module(&paths_root.join(target))
+            }))
+        });
+
+        let path = match mapped {
+            Some(path) => path?,
+            None => find_module(&self.base_url.as_ref()?.join(specifier))?,
+        };
+        ModuleSpecifier::from_file_path(path).ok()
+    }
+}
+
+/// Finds the module a mapped path refers to, trying the way `tsc` completes import paths
+fn find_module(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    // `./util.js` may refer to `./util.ts`
+    let stem = match path.extension().and_then(|e| e.to_str()) {
+        Some("js" | "jsx" | "mjs") => Some(path.with_extension("")),
+        _ => None,
+    };
+
+    let with_extension = |base: &Path| {
+        EXTENSIONS.iter().find_map(|ext| {
+            let mut candidate = base.as_os_str().to_owned();
+            candidate.push(".");
+            candidate.push(ext);
+            let candidate = PathBuf::from(candidate);
+            candidate.is_file().then_some(candidate)
+        })
+    };
+
+    stem.as_deref()
+        .and_then(with_extension)
+        .or_else(|| with_extension(path))
+        .or_else(|| with_extension(&path.join("index")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tsconfig_paths() {
+        let dir = std::env::temp_dir().join(format!("rustyscript_tsconfig_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/lib/nested")).unwrap();
+        std::fs::write(dir.join("src/lib/math.ts"), "").unwrap();
+        std::fs::write(dir.join("src/lib/nested/index.ts"), "").unwrap();
+        std::fs::write(dir.join("src/config.ts"), "").unwrap();
+        std::fs::write(
+            dir.join("tsconfig.json"),
+            r#"{
+                // Comments are allowed
+                "compilerOptions": {
+                    "baseUrl": "./src",
+                    "paths": {
+                        "@lib/*": ["lib/*"],
+                        "@lib/nested/*": ["missing/*", "lib/nested/*"],
+                        "config": ["config.ts"],
+                    },
+                },
+            }"#,
+        )
+        .unwrap();
+
+        let paths = TsConfigPaths::from_file(dir.join("tsconfig.json"));
+        let resolve = |specifier: &str| {
+            paths
+                .as_ref()
+                .unwrap()
+                .resolve(specifier)
+                .and_then(|url| url.to_file_path().ok())
+        };
+
+        let math = resolve("@lib/math");
+        let math_js = resolve("@lib/math.js");
+        let nested = resolve("@lib/nested/index");
+        let nested_dir = resolve("@lib/nested");
+        let config = resolve("config");
+        let base_url = resolve("lib/math");
+        let missing = resolve("@lib/missing");
+        let relative = resolve("./lib/math");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(math, Some(dir.join("src/lib/math.ts")));
+        assert_eq!(math_js, Some(dir.join("src/lib/math.ts")));
+        assert_eq!(nested, Some(dir.join("src/lib/nested/index.ts")));
+        assert_eq!(nested_dir, Some(dir.join("src/lib/nested/index.ts")));
+        assert_eq!(config, Some(dir.join("src/config.ts")));
+        assert_eq!(base_url, Some(dir.join("src/lib/math.ts")));
+        assert_eq!(missing, None);
+        assert_eq!(relative, None);
+    }
+}
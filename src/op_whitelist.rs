@@ -25,6 +25,9 @@ whitelist!(
         op_register_entrypoint,
         call_registered_function,
         call_registered_function_async,
+        op_builtin_module,
+        op_report_error,
+        op_console_record,
         op_panic2,
     ],
     "deno_core" => [
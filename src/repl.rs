@@ -0,0 +1,269 @@
+//! A read-eval-print loop over a [`Runtime`], see [`Repl`]
+use deno_ast::{
+    swc::ast::{Decl, Stmt, VarDeclKind},
+    MediaType, ParseParams, ProgramRef, SourceRangedForSpanned, TextChange,
+};
+use deno_core::v8;
+
+use crate::{js_value::Value, Error, Runtime};
+
+/// Evaluates snippets of javascript one at a time, the way an interactive prompt would
+///
+/// Each snippet runs as a script in the global context, so state accumulates between them, and:
+/// - Top-level `let`, `const` and `class` declarations can be repeated, to redefine a name
+/// - A snippet that throws keeps everything declared before it, including by the failing snippet
+/// - The completion value of each snippet is returned, and stored in the global `_`
+///
+/// Top-level declarations are rewritten to `var` to allow this, so `const` bindings can be reassigned
+/// Input starting with `{` and ending with `}` is evaluated as an object literal if it is one
+///
+/// As with node, `_` stops being updated once a snippet assigns to it
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{ Repl, Runtime, Error };
+///
+/// # fn main() -> Result<(), Error> {
+/// let mut repl = Repl::new(Runtime::new(Default::default())?);
+///
+/// repl.eval_line("const x = 2;")?;
+/// repl.eval_line("const x = 3;")?;
+///
+/// let value = repl.eval_line("x * 2")?;
+/// let value: u32 = value.try_into(repl.runtime())?;
+/// assert_eq!(value, 6);
+///
+/// let value: u32 = repl.eval_line("_ + 1")?.try_into(repl.runtime())?;
+/// assert_eq!(value, 7);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Repl {
+    runtime: Runtime,
+    last_value: Option<Value>,
+    capture_underscore: bool,
+}
+
+impl Repl {
+    /// Creates a REPL evaluating snippets in the global context of `runtime`
+    #[must_use]
+    pub fn new(runtime: Runtime) -> Self {
+        Self {
+            runtime,
+            last_value: None,
+            capture_underscore: true,
+        }
+    }
+
+    /// Returns the underlying runtime
+    pub fn runtime(&mut self) -> &mut Runtime {
+        &mut self.runtime
+    }
+
+    /// Consumes the REPL, returning the underlying runtime
+    #[must_use]
+    pub fn into_runtime(self) -> Runtime {
+        self.runtime
+    }
+
+    /// The completion value of the last snippet that succeeded, if any
+    #[must_use]
+    pub fn last_value(&self) -> Option<&Value> {
+        self.last_value.as_ref()
+    }
+
+    /// Evaluates a snippet of javascript, returning its completion value
+    ///
+    /// Like [`Runtime::eval`], promises are resolved and the event loop is run to completion
+    ///
+    /// # Errors
+    /// Will return an error if the snippet cannot be parsed, or if it throws
+    /// The state accumulated by earlier snippets is kept either way
+    pub fn eval_line(&mut self, src: &str) -> Result<Value, Error> {
+        let value: Value = self.runtime.eval(rewrite_snippet(src))?;
+        self.capture(&value);
+        self.last_value = Some(value.clone());
+        Ok(value)
+    }
+
+    /// Stores `value` in the global `_`, unless a snippet has taken over the name
+    fn capture(&mut self, value: &Value) {
+        if !self.capture_underscore {
+            return;
+        }
+
+        self.runtime.with_scope(|scope| {
+            let global = scope.get_current_context().global(scope);
+            let Some(key) = v8::String::new(scope, "_") else {
+                return;
+            };
+
+            // If `_` no longer holds what was last stored there, a snippet has assigned to it
+            let current = global.get(scope, key.into());
+            let untouched = match (&self.last_value, current) {
+                (Some(last), Some(current)) => {
+                    current.same_value(v8::Local::new(scope, last.as_v8()))
+                }
+                (None, Some(current)) => current.is_undefined(),
+                (_, None) => false,
+            };
+            if !untouched {
+                self.capture_underscore = false;
+                return;
+            }
+
+            let value = v8::Local::new(scope, value.as_v8());
+            global.set(scope, key.into(), value);
+        });
+    }
+}
+
+/// Parses `src` as a script, returning `None` if it is not valid javascript
+fn parse_script(src: &str) -> Option<deno_ast::ParsedSource> {
+    let specifier = deno_core::ModuleSpecifier::parse("repl:snippet").ok()?;
+    deno_ast::parse_script(ParseParams {
+        specifier,
+        text: src.into(),
+        media_type: MediaType::JavaScript,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })
+    .ok()
+}
+
+/// Rewrites the top-level `let`, `const` and `class` declarations of a snippet into `var` declarations,
+/// which can be repeated, and are never left uninitialized by an exception
+///
+/// Snippets that do not parse are returned unchanged, so that the error comes from v8
+fn rewrite_snippet(src: &str) -> String {
+    // `{ a: 1 }` would otherwise be a block containing a label
+    let trimmed = src.trim();
+    if trimmed.starts_with('{') && trimmed.ends_with('}') {
+        let wrapped = format!("({trimmed})");
+        if parse_script(&wrapped).is_some() {
+            return wrapped;
+        }
+    }
+
+    let Some(parsed) = parse_script(src) else {
+        return src.to_string();
+    };
+    let ProgramRef::Script(script) = parsed.program_ref() else {
+        return src.to_string();
+    };
+
+    let start_pos = parsed.range().start;
+    let mut changes = Vec::new();
+    for stmt in &script.body {
+        match stmt {
+            Stmt::Decl(Decl::Var(decl)) if decl.kind != VarDeclKind::Var => {
+                let start = decl.start().as_byte_index(start_pos);
+                let keyword = match decl.kind {
+                    VarDeclKind::Let => "let",
+                    _ => "const",
+                };
+                changes.push(TextChange::new(
+                    start,
+                    start + keyword.len(),
+                    "var".to_string(),
+                ));
+            }
+
+            Stmt::Decl(Decl::Class(decl)) => {
+                let start = decl.class.start().as_byte_index(start_pos);
+                let end = decl.class.end().as_byte_index(start_pos);
+                changes.push(TextChange::new(
+                    start,
+                    start,
+                    format!("var {} = ", decl.ident.sym),
+                ));
+                changes.push(TextChange::new(end, end, ";".to_string()));
+            }
+
+            _ => {}
+        }
+    }
+
+    deno_ast::apply_text_changes(src, changes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RuntimeOptions;
+
+    #[test]
+    fn test_repl() {
+        let mut repl = Repl::new(
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime"),
+        );
+
+        // Declarations persist, and can be repeated
+        repl.eval_line("let x = 1;").expect("Could not eval");
+        repl.eval_line("const x = 2, y = 3;")
+            .expect("Could not eval");
+        repl.eval_line("class Point { constructor(x) { this.x = x; } }")
+            .expect("Could not eval");
+        repl.eval_line("class Point { constructor(x) { this.x = x * 10; } }")
+            .expect("Could not eval");
+        let value = repl
+            .eval_line("new Point(x + y).x")
+            .expect("Could not eval");
+        let value: u32 = value
+            .try_into(repl.runtime())
+            .expect("Could not convert value");
+        assert_eq!(value, 50);
+
+        // Nested declarations are left alone
+        let value = repl
+            .eval_line("{ let inner = 4; inner * 2 }")
+            .expect("Could not eval")
+            .try_into::<u32>(repl.runtime())
+            .expect("Could not convert value");
+        assert_eq!(value, 8);
+
+        // Object literals
+        let value = repl.eval_line("{ a: 1, b: 2 }").expect("Could not eval");
+        let value: deno_core::serde_json::Value = value
+            .try_into(repl.runtime())
+            .expect("Could not convert value");
+        assert_eq!(value, deno_core::serde_json::json!({ "a": 1, "b": 2 }));
+
+        // The last value is captured
+        let value = repl.eval_line("_.b + 1").expect("Could not eval");
+        assert_eq!(
+            value
+                .clone()
+                .try_into::<u32>(repl.runtime())
+                .expect("Could not convert value"),
+            3
+        );
+        assert_eq!(repl.last_value(), Some(&value));
+
+        // A failed declaration does not leave the name unusable
+        repl.eval_line("let z = 5;").expect("Could not eval");
+        repl.eval_line("let z = (() => { throw new Error('nope') })();")
+            .unwrap_err();
+        repl.eval_line("let z = 6;").expect("Could not eval");
+
+        // Syntax errors come from v8 and do not lose state
+        repl.eval_line("let = = ;").unwrap_err();
+        let value: u32 = repl
+            .eval_line("x + z")
+            .expect("Could not eval")
+            .try_into(repl.runtime())
+            .expect("Could not convert value");
+        assert_eq!(value, 8);
+
+        // Assigning to `_` stops it being updated
+        repl.eval_line("_ = 'mine'").expect("Could not eval");
+        repl.eval_line("1 + 1").expect("Could not eval");
+        let value: String = repl
+            .eval_line("_")
+            .expect("Could not eval")
+            .try_into(repl.runtime())
+            .expect("Could not convert value");
+        assert_eq!(value, "mine");
+    }
+}
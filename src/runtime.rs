@@ -5,6 +5,7 @@ use tokio_util::sync::CancellationToken;
 
 use crate::{
     async_bridge::{AsyncBridge, AsyncBridgeExt, TokioRuntime},
+    cpu_profile::{CpuProfile, CpuProfiler},
     inner_runtime::{InnerRuntime, RsAsyncFunction, RsFunction},
     js_value::Function,
     Error, Module, ModuleHandle,
@@ -13,6 +14,17 @@ use crate::{
 /// Represents the set of options accepted by the runtime constructor
 pub use crate::inner_runtime::RuntimeOptions;
 
+/// The outcome of [`Runtime::run_until_idle`]
+pub use crate::inner_runtime::IdleResult;
+
+/// The outcome of [`Runtime::terminate_and_drain`]
+pub use crate::inner_runtime::{PrefetchReport, ShutdownReport};
+
+/// The heap state and response types for [`RuntimeOptions::on_memory_pressure`]
+pub use crate::inner_runtime::{
+    HeapStats, JsonLimits, MemoryPressureAction, MicrotaskPolicy, RuntimeConfigSummary,
+};
+
 /// For functions returning nothing. Acts as a placeholder for the return type  
 /// Should accept any type of value from javascript
 ///
@@ -29,9 +41,16 @@ pub type Undefined = crate::js_value::Value;
 /// - `call_function_immediate` will return the result immediately, without resolving promises or running the event loop
 ///   (See [`crate::js_value::Promise`])
 ///
+/// The blocking variants can be called from within a multi-threaded tokio runtime, such as an async web handler;
+/// they will block the current worker thread, while its other tasks are moved elsewhere.  
+/// From within a current-thread tokio runtime they return an error instead, as nothing else could run - use the async variants there
+///
 /// Note: For multithreaded applications, you may need to call `init_platform` before creating a `Runtime`  
 /// (See [[`crate::init_platform`])
 pub struct Runtime {
+    // Declared first, so the profiler's inspector session is dropped before the isolate
+    cpu_profiler: Option<CpuProfiler>,
+
     inner: InnerRuntime<deno_core::JsRuntime>,
     tokio: AsyncBridge,
 }
@@ -76,10 +95,18 @@ impl Runtime {
     /// Can fail if the tokio runtime cannot be created,  
     /// Or if the deno runtime initialization fails (usually issues with extensions)
     ///
+    /// If [`RuntimeOptions::tokio_handle`] is set, that runtime is used instead of creating a new one
     pub fn new(options: RuntimeOptions) -> Result<Self, Error> {
-        let tokio = AsyncBridge::new(options.timeout)?;
+        let tokio = AsyncBridge::with_optional_runtime_handle(
+            options.timeout,
+            options.tokio_handle.clone(),
+        )?;
         let inner = InnerRuntime::new(options, tokio.heap_exhausted_token())?;
-        Ok(Self { inner, tokio })
+        Ok(Self {
+            cpu_profiler: None,
+            inner,
+            tokio,
+        })
     }
 
     /// Creates a new instance of the runtime with the provided options and a pre-configured tokio runtime.  
@@ -93,7 +120,11 @@ impl Runtime {
     ) -> Result<Self, Error> {
         let tokio = AsyncBridge::with_tokio_runtime(options.timeout, tokio);
         let inner = InnerRuntime::new(options, tokio.heap_exhausted_token())?;
-        Ok(Self { inner, tokio })
+        Ok(Self {
+            cpu_profiler: None,
+            inner,
+            tokio,
+        })
     }
 
     /// Creates a new instance of the runtime with the provided options and a borrowed tokio runtime handle.  
@@ -107,7 +138,11 @@ impl Runtime {
     ) -> Result<Self, Error> {
         let tokio = AsyncBridge::with_runtime_handle(options.timeout, handle);
         let inner = InnerRuntime::new(options, tokio.heap_exhausted_token())?;
-        Ok(Self { inner, tokio })
+        Ok(Self {
+            cpu_profiler: None,
+            inner,
+            tokio,
+        })
     }
 
     /// Access the underlying deno runtime instance directly
@@ -115,6 +150,58 @@ impl Runtime {
         self.inner.deno_runtime()
     }
 
+    /// Runs a closure with a v8 scope entered into the runtime's main context
+    ///
+    /// The scope is created fresh for each call, so local handles do not outlive the closure;
+    /// keep values around as a `v8::Global`, or a [`crate::js_value::Value`] built from one
+    ///
+    /// Exceptions thrown by v8 calls inside the closure are not caught - use a `v8::TryCatch` scope for that
+    /// Does not run the event loop
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error, deno_core::v8 };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.eval::<()>("globalThis.answer = 42")?;
+    ///
+    /// let answer = runtime.with_scope(|scope| {
+    ///     let global = scope.get_current_context().global(scope);
+    ///     let key = v8::String::new(scope, "answer")?;
+    ///     global.get(scope, key.into())?.int32_value(scope)
+    /// });
+    /// assert_eq!(answer, Some(42));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_scope<R>(
+        &mut self,
+        f: impl FnOnce(&mut deno_core::v8::PinScope<'_, '_>) -> R,
+    ) -> R {
+        self.inner.with_scope(f)
+    }
+
+    /// Runs `f` with a helper function compiled from `source`, see `InnerRuntime::with_helper_function`
+    pub(crate) fn with_helper_function<R>(
+        &mut self,
+        source: &'static str,
+        f: impl FnOnce(
+            &mut deno_core::v8::PinScope<'_, '_>,
+            deno_core::v8::Local<'_, deno_core::v8::Function>,
+        ) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        self.inner.with_helper_function(source, f)
+    }
+
+    /// Get a thread-safe handle that can interrupt this runtime's execution from another thread
+    ///
+    /// See [`crate::InterruptHandle`] for more information, and an example
+    #[must_use]
+    pub fn interrupt_handle(&self) -> crate::InterruptHandle {
+        self.inner.interrupt.clone()
+    }
+
     /// Access the underlying tokio runtime used for blocking operations
     #[must_use]
     pub fn tokio_runtime(&self) -> TokioRuntime {
@@ -127,6 +214,26 @@ impl Runtime {
         self.tokio.timeout()
     }
 
+    /// Returns the effective configuration of the runtime, for diagnosing misconfiguration  
+    /// Includes the limits and flags it was created with, and the extensions that were actually installed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let runtime = Runtime::new(Default::default())?;
+    /// let config = runtime.config_summary();
+    /// println!("extensions: {}", config.extensions.join(", "));
+    /// println!("permissions: {:?}", config.permissions);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn config_summary(&self) -> RuntimeConfigSummary {
+        self.inner.config_summary().clone()
+    }
+
     /// Returns the heap exhausted token for the runtime  
     /// Used to detect when the runtime has run out of memory
     #[must_use]
@@ -134,6 +241,100 @@ impl Runtime {
         self.tokio.heap_exhausted_token()
     }
 
+    /// Writes a snapshot of the javascript heap, in the Chrome devtools `.heapsnapshot` format
+    ///
+    /// The snapshot can be loaded in the devtools memory tab to find what is retaining objects  
+    /// Note that devtools will only import files with a `.heapsnapshot` extension
+    ///
+    /// Taking a snapshot pauses the runtime while the whole heap is walked and serialized,
+    /// so this is slow and memory-hungry for large heaps
+    ///
+    /// # Errors
+    /// Will return an error if writing to `writer` fails
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let file = std::fs::File::create("runtime.heapsnapshot")?;
+    /// runtime.take_heap_snapshot(std::io::BufWriter::new(file))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_heap_snapshot(&mut self, mut writer: impl std::io::Write) -> Result<(), Error> {
+        let mut result = Ok(());
+        self.deno_runtime()
+            .v8_isolate()
+            .take_heap_snapshot(|chunk| match writer.write_all(chunk) {
+                Ok(()) => true,
+                Err(e) => {
+                    result = Err(e);
+                    false
+                }
+            });
+        result?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Starts sampling which javascript functions are running, until [`Runtime::stop_cpu_profile`] is called
+    ///
+    /// Profiling goes through the V8 inspector, which is enabled by the first call;
+    /// a runtime that is never profiled pays nothing for it
+    ///
+    /// # Arguments
+    /// * `name` - A name for the profile, see [`CpuProfile::name`]
+    /// * `sampling_interval` - How often to sample the call stack - V8 defaults to every 1ms
+    ///
+    /// # Errors
+    /// Will return an error if a profile is already running, or the profiler cannot be started
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.start_cpu_profile("startup", Some(Duration::from_micros(100)))?;
+    /// runtime.eval::<()>("for (let i = 0; i < 1e7; i++) {}")?;
+    ///
+    /// let profile = runtime.stop_cpu_profile()?;
+    /// profile.write(std::fs::File::create("startup.cpuprofile")?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn start_cpu_profile(
+        &mut self,
+        name: &str,
+        sampling_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        if let Some(profiler) = &self.cpu_profiler {
+            return Err(Error::Runtime(format!(
+                "CPU profile `{}` is already running",
+                profiler.name()
+            )));
+        }
+
+        let profiler = CpuProfiler::start(self.deno_runtime(), name, sampling_interval)?;
+        self.cpu_profiler = Some(profiler);
+        Ok(())
+    }
+
+    /// Stops the CPU profile started with [`Runtime::start_cpu_profile`], returning the collected samples
+    ///
+    /// # Errors
+    /// Will return an error if no profile is running, or the profile cannot be collected
+    pub fn stop_cpu_profile(&mut self) -> Result<CpuProfile, Error> {
+        let profiler = self
+            .cpu_profiler
+            .take()
+            .ok_or_else(|| Error::Runtime("No CPU profile is running".to_string()))?;
+        profiler.stop()
+    }
+
     /// Destroy the v8 runtime, releasing all resources  
     /// Then the internal tokio runtime will be returned
     #[must_use]
@@ -210,6 +411,43 @@ impl Runtime {
         self.inner.await_event_loop(options, timeout).await
     }
 
+    /// Returns a future that drives the JS event loop to completion  
+    /// Unlike [`Runtime::await_event_loop`] it has no timeout, and polls the event loop directly,
+    /// so that it can be raced against other futures - for example in a `tokio::select!`
+    ///
+    /// The future holds no state of its own: if it is dropped before completing,
+    /// pending work stays in the runtime, and resumes the next time the event loop runs  
+    /// (such as during `call_function_async`, or another call to this function)
+    ///
+    /// # Arguments
+    /// * `options` - Options for the event loop polling, see [`deno_core::PollEventLoopOptions`]
+    ///
+    /// # Errors
+    /// The future resolves to an error if a runtime error occurs during the event loop's execution
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
+    /// use std::time::Duration;
+    ///
+    /// # async fn run(runtime: &mut Runtime) -> Result<(), Error> {
+    /// loop {
+    ///     tokio::select! {
+    ///         result = runtime.poll_event_loop_future(Default::default()) => break result,
+    ///         () = tokio::time::sleep(Duration::from_millis(100)) => {
+    ///             // Handle other work, then resume the event loop
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn poll_event_loop_future(
+        &mut self,
+        options: PollEventLoopOptions,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + '_ {
+        std::future::poll_fn(move |cx| self.inner.poll_event_loop(cx, options))
+    }
+
     /// Run the JS event loop to completion, or until a timeout is reached  
     /// Required when using the `_immediate` variants of functions
     ///
@@ -229,1219 +467,6156 @@ impl Runtime {
         self.block_on(|runtime| async move { runtime.await_event_loop(options, timeout).await })
     }
 
-    /// Remove and return a value from the state, if one exists
-    /// ```no_run
-    /// use rustyscript::{ Runtime };
+    /// Advance the event loop by a single tick, then report whether the runtime has any pending work -
+    /// timers, promises, ops, or dynamic imports  
+    /// Useful to decide if a job has completed, or if background tasks are still running
     ///
-    /// # fn main() -> Result<(), rustyscript::Error> {
-    /// let mut runtime = Runtime::new(Default::default())?;
-    /// runtime.put("test".to_string())?;
-    /// let value: String = runtime.take().unwrap();
-    /// assert_eq!(value, "test");
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn take<T>(&mut self) -> Option<T>
-    where
-        T: 'static,
-    {
-        self.inner.take()
-    }
-
-    /// Add a value to the state  
-    /// Only one value of each type is stored - additional calls to `put` overwrite the old value
+    /// The tick flushes microtasks and runs any work that is already ready, so this can be called repeatedly to drive the loop
     ///
     /// # Errors
-    /// Can fail if the inner state cannot be borrowed mutably
+    /// Can fail if a runtime error occurs during the event loop's execution
     ///
+    /// # Example
     /// ```no_run
-    /// use rustyscript::{ Runtime };
+    /// use rustyscript::{ Runtime, Undefined, Error };
     ///
-    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// runtime.put("test".to_string())?;
-    /// let value: String = runtime.take().unwrap();
-    /// assert_eq!(value, "test");
+    /// runtime.eval::<Undefined>("setTimeout(() => {}, 100);")?;
+    ///
+    /// while runtime.poll_pending_work()? {
+    ///     // Do something else in the meantime
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn put<T>(&mut self, value: T) -> Result<(), Error>
-    where
-        T: 'static,
-    {
-        self.inner.put(value)
+    pub fn poll_pending_work(&mut self) -> Result<bool, Error> {
+        self.advance_event_loop(PollEventLoopOptions::default())
     }
 
-    /// Register a rust function to be callable from JS
-    /// - The [`crate::sync_callback`] macro can be used to simplify this process
+    /// Run the JS event loop until the runtime is idle - no pending timers, promises, or ops
+    ///
+    /// Returns a future that resolves to true if the runtime became idle,  
+    /// or false if there was still pending work once `timeout` elapsed
+    ///
+    /// Unlike [`Runtime::await_event_loop`], reaching the timeout is reported to the caller
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum amount of time to wait for the runtime to become idle
     ///
     /// # Errors
-    /// Since this function borrows the state, it can fail if the state cannot be borrowed mutably
+    /// Can fail if a runtime error occurs during the event loop's execution
+    pub async fn wait_until_idle_async(&mut self, timeout: Duration) -> Result<bool, Error> {
+        self.inner
+            .wait_until_idle(PollEventLoopOptions::default(), timeout)
+            .await
+    }
+
+    /// Run the JS event loop until the runtime is idle - no pending timers, promises, or ops
     ///
-    /// ```no_run
-    /// use rustyscript::{ Runtime, Module, serde_json::Value };
+    /// Returns true if the runtime became idle,  
+    /// or false if there was still pending work once `timeout` elapsed
     ///
-    /// # fn main() -> Result<(), rustyscript::Error> {
-    /// let module = Module::new("test.js", " rustyscript.functions.foo(); ");
-    /// let mut runtime = Runtime::new(Default::default())?;
-    /// runtime.register_function("foo", |args| {
-    ///     if let Some(value) = args.get(0) {
-    ///         println!("called with: {}", value);
-    ///     }
-    ///     Ok(Value::Null)
-    /// })?;
+    /// This is the blocking variant of [`Runtime::wait_until_idle_async`]
     ///
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn register_function<F>(&mut self, name: &str, callback: F) -> Result<(), Error>
-    where
-        F: RsFunction,
-    {
-        self.inner.register_function(name, callback)
+    /// # Arguments
+    /// * `timeout` - Maximum amount of time to wait for the runtime to become idle
+    ///
+    /// # Errors
+    /// Can fail if a runtime error occurs during the event loop's execution,  
+    /// or if the runtime's own timeout is reached first
+    pub fn wait_until_idle(&mut self, timeout: Duration) -> Result<bool, Error> {
+        self.block_on(|runtime| async move { runtime.wait_until_idle_async(timeout).await })
     }
 
-    /// Register a non-blocking rust function to be callable from JS
-    /// - The [`crate::async_callback`] macro can be used to simplify this process
+    /// Run the JS event loop until the runtime is idle, within a budget of turns and time
+    ///
+    /// Each turn is a single poll of the event loop - running ready timers, microtasks, and completed ops  
+    /// This bounds the work done for scripts that never become idle, such as those with self-rescheduling timers
+    ///
+    /// Returns a future that resolves to [`IdleResult::Idle`] if the runtime became idle,  
+    /// or [`IdleResult::BudgetExceeded`] if work was still pending after `max_turns` turns, or once `timeout` elapsed  
+    /// Pending work is left in the runtime, and resumes the next time the event loop runs
+    ///
+    /// # Arguments
+    /// * `max_turns` - Maximum number of times to poll the event loop
+    /// * `timeout` - Maximum amount of time to wait for the runtime to become idle
     ///
     /// # Errors
-    /// Since this function borrows the state, it can fail if the state cannot be borrowed mutably
+    /// Can fail if a runtime error occurs during the event loop's execution
     ///
+    /// # Example
     /// ```no_run
-    /// use rustyscript::{ Runtime, Module, serde_json::Value, async_callback, Error };
+    /// use rustyscript::{ IdleResult, Runtime, Undefined, Error };
+    /// use std::time::Duration;
     ///
-    /// # fn main() -> Result<(), rustyscript::Error> {
-    /// let module = Module::new("test.js", " rustyscript.async_functions.add(1, 2); ");
-    /// let mut runtime = Runtime::new(Default::default())?;
-    /// runtime.register_async_function("add", async_callback!(
-    ///     |a: i64, b: i64| async move {
-    ///         Ok::<i64, Error>(a + b)
-    ///     }
-    /// ))?;
+    /// # async fn run(runtime: &mut Runtime) -> Result<(), Error> {
+    /// runtime.eval_immediate::<Undefined>("setInterval(() => {}, 0)").await?;
     ///
+    /// let result = runtime.run_until_idle_async(1000, Duration::from_secs(1)).await?;
+    /// if result == IdleResult::BudgetExceeded {
+    ///     // The script will never finish - recycle the runtime
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn register_async_function<F>(&mut self, name: &str, callback: F) -> Result<(), Error>
-    where
-        F: RsAsyncFunction,
-    {
-        self.inner.register_async_function(name, callback)
+    pub async fn run_until_idle_async(
+        &mut self,
+        max_turns: usize,
+        timeout: Duration,
+    ) -> Result<IdleResult, Error> {
+        self.inner
+            .run_until_idle(PollEventLoopOptions::default(), max_turns, timeout)
+            .await
     }
 
-    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
-    /// The expression is evaluated in the global context, so changes persist
+    /// Run the JS event loop until the runtime is idle, within a budget of turns and time
     ///
-    /// Blocks on promise resolution, and runs the event loop to completion
+    /// This is the blocking variant of [`Runtime::run_until_idle_async`]
     ///
-    /// Asynchronous code is supported, partially
-    /// - Top-level await is not supported
-    /// - The event loop will be run to completion after the expression is evaluated
+    /// # Arguments
+    /// * `max_turns` - Maximum number of times to poll the event loop
+    /// * `timeout` - Maximum amount of time to wait for the runtime to become idle
     ///
-    /// For top-level await support, use one of:
-    /// - `call_function_async`
-    /// - `call_stored_function_async`
-    /// - `load_module_async`
-    /// - `load_modules_async`
+    /// # Errors
+    /// Can fail if a runtime error occurs during the event loop's execution,  
+    /// or if the runtime's own timeout is reached first
+    pub fn run_until_idle(
+        &mut self,
+        max_turns: usize,
+        timeout: Duration,
+    ) -> Result<IdleResult, Error> {
+        self.block_on(
+            |runtime| async move { runtime.run_until_idle_async(max_turns, timeout).await },
+        )
+    }
+
+    /// Shut the runtime down within a deadline, reporting the work that was still pending
     ///
-    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
+    /// Returns a future that resolves once:
+    /// - Intervals have been cancelled, so that scripts stop scheduling new work
+    /// - The event loop has run until idle, or until `deadline` - pending I/O and timers get a chance to finish,
+    ///   and a script still running at the deadline is terminated
+    /// - Resources that scripts left open, such as sockets and files, have been closed
+    ///
+    /// Errors thrown by scripts while draining are collected in the report, rather than ending the drain  
+    /// Once the drain has started, calls into the runtime are rejected with [`Error::Runtime`] - it should be dropped afterwards
     ///
     /// # Arguments
-    /// * `expr` - A string representing the JavaScript expression to evaluate
+    /// * `deadline` - Maximum amount of time to spend draining the event loop
+    pub async fn terminate_and_drain_async(&mut self, deadline: Duration) -> ShutdownReport {
+        self.inner.terminate_and_drain(deadline).await
+    }
+
+    /// Shut the runtime down within a deadline, reporting the work that was still pending
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the expression (`T`)  
-    /// or an error (`Error`) if the expression cannot be evaluated or if the
-    /// result cannot be deserialized.
+    /// This is the blocking variant of [`Runtime::terminate_and_drain_async`]  
+    /// The deadline is capped to the runtime's own timeout
     ///
-    /// # Errors
-    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
+    /// # Arguments
+    /// * `deadline` - Maximum amount of time to spend draining the event loop
     ///
     /// # Example
     /// ```no_run
-    /// use rustyscript::{ Runtime, Error };
+    /// use rustyscript::{ Runtime, Undefined, Error };
+    /// use std::time::Duration;
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.eval::<Undefined>("setTimeout(() => {}, 60_000); undefined")?;
     ///
-    /// let value: u32 = runtime.eval("2 + 2")?;
-    /// assert_eq!(4, value);
-    ///
-    /// let value: String = runtime.eval("new Promise(resolve => resolve('test'))")?;
-    /// assert_eq!("test", value);
-    ///
+    /// let report = runtime.terminate_and_drain(Duration::from_secs(1));
+    /// if !report.idle {
+    ///     eprintln!("{} timers and {:?} were still pending", report.pending_timers, report.pending_ops);
+    /// }
+    /// drop(runtime);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn eval<T>(&mut self, expr: impl ToString) -> Result<T, Error>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        self.block_on(|runtime| async move { runtime.eval_async(expr).await })
+    pub fn terminate_and_drain(&mut self, deadline: Duration) -> ShutdownReport {
+        let deadline = deadline.min(self.timeout());
+        self.block_on(
+            |runtime| async move { Ok(runtime.terminate_and_drain_async(deadline).await) },
+        )
+        .unwrap_or_else(|e| ShutdownReport {
+            errors: vec![e],
+            ..Default::default()
+        })
     }
 
-    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
-    /// The expression is evaluated in the global context, so changes persist
+    /// Run only the microtask queue - promise continuations and `queueMicrotask` callbacks - to completion
     ///
-    /// Awaits promise resolution, and runs the event loop to completion
+    /// Unlike [`Runtime::advance_event_loop`], timers are not advanced and pending async ops are not polled,
+    /// which allows promise-chain behavior to be tested deterministically, separately from timers
     ///
-    /// Asynchronous code is supported, partially
-    /// - Top-level await is not supported
-    /// - The event loop will be run to completion after the expression is evaluated
+    /// Under the default [`crate::MicrotaskPolicy::Auto`], V8 already runs the queue as each call into javascript
+    /// returns, so there is usually nothing left to run  
+    /// Set [`RuntimeOptions::microtask_policy`] to [`crate::MicrotaskPolicy::Explicit`] so that microtasks
+    /// wait for this call instead
     ///
-    /// For top-level await support, use one of:
-    /// - `call_function_async`
-    /// - `call_stored_function_async`
-    /// - `load_module_async`
-    /// - `load_modules_async`
+    /// # Errors
+    /// Can fail if the runtime was shut down by [`Runtime::terminate_and_drain`]  
+    /// An exception thrown by a microtask is not returned here: a promise callback rejects its promise instead,
+    /// and other exceptions are reported as uncaught on the next run of the event loop
     ///
-    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
-    ///
-    /// # Arguments
-    /// * `expr` - A string representing the JavaScript expression to evaluate
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, RuntimeOptions, MicrotaskPolicy, Module, Undefined, json_args, Error };
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the expression (`T`)  
-    /// or an error (`Error`) if the expression cannot be evaluated or if the
-    /// result cannot be deserialized.
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions {
+    ///     microtask_policy: MicrotaskPolicy::Explicit,
+    ///     ..Default::default()
+    /// })?;
+    /// let module = Module::new("test.js", "
+    ///     globalThis.log = [];
+    ///     export function schedule() {
+    ///         Promise.resolve().then(() => log.push('microtask'));
+    ///         setTimeout(() => log.push('timer'), 0);
+    ///     }
+    /// ");
+    /// let module = runtime.load_module(&module)?;
     ///
-    /// # Errors
-    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
+    /// runtime.call_function_immediate::<Undefined>(Some(&module), "schedule", json_args!())?;
+    /// runtime.run_microtasks()?;
     ///
-    /// # Example
-    /// For an example, see [`Runtime::eval`]
-    pub async fn eval_async<T>(&mut self, expr: impl ToString) -> Result<T, Error>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let result = self.inner.eval(expr.to_string()).await?;
-        let result = self.inner.resolve_with_event_loop(result).await?;
-        self.inner.decode_value(result)
+    /// let log: Vec<String> = runtime.get_value_immediate(None, "log")?;
+    /// assert_eq!(log, vec!["microtask"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_microtasks(&mut self) -> Result<(), Error> {
+        self.inner.run_microtasks()
     }
 
-    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
-    /// The expression is evaluated in the global context, so changes persist
+    /// Returns the number of promises that have been created, but not yet resolved or rejected  
+    /// Useful to check that a runtime is idle before reusing it, since a nonzero count can indicate a leaked or hung script
     ///
-    /// Does not await promise resolution, or run the event loop  
-    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
-    /// The event loop should be run using [`Runtime::await_event_loop`]
+    /// This is a diagnostic, and the count is approximate:
+    /// - Promises that are garbage-collected while still pending are still counted
+    /// - It includes promises created by the runtime's own extensions, so compare against
+    ///   the count of a freshly created runtime rather than against zero
     ///
-    /// Note that this function needs to be async because calls to `setTimeout` must be evaluated from within an async runtime.
+    /// Promises are only counted if [`RuntimeOptions::track_pending_promises`] is set - otherwise this is always 0
     ///
-    /// Asynchronous code is supported, partially
-    /// - Top-level await is not supported
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, RuntimeOptions, Undefined, Error };
     ///
-    /// For top-level await support, use one of:
-    /// - `call_function_async`
-    /// - `call_stored_function_async`
-    /// - `load_module_async`
-    /// - `load_modules_async`
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions {
+    ///     track_pending_promises: true,
+    ///     ..Default::default()
+    /// })?;
+    /// let baseline = runtime.pending_promise_count();
     ///
-    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
+    /// runtime.eval::<Undefined>("globalThis.hung = new Promise(() => {}); undefined")?;
+    /// assert!(runtime.pending_promise_count() > baseline);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pending_promise_count(&mut self) -> usize {
+        self.inner.pending_promise_count()
+    }
+
+    /// Remove and return the stack trace of the most recent javascript error raised by this runtime
     ///
-    /// # Arguments
-    /// * `expr` - A string representing the JavaScript expression to evaluate
+    /// The stack is kept separate from the error message, one frame per line,  
+    /// and is source-mapped so that locations refer to the original source
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the expression (`T`)  
-    /// or an error (`Error`) if the expression cannot be evaluated or if the
-    /// result cannot be deserialized.
+    /// Returns `None` if no javascript error has occured since the last call
     ///
-    /// # Errors
-    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
+    /// See [`Error::stack`] and [`Error::stack_frames`] to get the same information from an error directly
     ///
     /// # Example
-    /// For an example, see [`Runtime::eval`]
-    pub async fn eval_immediate<T>(&mut self, expr: impl ToString) -> Result<T, Error>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let result = self.inner.eval(expr.to_string()).await?;
-        self.inner.decode_value(result)
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export function f() { throw new Error('oops'); }");
+    /// let module = runtime.load_module(&module)?;
+    ///
+    /// if let Err(e) = runtime.call_function::<()>(Some(&module), "f", &()) {
+    ///     let stack = runtime.take_error_stack();
+    ///     println!("message: {e}\nstack: {stack:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_error_stack(&mut self) -> Option<String> {
+        self.inner.take_error_stack()
     }
 
-    /// Calls a stored javascript function and deserializes its return value.
-    ///
-    /// Returns a future that resolves when:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
+    /// Remove and return the errors recorded since the last call, when [`RuntimeOptions::fail_on_console_error`] is set
     ///
-    /// See [`Runtime::call_function`] for an example
+    /// This includes every message printed with `console.error`, and the message of every uncaught javascript error returned by the runtime  
+    /// Always empty if the option is not set
     ///
-    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, RuntimeOptions, Error };
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module providing global context for the function
-    /// * `function` - A The function object
-    /// * `args` - The arguments to pass to the function
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions {
+    ///     fail_on_console_error: true,
+    ///     ..Default::default()
+    /// })?;
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if there are issues with calling the function,
-    /// or if the result cannot be deserialized.
+    /// runtime.eval::<()>("console.error('Something went wrong')")?;
+    /// assert_eq!(runtime.take_reported_errors(), vec!["Something went wrong"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_reported_errors(&mut self) -> Vec<String> {
+        self.inner.take_reported_errors()
+    }
+
+    /// Fail if any errors were recorded since the last call, when [`RuntimeOptions::fail_on_console_error`] is set  
+    /// The recorded errors are cleared, see [`Runtime::take_reported_errors`]
     ///
     /// # Errors
-    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
-    pub async fn call_stored_function_async<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        function: &Function,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
+    /// Returns a [`Error::Runtime`] listing the recorded errors, if there were any
+    pub fn check_reported_errors(&mut self) -> Result<(), Error> {
+        let errors = self.take_reported_errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Runtime(format!(
+                "{} error(s) reported:\n{}",
+                errors.len(),
+                errors.join("\n")
+            )))
+        }
+    }
+
+    /// Remove and return a value from the state, if one exists
+    /// ```no_run
+    /// use rustyscript::{ Runtime };
+    ///
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.put("test".to_string())?;
+    /// let value: String = runtime.take().unwrap();
+    /// assert_eq!(value, "test");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take<T>(&mut self) -> Option<T>
     where
-        T: serde::de::DeserializeOwned,
+        T: 'static,
     {
-        let isolate = self.deno_runtime().v8_isolate();
-        let function = function.as_global(isolate);
-        let result = self
-            .inner
-            .call_function_by_ref(module_context, &function, args)?;
-        let result = self.inner.resolve_with_event_loop(result).await?;
-        self.inner.decode_value(result)
+        self.inner.take()
     }
 
-    /// Calls a stored javascript function and deserializes its return value.
-    ///
-    /// Blocks until:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
-    ///
-    /// See [`Runtime::call_function`] for an example
+    /// Add a value to the state  
+    /// Only one value of each type is stored - additional calls to `put` overwrite the old value
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module providing global context for the function
-    /// * `function` - A The function object
-    /// * `args` - The arguments to pass to the function
+    /// # Errors
+    /// Can fail if the inner state cannot be borrowed mutably
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if there are issues with calling the function,
-    /// or if the result cannot be deserialized.
+    /// ```no_run
+    /// use rustyscript::{ Runtime };
     ///
-    /// # Errors
-    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
-    pub fn call_stored_function<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        function: &Function,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.put("test".to_string())?;
+    /// let value: String = runtime.take().unwrap();
+    /// assert_eq!(value, "test");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn put<T>(&mut self, value: T) -> Result<(), Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        T: 'static,
     {
-        self.block_on(|runtime| async move {
-            runtime
-                .call_stored_function_async(module_context, function, args)
-                .await
-        })
+        self.inner.put(value)
     }
 
-    /// Calls a stored javascript function and deserializes its return value.
-    ///
-    /// Will not attempt to resolve promises, or run the event loop  
-    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
-    /// The event loop should be run using [`Runtime::await_event_loop`]
+    /// Register a rust function to be callable from JS
+    /// - The [`crate::sync_callback`] macro can be used to simplify this process
     ///
-    /// See [`Runtime::call_function`] for an example
+    /// # Errors
+    /// Since this function borrows the state, it can fail if the state cannot be borrowed mutably
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module providing global context for the function
-    /// * `function` - A The function object
-    /// * `args` - The arguments to pass to the function
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, serde_json::Value };
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if there are issues with calling the function,
-    /// or if the result cannot be deserialized.
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let module = Module::new("test.js", " rustyscript.functions.foo(); ");
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.register_function("foo", |args| {
+    ///     if let Some(value) = args.get(0) {
+    ///         println!("called with: {}", value);
+    ///     }
+    ///     Ok(Value::Null)
+    /// })?;
     ///
-    /// # Errors
-    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
-    pub fn call_stored_function_immediate<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        function: &Function,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_function<F>(&mut self, name: &str, callback: F) -> Result<(), Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        F: RsFunction,
     {
-        let isolate = self.deno_runtime().v8_isolate();
-        let function = function.as_global(isolate);
-        let result = self
-            .inner
-            .call_function_by_ref(module_context, &function, args)?;
-        self.inner.decode_value(result)
+        self.inner.register_function(name, callback)
     }
 
-    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
-    ///
-    /// Returns a future that resolves when:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
-    ///
-    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
+    /// Register a non-blocking rust function to be callable from JS
+    /// - The [`crate::async_callback`] macro can be used to simplify this process
     ///
-    /// See [`Runtime::call_function`] for an example
+    /// # Errors
+    /// Since this function borrows the state, it can fail if the state cannot be borrowed mutably
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the javascript function to call.
-    /// * `args` - The arguments to pass to the function
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, serde_json::Value, async_callback, Error };
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if the function cannot be found, if there are issues with
-    /// calling the function, or if the result cannot be deserialized.
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let module = Module::new("test.js", " rustyscript.async_functions.add(1, 2); ");
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.register_async_function("add", async_callback!(
+    ///     |a: i64, b: i64| async move {
+    ///         Ok::<i64, Error>(a + b)
+    ///     }
+    /// ))?;
     ///
-    /// # Errors
-    /// Fails if the function cannot be found, if there are issues with calling the function,
-    /// Or if the result cannot be deserialized into the requested type
-    pub async fn call_function_async<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_async_function<F>(&mut self, name: &str, callback: F) -> Result<(), Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        F: RsAsyncFunction,
     {
-        let function = self.inner.get_function_by_name(module_context, name)?;
-        let result = self
-            .inner
-            .call_function_by_ref(module_context, &function, args)?;
-        let result = self.inner.resolve_with_event_loop(result).await?;
-        self.inner.decode_value(result)
+        self.inner.register_async_function(name, callback)
     }
 
-    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
-    ///
-    /// Blocks until:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
+    /// Define a module that scripts can import by specifier, backed by a javascript object  
+    /// This allows exposing host APIs, such as registered rust functions, without generating wrapper JS
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the javascript function to call.
-    /// * `args` - The arguments to pass to the function
+    /// - The specifier can use any scheme, such as `host:db`, and does not need an import provider
+    /// - Each enumerable property of `exports` becomes a named export, as it was when the module was first imported
+    /// - The object itself is the default export, unless it has a `default` property
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if the function cannot be found, if there are issues with
-    /// calling the function, or if the result cannot be deserialized.
+    /// The module must be defined before it is first imported
     ///
     /// # Errors
-    /// Fails if the function cannot be found, if there are issues with calling the function,  
-    /// Or if the result cannot be deserialized into the requested type
+    /// Will return an error if the specifier is not a valid URL, or if `exports` is not an object
     ///
-    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error, js_value::Value, sync_callback };
+    ///
+    /// # fn main() -> Result<(), rustyscript::Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.register_function("query", sync_callback!(
+    ///     |sql: String| Ok::<String, Error>(format!("rows for {sql}"))
+    /// ))?;
+    ///
+    /// let exports: Value = runtime.eval("({ query: rustyscript.functions.query })")?;
+    /// runtime.define_builtin_module("host:db", &exports)?;
+    ///
+    /// let module = Module::new("test.js", "
+    ///     import { query } from 'host:db';
+    ///     console.log(query('select 1'));
+    /// ");
+    /// runtime.load_module(&module)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_builtin_module(
+        &mut self,
+        specifier: &str,
+        exports: &crate::js_value::Value,
+    ) -> Result<(), Error> {
+        self.inner.define_builtin_module(specifier, exports.as_v8())
+    }
+
+    /// Returns the names of the properties of `globalThis`, enumerable or not, in the order they were defined
+    ///
+    /// Includes globals created by `var`, function declarations, assignment to `globalThis` and `Object.defineProperty`  
+    /// Top-level `let`, `const` and `class` declarations, and the top-level declarations of modules,
+    /// do not create properties of `globalThis`, so are not included
+    ///
+    /// See [`Runtime::user_global_keys`] to leave out the globals defined by the runtime itself
+    pub fn global_keys(&mut self) -> Vec<String> {
+        self.inner.global_keys()
+    }
+
+    /// Returns the names of the globals defined since the runtime was created, such as by scripts
+    /// Globals defined by extensions and the runtime itself are left out, unless their value, getter or setter was replaced
     ///
+    /// # Example
     /// ```no_run
-    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    /// use rustyscript::{ Runtime, Error };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("/path/to/module.js", "export function f() { return 2; };");
-    /// let module = runtime.load_module(&module)?;
-    /// let value: usize = runtime.call_function(Some(&module), "f", json_args!())?;
+    /// runtime.eval::<()>("var foo = 1; function bar() {}")?;
+    /// assert_eq!(runtime.user_global_keys(), vec!["foo", "bar"]);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn call_function<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
-    where
-        T: deno_core::serde::de::DeserializeOwned,
-    {
-        self.block_on(|runtime| async move {
-            runtime
-                .call_function_async(module_context, name, args)
-                .await
-        })
+    pub fn user_global_keys(&mut self) -> Vec<String> {
+        self.inner.user_global_keys()
     }
 
-    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
+    /// Returns the names of the globals defined since `baseline` was taken with [`Runtime::global_keys`]
     ///
-    /// Will not attempt to resolve promises, or run the event loop  
-    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
-    /// The event loop should be run using [`Runtime::await_event_loop`]
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
     ///
-    /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the javascript function to call.
-    /// * `args` - The arguments to pass to the function
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let baseline = runtime.global_keys();
+    /// runtime.eval::<()>("globalThis.result = 42")?;
+    /// println!("defined: {}", runtime.globals_added_since(&baseline).join(", "));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn globals_added_since(&mut self, baseline: &[String]) -> Vec<String> {
+        let mut keys = self.global_keys();
+        keys.retain(|key| !baseline.contains(key));
+        keys
+    }
+
+    /// Create a string ahead of time, to be reused whenever it is passed as an argument
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the function call (`T`)  
-    /// or an error (`Error`) if the function cannot be found, if there are issues with
-    /// calling the function, or if the result cannot be deserialized.
+    /// Arguments to [`Runtime::call_function`] and friends are normally converted into new javascript strings on every call  
+    /// Any string argument equal to an interned string reuses it instead - including strings nested inside of
+    /// structs, arrays and map values, but not map keys - which cuts allocation for hot loops passing the same strings repeatedly
+    ///
+    /// Strings belong to the runtime's isolate rather than to a context, and a runtime never replaces its isolate,
+    /// so interned strings stay valid until [`Runtime::clear_string_cache`] is called, or the runtime is dropped
     ///
     /// # Errors
-    /// Fails if the function cannot be found, if there are issues with calling the function,  
-    /// Or if the result cannot be deserialized into the requested type
+    /// Can fail if the string cannot be created
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    /// use rustyscript::{ Runtime, Module, Error, json_args };
     ///
     /// # fn main() -> Result<(), Error> {
+    /// let module = Module::new("test.js", "export const get = (key, n) => `${key}:${n}`;");
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("/path/to/module.js", "export function f() { return 2; };");
-    /// let module = runtime.load_module(&module)?;
-    /// let value: usize = runtime.call_function_immediate(Some(&module), "f", json_args!())?;
+    /// let handle = runtime.load_module(&module)?;
+    ///
+    /// runtime.intern_string("user_id")?;
+    /// for n in 0..1000 {
+    ///     let _: String = runtime.call_function(Some(&handle), "get", json_args!("user_id", n))?;
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn call_function_immediate<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
-    where
-        T: deno_core::serde::de::DeserializeOwned,
-    {
-        let function = self.inner.get_function_by_name(module_context, name)?;
-        let result = self
-            .inner
-            .call_function_by_ref(module_context, &function, args)?;
-        self.inner.decode_value(result)
+    pub fn intern_string(&mut self, value: &str) -> Result<(), Error> {
+        self.inner.intern_string(value)
     }
 
-    /// Get a value from a runtime instance
+    /// Remove every string created with [`Runtime::intern_string`]
+    pub fn clear_string_cache(&mut self) {
+        self.inner.string_cache.clear();
+    }
+
+    /// Returns the number of strings created with [`Runtime::intern_string`]
+    #[must_use]
+    pub fn interned_string_count(&self) -> usize {
+        self.inner.string_cache.len()
+    }
+
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
+    /// The expression is evaluated in the global context, so changes persist
     ///
-    /// Blocks until:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
+    /// Blocks on promise resolution, and runs the event loop to completion
+    ///
+    /// Asynchronous code is supported, partially
+    /// - Top-level await is not supported
+    /// - The event loop will be run to completion after the expression is evaluated
+    ///
+    /// For top-level await support, use one of:
+    /// - `call_function_async`
+    /// - `call_stored_function_async`
+    /// - `load_module_async`
+    /// - `load_modules_async`
+    ///
+    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
     ///
     /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the value to find
+    /// * `expr` - A string representing the JavaScript expression to evaluate
     ///
     /// # Returns
-    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,
-    /// Or if the result cannot be deserialized into the requested type
+    /// A `Result` containing the deserialized result of the expression (`T`)  
+    /// or an error (`Error`) if the expression cannot be evaluated or if the
+    /// result cannot be deserialized.
     ///
     /// # Errors
-    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
+    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// use rustyscript::{ Runtime, Module, Error };
+    /// use rustyscript::{ Runtime, Error };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("/path/to/module.js", "globalThis.my_value = 2;");
-    /// let module = runtime.load_module(&module)?;
-    /// let value: usize = runtime.get_value(Some(&module), "my_value")?;
+    ///
+    /// let value: u32 = runtime.eval("2 + 2")?;
+    /// assert_eq!(4, value);
+    ///
+    /// let value: String = runtime.eval("new Promise(resolve => resolve('test'))")?;
+    /// assert_eq!("test", value);
+    ///
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_value<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
-    ) -> Result<T, Error>
+    pub fn eval<T>(&mut self, expr: impl ToString) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        self.block_on(|runtime| async move { runtime.get_value_async(module_context, name).await })
+        self.block_on(|runtime| async move { runtime.eval_async(expr).await })
     }
 
-    /// Get a value from a runtime instance
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
+    /// The expression is evaluated in the global context, so changes persist
     ///
-    /// Returns a future that resolves when:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
+    /// Awaits promise resolution, and runs the event loop to completion
     ///
-    /// See [`Runtime::get_value`] for an example
+    /// Asynchronous code is supported, partially
+    /// - Top-level await is not supported
+    /// - The event loop will be run to completion after the expression is evaluated
+    ///
+    /// For top-level await support, use one of:
+    /// - `call_function_async`
+    /// - `call_stored_function_async`
+    /// - `load_module_async`
+    /// - `load_modules_async`
+    ///
+    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
     ///
     /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the value to find
+    /// * `expr` - A string representing the JavaScript expression to evaluate
     ///
     /// # Returns
-    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,  
-    /// Or if the result cannot be deserialized into the requested type
+    /// A `Result` containing the deserialized result of the expression (`T`)  
+    /// or an error (`Error`) if the expression cannot be evaluated or if the
+    /// result cannot be deserialized.
     ///
     /// # Errors
-    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
-    pub async fn get_value_async<T>(
-        &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
-    ) -> Result<T, Error>
+    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    /// For an example, see [`Runtime::eval`]
+    pub async fn eval_async<T>(&mut self, expr: impl ToString) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        let result = self.inner.get_value_ref(module_context, name)?;
+        let result = self.inner.eval(expr.to_string()).await?;
         let result = self.inner.resolve_with_event_loop(result).await?;
         self.inner.decode_value(result)
     }
 
-    /// Get a value from a runtime instance
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code  
+    /// The expression is evaluated in the global context, so changes persist
     ///
-    /// Will not attempt to resolve promises, or run the event loop  
+    /// Does not await promise resolution, or run the event loop  
     /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
     /// The event loop should be run using [`Runtime::await_event_loop`]
     ///
+    /// Note that this function needs to be async because calls to `setTimeout` must be evaluated from within an async runtime.
+    ///
+    /// Asynchronous code is supported, partially
+    /// - Top-level await is not supported
+    ///
+    /// For top-level await support, use one of:
+    /// - `call_function_async`
+    /// - `call_stored_function_async`
+    /// - `load_module_async`
+    /// - `load_modules_async`
+    ///
+    /// Or any of the `_immmediate` variants, paired with [`crate::js_value::Promise`]
+    ///
     /// # Arguments
-    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
-    /// * `name` - A string representing the name of the value to find
+    /// * `expr` - A string representing the JavaScript expression to evaluate
     ///
     /// # Returns
-    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,
-    /// Or if the result cannot be deserialized into the requested type
+    /// A `Result` containing the deserialized result of the expression (`T`)  
+    /// or an error (`Error`) if the expression cannot be evaluated or if the
+    /// result cannot be deserialized.
     ///
     /// # Errors
-    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
+    /// Can fail if the expression cannot be evaluated, or if the result cannot be deserialized into the requested type
     ///
     /// # Example
+    /// For an example, see [`Runtime::eval`]
+    pub async fn eval_immediate<T>(&mut self, expr: impl ToString) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let result = self.inner.eval(expr.to_string()).await?;
+        self.inner.decode_value(result)
+    }
+
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code, with a set of globals that exist only for this evaluation
+    ///
+    /// The globals are installed on `globalThis`, the expression is evaluated as with [`Runtime::eval`],
+    /// then the globals are removed again - or restored to their previous values, if they shadowed existing ones  
+    /// This happens even if the evaluation fails
     ///
+    /// Note that callbacks which run after the evaluation, such as timers, will no longer see the globals
+    ///
+    /// # Errors
+    /// Can fail if the globals cannot be installed, if the expression cannot be evaluated,
+    /// or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
     /// ```no_run
-    /// use rustyscript::{ Runtime, Module, Error };
+    /// use std::collections::HashMap;
+    /// use rustyscript::{ Runtime, Error, serde_json::json };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("/path/to/module.js", "globalThis.my_value = 2;");
-    /// let module = runtime.load_module(&module)?;
-    /// let value: usize = runtime.get_value_immediate(Some(&module), "my_value")?;
+    ///
+    /// let globals = HashMap::from([("vars".to_string(), json!({ "x": 2, "y": 3 }))]);
+    /// let value: u32 = runtime.eval_with_globals("vars.x * vars.y", globals)?;
+    /// assert_eq!(6, value);
+    ///
+    /// let defined: bool = runtime.eval("'vars' in globalThis")?;
+    /// assert!(!defined);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_value_immediate<T>(
+    pub fn eval_with_globals<T>(
         &mut self,
-        module_context: Option<&ModuleHandle>,
-        name: &str,
+        expr: impl ToString,
+        globals: std::collections::HashMap<String, deno_core::serde_json::Value>,
     ) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        let result = self.inner.get_value_ref(module_context, name)?;
-        self.inner.decode_value(result)
+        self.block_on(|runtime| async move { runtime.eval_with_globals_async(expr, globals).await })
     }
 
-    /// Executes the given module, and returns a handle allowing you to extract values
-    /// and call functions
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code, with a set of globals that exist only for this evaluation
     ///
-    /// Blocks until the module has been executed AND the event loop has fully resolved  
-    /// See [`Runtime::load_module_async`] for a non-blocking variant, or use with async
-    /// background tasks
+    /// See [`Runtime::eval_with_globals`]
+    ///
+    /// # Errors
+    /// Can fail if the globals cannot be installed, if the expression cannot be evaluated,
+    /// or if the result cannot be deserialized into the requested type
+    pub async fn eval_with_globals_async<T>(
+        &mut self,
+        expr: impl ToString,
+        globals: std::collections::HashMap<String, deno_core::serde_json::Value>,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        // Install the globals, saving whatever they shadow
+        let globals = self.inner.serialize_json(&globals)?;
+        let restore: Function = self
+            .eval_immediate(format!(
+                "(() => {{
+                    const globals = {globals};
+                    const saved = Object.keys(globals).map(name => [name, Object.getOwnPropertyDescriptor(globalThis, name)]);
+                    const restore = () => {{
+                        for (const [name, descriptor] of saved) {{
+                            if (descriptor) Object.defineProperty(globalThis, name, descriptor);
+                            else delete globalThis[name];
+                        }}
+                    }};
+
+                    try {{
+                        for (const [name, value] of Object.entries(globals)) {{
+                            Object.defineProperty(globalThis, name, {{ value, writable: true, enumerable: true, configurable: true }});
+                        }}
+                    }} catch (e) {{
+                        restore();
+                        throw e;
+                    }}
+                    return restore;
+                }})()"
+            ))
+            .await?;
+
+        let result = self.eval_async(expr).await;
+        self.call_stored_function_immediate::<Undefined>(None, &restore, &())?;
+        result
+    }
+
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code, keeping the result as a [`crate::js_value::Value`]
+    ///
+    /// The result is not deserialized, so it can be held across calls and converted only when needed,
+    /// using [`crate::js_value::Value::try_into`], or into a [`crate::js_value::Function`] for use with [`Runtime::call_stored_function`]
+    ///
+    /// As with [`Runtime::eval`], promises are resolved, and the event loop is run to completion
     ///
     /// # Arguments
-    /// * `module` - A `Module` object containing the module's filename and contents.
+    /// * `expr` - A string representing the JavaScript expression to evaluate
     ///
     /// # Returns
-    /// A `Result` containing a handle for the loaded module
-    /// or an error (`Error`) if there are issues with loading or executing the module
+    /// A `Result` containing the value of the expression, or an error (`Error`) if it cannot be evaluated
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, or execution fails
+    /// Can fail if the expression cannot be evaluated
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// // Create a module with filename and contents
-    /// use rustyscript::{Runtime, Module, Error};
+    /// use rustyscript::{ Runtime, js_value::Function, json_args, Error };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("test.js", "export default () => 'test'");
-    /// runtime.load_module(&module);
+    ///
+    /// let value = runtime.eval_value("(n) => n * 2")?;
+    ///
+    /// // Later, once we know what the value is
+    /// let double = Function::try_from(value.into_v8())?;
+    /// let value: u32 = double.call(&mut runtime, None, &json_args!(21))?;
+    /// assert_eq!(42, value);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn load_module(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
-        self.block_on(|runtime| async move {
-            let handle = runtime.load_module_async(module).await;
-            runtime
-                .await_event_loop(PollEventLoopOptions::default(), None)
-                .await?;
-            handle
-        })
+    pub fn eval_value(&mut self, expr: impl ToString) -> Result<crate::js_value::Value, Error> {
+        self.block_on(|runtime| async move { runtime.eval_value_async(expr).await })
     }
 
-    /// Executes the given module, and returns a handle allowing you to extract values
-    /// and call functions
+    /// Evaluate a piece of non-ECMAScript-module JavaScript code, keeping the result as a [`crate::js_value::Value`]
     ///
-    /// Returns a future that resolves to the handle for the loaded module  
-    /// Makes no attempt to fully resolve the event loop - call [`Runtime::await_event_loop`]
-    /// to resolve background tasks and async listeners
+    /// The result is not deserialized, so it can be held across calls and converted only when needed,
+    /// using [`crate::js_value::Value::try_into`], or into a [`crate::js_value::Function`] for use with [`Runtime::call_stored_function`]
+    ///
+    /// As with [`Runtime::eval_async`], promises are resolved, and the event loop is run to completion
     ///
     /// # Arguments
-    /// * `module` - A `Module` object containing the module's filename and contents.
+    /// * `expr` - A string representing the JavaScript expression to evaluate
     ///
     /// # Returns
-    /// A `Result` containing a handle for the loaded module
-    /// or an error (`Error`) if there are issues with loading or executing the module
+    /// A `Result` containing the value of the expression, or an error (`Error`) if it cannot be evaluated
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, or execution fails
+    /// Can fail if the expression cannot be evaluated
     ///
-    /// See [`Runtime::load_module`] for an example
-    pub async fn load_module_async(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
-        self.inner.load_modules(None, vec![module]).await
+    /// # Example
+    /// For an example, see [`Runtime::eval_value`]
+    pub async fn eval_value_async(
+        &mut self,
+        expr: impl ToString,
+    ) -> Result<crate::js_value::Value, Error> {
+        let result = self.inner.eval(expr.to_string()).await?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        Ok(crate::js_value::Value::from_v8(result))
     }
 
-    /// Executes the given module, and returns a handle allowing you to extract values
-    /// and call functions.
-    ///
-    /// Blocks until all modules have been executed AND the event loop has fully resolved  
-    /// See [`Runtime::load_module_async`] for a non-blocking variant, or use with async
-    /// background tasks
+    /// Freezes the JavaScript built-ins (`Object`, `Array.prototype`, `Promise`, etc.)
+    /// Intended to be called after trusted setup code has run, and before untrusted modules are loaded
     ///
-    /// This will load 'module' as the main module, and the others as side-modules.  
-    /// Only one main module can be loaded per runtime
+    /// Once frozen, built-ins cannot be reassigned, extended or monkey-patched:
+    /// - In sloppy-mode scripts (such as [`Runtime::eval`]), such assignments are silently ignored
+    /// - In modules (which are always strict), such assignments throw a `TypeError`
     ///
-    /// # Arguments
-    /// * `module` - A `Module` object containing the module's filename and contents.
-    /// * `side_modules` - A set of additional modules to be loaded into memory for use
+    /// This cannot be undone for the lifetime of the runtime.
     ///
-    /// # Returns
-    /// A `Result` containing a handle for the loaded module
-    /// or an error (`Error`) if there are issues with loading or executing the module
+    /// Note that, as with other lockdown approaches, this also prevents shadowing
+    /// inherited properties by assignment (`obj.toString = ...`); use `Object.defineProperty` instead
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, or execution fails
+    /// Can fail if the built-ins were already altered in a way that prevents freezing them
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// // Create a module with filename and contents
-    /// use rustyscript::{Runtime, Module, Error};
+    /// use rustyscript::{ Runtime, Error };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("test.js", "export default () => 'test'");
-    /// runtime.load_modules(&module, vec![]);
+    /// runtime.freeze_intrinsics()?;
+    ///
+    /// let value: String = runtime.eval("Array.prototype.map = null; typeof [].map")?;
+    /// assert_eq!("function", value);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn load_modules(
-        &mut self,
-        module: &Module,
-        side_modules: Vec<&Module>,
-    ) -> Result<ModuleHandle, Error> {
-        self.block_on(move |runtime| async move {
-            let handle = runtime.load_modules_async(module, side_modules).await;
-            runtime
-                .await_event_loop(PollEventLoopOptions::default(), None)
-                .await?;
-            handle
-        })
+    pub fn freeze_intrinsics(&mut self) -> Result<(), Error> {
+        self.inner.freeze_intrinsics()
     }
 
-    /// Executes the given module, and returns a handle allowing you to extract values
-    /// and call functions.
+    /// Define a global whose value is computed by a rust closure, only once a script reads it
     ///
-    /// Returns a future that resolves to the handle for the loaded module  
-    /// Makes no attempt to resolve the event loop - call [`Runtime::await_event_loop`] to
-    /// resolve background tasks and async listeners
+    /// The closure runs on the first read of `globalThis[name]`, and its result replaces the getter,
+    /// so later reads return the same value without calling it again  
+    /// If the closure returns an error, the read throws, and the next read calls it again  
+    /// Assigning to the global also replaces the getter, without calling the closure
     ///
-    /// This will load 'module' as the main module, and the others as side-modules.  
-    /// Only one main module can be loaded per runtime
+    /// # Errors
+    /// Can fail if the state cannot be borrowed mutably, or if the global cannot be redefined
     ///
-    /// See [`Runtime::load_modules`] for an example
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error, serde_json::json };
     ///
-    /// # Arguments
-    /// * `module` - A `Module` object containing the module's filename and contents.
-    /// * `side_modules` - A set of additional modules to be loaded into memory for use
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// runtime.define_global_getter("hostInfo", || {
+    ///     // Only computed if a script uses it
+    ///     Ok(json!({ "cpus": 8 }))
+    /// })?;
     ///
-    /// # Returns
-    /// A `Result` containing a handle for the loaded main module, or the last side-module
-    /// or an error (`Error`) if there are issues with loading or executing the modules
+    /// let cpus: usize = runtime.eval("hostInfo.cpus")?;
+    /// assert_eq!(cpus, 8);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_global_getter<F>(&mut self, name: &str, getter: F) -> Result<(), Error>
+    where
+        F: Fn() -> Result<deno_core::serde_json::Value, Error> + 'static,
+    {
+        self.inner.define_global_getter(name, getter)
+    }
+
+    /// Serializes a javascript value into a JSON string
+    ///
+    /// If the runtime was created with [`RuntimeOptions::sorted_keys`], object keys are emitted in sorted order,
+    /// which makes the output stable for snapshot or golden-file testing
+    ///
+    /// Will not attempt to resolve promises, or run the event loop
     ///
     /// # Errors
-    /// Can fail if the modules cannot be loaded, or execution fails
-    pub async fn load_modules_async(
-        &mut self,
-        module: &Module,
-        side_modules: Vec<&Module>,
-    ) -> Result<ModuleHandle, Error> {
-        self.inner.load_modules(Some(module), side_modules).await
+    /// Can fail if the value cannot be represented as JSON
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, RuntimeOptions, js_value::Value, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions {
+    ///     sorted_keys: true,
+    ///     ..Default::default()
+    /// })?;
+    ///
+    /// let value: Value = runtime.eval("({ b: 1, a: 2 })")?;
+    /// assert_eq!(r#"{"a":2,"b":1}"#, runtime.to_json_string(&value)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json_string(&mut self, value: &crate::js_value::Value) -> Result<String, Error> {
+        let value: deno_core::serde_json::Value = self.inner.decode_value(value.as_v8().clone())?;
+        self.inner.serialize_json(&value)
     }
 
-    /// Executes the entrypoint function of a module within the Deno runtime.
-    ///
-    /// Blocks until:
-    /// - The event loop is resolved, and
-    /// - If the value is a promise, the promise is resolved
+    /// Creates an instance of a javascript class, as with `new class(...args)`  
+    /// Methods of the instance can then be called with [`Runtime::call_method`], and its fields read
+    /// with [`crate::js_value::Value::get_property`]
     ///
     /// # Arguments
-    /// * `module_context` - A handle returned by loading a module into the runtime
-    ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
-    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
-    /// or the result cannot be deserialized.
+    /// * `class` - The class, or constructor function, such as one exported from a module
+    /// * `args` - The arguments to pass to the constructor
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
-    /// Or if the result cannot be deserialized into the requested type
+    /// Can fail if `class` is not a constructor, if the constructor throws,
+    /// or if the arguments cannot be serialized
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// use rustyscript::{json_args, Runtime, Module, Error};
+    /// use rustyscript::{ json_args, Runtime, Module, Error, js_value::Value };
     ///
     /// # fn main() -> Result<(), Error> {
+    /// let module = Module::new("test.js", "
+    ///     export class Counter {
+    ///         constructor(start) { this.count = start; }
+    ///         increment() { return ++this.count; }
+    ///     }
+    /// ");
+    ///
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("test.js", "export default () => 'test'");
-    /// let module = runtime.load_module(&module)?;
+    /// let handle = runtime.load_module(&module)?;
     ///
-    /// // Run the entrypoint and handle the result
-    /// let value: String = runtime.call_entrypoint(&module, json_args!())?;
+    /// let class: Value = runtime.get_value(Some(&handle), "Counter")?;
+    /// let counter = runtime.call_constructor(&class, json_args!(5))?;
+    ///
+    /// let count: u32 = runtime.call_method(&counter, "increment", json_args!())?;
+    /// assert_eq!(count, 6);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn call_entrypoint<T>(
+    pub fn call_constructor(
         &mut self,
-        module_context: &ModuleHandle,
+        class: &crate::js_value::Value,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<crate::js_value::Value, Error> {
+        let instance = self.inner.call_constructor(class.as_v8(), args)?;
+        Ok(crate::js_value::Value::from_v8(instance))
+    }
+
+    /// Calls a method of a javascript object, with the object as `this`, and deserializes its return value
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// See [`Runtime::call_constructor`] for an example
+    ///
+    /// # Errors
+    /// Can fail if `object` is not an object, if the method does not exist or throws,
+    /// or if the result cannot be deserialized into the requested type
+    pub fn call_method<T>(
+        &mut self,
+        object: &crate::js_value::Value,
+        name: &str,
         args: &impl serde::ser::Serialize,
     ) -> Result<T, Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        T: serde::de::DeserializeOwned,
     {
-        self.block_on(
-            |runtime| async move { runtime.call_entrypoint_async(module_context, args).await },
-        )
+        self.block_on(|runtime| async move { runtime.call_method_async(object, name, args).await })
     }
 
-    /// Executes the entrypoint function of a module within the Deno runtime.
+    /// Calls a method of a javascript object, with the object as `this`, and deserializes its return value
     ///
     /// Returns a future that resolves when:
     /// - The event loop is resolved, and
     /// - If the value is a promise, the promise is resolved
     ///
-    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
-    ///
-    /// See [`Runtime::call_entrypoint`] for an example
-    ///
-    /// # Arguments
-    /// * `module_context` - A handle returned by loading a module into the runtime
-    ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
-    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
-    /// or the result cannot be deserialized.
+    /// See [`Runtime::call_constructor`] for an example
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
-    /// Or if the result cannot be deserialized into the requested type
-    pub async fn call_entrypoint_async<T>(
+    /// Can fail if `object` is not an object, if the method does not exist or throws,
+    /// or if the result cannot be deserialized into the requested type
+    pub async fn call_method_async<T>(
         &mut self,
-        module_context: &ModuleHandle,
+        object: &crate::js_value::Value,
+        name: &str,
         args: &impl serde::ser::Serialize,
     ) -> Result<T, Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        T: serde::de::DeserializeOwned,
     {
-        if let Some(entrypoint) = module_context.entrypoint() {
-            let result = self
-                .inner
-                .call_function_by_ref(Some(module_context), entrypoint, args)?;
-            let result = self.inner.resolve_with_event_loop(result).await?;
-            self.inner.decode_value(result)
-        } else {
-            Err(Error::MissingEntrypoint(module_context.module().clone()))
-        }
+        let args = self.inner.encode_args(args)?;
+        let result = self.inner.call_method(object.as_v8(), name, &args)?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        self.inner.decode_value(result)
     }
 
-    /// Executes the entrypoint function of a module within the Deno runtime.
-    ///
-    /// Will not attempt to resolve promises, or run the event loop  
-    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
-    /// The event loop should be run using [`Runtime::await_event_loop`]
-    ///
-    /// # Arguments
-    /// * `module_context` - A handle returned by loading a module into the runtime
+    /// Reads the bytes of a `Uint8Array`, any other typed array, a `DataView`, or an `ArrayBuffer`  
+    /// The contents are copied straight from the backing store, rather than deserialized as an array of numbers
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)
-    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
-    /// or the result cannot be deserialized.
+    /// This is the counterpart to passing [`crate::js_value::Bytes`] as an argument
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,
-    /// Or if the result cannot be deserialized into the requested type
+    /// Will return an error if the value is not binary data
     ///
     /// # Example
-    ///
     /// ```no_run
-    /// use rustyscript::{json_args, Runtime, Module, Error};
+    /// use rustyscript::{ Runtime, Error, js_value::Value };
     ///
     /// # fn main() -> Result<(), Error> {
     /// let mut runtime = Runtime::new(Default::default())?;
-    /// let module = Module::new("test.js", "export default () => 'test'");
-    /// let module = runtime.load_module(&module)?;
+    /// let value: Value = runtime.eval("new TextEncoder().encode('hello')")?;
+    /// let bytes = runtime.bytes_from_value(&value)?;
+    /// assert_eq!(bytes, b"hello");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bytes_from_value(&mut self, value: &crate::js_value::Value) -> Result<Vec<u8>, Error> {
+        self.inner.bytes_from_value(value.as_v8())
+    }
+
+    /// Serializes a javascript value into bytes, using V8's structured serialization  
+    /// This is the format used by `structuredClone`, so unlike JSON it preserves `Map`, `Set`, `Date`,
+    /// typed arrays, `BigInt`, `undefined`, and cyclic references
     ///
-    /// // Run the entrypoint and handle the result
-    /// let value: String = runtime.call_entrypoint_immediate(&module, json_args!())?;
+    /// The bytes can be stored, and restored later with [`Runtime::deserialize_value`] - including in a different runtime
+    ///
+    /// # Errors
+    /// Can fail if the value contains something that cannot be cloned, such as a function or a symbol
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, js_value::Value, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let value: Value = runtime.eval("new Map([['a', new Set([1, 2])]])")?;
+    /// let bytes = runtime.serialize_value(&value)?;
+    ///
+    /// let mut other = Runtime::new(Default::default())?;
+    /// let restored = other.deserialize_value(&bytes)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn call_entrypoint_immediate<T>(
+    pub fn serialize_value(&mut self, value: &crate::js_value::Value) -> Result<Vec<u8>, Error> {
+        self.inner.serialize_value(value.as_v8())
+    }
+
+    /// Restores a javascript value from bytes written by [`Runtime::serialize_value`]
+    ///
+    /// # Errors
+    /// Can fail if the bytes are not a valid serialized value
+    pub fn deserialize_value(&mut self, data: &[u8]) -> Result<crate::js_value::Value, Error> {
+        let value = self.inner.deserialize_value(data)?;
+        Ok(crate::js_value::Value::from_v8(value))
+    }
+
+    /// Snapshots the state of a module, by calling its exported `exportState` function
+    ///
+    /// The state is returned as a structured clone, detached from the module: nested objects, `Map`s, `Set`s,
+    /// `Date`s, typed arrays, and cyclic references are preserved, but functions cannot be included  
+    /// If `exportState` returns a promise, it is resolved first
+    ///
+    /// See [`Runtime::snapshot_and_rebuild`] to swap a module for a new version while keeping its state
+    ///
+    /// # Errors
+    /// Fails if the module does not export `exportState`, if it throws, or if the state cannot be cloned
+    pub fn export_state(
         &mut self,
         module_context: &ModuleHandle,
-        args: &impl serde::ser::Serialize,
-    ) -> Result<T, Error>
-    where
-        T: deno_core::serde::de::DeserializeOwned,
-    {
-        if let Some(entrypoint) = module_context.entrypoint() {
-            let result = self.block_on(|runtime| async move {
-                runtime
-                    .inner
-                    .call_function_by_ref(Some(module_context), entrypoint, args)
-            })?;
-            self.inner.decode_value(result)
-        } else {
-            Err(Error::MissingEntrypoint(module_context.module().clone()))
-        }
+    ) -> Result<crate::js_value::Value, Error> {
+        self.block_on(|runtime| async move { runtime.export_state_async(module_context).await })
     }
 
-    /// Loads a module into a new runtime, executes the entry function and returns the
-    /// result of the module's execution, deserialized into the specified Rust type (`T`).
+    /// Snapshots the state of a module, by calling its exported `exportState` function
     ///
-    /// # Arguments
-    /// * `module` - A `Module` object containing the module's filename and contents.
-    /// * `side_modules` - A set of additional modules to be loaded into memory for use
-    /// * `runtime_options` - Options for the creation of the runtime
-    /// * `entrypoint_args` - Arguments to pass to the entrypoint function
+    /// Returns a future that resolves once the state has been cloned
     ///
-    /// # Returns
-    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
-    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
-    /// or the result cannot be deserialized.
+    /// # Errors
+    /// Fails if the module does not export `exportState`, if it throws, or if the state cannot be cloned
+    ///
+    /// See [`Runtime::export_state`] for more information
+    pub async fn export_state_async(
+        &mut self,
+        module_context: &ModuleHandle,
+    ) -> Result<crate::js_value::Value, Error> {
+        let namespace = self.inner.get_module_namespace(module_context)?;
+        let state = self.inner.call_method(&namespace, "exportState", &[])?;
+        let state = self.inner.resolve_with_event_loop(state).await?;
+
+        // Cloning detaches the state from the objects the old module still holds
+        let bytes = self.inner.serialize_value(&state)?;
+        let state = self.inner.deserialize_value(&bytes)?;
+        Ok(crate::js_value::Value::from_v8(state))
+    }
+
+    /// Rehydrates the state of a module, by passing it to the module's exported `importState` function
+    ///
+    /// If `importState` returns a promise, it is resolved first
     ///
     /// # Errors
-    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
-    /// Or if the result cannot be deserialized into the requested type
+    /// Fails if the module does not export `importState`, or if it throws
+    pub fn import_state(
+        &mut self,
+        module_context: &ModuleHandle,
+        state: &crate::js_value::Value,
+    ) -> Result<(), Error> {
+        self.block_on(
+            |runtime| async move { runtime.import_state_async(module_context, state).await },
+        )
+    }
+
+    /// Rehydrates the state of a module, by passing it to the module's exported `importState` function
     ///
-    /// # Example
+    /// Returns a future that resolves once `importState` has completed
+    ///
+    /// # Errors
+    /// Fails if the module does not export `importState`, or if it throws
+    pub async fn import_state_async(
+        &mut self,
+        module_context: &ModuleHandle,
+        state: &crate::js_value::Value,
+    ) -> Result<(), Error> {
+        let namespace = self.inner.get_module_namespace(module_context)?;
+        let result = self
+            .inner
+            .call_method(&namespace, "importState", &[state.as_v8().clone()])?;
+        self.inner.resolve_with_event_loop(result).await?;
+        Ok(())
+    }
+
+    /// Replaces a module with a new version of its code, carrying its state across the swap
     ///
+    /// The state is taken with [`Runtime::export_state`], the new module is loaded, and the state
+    /// is passed to it with [`Runtime::import_state`] - so data structures survive, while every function is
+    /// replaced with the new version  
+    /// Both versions of the module must export the `exportState` and `importState` functions
+    ///
+    /// The new module is loaded under the runtime's [`crate::module_loader::ReloadPolicy`], which must allow
+    /// it to be evaluated again - such as the default, `AlwaysReload`  
+    /// Modules that imported the old version keep using it; the returned handle refers to the new one
+    ///
+    /// # Errors
+    /// Fails if the state cannot be exported or imported, or if the new module cannot be loaded
+    ///
+    /// # Example
     /// ```no_run
-    /// // Create a module with filename and contents
-    /// use rustyscript::{json_args, Runtime, Module, Error};
+    /// use rustyscript::{ json_args, Runtime, Module, Error, Undefined };
     ///
     /// # fn main() -> Result<(), Error> {
-    /// let module = Module::new("test.js", "export default () => 2");
-    /// let value: usize = Runtime::execute_module(&module, vec![], Default::default(), json_args!())?;
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let plugin = Module::new("plugin.js", "
+    ///     let hits = new Map();
+    ///     export const hit = (page) => { hits.set(page, (hits.get(page) ?? 0) + 1); };
+    ///     export const exportState = () => ({ hits });
+    ///     export const importState = (state) => { hits = state.hits; };
+    /// ");
+    /// let handle = runtime.load_module(&plugin)?;
+    /// runtime.call_function::<Undefined>(Some(&handle), "hit", json_args!("/"))?;
+    ///
+    /// let update = Module::new("plugin.js", "/* new version of the same plugin */");
+    /// let handle = runtime.snapshot_and_rebuild(&handle, &update)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn execute_module<T>(
+    pub fn snapshot_and_rebuild(
+        &mut self,
+        module_context: &ModuleHandle,
         module: &Module,
-        side_modules: Vec<&Module>,
-        runtime_options: RuntimeOptions,
-        entrypoint_args: &impl serde::ser::Serialize,
+    ) -> Result<ModuleHandle, Error> {
+        self.block_on(|runtime| async move {
+            let handle = runtime
+                .snapshot_and_rebuild_async(module_context, module)
+                .await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Replaces a module with a new version of its code, carrying its state across the swap
+    ///
+    /// Returns a future that resolves to the handle for the new module  
+    /// Makes no attempt to fully resolve the event loop - call [`Runtime::await_event_loop`]
+    /// to resolve background tasks and async listeners
+    ///
+    /// # Errors
+    /// Fails if the state cannot be exported or imported, or if the new module cannot be loaded
+    ///
+    /// See [`Runtime::snapshot_and_rebuild`] for more information
+    pub async fn snapshot_and_rebuild_async(
+        &mut self,
+        module_context: &ModuleHandle,
+        module: &Module,
+    ) -> Result<ModuleHandle, Error> {
+        let state = self.export_state_async(module_context).await?;
+        let handle = self.load_module_async(module).await?;
+        self.import_state_async(&handle, &state).await?;
+        Ok(handle)
+    }
+
+    /// Calls a stored javascript function and deserializes its return value.
+    ///
+    /// Returns a future that resolves when:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// See [`Runtime::call_function`] for an example
+    ///
+    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module providing global context for the function
+    /// * `function` - A The function object
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if there are issues with calling the function,
+    /// or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
+    pub async fn call_stored_function_async<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        function: &Function,
+        args: &impl serde::ser::Serialize,
     ) -> Result<T, Error>
     where
-        T: deno_core::serde::de::DeserializeOwned,
+        T: serde::de::DeserializeOwned,
     {
-        let mut runtime = Runtime::new(runtime_options)?;
-        let module = runtime.load_modules(module, side_modules)?;
-        let value: T = runtime.call_entrypoint(&module, entrypoint_args)?;
-        Ok(value)
+        let isolate = self.deno_runtime().v8_isolate();
+        let function = function.as_global(isolate);
+        let result = self
+            .inner
+            .call_function_by_ref(module_context, &function, args)?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        self.inner.decode_value(result)
     }
-}
 
-impl AsyncBridgeExt for Runtime {
-    fn bridge(&self) -> &AsyncBridge {
-        &self.tokio
+    /// Calls a stored javascript function and deserializes its return value.
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// See [`Runtime::call_function`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module providing global context for the function
+    /// * `function` - A The function object
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if there are issues with calling the function,
+    /// or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
+    pub fn call_stored_function<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        function: &Function,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        self.block_on(|runtime| async move {
+            runtime
+                .call_stored_function_async(module_context, function, args)
+                .await
+        })
     }
-}
-
-#[cfg(test)]
-mod test_runtime {
-    use crate::json_args;
-    use std::time::Duration;
 
-    use super::*;
-    use deno_core::extension;
+    /// Calls a stored javascript function and deserializes its return value.
+    ///
+    /// Will not attempt to resolve promises, or run the event loop  
+    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
+    /// The event loop should be run using [`Runtime::await_event_loop`]
+    ///
+    /// See [`Runtime::call_function`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module providing global context for the function
+    /// * `function` - A The function object
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if there are issues with calling the function,
+    /// or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if there are issues with calling the function, or if the result cannot be deserialized into the requested type
+    pub fn call_stored_function_immediate<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        function: &Function,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        let isolate = self.deno_runtime().v8_isolate();
+        let function = function.as_global(isolate);
+        let result = self
+            .inner
+            .call_function_by_ref(module_context, &function, args)?;
+        self.inner.decode_value(result)
+    }
 
-    #[test]
-    fn test_new() {
-        Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
+    ///
+    /// Returns a future that resolves when:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
+    ///
+    /// See [`Runtime::call_function`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the javascript function to call.
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if the function cannot be found, if there are issues with
+    /// calling the function, or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,
+    /// Or if the result cannot be deserialized into the requested type
+    pub async fn call_function_async<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        let function = self.inner.get_function_by_name(module_context, name)?;
+        let result = self
+            .inner
+            .call_function_by_ref(module_context, &function, args)?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        self.inner.decode_value(result)
+    }
+
+    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the javascript function to call.
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if the function cannot be found, if there are issues with
+    /// calling the function, or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "export function f() { return 2; };");
+    /// let module = runtime.load_module(&module)?;
+    /// let value: usize = runtime.call_function(Some(&module), "f", json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_function<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        self.block_on(|runtime| async move {
+            runtime
+                .call_function_async(module_context, name, args)
+                .await
+        })
+    }
+
+    /// Calls a javascript function by its name, with tightened network permissions for the duration of the call
+    ///
+    /// Returns a future that resolves when:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// See [`Runtime::call_function_with_permissions`] for more information, and an example
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be deserialized into the requested type
+    #[cfg(feature = "web")]
+    pub async fn call_function_with_permissions_async<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+        permissions: std::sync::Arc<dyn crate::WebPermissions>,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        let _guard = self.inner.push_call_permissions(permissions)?;
+        self.call_function_async(module_context, name, args).await
+    }
+
+    /// Calls a javascript function by its name, with tightened network permissions for the duration of the call
+    ///
+    /// While the call runs, including the event loop and any promise it returns, network access is denied
+    /// if `permissions` do not allow it - so a callback can be denied the network, while the rest of the script keeps it  
+    /// The runtime-wide `WebOptions::permissions` still apply, so this can only take access away
+    ///
+    /// Like the runtime-wide permissions, `permissions` reach the deno extensions through [`crate::to_permissions_options`],
+    /// so network access is kept or denied as a whole - an [`crate::AllowlistWebPermissions`] allowing only some URLs denies all of them
+    ///
+    /// The tightened permissions apply to the whole runtime while the call is in progress, not only to the function called -
+    /// timers, promises and other modules' code that run before the call returns are denied the network as well  
+    /// The permissions are lifted once the call returns, even if it fails or times out  
+    /// Timers, promises and connections that outlive the call are not covered - a `setTimeout` callback that
+    /// runs after the call has returned gets the runtime-wide permissions, and a socket opened during the call stays open  
+    /// Only network access is scoped - other checks, such as for the filesystem, use the runtime-wide permissions
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use rustyscript::{ json_args, AllowlistWebPermissions, Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("host.js", "export const callback = () => fetch('https://example.com');");
+    /// let module = runtime.load_module(&module)?;
+    ///
+    /// // No network access for this one call
+    /// let offline = Arc::new(AllowlistWebPermissions::new());
+    /// let result = runtime.call_function_with_permissions::<()>(Some(&module), "callback", json_args!(), offline);
+    /// assert!(result.is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "web")]
+    pub fn call_function_with_permissions<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+        permissions: std::sync::Arc<dyn crate::WebPermissions>,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        self.block_on(|runtime| async move {
+            runtime
+                .call_function_with_permissions_async(module_context, name, args, permissions)
+                .await
+        })
+    }
+
+    /// Calls a javascript function within the Deno runtime by its name and deserializes its return value.
+    ///
+    /// Will not attempt to resolve promises, or run the event loop  
+    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
+    /// The event loop should be run using [`Runtime::await_event_loop`]
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the javascript function to call.
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the function call (`T`)  
+    /// or an error (`Error`) if the function cannot be found, if there are issues with
+    /// calling the function, or if the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "export function f() { return 2; };");
+    /// let module = runtime.load_module(&module)?;
+    /// let value: usize = runtime.call_function_immediate(Some(&module), "f", json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_function_immediate<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        let function = self.inner.get_function_by_name(module_context, name)?;
+        let result = self
+            .inner
+            .call_function_by_ref(module_context, &function, args)?;
+        self.inner.decode_value(result)
+    }
+
+    /// Calls a javascript function by its name, returning its result as untyped JSON
+    ///
+    /// Equivalent to `call_function::<serde_json::Value>`, except that a function returning `undefined`,
+    /// or nothing at all, deliberately results in `serde_json::Value::Null`  
+    /// Useful for forwarding results of unknown shape, such as in a JSON-RPC gateway
+    ///
+    /// Blocks until the event loop is resolved, and the value is resolved if it is a promise
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be represented as JSON
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("rpc.js", "export function handle(params) { return { echo: params }; };");
+    /// let module = runtime.load_module(&module)?;
+    /// let value = runtime.call_function_json(Some(&module), "handle", json_args!([1, 2]))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_function_json(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<deno_core::serde_json::Value, Error> {
+        self.block_on(|runtime| async move {
+            runtime
+                .call_function_json_async(module_context, name, args)
+                .await
+        })
+    }
+
+    /// Calls a javascript function by its name, returning its result as untyped JSON
+    ///
+    /// Returns a future that resolves when the event loop is resolved, and the value is resolved if it is a promise  
+    /// A result of `undefined` becomes `serde_json::Value::Null`
+    ///
+    /// # Errors
+    /// Fails if the function cannot be found, if there are issues with calling the function,  
+    /// Or if the result cannot be represented as JSON
+    ///
+    /// See [`Runtime::call_function_json`] for an example
+    pub async fn call_function_json_async(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<deno_core::serde_json::Value, Error> {
+        let function = self.inner.get_function_by_name(module_context, name)?;
+        let result = self
+            .inner
+            .call_function_by_ref(module_context, &function, args)?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        self.inner.decode_json(result)
+    }
+
+    /// Calls a javascript async generator function by its name, and consumes it as a stream
+    ///
+    /// Each item is pulled by calling the generator's `next()`, and awaiting the result through the event loop  
+    /// The stream ends when the generator completes; its `return` value, if any, is discarded  
+    /// If the generator throws, or a value cannot be deserialized, the error is the last item of the stream
+    ///
+    /// Synchronous generators, or any other function returning an iterator, can be consumed the same way
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the javascript function to call.
+    /// * `args` - The arguments to pass to the function
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ json_args, Runtime, Module, Error };
+    /// use deno_core::futures::StreamExt;
+    ///
+    /// # async fn example() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "
+    ///     export async function* pages(count) {
+    ///         for (let i = 0; i < count; i++) yield i;
+    ///     }
+    /// ");
+    /// let module = runtime.load_module_async(&module).await?;
+    ///
+    /// let mut pages = Box::pin(runtime.call_async_generator::<u32>(Some(&module), "pages", json_args!(3)));
+    /// while let Some(page) = pages.next().await {
+    ///     println!("page {}", page?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_async_generator<'a, T>(
+        &'a mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+        args: &impl serde::ser::Serialize,
+    ) -> impl deno_core::futures::Stream<Item = Result<T, Error>> + 'a
+    where
+        T: deno_core::serde::de::DeserializeOwned + 'a,
+    {
+        // A failure to create the generator is the only item
+        let generator =
+            self.call_function_immediate::<crate::js_value::Value>(module_context, name, args);
+
+        deno_core::futures::stream::unfold(Some((self, generator)), |state| async move {
+            let (runtime, generator) = state?;
+            let generator = match generator {
+                Ok(generator) => generator,
+                Err(e) => return Some((Err(e), None)),
+            };
+
+            match runtime.async_generator_next::<T>(&generator).await {
+                Ok(Some(value)) => Some((Ok(value), Some((runtime, Ok(generator))))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Pulls the next value from an iterator, returning `None` once it is done
+    async fn async_generator_next<T>(
+        &mut self,
+        generator: &crate::js_value::Value,
+    ) -> Result<Option<T>, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        #[derive(deno_core::serde::Deserialize)]
+        struct IteratorResult {
+            #[serde(default)]
+            done: bool,
+            value: crate::js_value::Value,
+        }
+
+        let result = self.inner.call_method(generator.as_v8(), "next", &[])?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        let result: IteratorResult = self.inner.decode_value(result)?;
+        if result.done {
+            return Ok(None);
+        }
+
+        result.value.try_into(self).map(Some)
+    }
+
+    /// Get a value from a runtime instance
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the value to find
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Errors
+    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "globalThis.my_value = 2;");
+    /// let module = runtime.load_module(&module)?;
+    /// let value: usize = runtime.get_value(Some(&module), "my_value")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_value<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.block_on(|runtime| async move { runtime.get_value_async(module_context, name).await })
+    }
+
+    /// Get a value from a runtime instance
+    ///
+    /// Returns a future that resolves when:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// See [`Runtime::get_value`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the value to find
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Errors
+    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
+    pub async fn get_value_async<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let result = self.inner.get_value_ref(module_context, name)?;
+        let result = self.inner.resolve_with_event_loop(result).await?;
+        self.inner.decode_value(result)
+    }
+
+    /// Get a value from a runtime instance
+    ///
+    /// Will not attempt to resolve promises, or run the event loop  
+    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
+    /// The event loop should be run using [`Runtime::await_event_loop`]
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the value to find
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result or an error (`Error`) if the value cannot be found,
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Errors
+    /// Can fail if the value cannot be found, or if the result cannot be deserialized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "globalThis.my_value = 2;");
+    /// let module = runtime.load_module(&module)?;
+    /// let value: usize = runtime.get_value_immediate(Some(&module), "my_value")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_value_immediate<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let result = self.inner.get_value_ref(module_context, name)?;
+        self.inner.decode_value(result)
+    }
+
+    /// Get a value from a runtime instance, after the event loop has settled
+    ///
+    /// Unlike [`Runtime::get_value`], the event loop is run to completion *before* the value is read,
+    /// so exports assigned by pending async work (timers, unawaited promises) are visible
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the value to find
+    ///
+    /// # Errors
+    /// Can fail if the event loop does not settle within the runtime's timeout,
+    /// if the value cannot be found, or if the result cannot be deserialized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("/path/to/module.js", "
+    ///     export let config;
+    ///     Promise.resolve().then(() => config = 2);
+    /// ");
+    /// let module = runtime.load_module(&module)?;
+    /// let value: usize = runtime.get_value_settled(Some(&module), "config")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_value_settled<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.block_on(|runtime| async move {
+            runtime.get_value_settled_async(module_context, name).await
+        })
+    }
+
+    /// Get a value from a runtime instance, after the event loop has settled
+    ///
+    /// Returns a future that runs the event loop to completion, then resolves the value  
+    /// See [`Runtime::get_value_settled`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - Optional handle to a module to search - if None, or if the search fails, the global context is used
+    /// * `name` - A string representing the name of the value to find
+    ///
+    /// # Errors
+    /// Can fail if the event loop does not settle within the runtime's timeout,
+    /// if the value cannot be found, or if the result cannot be deserialized.
+    pub async fn get_value_settled_async<T>(
+        &mut self,
+        module_context: Option<&ModuleHandle>,
+        name: &str,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let timeout = self.timeout();
+        let event_loop = self
+            .inner
+            .await_event_loop(PollEventLoopOptions::default(), None);
+        tokio::time::timeout(timeout, event_loop).await??;
+        self.get_value_async(module_context, name).await
+    }
+
+    /// Get the namespace object of a loaded module, as a [`crate::js_value::Value`]
+    ///
+    /// The namespace holds every export of the module as a property, and can be reflected over
+    /// or passed around as a single object
+    ///
+    /// # Arguments
+    /// * `module_context` - A handle to the module
+    ///
+    /// # Returns
+    /// A `Result` containing the namespace object, or an error (`Error`) if the module has not been evaluated
+    ///
+    /// # Errors
+    /// Can fail if the module has not been loaded, or has not finished evaluating
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export const name = 'plugin'; export const version = 1;");
+    /// let module = runtime.load_module(&module)?;
+    ///
+    /// let namespace = runtime.module_namespace(&module)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn module_namespace(
+        &mut self,
+        module_context: &ModuleHandle,
+    ) -> Result<crate::js_value::Value, Error> {
+        let namespace = self.inner.get_module_namespace(module_context)?;
+        Ok(crate::js_value::Value::from_v8(namespace))
+    }
+
+    /// Returns the javascript the runtime compiled for a loaded module, after transpilation  
+    /// Useful to debug transpilation of typescript, JSX, or decorators
+    ///
+    /// Returns `None` if the module's source is not held by the loader
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.ts", "export const x: number = 42;");
+    /// let handle = runtime.load_module(&module)?;
+    ///
+    /// let source = runtime.module_source(&handle).unwrap();
+    /// assert!(!source.contains(": number"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn module_source(&self, module_context: &ModuleHandle) -> Option<String> {
+        self.inner.module_source(module_context)
+    }
+
+    /// Resolve an import the way the module loader would, without loading anything  
+    /// Answers "what would `import 'x'` from this module refer to?"
+    ///
+    /// Runs the full resolution chain - `tsconfig` paths, builtin modules, [`crate::module_loader::ImportProvider::resolve`],
+    /// node aliases and resolution, and the scheme checks that decide whether the import is allowed
+    ///
+    /// # Arguments
+    /// * `specifier` - The specifier as written in the import
+    /// * `referrer` - The URL or path of the importing module, or `None` to resolve from the current directory
+    ///
+    /// # Errors
+    /// Will return an error if the specifier cannot be resolved, or the import would not be allowed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let runtime = Runtime::new(Default::default())?;
+    /// let url = runtime.resolve_specifier("./util.ts", Some("src/main.ts"))?;
+    /// assert!(url.as_str().ends_with("/src/util.ts"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_specifier(
+        &self,
+        specifier: &str,
+        referrer: Option<&str>,
+    ) -> Result<deno_core::ModuleSpecifier, Error> {
+        self.inner.resolve_specifier(specifier, referrer)
+    }
+
+    /// Executes the given module, and returns a handle allowing you to extract values
+    /// and call functions
+    ///
+    /// Blocks until the module has been executed AND the event loop has fully resolved  
+    /// See [`Runtime::load_module_async`] for a non-blocking variant, or use with async
+    /// background tasks
+    ///
+    /// # Arguments
+    /// * `module` - A `Module` object containing the module's filename and contents.
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded module
+    /// or an error (`Error`) if there are issues with loading or executing the module
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// // Create a module with filename and contents
+    /// use rustyscript::{Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export default () => 'test'");
+    /// runtime.load_module(&module);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_module(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
+        self.block_on(|runtime| async move {
+            let handle = runtime.load_module_async(module).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Resolves, fetches and transpiles the graph of modules statically imported by a module,
+    /// without evaluating any of them
+    ///
+    /// Fetched modules are stored in the [`crate::module_loader::ModuleCacheProvider`], so a later
+    /// `load_module` of the same module does not need to fetch them again  
+    /// Without a cache provider, this only checks that the graph can be fetched
+    ///
+    /// The module itself is not cached, and dynamic imports are not followed
+    ///
+    /// See [`Runtime::prefetch_graph`] for a blocking version
+    ///
+    /// # Errors
+    /// Can fail if the module itself cannot be transpiled  
+    /// Imports that fail to resolve or fetch are listed in [`PrefetchReport::failed`] instead,
+    /// without stopping the rest of the graph
+    pub async fn prefetch_graph_async(&mut self, module: &Module) -> Result<PrefetchReport, Error> {
+        self.inner.prefetch_graph(module).await
+    }
+
+    /// Resolves, fetches and transpiles the graph of modules statically imported by a module,
+    /// without evaluating any of them
+    ///
+    /// Blocking version of [`Runtime::prefetch_graph_async`]
+    ///
+    /// # Errors
+    /// Can fail if the module itself cannot be transpiled  
+    /// Imports that fail to resolve or fetch are listed in [`PrefetchReport::failed`] instead
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("main.js", "import { x } from './lib.js'; export default x;");
+    ///
+    /// let report = runtime.prefetch_graph(&module)?;
+    /// for (specifier, error) in &report.failed {
+    ///     eprintln!("could not prefetch {specifier}: {error}");
+    /// }
+    ///
+    /// runtime.load_module(&module)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prefetch_graph(&mut self, module: &Module) -> Result<PrefetchReport, Error> {
+        self.block_on(|runtime| async move { runtime.prefetch_graph_async(module).await })
+    }
+
+    /// Sets the command line arguments seen by scripts, as `Deno.args`
+    ///
+    /// With the `node_experimental` feature, they also follow the first two entries of `process.argv`
+    ///
+    /// `Deno.args` is an empty array until this is called
+    ///
+    /// # Errors
+    /// Can fail if `process.argv` cannot be updated
+    pub fn set_args(&mut self, args: Vec<String>) -> Result<(), Error> {
+        self.inner.set_args(args)
+    }
+
+    /// Runs a module as a command line script would be, with the given arguments in `Deno.args`
+    ///
+    /// The arguments stay set for the rest of the runtime's life, see [`Runtime::set_args`]
+    ///
+    /// See [`Runtime::run_script_with_args`] for a blocking version
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    pub async fn run_script_with_args_async(
+        &mut self,
+        module: &Module,
+        args: Vec<String>,
+    ) -> Result<ModuleHandle, Error> {
+        self.set_args(args)?;
+        self.load_module_async(module).await
+    }
+
+    /// Runs a module as a command line script would be, with the given arguments in `Deno.args`
+    ///
+    /// The arguments stay set for the rest of the runtime's life, see [`Runtime::set_args`]
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Module, Error };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("greet.js", "console.log(`Hello, ${Deno.args[0]}!`);");
+    ///
+    /// let args = std::env::args().skip(1).collect();
+    /// runtime.run_script_with_args(&module, args)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_script_with_args(
+        &mut self,
+        module: &Module,
+        args: Vec<String>,
+    ) -> Result<ModuleHandle, Error> {
+        self.set_args(args)?;
+        self.load_module(module)
+    }
+
+    /// Executes the given module, and returns a handle allowing you to extract values
+    /// and call functions
+    ///
+    /// Returns a future that resolves to the handle for the loaded module  
+    /// Makes no attempt to fully resolve the event loop - call [`Runtime::await_event_loop`]
+    /// to resolve background tasks and async listeners
+    ///
+    /// # Arguments
+    /// * `module` - A `Module` object containing the module's filename and contents.
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded module
+    /// or an error (`Error`) if there are issues with loading or executing the module
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// See [`Runtime::load_module`] for an example
+    pub async fn load_module_async(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
+        self.inner.load_modules(None, vec![module]).await
+    }
+
+    /// Returns the non-fatal diagnostics the transpiler reported for the modules loaded since the last call
+    ///
+    /// Only collected if [`RuntimeOptions::collect_diagnostics`] is set - otherwise this is always empty  
+    /// Only transpiled modules, such as typescript, are checked; plain JS, and code found in the module cache, report nothing
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{Runtime, RuntimeOptions, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(RuntimeOptions {
+    ///     collect_diagnostics: true,
+    ///     ..Default::default()
+    /// })?;
+    ///
+    /// let module = Module::new("test.ts", "abstract class A { abstract m() {} }");
+    /// runtime.load_module(&module)?;
+    /// for diagnostic in runtime.take_diagnostics() {
+    ///     println!("warning: {diagnostic}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_diagnostics(&mut self) -> Vec<crate::Diagnostic> {
+        self.inner.take_diagnostics()
+    }
+
+    /// Executes the given module, then reads one of its exports, such as a plugin's manifest
+    ///
+    /// Blocks until the module has been executed AND the event loop has fully resolved,
+    /// then deserializes the export - resolving it first if it is a promise  
+    /// Only the module's exports are searched, not the global context
+    ///
+    /// The handle is returned even if the export cannot be read, so the module can still be used -
+    /// a missing export, or one that cannot be deserialized into `T`, is returned alongside it
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{Runtime, Module, Error, json_args};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("plugin.js", "
+    ///     export const manifest = { name: 'example', version: 1 };
+    ///     export function run() { return 'ran'; }
+    /// ");
+    ///
+    /// let (handle, manifest) = runtime.load_and_get::<deno_core::serde_json::Value>(&module, "manifest")?;
+    /// println!("Loaded {}", manifest?["name"]);
+    ///
+    /// let result: String = runtime.call_function(Some(&handle), "run", json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_and_get<T>(
+        &mut self,
+        module: &Module,
+        export_name: &str,
+    ) -> Result<(ModuleHandle, Result<T, Error>), Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.block_on(
+            |runtime| async move { runtime.load_and_get_async(module, export_name).await },
+        )
+    }
+
+    /// Executes the given module, then reads one of its exports
+    ///
+    /// Returns a future that resolves once the module has been executed, the event loop has fully resolved,
+    /// and the export has been read  
+    /// The handle is returned even if the export cannot be read
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// See [`Runtime::load_and_get`] for an example
+    pub async fn load_and_get_async<T>(
+        &mut self,
+        module: &Module,
+        export_name: &str,
+    ) -> Result<(ModuleHandle, Result<T, Error>), Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let handle = self.load_module_async(module).await?;
+        self.await_event_loop(PollEventLoopOptions::default(), None)
+            .await?;
+
+        let value = match self.inner.get_module_export_value(&handle, export_name) {
+            Ok(value) => match self.inner.resolve_with_event_loop(value).await {
+                Ok(value) => self.inner.decode_value(value),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        Ok((handle, value))
+    }
+
+    /// Loads a Node-API native addon (a `.node` file) and returns its exports
+    ///
+    /// The addon is opened through the napi extension, exactly as a `require` of the file would  
+    /// Within scripts, `.node` files can also be imported, with the exports as the default export:
+    /// `import addon from './addon.node'`
+    ///
+    /// Relative paths are resolved against [`Runtime::current_dir`]  
+    /// Loading native code requires FFI permissions, which are only granted by permissions that allow everything
+    ///
+    /// # Errors
+    /// Will return an error if the file does not exist, if FFI access is denied,
+    /// or if the file is not a compatible addon
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{Runtime, Error, js_value::Value};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let exports: Value = runtime.load_napi_addon("native/addon.node")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "node_experimental")]
+    pub fn load_napi_addon(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<crate::js_value::Value, Error> {
+        let path = self.current_dir().join(path);
+        if !path.is_file() {
+            return Err(Error::ModuleNotFound(path.display().to_string()));
+        }
+
+        let loader = self
+            .deno_runtime()
+            .op_state()
+            .borrow()
+            .try_borrow::<crate::ext::node::NapiAddonLoader>()
+            .map(|loader| loader.0.clone())
+            .ok_or_else(|| Error::Runtime("The node extension is not loaded".to_string()))?;
+
+        let path = path.to_string_lossy().to_string();
+        let result = self.inner.call_function_by_ref(None, &loader, &path)?;
+        self.inner.decode_value(result)
+    }
+
+    /// Executes the given module like [`Runtime::load_module`], but caches its transpiled code
+    /// under `cache_key` rather than under the module's specifier
+    ///
+    /// The [`crate::module_loader::ModuleCacheProvider`] set in `RuntimeOptions::module_cache` is consulted
+    /// with the key before transpiling, so modules with different names but identical contents - such as
+    /// ones keyed by a content hash - can share a single cache entry  
+    /// The source map is cached beside the code, so errors from a cached module are still source-mapped  
+    /// Otherwise the module is loaded exactly like [`Runtime::load_module`] - including the reload policy,
+    /// `RuntimeOptions::module_eval_timeout` and the loader callbacks
+    /// Collisions are the responsibility of the caller; on a cache hit the cached code is used
+    /// regardless of the module's contents
+    ///
+    /// Keys are stored as `rustyscript-cache:/<key>` specifiers, separate from those of real modules
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("upload-1234.ts", "export const answer: number = 42;");
+    /// let handle = runtime.load_module_with_cache_key(&module, "sha256-1f2e3d")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_module_with_cache_key(
+        &mut self,
+        module: &Module,
+        cache_key: &str,
+    ) -> Result<ModuleHandle, Error> {
+        self.block_on(|runtime| async move {
+            let handle = runtime
+                .load_module_with_cache_key_async(module, cache_key)
+                .await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Executes the given module, caching its transpiled code under `cache_key`
+    ///
+    /// Returns a future that resolves to the handle for the loaded module  
+    /// Makes no attempt to fully resolve the event loop - call [`Runtime::await_event_loop`]
+    /// to resolve background tasks and async listeners
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// See [`Runtime::load_module_with_cache_key`] for an example
+    pub async fn load_module_with_cache_key_async(
+        &mut self,
+        module: &Module,
+        cache_key: &str,
+    ) -> Result<ModuleHandle, Error> {
+        self.inner
+            .load_module_with_cache_key(module, cache_key)
+            .await
+    }
+
+    /// Resolves, loads, and links the given module and its imports, without running any of their
+    /// top-level code
+    ///
+    /// Together with [`Runtime::evaluate_module`], this splits [`Runtime::load_module`] into its two phases,
+    /// so that a module can be linked up front and executed later, on demand  
+    /// Until then, extracting values or calling functions from the module will fail with an error
+    ///
+    /// # Errors
+    /// Can fail if the module, or any of its imports, cannot be loaded or linked
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("plugin.js", "export const name = 'plugin';");
+    ///
+    /// let handle = runtime.instantiate_module(&module)?;
+    /// // ... later, on first use
+    /// let handle = runtime.evaluate_module(&handle)?;
+    /// let name: String = runtime.get_value(Some(&handle), "name")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn instantiate_module(&mut self, module: &Module) -> Result<ModuleHandle, Error> {
+        self.block_on(|runtime| async move { runtime.instantiate_module_async(module).await })
+    }
+
+    /// Resolves, loads, and links the given module and its imports, without running any of their
+    /// top-level code
+    ///
+    /// Returns a future that resolves to a handle for the instantiated module
+    ///
+    /// # Errors
+    /// Can fail if the module, or any of its imports, cannot be loaded or linked
+    ///
+    /// See [`Runtime::instantiate_module`] for an example
+    pub async fn instantiate_module_async(
+        &mut self,
+        module: &Module,
+    ) -> Result<ModuleHandle, Error> {
+        self.inner.instantiate_module(module).await
+    }
+
+    /// Runs the top-level code of a module from [`Runtime::instantiate_module`], and of any imports
+    /// that have not already run
+    ///
+    /// Blocks until the module has been executed AND the event loop has fully resolved  
+    /// Returns a new handle for the module, which includes its entrypoint, if any
+    ///
+    /// Evaluation is bounded by `RuntimeOptions::module_eval_timeout`, and reported to the loader callbacks,
+    /// the same as with [`Runtime::load_module`]
+    ///
+    /// # Errors
+    /// Can fail if execution fails or times out, or if the module was not instantiated with
+    /// [`Runtime::instantiate_module`], or has already been evaluated
+    ///
+    /// See [`Runtime::instantiate_module`] for an example
+    pub fn evaluate_module(&mut self, handle: &ModuleHandle) -> Result<ModuleHandle, Error> {
+        self.block_on(|runtime| async move {
+            let handle = runtime.evaluate_module_async(handle).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Runs the top-level code of a module from [`Runtime::instantiate_module`], and of any imports
+    /// that have not already run
+    ///
+    /// Returns a future that resolves to a new handle for the module  
+    /// Makes no attempt to fully resolve the event loop - call [`Runtime::await_event_loop`]
+    /// to resolve background tasks and async listeners
+    ///
+    /// # Errors
+    /// Can fail if execution fails, or if the module was not instantiated with
+    /// [`Runtime::instantiate_module`], or has already been evaluated
+    ///
+    /// See [`Runtime::instantiate_module`] for an example
+    pub async fn evaluate_module_async(
+        &mut self,
+        handle: &ModuleHandle,
+    ) -> Result<ModuleHandle, Error> {
+        self.inner.evaluate_module(handle).await
+    }
+
+    /// Executes the given module, and returns a handle allowing you to extract values
+    /// and call functions.
+    ///
+    /// Blocks until all modules have been executed AND the event loop has fully resolved  
+    /// See [`Runtime::load_module_async`] for a non-blocking variant, or use with async
+    /// background tasks
+    ///
+    /// This will load 'module' as the main module, and the others as side-modules.  
+    /// Only one main module can be loaded per runtime
+    ///
+    /// # Arguments
+    /// * `module` - A `Module` object containing the module's filename and contents.
+    /// * `side_modules` - A set of additional modules to be loaded into memory for use
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded module
+    /// or an error (`Error`) if there are issues with loading or executing the module
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, or execution fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// // Create a module with filename and contents
+    /// use rustyscript::{Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export default () => 'test'");
+    /// runtime.load_modules(&module, vec![]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_modules(
+        &mut self,
+        module: &Module,
+        side_modules: Vec<&Module>,
+    ) -> Result<ModuleHandle, Error> {
+        self.block_on(move |runtime| async move {
+            let handle = runtime.load_modules_async(module, side_modules).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Executes the given module, and returns a handle allowing you to extract values
+    /// and call functions.
+    ///
+    /// Returns a future that resolves to the handle for the loaded module  
+    /// Makes no attempt to resolve the event loop - call [`Runtime::await_event_loop`] to
+    /// resolve background tasks and async listeners
+    ///
+    /// This will load 'module' as the main module, and the others as side-modules.  
+    /// Only one main module can be loaded per runtime
+    ///
+    /// See [`Runtime::load_modules`] for an example
+    ///
+    /// # Arguments
+    /// * `module` - A `Module` object containing the module's filename and contents.
+    /// * `side_modules` - A set of additional modules to be loaded into memory for use
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded main module, or the last side-module
+    /// or an error (`Error`) if there are issues with loading or executing the modules
+    ///
+    /// # Errors
+    /// Can fail if the modules cannot be loaded, or execution fails
+    pub async fn load_modules_async(
+        &mut self,
+        module: &Module,
+        side_modules: Vec<&Module>,
+    ) -> Result<ModuleHandle, Error> {
+        self.inner.load_modules(Some(module), side_modules).await
+    }
+
+    /// Executes a set of independent modules in order, returning a result for each one
+    ///
+    /// Unlike [`Runtime::load_modules`], each module is loaded as a side-module with its own handle,
+    /// and a module failing to load does not stop the rest from loading  
+    /// The results are in the same order as `modules`, so a failure can be traced back to its module
+    ///
+    /// The modules share the runtime's module map, so an import common to several of them is loaded and evaluated once  
+    /// The event loop is run once, after every module has been loaded
+    ///
+    /// # Arguments
+    /// * `modules` - The modules to load
+    ///
+    /// # Returns
+    /// A `Result` containing the result of loading each module,
+    /// or an error (`Error`) if the event loop fails or times out afterwards
+    ///
+    /// # Errors
+    /// Can fail if the event loop fails, or the runtime's timeout is reached
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let plugins = vec![
+    ///     Module::new("plugin_a.js", "export const name = 'a';"),
+    ///     Module::new("plugin_b.js", "throw new Error('broken');"),
+    /// ];
+    ///
+    /// for (plugin, result) in plugins.iter().zip(runtime.load_module_batch(&plugins)?) {
+    ///     if let Err(e) = result {
+    ///         println!("{} failed to load: {e}", plugin.filename().display());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_module_batch(
+        &mut self,
+        modules: &[Module],
+    ) -> Result<Vec<Result<ModuleHandle, Error>>, Error> {
+        self.block_on(move |runtime| async move {
+            let results = runtime.load_module_batch_async(modules).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            Ok(results)
+        })
+    }
+
+    /// Executes a set of independent modules in order, returning a result for each one
+    ///
+    /// Returns a future that resolves once every module has been loaded  
+    /// Makes no attempt to resolve the event loop - call [`Runtime::await_event_loop`] to
+    /// resolve background tasks and async listeners
+    ///
+    /// See [`Runtime::load_module_batch`] for an example
+    ///
+    /// # Arguments
+    /// * `modules` - The modules to load
+    ///
+    /// # Returns
+    /// The result of loading each module, in the same order as `modules`
+    pub async fn load_module_batch_async(
+        &mut self,
+        modules: &[Module],
+    ) -> Vec<Result<ModuleHandle, Error>> {
+        let mut results = Vec::with_capacity(modules.len());
+        for module in modules {
+            results.push(self.inner.load_modules(None, vec![module]).await);
+        }
+        results
+    }
+
+    /// Fetches a remote module, and loads it as the main module
+    ///
+    /// The module is fetched through the module loader, so the cache provider, import provider,
+    /// and `url_import` crate feature all apply - without the feature, or an import provider
+    /// that serves the URL, the load will fail
+    ///
+    /// Relative imports in the module are resolved against its URL.  
+    /// Only `https://` URLs are accepted, unless [`RuntimeOptions::allow_http_entrypoint`] is set
+    ///
+    /// Only one main module can be loaded per runtime
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the module to load
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded module,
+    /// or an error (`Error`) if there are issues with fetching or executing the module
+    ///
+    /// # Errors
+    /// Can fail if the URL is invalid or not allowed, or if the module cannot be fetched or executed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error, Undefined, json_args };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = runtime.load_module_from_url("https://example.com/main.js")?;
+    /// runtime.call_entrypoint::<Undefined>(&module, json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_module_from_url(&mut self, url: &str) -> Result<ModuleHandle, Error> {
+        self.block_on(move |runtime| async move {
+            let handle = runtime.load_module_from_url_async(url).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Fetches a remote module, and loads it as the main module
+    ///
+    /// Returns a future that resolves to the handle for the loaded module  
+    /// Makes no attempt to resolve the event loop - call [`Runtime::await_event_loop`] to
+    /// resolve background tasks and async listeners
+    ///
+    /// See [`Runtime::load_module_from_url`] for more information, and an example
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the module to load
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the loaded module,
+    /// or an error (`Error`) if there are issues with fetching or executing the module
+    ///
+    /// # Errors
+    /// Can fail if the URL is invalid or not allowed, or if the module cannot be fetched or executed
+    pub async fn load_module_from_url_async(&mut self, url: &str) -> Result<ModuleHandle, Error> {
+        self.inner.load_main_module_from_url(url).await
+    }
+
+    /// Loads a directory as an npm-style package, and returns a handle to its entry module
+    ///
+    /// The entry point is taken from the `exports`, `module` or `main` fields of the package's `package.json`,
+    /// falling back to `index.js` if none are present  
+    /// With the `node_experimental` feature it is resolved by the node resolver, which applies the conditions
+    /// and patterns of `exports` as node does - without it, only the root export's `import`, `default`
+    /// and `require` conditions are understood
+    ///
+    /// Every importable file in the package is allowed to be imported; symlinked directories are not followed
+    ///
+    /// Relative imports within the package are resolved against the directory, and are allowed even without the `fs_import` feature  
+    /// With the `node_experimental` feature, `node:` and `npm:` imports are handled by the node resolver
+    ///
+    /// The entry is loaded as a side-module, so several packages can be loaded into the same runtime
+    ///
+    /// # Arguments
+    /// * `dir` - The package directory, relative to the runtime's current directory
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the entry module,
+    /// or an error (`Error`) if there are issues with loading or executing the package
+    ///
+    /// # Errors
+    /// Can fail if the package has no entry point, if `package.json` cannot be parsed, or if the modules cannot be loaded or executed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustyscript::{ Runtime, Error, json_args };
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let package = runtime.load_package("plugins/my_plugin")?;
+    /// let value: String = runtime.call_function(Some(&package), "describe", json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_package(&mut self, dir: impl AsRef<Path>) -> Result<ModuleHandle, Error> {
+        self.block_on(move |runtime| async move {
+            let handle = runtime.load_package_async(dir).await;
+            runtime
+                .await_event_loop(PollEventLoopOptions::default(), None)
+                .await?;
+            handle
+        })
+    }
+
+    /// Loads a directory as an npm-style package, and returns a handle to its entry module
+    ///
+    /// Returns a future that resolves to the handle for the entry module  
+    /// Makes no attempt to resolve the event loop - call [`Runtime::await_event_loop`] to
+    /// resolve background tasks and async listeners
+    ///
+    /// See [`Runtime::load_package`] for more information, and an example
+    ///
+    /// # Arguments
+    /// * `dir` - The package directory, relative to the runtime's current directory
+    ///
+    /// # Returns
+    /// A `Result` containing a handle for the entry module,
+    /// or an error (`Error`) if there are issues with loading or executing the package
+    ///
+    /// # Errors
+    /// Can fail if the package has no entry point, if `package.json` cannot be parsed, or if the modules cannot be loaded or executed
+    pub async fn load_package_async(
+        &mut self,
+        dir: impl AsRef<Path>,
+    ) -> Result<ModuleHandle, Error> {
+        self.inner.load_package(dir.as_ref()).await
+    }
+
+    /// Executes the entrypoint function of a module within the Deno runtime.
+    ///
+    /// Blocks until:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// # Arguments
+    /// * `module_context` - A handle returned by loading a module into the runtime
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
+    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
+    /// or the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{json_args, Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export default () => 'test'");
+    /// let module = runtime.load_module(&module)?;
+    ///
+    /// // Run the entrypoint and handle the result
+    /// let value: String = runtime.call_entrypoint(&module, json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_entrypoint<T>(
+        &mut self,
+        module_context: &ModuleHandle,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        self.block_on(
+            |runtime| async move { runtime.call_entrypoint_async(module_context, args).await },
+        )
+    }
+
+    /// Executes the entrypoint function of a module within the Deno runtime.
+    ///
+    /// Returns a future that resolves when:
+    /// - The event loop is resolved, and
+    /// - If the value is a promise, the promise is resolved
+    ///
+    /// Note that synchronous functions are run synchronously. Returned promises will be run asynchronously, however.
+    ///
+    /// See [`Runtime::call_entrypoint`] for an example
+    ///
+    /// # Arguments
+    /// * `module_context` - A handle returned by loading a module into the runtime
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
+    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
+    /// or the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
+    /// Or if the result cannot be deserialized into the requested type
+    pub async fn call_entrypoint_async<T>(
+        &mut self,
+        module_context: &ModuleHandle,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        if let Some(entrypoint) = module_context.entrypoint() {
+            let result = self
+                .inner
+                .call_function_by_ref(Some(module_context), entrypoint, args)?;
+            let result = self.inner.resolve_with_event_loop(result).await?;
+            self.inner.decode_value(result)
+        } else {
+            Err(Error::MissingEntrypoint(module_context.module().clone()))
+        }
+    }
+
+    /// Executes the entrypoint function of a module within the Deno runtime.
+    ///
+    /// Will not attempt to resolve promises, or run the event loop  
+    /// Promises can be returned by specifying the return type as [`crate::js_value::Promise`]  
+    /// The event loop should be run using [`Runtime::await_event_loop`]
+    ///
+    /// # Arguments
+    /// * `module_context` - A handle returned by loading a module into the runtime
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)
+    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
+    /// or the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rustyscript::{json_args, Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let mut runtime = Runtime::new(Default::default())?;
+    /// let module = Module::new("test.js", "export default () => 'test'");
+    /// let module = runtime.load_module(&module)?;
+    ///
+    /// // Run the entrypoint and handle the result
+    /// let value: String = runtime.call_entrypoint_immediate(&module, json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_entrypoint_immediate<T>(
+        &mut self,
+        module_context: &ModuleHandle,
+        args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        if let Some(entrypoint) = module_context.entrypoint() {
+            let result = self.block_on(|runtime| async move {
+                runtime
+                    .inner
+                    .call_function_by_ref(Some(module_context), entrypoint, args)
+            })?;
+            self.inner.decode_value(result)
+        } else {
+            Err(Error::MissingEntrypoint(module_context.module().clone()))
+        }
+    }
+
+    /// Loads a module into a new runtime, executes the entry function and returns the
+    /// result of the module's execution, deserialized into the specified Rust type (`T`).
+    ///
+    /// # Arguments
+    /// * `module` - A `Module` object containing the module's filename and contents.
+    /// * `side_modules` - A set of additional modules to be loaded into memory for use
+    /// * `runtime_options` - Options for the creation of the runtime
+    /// * `entrypoint_args` - Arguments to pass to the entrypoint function
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized result of the entrypoint execution (`T`)  
+    /// if successful, or an error (`Error`) if the entrypoint is missing, the execution fails,
+    /// or the result cannot be deserialized.
+    ///
+    /// # Errors
+    /// Can fail if the module cannot be loaded, if the entrypoint is missing, if the execution fails,  
+    /// Or if the result cannot be deserialized into the requested type
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// // Create a module with filename and contents
+    /// use rustyscript::{json_args, Runtime, Module, Error};
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let module = Module::new("test.js", "export default () => 2");
+    /// let value: usize = Runtime::execute_module(&module, vec![], Default::default(), json_args!())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_module<T>(
+        module: &Module,
+        side_modules: Vec<&Module>,
+        runtime_options: RuntimeOptions,
+        entrypoint_args: &impl serde::ser::Serialize,
+    ) -> Result<T, Error>
+    where
+        T: deno_core::serde::de::DeserializeOwned,
+    {
+        let mut runtime = Runtime::new(runtime_options)?;
+        let module = runtime.load_modules(module, side_modules)?;
+        let value: T = runtime.call_entrypoint(&module, entrypoint_args)?;
+        Ok(value)
+    }
+}
+
+impl AsyncBridgeExt for Runtime {
+    fn bridge(&self) -> &AsyncBridge {
+        &self.tokio
+    }
+}
+
+#[cfg(test)]
+mod test_runtime {
+    use crate::json_args;
+    use std::time::Duration;
+
+    use super::*;
+    use deno_core::extension;
+
+    #[test]
+    fn test_new() {
+        Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        extension!(test_extension);
+        Runtime::new(RuntimeOptions {
+            extensions: vec![test_extension::init()],
+            ..Default::default()
+        })
+        .expect("Could not create runtime with extensions");
+    }
+
+    #[test]
+    fn test_get_value() {
+        let module = Module::new(
+            "test.js",
+            "
+            globalThis.a = 2;
+            export const b = 'test';
+            export const fnc = null;
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+
+        assert_eq!(
+            2,
+            runtime
+                .get_value::<usize>(Some(&module), "a")
+                .expect("Could not find global")
+        );
+        assert_eq!(
+            "test",
+            runtime
+                .get_value::<String>(Some(&module), "b")
+                .expect("Could not find export")
+        );
+        runtime
+            .get_value::<Undefined>(Some(&module), "c")
+            .expect_err("Could not detect null");
+        runtime
+            .get_value::<Undefined>(Some(&module), "d")
+            .expect_err("Could not detect undeclared");
+    }
+
+    #[test]
+    fn test_load_module() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            export default () => 2;
+        ",
+        );
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+        assert_ne!(0, module.id());
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module1 = Module::new(
+            "importme.js",
+            "
+            export const value = 2;
+        ",
+        );
+        let module2 = Module::new(
+            "test.js",
+            "
+            import { value } from './importme.js';
+            rustyscript.register_entrypoint(() => value);
+        ",
+        );
+        runtime
+            .load_module(&module1)
+            .expect("Could not load modules");
+        let module = runtime
+            .load_module(&module2)
+            .expect("Could not load modules");
+        let value: usize = runtime
+            .call_entrypoint(&module, json_args!())
+            .expect("Could not call exported fn");
+        assert_eq!(2, value);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            await new Promise(r => setTimeout(r, 2000));
+        ",
+        );
+        runtime
+            .load_modules(&module, vec![])
+            .expect_err("Did not interupt after timeout");
+    }
+
+    #[test]
+    fn test_load_modules() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            rustyscript.register_entrypoint(() => 2);
+        ",
+        );
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+        assert_ne!(0, module.id());
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module1 = Module::new(
+            "importme.js",
+            "
+            export const value = 2;
+        ",
+        );
+        let module2 = Module::new(
+            "test.js",
+            "
+            import { value } from './importme.js';
+            rustyscript.register_entrypoint(() => value);
+        ",
+        );
+        let module = runtime
+            .load_modules(&module2, vec![&module1])
+            .expect("Could not load modules");
+        let value: usize = runtime
+            .call_entrypoint(&module, json_args!())
+            .expect("Could not call exported fn");
+        assert_eq!(2, value);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            await new Promise(r => setTimeout(r, 5000));
+        ",
+        );
+        runtime
+            .load_modules(&module, vec![])
+            .expect_err("Did not interupt after timeout");
+    }
+
+    #[test]
+    fn test_call_entrypoint() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            rustyscript.register_entrypoint(() => 2);
+        ",
+        );
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+        let value: usize = runtime
+            .call_entrypoint(&module, json_args!())
+            .expect("Could not call registered fn");
+        assert_eq!(2, value);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            default_entrypoint: Some("load".to_string()),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            export const load = () => 2;
+        ",
+        );
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+        let value: usize = runtime
+            .call_entrypoint(&module, json_args!())
+            .expect("Could not call exported fn");
+        assert_eq!(2, value);
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = Module::new(
+            "test.js",
+            "
+            export const load = () => 2;
+        ",
+        );
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+        runtime
+            .call_entrypoint::<Undefined>(&module, json_args!())
+            .expect_err("Did not detect no entrypoint");
+    }
+
+    #[test]
+    fn test_execute_module() {
+        let module = Module::new(
+            "test.js",
+            "
+            rustyscript.register_entrypoint(() => 2);
+        ",
+        );
+        let value: usize =
+            Runtime::execute_module(&module, vec![], RuntimeOptions::default(), json_args!())
+                .expect("Could not exec module");
+        assert_eq!(2, value);
+
+        let module = Module::new(
+            "test.js",
+            "
+            function load() { return 2; }
+        ",
+        );
+        Runtime::execute_module::<Undefined>(
+            &module,
+            vec![],
+            RuntimeOptions::default(),
+            json_args!(),
+        )
+        .expect_err("Could not detect no entrypoint");
+    }
+
+    #[test]
+    fn call_function() {
+        let module = Module::new(
+            "test.js",
+            "
+            globalThis.fna = (i) => i;
+            export function fnb() { return 'test'; }
+            export const fnc = 2;
+            export const fne = () => {};
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = runtime
+            .load_modules(&module, vec![])
+            .expect("Could not load module");
+
+        let result: usize = runtime
+            .call_function(Some(&module), "fna", json_args!(2))
+            .expect("Could not call global");
+        assert_eq!(2, result);
+
+        let result: String = runtime
+            .call_function(Some(&module), "fnb", json_args!())
+            .expect("Could not call export");
+        assert_eq!("test", result);
+
+        runtime
+            .call_function::<Undefined>(Some(&module), "fnc", json_args!())
+            .expect_err("Did not detect non-function");
+        runtime
+            .call_function::<Undefined>(Some(&module), "fnd", json_args!())
+            .expect_err("Did not detect undefined");
+        runtime
+            .call_function::<Undefined>(Some(&module), "fne", json_args!())
+            .expect("Did not allow undefined return");
+    }
+
+    #[test]
+    fn test_freeze_intrinsics() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .eval::<Undefined>("globalThis.trusted = [1, 2].map((x) => x * 2);")
+            .expect("Could not run prelude");
+        runtime
+            .freeze_intrinsics()
+            .expect("Could not freeze intrinsics");
+
+        let value: String = runtime
+            .eval("Array.prototype.map = null; typeof [].map")
+            .expect("Could not eval after freezing");
+        assert_eq!("function", value);
+
+        let value: bool = runtime
+            .eval("Object = null; typeof Object === 'function'")
+            .expect("Could not eval after freezing");
+        assert!(value);
+
+        let module = Module::new("test.js", "Array.prototype.map = () => 'pwned';");
+        runtime
+            .load_module(&module)
+            .expect_err("Did not prevent tampering from strict code");
+
+        let value: Vec<usize> = runtime
+            .eval("[1, 2].map((x) => x + 1)")
+            .expect("Could not use frozen intrinsics");
+        assert_eq!(vec![2, 3], value);
+    }
+
+    #[test]
+    fn test_wait_until_idle() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        assert!(!runtime
+            .poll_pending_work()
+            .expect("Could not poll event loop"));
+
+        runtime
+            .eval::<Undefined>("globalThis.done = false; setTimeout(() => { done = true; }, 50);")
+            .expect("Could not schedule timer");
+        assert!(runtime
+            .poll_pending_work()
+            .expect("Could not poll event loop"));
+
+        runtime
+            .eval::<Undefined>("setTimeout(() => {}, 5000);")
+            .expect("Could not schedule timer");
+        let idle = runtime
+            .wait_until_idle(Duration::from_millis(500))
+            .expect("Could not wait for idle");
+        assert!(!idle);
+        assert!(runtime
+            .get_value::<bool>(None, "done")
+            .expect("Could not get value"));
+    }
+
+    #[test]
+    fn test_sorted_keys() {
+        let module = Module::new(
+            "test.js",
+            "
+            export const echo = (v) => JSON.stringify(v);
+            export const value = { c: 1, a: { z: 1, y: 2 }, b: [ { d: 1, c: 2 } ] };
+        ",
+        );
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            sorted_keys: true,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let module = runtime.load_module(&module).expect("Could not load module");
+
+        let map: std::collections::HashMap<&str, usize> =
+            [("b", 1), ("c", 2), ("a", 3)].into_iter().collect();
+        let result: String = runtime
+            .call_function(Some(&module), "echo", json_args!(map))
+            .expect("Could not call function");
+        assert_eq!(r#"{"a":3,"b":1,"c":2}"#, result);
+
+        let value: Undefined = runtime
+            .get_value(Some(&module), "value")
+            .expect("Could not get value");
+        let json = runtime.to_json_string(&value).expect("Could not serialize");
+        assert_eq!(r#"{"a":{"y":2,"z":1},"b":[{"c":2,"d":1}],"c":1}"#, json);
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let value: Undefined = runtime.eval("({ b: 1, a: 2 })").expect("Could not eval");
+        let json = runtime.to_json_string(&value).expect("Could not serialize");
+        assert_eq!(r#"{"b":1,"a":2}"#, json);
+    }
+
+    #[test]
+    fn test_intern_string() {
+        #[derive(serde::Serialize)]
+        struct Query {
+            key: &'static str,
+            tags: Vec<&'static str>,
+            data: crate::js_value::Bytes,
+            missing: Option<&'static str>,
+        }
+
+        let module = Module::new(
+            "test.js",
+            "
+            export const describe = (...args) => JSON.stringify(args);
+            export const query = (q) => `${q.key}:${q.tags}:${q.data.length}:${q.missing}`;
+        ",
+        );
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        runtime
+            .intern_string("key")
+            .expect("Could not intern string");
+        runtime
+            .intern_string("a,\"b\"")
+            .expect("Could not intern string");
+        runtime
+            .intern_string("key")
+            .expect("Could not intern string");
+        assert_eq!(runtime.interned_string_count(), 2);
+
+        // Cached and created strings keep their order
+        let value: String = runtime
+            .call_function(
+                Some(&handle),
+                "describe",
+                json_args!(1, "key", ["key"], "a,\"b\"", "other"),
+            )
+            .expect("Could not call function");
+        assert_eq!(value, r#"[1,"key",["key"],"a,\"b\"","other"]"#);
+
+        // Map keys are not replaced, but map values are
+        let map: std::collections::HashMap<&str, &str> = [("key", "key")].into_iter().collect();
+        let value: String = runtime
+            .call_function(Some(&handle), "describe", &map)
+            .expect("Could not call function");
+        assert_eq!(value, r#"[{"key":"key"}]"#);
+
+        // Nested strings, alongside serde_v8's own types
+        let query = Query {
+            key: "key",
+            tags: vec!["key", "other"],
+            data: crate::js_value::Bytes::new(vec![0; 3]),
+            missing: None,
+        };
+        let value: String = runtime
+            .call_function(Some(&handle), "query", &query)
+            .expect("Could not call function");
+        assert_eq!(value, "key:key,other:3:null");
+
+        runtime.clear_string_cache();
+        assert_eq!(runtime.interned_string_count(), 0);
+        let value: String = runtime
+            .call_function(Some(&handle), "describe", json_args!("key"))
+            .expect("Could not call function");
+        assert_eq!(value, r#"["key"]"#);
+    }
+
+    #[test]
+    fn test_argument_encoding() {
+        let module = Module::new(
+            "test.js",
+            "
+            export const describe = (nan, inf, big, obj) => [
+                Number.isNaN(nan),
+                inf === Infinity,
+                typeof big,
+                Object.getPrototypeOf(obj) === null,
+            ];
+        ",
+        );
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let map: std::collections::HashMap<&str, u32> = [("a", 1)].into_iter().collect();
+        let result: (bool, bool, String, bool) = runtime
+            .call_function(
+                Some(&handle),
+                "describe",
+                json_args!(f64::NAN, f64::INFINITY, u64::MAX, map),
+            )
+            .expect("Could not call function");
+        assert_eq!(result, (true, true, "bigint".to_string(), true));
+
+        // JSON turned integer keys into strings, serde_v8 rejects them
+        let map: std::collections::HashMap<u32, u32> = [(1, 1)].into_iter().collect();
+        assert!(runtime
+            .call_function::<Undefined>(Some(&handle), "describe", json_args!(0, 0, 0, map))
+            .is_err());
+    }
+
+    #[test]
+    fn test_take_error_stack() {
+        let module = Module::new(
+            "test.ts",
+            "
+            type Unused = number;
+            function inner(): never {
+                throw new Error('oops');
+            }
+            export function outer() {
+                inner();
+            }
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let module = runtime.load_module(&module).expect("Could not load module");
+        assert!(runtime.take_error_stack().is_none());
+
+        let e = runtime
+            .call_function::<Undefined>(Some(&module), "outer", json_args!())
+            .expect_err("Did not throw");
+        assert!(e.to_string().contains("oops"));
+
+        let stack = runtime.take_error_stack().expect("No stack was recorded");
+        assert_eq!(Some(stack.clone()), e.stack());
+
+        let lines: Vec<&str> = stack.lines().collect();
+        assert!(lines[0].starts_with("    at inner ("));
+        assert!(lines[0].contains("test.ts:4:"));
+        assert!(lines[1].starts_with("    at outer ("));
+
+        assert!(runtime.take_error_stack().is_none());
+    }
+
+    #[test]
+    fn test_eval_value() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        let value = runtime
+            .eval_value("({ a: 1, b: 'two' })")
+            .expect("Could not eval");
+        let value: deno_core::serde_json::Value = value
+            .try_into(&mut runtime)
+            .expect("Could not convert value");
+        assert_eq!(value, deno_core::serde_json::json!({ "a": 1, "b": "two" }));
+
+        let value = runtime.eval_value("(n) => n * 2").expect("Could not eval");
+        let double = Function::try_from(value.into_v8()).expect("Value is not a function");
+        let value: u32 = double
+            .call(&mut runtime, None, &json_args!(21))
+            .expect("Could not call function");
+        assert_eq!(value, 42);
+
+        let value = runtime
+            .eval_value("new Promise(resolve => resolve('test'))")
+            .expect("Could not eval");
+        let value: String = value
+            .try_into(&mut runtime)
+            .expect("Could not convert value");
+        assert_eq!(value, "test");
+
+        runtime.eval_value("throw new Error('oops')").unwrap_err();
+    }
+
+    #[test]
+    fn test_module_namespace() {
+        let module = Module::new(
+            "test.js",
+            "
+            export const name = 'plugin';
+            export const version = 2;
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let namespace = runtime
+            .module_namespace(&handle)
+            .expect("Could not get module namespace");
+        let namespace: deno_core::serde_json::Value = namespace
+            .try_into(&mut runtime)
+            .expect("Could not convert value");
+        assert_eq!(
+            namespace,
+            deno_core::serde_json::json!({ "name": "plugin", "version": 2 })
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_in_multi_thread_runtime() {
+        let handle = tokio::runtime::Handle::current();
+        let mut runtime = Runtime::with_tokio_runtime_handle(RuntimeOptions::default(), handle)
+            .expect("Could not create the runtime");
+        let value: u32 = runtime.eval("2 + 2").expect("Could not eval");
+        assert_eq!(value, 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tokio_handle_option() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            tokio_handle: Some(tokio::runtime::Handle::current()),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        assert!(matches!(
+            runtime.tokio_runtime(),
+            crate::async_bridge::TokioRuntime::Borrowed(_)
+        ));
+
+        let module = Module::new(
+            "test.js",
+            "export const value = await new Promise(r => setTimeout(() => r(42), 10));",
+        );
+        let handle = runtime
+            .load_module_async(&module)
+            .await
+            .expect("Could not load module");
+        let value: u32 = runtime
+            .get_value_async(Some(&handle), "value")
+            .await
+            .expect("Could not get value");
+        assert_eq!(value, 42);
+
+        let value: u32 = runtime.eval("2 + 2").expect("Could not eval");
+        assert_eq!(value, 4);
+    }
+
+    #[tokio::test]
+    async fn test_poll_event_loop_future() {
+        let module = Module::new(
+            "test.js",
+            "
+            globalThis.ticks = 0;
+            export function tick() { return ++ticks; }
+            setTimeout(() => globalThis.done = true, 50);
+        ",
+        );
+
+        let handle = tokio::runtime::Handle::current();
+        let mut runtime = Runtime::with_tokio_runtime_handle(RuntimeOptions::default(), handle)
+            .expect("Could not create the runtime");
+        let module = runtime
+            .load_module_async(&module)
+            .await
+            .expect("Could not load module");
+
+        // Interrupted by another future, before the timer fires
+        tokio::select! {
+            r = runtime.poll_event_loop_future(PollEventLoopOptions::default()) => {
+                panic!("Event loop finished early: {r:?}");
+            }
+            () = tokio::time::sleep(Duration::from_millis(5)) => {}
+        }
+
+        let ticks: u32 = runtime
+            .call_function_async(Some(&module), "tick", json_args!())
+            .await
+            .expect("Could not call function");
+        assert_eq!(ticks, 1);
+
+        runtime
+            .poll_event_loop_future(PollEventLoopOptions::default())
+            .await
+            .expect("Could not run the event loop");
+        let done: bool = runtime
+            .get_value_async(None, "done")
+            .await
+            .expect("Could not get value");
+        assert!(done);
+    }
+
+    #[tokio::test]
+    async fn test_block_in_current_thread_runtime() {
+        let handle = tokio::runtime::Handle::current();
+        let mut runtime = Runtime::with_tokio_runtime_handle(RuntimeOptions::default(), handle)
+            .expect("Could not create the runtime");
+        runtime.eval::<u32>("2 + 2").unwrap_err();
+
+        let value: u32 = runtime.eval_async("2 + 2").await.expect("Could not eval");
+        assert_eq!(value, 4);
+    }
+
+    #[test]
+    fn test_run_microtasks() {
+        let module = Module::new(
+            "test.js",
+            "
+            globalThis.log = [];
+            export function schedule() {
+                Promise.resolve()
+                    .then(() => log.push('first'))
+                    .then(() => log.push('second'));
+                setTimeout(() => log.push('timer'), 0);
+            }
+        ",
+        );
+
+        // By default, V8 runs the microtasks as soon as the call returns
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        runtime
+            .call_function_immediate::<Undefined>(Some(&handle), "schedule", json_args!())
+            .expect("Could not call function");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert_eq!(log, vec!["first", "second"]);
+        runtime.run_microtasks().expect("Could not run microtasks");
+
+        // With the explicit policy they wait for `run_microtasks`, and timers for the event loop
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_microtask_policy(crate::MicrotaskPolicy::Explicit)
+            .build()
+            .expect("Could not build the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        runtime
+            .call_function_immediate::<Undefined>(Some(&handle), "schedule", json_args!())
+            .expect("Could not call function");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert!(log.is_empty());
+
+        runtime.run_microtasks().expect("Could not run microtasks");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert_eq!(log, vec!["first", "second"]);
+
+        runtime
+            .block_on_event_loop(PollEventLoopOptions::default(), None)
+            .expect("Could not run the event loop");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert_eq!(log, vec!["first", "second", "timer"]);
+    }
+
+    #[test]
+    fn test_reported_errors() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            fail_on_console_error: true,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        runtime
+            .check_reported_errors()
+            .expect("Unexpected reported error");
+
+        runtime
+            .eval::<()>("console.log('fine'); console.warn('fine')")
+            .expect("Could not eval");
+        runtime
+            .check_reported_errors()
+            .expect("Unexpected reported error");
+
+        runtime
+            .eval::<()>("console.error('bad', 1)")
+            .expect("Could not eval");
+        runtime.eval::<()>("throw new Error('worse')").unwrap_err();
+        let errors = runtime.take_reported_errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], "bad 1");
+        assert!(errors[1].contains("worse"));
+        assert!(runtime.take_reported_errors().is_empty());
+
+        runtime
+            .eval::<()>("console.error('bad')")
+            .expect("Could not eval");
+        runtime.check_reported_errors().unwrap_err();
+
+        // Not recorded unless enabled
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .eval::<()>("console.error('bad')")
+            .expect("Could not eval");
+        runtime
+            .check_reported_errors()
+            .expect("Unexpected reported error");
+    }
+
+    #[test]
+    fn test_load_package() {
+        let dir = std::env::temp_dir().join(format!("rustyscript_package_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).expect("Could not create directory");
+        std::fs::write(
+            dir.join("package.json"),
+            r#"{ "name": "test", "exports": { ".": { "import": "./lib/main.js" } } }"#,
+        )
+        .expect("Could not write file");
+        std::fs::write(
+            dir.join("lib/main.js"),
+            "import { greet } from './util.js'; export const describe = () => greet('package');",
+        )
+        .expect("Could not write file");
+        std::fs::write(
+            dir.join("lib/util.js"),
+            "export const greet = (name) => `hello ${name}`;",
+        )
+        .expect("Could not write file");
+
+        // A link back into the package must not be walked forever
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("lib/loop")).expect("Could not create symlink");
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_package(&dir);
+        std::fs::remove_dir_all(&dir).expect("Could not remove directory");
+
+        let handle = handle.expect("Could not load module");
+        let value: String = runtime
+            .call_function(Some(&handle), "describe", json_args!())
+            .expect("Could not call function");
+        assert_eq!(value, "hello package");
+
+        runtime.load_package("does/not/exist").unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "fs_import")]
+    fn test_tsconfig_paths() {
+        let dir = std::env::temp_dir().join(format!("rustyscript_paths_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/lib")).expect("Could not create directory");
+        std::fs::write(
+            dir.join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@lib/*": ["src/lib/*"] } } }"#,
+        )
+        .expect("Could not write file");
+        std::fs::write(
+            dir.join("src/lib/math.ts"),
+            "export const double = (n: number): number => n * 2;",
+        )
+        .expect("Could not write file");
+
+        let paths = crate::module_loader::TsConfigPaths::from_file(dir.join("tsconfig.json"))
+            .expect("Could not read tsconfig.json");
+        let mut runtime = Runtime::new(RuntimeOptions {
+            tsconfig_paths: Some(paths),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let module = Module::new(
+            dir.join("main.ts"),
+            "import { double } from '@lib/math'; export const value = double(21);",
+        );
+        let handle = runtime.load_module(&module);
+        std::fs::remove_dir_all(&dir).expect("Could not remove directory");
+
+        let value: usize = runtime
+            .get_value(Some(&handle.expect("Could not load module")), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_load_module_from_url() {
+        struct RemoteProvider;
+        impl crate::module_loader::ImportProvider for RemoteProvider {
+            fn resolve(
+                &mut self,
+                specifier: &deno_core::ModuleSpecifier,
+                _referrer: &str,
+                _kind: deno_core::ResolutionKind,
+            ) -> Option<Result<deno_core::ModuleSpecifier, deno_core::error::ModuleLoaderError>>
+            {
+                Some(Ok(specifier.clone()))
+            }
+
+            fn import(
+                &mut self,
+                specifier: &deno_core::ModuleSpecifier,
+                _referrer: Option<&deno_core::ModuleSpecifier>,
+                _is_dyn_import: bool,
+            ) -> Option<Result<String, deno_core::error::ModuleLoaderError>> {
+                match specifier.as_str() {
+                    "https://example.com/app/main.js" => Some(Ok(
+                        "import { value } from './lib.js'; export default () => value;".to_string(),
+                    )),
+                    "https://example.com/app/lib.js" => {
+                        Some(Ok("export const value = 42;".to_string()))
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            import_provider: Some(Box::new(RemoteProvider)),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        runtime
+            .load_module_from_url("http://example.com/app/main.js")
+            .unwrap_err();
+        runtime
+            .load_module_from_url("ftp://example.com/app/main.js")
+            .unwrap_err();
+
+        let handle = runtime
+            .load_module_from_url("https://example.com/app/main.js")
+            .expect("Could not load module");
+        let value: u32 = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_error_transform() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            error_transform: Some(std::sync::Arc::new(|e| match e {
+                Error::JsError(mut e) => {
+                    e.exception_message = e.exception_message.replace("/secret/", "");
+                    Error::JsError(e)
+                }
+                e => e,
+            })),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let e = runtime
+            .eval::<()>("throw new Error('failed at /secret/app.js')")
+            .unwrap_err();
+        assert!(e.to_string().contains("failed at app.js"));
+
+        let module = Module::new(
+            "test.js",
+            "export function f() { throw new Error('/secret/f'); }",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let e = runtime
+            .call_function::<()>(Some(&handle), "f", json_args!())
+            .unwrap_err();
+        assert!(!e.to_string().contains("/secret/"));
+    }
+
+    #[test]
+    fn test_specifier_display() {
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_specifier_display(|specifier| match specifier.scheme() {
+                "file" => specifier
+                    .path()
+                    .rsplit_once("/src/")
+                    .map_or_else(|| specifier.to_string(), |(_, path)| format!("src/{path}")),
+                _ => specifier.to_string(),
+            })
+            .build()
+            .expect("Could not build the runtime");
+
+        let module = Module::new(
+            "/long/abs/path/src/foo.ts",
+            "export function f() { throw new Error('failed'); }",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let e = runtime
+            .call_function::<()>(Some(&handle), "f", json_args!())
+            .unwrap_err();
+        let frame = &e.stack_frames().expect("Missing stack frames")[0];
+        assert_eq!(frame.file_name.as_deref(), Some("src/foo.ts"));
+        assert!(e.stack().expect("Missing stack").contains("(src/foo.ts:1:"));
+        assert!(!e.to_string().contains("/long/abs/path"), "{e}");
+
+        // Specifiers are also found in messages
+        let module = Module::new(
+            "/long/abs/path/src/bar.js",
+            "throw new Error(`bad module ${import.meta.url}`);",
+        );
+        let e = runtime.load_module(&module).unwrap_err();
+        assert!(e.to_string().contains("bad module src/bar.js"), "{e}");
+        assert!(!e.to_string().contains("/long/abs/path"), "{e}");
+
+        // Other URLs in a script's messages are left as written
+        let e = runtime
+            .eval::<()>(
+                "throw new Error('see http://docs.host:8080 or file:///long/abs/path/src/x.js')",
+            )
+            .unwrap_err();
+        assert!(
+            e.to_string()
+                .contains("see http://docs.host:8080 or file:///long/abs/path/src/x.js"),
+            "{e}"
+        );
+    }
+
+    #[test]
+    fn test_interrupt_handle() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.interrupt_handle();
+
+        let watchdog = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            handle.terminate();
+        });
+
+        let result = runtime.eval::<()>("while (true) {}");
+        watchdog.join().expect("Watchdog thread panicked");
+        assert!(matches!(result, Err(Error::Interrupted)));
+        assert!(!runtime.interrupt_handle().is_interrupted());
+
+        // The runtime is still usable
+        let value: u32 = runtime.eval("2 + 2").expect("Could not eval");
+        assert_eq!(value, 4);
+    }
+
+    #[test]
+    fn test_json5_modules() {
+        struct ConfigProvider;
+        impl crate::module_loader::ImportProvider for ConfigProvider {
+            fn import(
+                &mut self,
+                specifier: &deno_core::ModuleSpecifier,
+                _referrer: Option<&deno_core::ModuleSpecifier>,
+                _is_dyn_import: bool,
+            ) -> Option<Result<String, deno_core::error::ModuleLoaderError>> {
+                match specifier.path() {
+                    "/config.json5" => Some(Ok("{
+                        // Hand-maintained settings
+                        name: 'app',
+                        ports: [80, 443,],
+                        mask: 0xFF,
+                    }"
+                    .to_string())),
+                    "/broken.json5" => Some(Ok("{ name: 'app',\n ports: [80 }".to_string())),
+                    _ => None,
+                }
+            }
+        }
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            import_provider: Some(Box::new(ConfigProvider)),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let module = Module::new(
+            "/main.js",
+            "
+            import cfg from 'file:///config.json5' with { type: 'json5' };
+            export default () => cfg;
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let config: deno_core::serde_json::Value = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(
+            config,
+            deno_core::serde_json::json!({ "name": "app", "ports": [80, 443], "mask": 255 })
+        );
+
+        let module = Module::new(
+            "/broken.js",
+            "import cfg from 'file:///broken.json5' with { type: 'json5' };",
+        );
+        let e = runtime.load_module(&module).unwrap_err().to_string();
+        assert!(e.contains("line 2"), "{e}");
+    }
+
+    #[test]
+    fn test_module_type_handlers() {
+        struct ConfigProvider;
+        impl crate::module_loader::ImportProvider for ConfigProvider {
+            fn import(
+                &mut self,
+                specifier: &deno_core::ModuleSpecifier,
+                _referrer: Option<&deno_core::ModuleSpecifier>,
+                _is_dyn_import: bool,
+            ) -> Option<Result<String, deno_core::error::ModuleLoaderError>> {
+                match specifier.path() {
+                    "/config.toml" => Some(Ok(
+                        "name = 'app'\n'__proto__' = 'key'\n[server]\nport = 8080\n".to_string(),
+                    )),
+                    "/broken.toml" => Some(Ok("name = 'app'\nport = \n".to_string())),
+                    _ => None,
+                }
+            }
+        }
+
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_import_provider(Box::new(ConfigProvider))
+            .with_module_type_handler("toml", |bytes| {
+                let text = std::str::from_utf8(bytes).map_err(|e| Error::Runtime(e.to_string()))?;
+                toml::from_str(text).map_err(|e| Error::JsonDecode(e.to_string()))
+            })
+            .with_module_type_handler("bin", |bytes| Ok(bytes.into()))
+            .build()
+            .expect("Could not build the runtime");
+
+        // By file extension, and by import attribute
+        let module = Module::new(
+            "/main.js",
+            "
+            import cfg from 'file:///config.toml';
+            import same from 'file:///config.toml' with { type: 'toml' };
+            export default () => [cfg.server.port, same.name];
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: (u16, String) = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, (8080, "app".to_string()));
+
+        // A `__proto__` key is an ordinary property, as with JSON.parse
+        let module = Module::new(
+            "/proto.js",
+            "
+            import cfg from 'file:///config.toml';
+            export default () => [Object.hasOwn(cfg, '__proto__'), Object.getPrototypeOf(cfg) === Object.prototype];
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: (bool, bool) = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, (true, true));
+
+        // Files are passed to the handler as bytes, even if they are not valid UTF-8
+        #[cfg(feature = "fs_import")]
+        {
+            let path =
+                std::env::temp_dir().join(format!("rustyscript_types_{}.bin", std::process::id()));
+            std::fs::write(&path, [0xff, 0x00, 0x80]).expect("Could not write file");
+            let url = deno_core::ModuleSpecifier::from_file_path(&path).expect("Invalid path");
+            let module = Module::new(
+                "/binary.js",
+                format!("import data from '{url}'; export default () => data;"),
+            );
+            let handle = runtime.load_module(&module);
+            std::fs::remove_file(&path).expect("Could not remove file");
+            let value: Vec<u8> = runtime
+                .call_entrypoint(&handle.expect("Could not load module"), json_args!())
+                .expect("Could not call entrypoint");
+            assert_eq!(value, vec![0xff, 0x00, 0x80]);
+        }
+
+        let module = Module::new("/broken.js", "import cfg from 'file:///broken.toml';");
+        let e = runtime.load_module(&module).unwrap_err().to_string();
+        assert!(e.contains("broken.toml") && e.contains("line 2"), "{e}");
+    }
+
+    #[test]
+    fn test_scheme_handlers() {
+        struct DbHandler;
+        impl crate::module_loader::SchemeHandler for DbHandler {
+            fn resolve(
+                &self,
+                specifier: &deno_core::ModuleSpecifier,
+                _referrer: &str,
+            ) -> Result<deno_core::ModuleSpecifier, deno_core::error::ModuleLoaderError>
+            {
+                if specifier.path().contains("secret") {
+                    return Err(deno_core::error::ModuleLoaderError::generic("denied"));
+                }
+                Ok(specifier.clone())
+            }
+
+            fn load(
+                &self,
+                specifier: &deno_core::ModuleSpecifier,
+            ) -> deno_core::futures::future::LocalBoxFuture<
+                'static,
+                Result<String, deno_core::error::ModuleLoaderError>,
+            > {
+                let name = specifier.path().trim_start_matches('/').to_string();
+                Box::pin(async move { Ok(format!("export default {name:?};")) })
+            }
+        }
+
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_scheme_handler("db", DbHandler)
+            .with_scheme_handler("s3://", |specifier: &deno_core::ModuleSpecifier| {
+                let path = specifier.path().to_string();
+                async move {
+                    match path.as_str() {
+                        "/bucket/lib.ts" => Ok(
+                            "export const add = (a: number, b: number): number => a + b;"
+                                .to_string(),
+                        ),
+                        "/bucket/util.js" => Ok("export { add } from './lib.ts';".to_string()),
+                        _ => Err(deno_core::error::ModuleLoaderError::generic(format!(
+                            "no such object: {path}"
+                        ))),
+                    }
+                }
+            })
+            .build()
+            .expect("Could not build the runtime");
+
+        // Relative imports resolve within the scheme, and typescript is transpiled
+        let module = Module::new(
+            "/main.js",
+            "
+            import { add } from 's3://bucket/util.js';
+            import users from 'db:users';
+            export default () => [add(1, 2), users];
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: (u32, String) = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, (3, "users".to_string()));
+
+        // Errors from the handler fail the import
+        let module = Module::new("/missing.js", "import 's3://bucket/missing.js';");
+        let e = runtime.load_module(&module).unwrap_err().to_string();
+        assert!(e.contains("no such object"), "{e}");
+
+        let module = Module::new("/secret.js", "import 'db:secret';");
+        let e = runtime.load_module(&module).unwrap_err().to_string();
+        assert!(e.contains("denied"), "{e}");
+
+        // Other schemes fall through to the default handling
+        let module = Module::new("/gs.js", "import 'gs://bucket/mod.js';");
+        let e = runtime.load_module(&module).unwrap_err().to_string();
+        assert!(e.contains("unsupported scheme"), "{e}");
+    }
+
+    #[test]
+    fn test_pending_promise_count() {
+        // Not counted by default
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .eval::<Undefined>("globalThis.hung = new Promise(() => {}); undefined")
+            .expect("Could not eval");
+        assert_eq!(runtime.pending_promise_count(), 0);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            track_pending_promises: true,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let baseline = runtime.pending_promise_count();
+
+        // Settled promises are not counted
+        runtime
+            .eval::<Undefined>(
+                "Promise.resolve(1).then(() => {}); Promise.reject(2).catch(() => {}); undefined",
+            )
+            .expect("Could not eval");
+        runtime.run_microtasks().expect("Could not run microtasks");
+        assert_eq!(runtime.pending_promise_count(), baseline);
+
+        // A script awaiting a promise that never settles leaves both pending
+        runtime
+            .eval::<Undefined>(
+                "globalThis.hung = (async () => { await new Promise(() => {}); })(); undefined",
+            )
+            .expect("Could not eval");
+        runtime.run_microtasks().expect("Could not run microtasks");
+        assert!(runtime.pending_promise_count() >= baseline + 2);
+    }
+
+    #[test]
+    fn test_reload_policy() {
+        use crate::module_loader::ReloadPolicy;
+
+        let dir = std::env::temp_dir().join(format!("rustyscript_reload_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("Could not create directory");
+        let path = dir.join("config.js");
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            reload_policy: ReloadPolicy::ReloadIfChanged,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        std::fs::write(&path, "export const value = 1;").expect("Could not write file");
+        let first = runtime
+            .load_module(&Module::load(&path).expect("Could not read module"))
+            .expect("Could not load module");
+        let value: u32 = runtime
+            .get_value(Some(&first), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 1);
+
+        // Unchanged, so the same instance is returned
+        let again = runtime
+            .load_module(&Module::load(&path).expect("Could not read module"))
+            .expect("Could not load module");
+        assert_eq!(again.id(), first.id());
+
+        std::fs::write(&path, "export const value = 2;").expect("Could not write file");
+        let changed = runtime
+            .load_module(&Module::load(&path).expect("Could not read module"))
+            .expect("Could not load module");
+        std::fs::remove_dir_all(&dir).expect("Could not remove directory");
+
+        assert_ne!(changed.id(), first.id());
+        let value: u32 = runtime
+            .get_value(Some(&changed), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 2);
+
+        // The first instance is kept, even once changed
+        let mut runtime = Runtime::new(RuntimeOptions {
+            reload_policy: ReloadPolicy::CacheForever,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let module = Module::new(
+            "counter.js",
+            "globalThis.loads = (globalThis.loads ?? 0) + 1;",
+        );
+        runtime.load_module(&module).expect("Could not load module");
+        let module = Module::new(
+            "counter.js",
+            "globalThis.loads = (globalThis.loads ?? 0) + 10;",
+        );
+        runtime.load_module(&module).expect("Could not load module");
+        let loads: u32 = runtime.eval("loads").expect("Could not eval");
+        assert_eq!(loads, 1);
+
+        // By default, each load is a new instance
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime.load_module(&module).expect("Could not load module");
+        runtime.load_module(&module).expect("Could not load module");
+        let loads: u32 = runtime.eval("loads").expect("Could not eval");
+        assert_eq!(loads, 20);
+    }
+
+    #[test]
+    fn test_module_source() {
+        let module = Module::new(
+            "test.ts",
+            "
+            interface Point { x: number }
+            export const origin: Point = { x: 0 };
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let source = runtime
+            .module_source(&handle)
+            .expect("Could not get module source");
+        assert!(source.contains("export const origin = {"), "{source}");
+        assert!(!source.contains("interface"), "{source}");
+    }
+
+    #[test]
+    fn test_define_builtin_module() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .register_function(
+                "query",
+                crate::sync_callback!(|sql: String| Ok::<String, Error>(format!("rows for {sql}"))),
+            )
+            .expect("Could not register function");
+
+        let exports: crate::js_value::Value = runtime
+            .eval("({ query: rustyscript.functions.query, version: 2 })")
+            .expect("Could not eval");
+        runtime
+            .define_builtin_module("host:db", &exports)
+            .expect("Could not define module");
+
+        let module = Module::new(
+            "test.js",
+            "
+            import db, { query, version } from 'host:db';
+            export const rows = query('select 1');
+            export const matches = db.version === version;
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let rows: String = runtime
+            .get_value(Some(&handle), "rows")
+            .expect("Could not get value");
+        assert_eq!(rows, "rows for select 1");
+
+        let matches: bool = runtime
+            .get_value(Some(&handle), "matches")
+            .expect("Could not get value");
+        assert!(matches);
+
+        // Only objects can back a module
+        let exports: crate::js_value::Value = runtime.eval("5").expect("Could not eval");
+        runtime
+            .define_builtin_module("host:number", &exports)
+            .unwrap_err();
+
+        // Undefined builtins are rejected as usual
+        let module = Module::new("test2.js", "import { query } from 'host:other';");
+        runtime.load_module(&module).unwrap_err();
+    }
+
+    #[test]
+    fn test_minimal_runtime() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::minimal()).expect("Could not create the runtime");
+
+        let module = Module::new(
+            "test.ts",
+            "
+            export function sum(values: number[]): number {
+                return values.reduce((a, b) => a + b, 0);
+            }
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: i64 = runtime
+            .call_function(Some(&handle), "sum", json_args!([1, 2, 3]))
+            .expect("Could not call function");
+        assert_eq!(value, 6);
+
+        let globals: Vec<String> = runtime
+            .eval("['fetch', 'URL'].map(name => typeof globalThis[name])")
+            .expect("Could not eval");
+        assert_eq!(globals, vec!["undefined", "undefined"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_call_async_generator() {
+        use deno_core::futures::StreamExt;
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Page {
+            page: u32,
+        }
+
+        let module = Module::new(
+            "test.js",
+            "
+            export async function* pages(count) {
+                for (let i = 1; i <= count; i++) {
+                    await new Promise(r => setTimeout(r, 1));
+                    yield { page: i };
+                }
+                return 'ignored';
+            }
+
+            export async function* failing() {
+                yield { page: 1 };
+                throw new Error('connection lost');
+            }
+        ",
+        );
+
+        let handle = tokio::runtime::Handle::current();
+        let mut runtime = Runtime::with_tokio_runtime_handle(RuntimeOptions::default(), handle)
+            .expect("Could not create the runtime");
+        let module = runtime
+            .load_module_async(&module)
+            .await
+            .expect("Could not load module");
+
+        let pages: Vec<Page> = runtime
+            .call_async_generator(Some(&module), "pages", json_args!(3))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(
+            pages,
+            vec![Page { page: 1 }, Page { page: 2 }, Page { page: 3 }]
+        );
+
+        let results: Vec<Result<Page, Error>> = runtime
+            .call_async_generator(Some(&module), "failing", json_args!())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().expect("Module failed to load"),
+            &Page { page: 1 }
+        );
+        let error = results[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("connection lost"), "{error}");
+
+        // Not a function
+        let results: Vec<Result<Page, Error>> = runtime
+            .call_async_generator(Some(&module), "missing", json_args!())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn test_eval_with_globals() {
+        use std::collections::HashMap;
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .eval::<Undefined>("globalThis.shadowed = 'original'")
+            .expect("Could not eval");
+
+        let globals = HashMap::from([
+            (
+                "vars".to_string(),
+                deno_core::serde_json::json!({ "x": 2, "y": 3 }),
+            ),
+            (
+                "shadowed".to_string(),
+                deno_core::serde_json::json!("temporary"),
+            ),
+        ]);
+        let value: String = runtime
+            .eval_with_globals("`${vars.x * vars.y} ${shadowed}`", globals.clone())
+            .expect("Could not eval");
+        assert_eq!(value, "6 temporary");
+
+        let defined: bool = runtime
+            .eval("'vars' in globalThis")
+            .expect("Could not eval");
+        assert!(!defined);
+        let shadowed: String = runtime.eval("shadowed").expect("Could not eval");
+        assert_eq!(shadowed, "original");
+
+        // Removed even if the evaluation fails
+        runtime
+            .eval_with_globals::<Undefined>("throw new Error(vars.x)", globals)
+            .unwrap_err();
+        let defined: bool = runtime
+            .eval("'vars' in globalThis")
+            .expect("Could not eval");
+        assert!(!defined);
+
+        // Non-configurable globals cannot be replaced
+        let globals = HashMap::from([
+            ("vars".to_string(), deno_core::serde_json::json!(1)),
+            ("undefined".to_string(), deno_core::serde_json::json!(1)),
+        ]);
+        runtime
+            .eval_with_globals::<Undefined>("vars", globals)
+            .unwrap_err();
+        let defined: bool = runtime
+            .eval("'vars' in globalThis")
+            .expect("Could not eval");
+        assert!(!defined);
+    }
+
+    #[cfg(feature = "console")]
+    #[test]
+    fn test_console_handler() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{LogLevel, LogRecord};
+
+        let records: Arc<Mutex<Vec<LogRecord>>> = Arc::default();
+        let handler_records = records.clone();
+        let mut runtime = Runtime::new(RuntimeOptions {
+            console_handler: Some(Arc::new(move |record| {
+                handler_records.lock().expect("Lock poisoned").push(record);
+            })),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let before = std::time::SystemTime::now();
+        let module = Module::new(
+            "test.js",
+            "
+            console.log('hello', { a: 1 });
+            console.warn('careful');
+            export function fail() { console.error('broken'); }
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        runtime
+            .call_function::<Undefined>(Some(&handle), "fail", json_args!())
+            .expect("Could not call function");
+
+        let records = records.lock().expect("Lock poisoned");
+        assert_eq!(records.len(), 3);
+
+        assert_eq!(records[0].level, LogLevel::Info);
+        assert_eq!(records[0].message, "hello { a: 1 }");
+        assert!(records[0].timestamp >= before);
+
+        let (file, line) = records[0].origin.clone().expect("Missing origin");
+        assert!(file.ends_with("test.js"), "{file}");
+        assert_eq!(line, 2);
+
+        assert_eq!(records[1].level, LogLevel::Warn);
+        assert_eq!(records[1].message, "careful");
+
+        assert_eq!(records[2].level, LogLevel::Error);
+        assert_eq!(records[2].origin.as_ref().map(|(_, line)| *line), Some(4));
+    }
+
+    #[test]
+    fn test_run_until_idle() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let tokio_runtime = runtime.tokio_runtime();
+
+        // Finishes within the budget
+        tokio_runtime
+            .block_on(
+                runtime.eval_immediate::<Undefined>(
+                    "setTimeout(() => { globalThis.done = true; }, 1)",
+                ),
+            )
+            .expect("Could not eval");
+        let result = runtime
+            .run_until_idle(100, Duration::from_secs(1))
+            .expect("Could not run the event loop");
+        assert_eq!(result, IdleResult::Idle);
+        let done: bool = runtime
+            .get_value_immediate(None, "done")
+            .expect("Could not get value");
+        assert!(done);
+
+        // An idle loop is reported as such, even without a budget
+        let result = runtime
+            .run_until_idle(0, Duration::from_secs(1))
+            .expect("Could not run the event loop");
+        assert_eq!(result, IdleResult::Idle);
+
+        // Self-perpetuating timers run out of turns
+        tokio_runtime
+            .block_on(runtime.eval_immediate::<Undefined>("setInterval(() => {}, 0)"))
+            .expect("Could not eval");
+        let result = runtime
+            .run_until_idle(10, Duration::from_secs(5))
+            .expect("Could not run the event loop");
+        assert_eq!(result, IdleResult::BudgetExceeded);
+
+        // Or out of time
+        let result = runtime
+            .run_until_idle(usize::MAX, Duration::from_millis(50))
+            .expect("Could not run the event loop");
+        assert_eq!(result, IdleResult::BudgetExceeded);
+    }
+
+    #[test]
+    fn test_get_value_settled() {
+        let module = Module::new(
+            "test.js",
+            "
+            export let value;
+            setTimeout(() => value = 42, 10);
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let value: Option<usize> = runtime
+            .get_value_immediate(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, None);
+
+        let value: usize = runtime
+            .get_value_settled(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 42);
+
+        // An event loop that never settles is stopped by the runtime's timeout
+        let module = Module::new(
+            "forever.js",
+            "
+            export const value = 1;
+            setInterval(() => {}, 10);
+        ",
+        );
+        let mut runtime = Runtime::new(RuntimeOptions {
+            timeout: Duration::from_millis(100),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let result = runtime.get_value_settled::<usize>(Some(&handle), "value");
+        assert!(matches!(result, Err(Error::Timeout(_))));
+    }
+
+    #[test]
+    fn test_custom_op_extension() {
+        #[deno_core::op2(fast)]
+        fn op_test_checksum(#[buffer] data: &[u8]) -> u32 {
+            data.iter().map(|b| u32::from(*b)).sum()
+        }
+        extension!(test_op_extension, ops = [op_test_checksum]);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            extensions: vec![test_op_extension::init()],
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let sum: u32 = runtime
+            .eval("Deno.core.ops.op_test_checksum(new Uint8Array([1, 2, 3, 250]))")
+            .expect("Could not eval");
+        assert_eq!(sum, 256);
+    }
+
+    #[test]
+    fn test_module_eval_timeout() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            module_eval_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        // Synchronous top-level work is terminated
+        let module = Module::new("busy.js", "while (true) {}");
+        let result = runtime.load_module(&module);
+        assert!(matches!(result, Err(Error::Timeout(_))));
+
+        // As is a top-level await that never settles in time
+        let module = Module::new("slow.js", "await new Promise((r) => setTimeout(r, 1000));");
+        let result = runtime.load_module(&module);
+        assert!(matches!(result, Err(Error::Timeout(_))));
+
+        // Exported functions are not bound by the limit
+        let module = Module::new(
+            "ok.js",
+            "
+            export function spin(ms) {
+                const end = Date.now() + ms;
+                while (Date.now() < end) {}
+                return 'done';
+            }
+        ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: String = runtime
+            .call_function(Some(&handle), "spin", json_args!(300))
+            .expect("Could not call function");
+        assert_eq!(value, "done");
+    }
+
+    #[test]
+    fn test_load_module_batch() {
+        let modules = vec![
+            Module::new(
+                "shared.js",
+                "globalThis.loads = (globalThis.loads ?? 0) + 1; export const base = 10;",
+            ),
+            Module::new(
+                "plugin_a.js",
+                "import { base } from './shared.js'; export const value = base + 1;",
+            ),
+            Module::new("plugin_b.js", "throw new Error('broken plugin');"),
+            Module::new(
+                "plugin_c.js",
+                "import { base } from './shared.js'; export const value = base + 3;",
+            ),
+        ];
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let results = runtime
+            .load_module_batch(&modules)
+            .expect("Could not load modules");
+        assert_eq!(results.len(), 4);
+
+        let a = results[1].as_ref().expect("Module failed to load");
+        let value: usize = runtime
+            .get_value(Some(a), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 11);
+
+        let error = results[2].as_ref().unwrap_err();
+        assert!(error.to_string().contains("broken plugin"));
+
+        let c = results[3].as_ref().expect("Module failed to load");
+        let value: usize = runtime
+            .get_value(Some(c), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 13);
+
+        // The shared import was evaluated once
+        let loads: usize = runtime.eval("globalThis.loads").expect("Could not eval");
+        assert_eq!(loads, 1);
+    }
+
+    #[test]
+    fn test_take_heap_snapshot() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime
+            .eval::<()>("class RetainedThing {}; globalThis.retained = new RetainedThing();")
+            .expect("Could not eval");
+
+        let mut snapshot = Vec::new();
+        runtime
+            .take_heap_snapshot(&mut snapshot)
+            .expect("Could not take heap snapshot");
+
+        let snapshot: deno_core::serde_json::Value =
+            deno_core::serde_json::from_slice(&snapshot).expect("Invalid JSON");
+        assert!(snapshot["snapshot"]["meta"]["node_fields"].is_array());
+        assert!(snapshot["nodes"].is_array());
+
+        let strings = snapshot["strings"].as_array().expect("Missing strings");
+        assert!(strings.iter().any(|s| s == "RetainedThing"));
+    }
+
+    #[test]
+    fn test_cpu_profile() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime.stop_cpu_profile().unwrap_err();
+
+        runtime
+            .start_cpu_profile("test", Some(Duration::from_micros(100)))
+            .expect("Could not start the profiler");
+        runtime.start_cpu_profile("again", None).unwrap_err();
+
+        runtime
+            .eval::<()>(
+                "
+                function hotFunction() {
+                    const end = Date.now() + 100;
+                    let n = 0;
+                    while (Date.now() < end) n++;
+                    return n;
+                }
+                hotFunction();
+            ",
+            )
+            .expect("Could not eval");
+
+        let profile = runtime
+            .stop_cpu_profile()
+            .expect("Could not stop the profiler");
+        assert_eq!(profile.name(), "test");
+        assert!(profile.sample_count() > 0);
+        assert!(profile.duration() >= Duration::from_millis(100));
+        assert!(profile.function_names().contains(&"hotFunction"));
+
+        let mut output = Vec::new();
+        profile.write(&mut output).expect("Could not write profile");
+        let json: deno_core::serde_json::Value =
+            deno_core::serde_json::from_slice(&output).expect("Invalid JSON");
+        assert!(json["nodes"].is_array());
+    }
+
+    #[test]
+    fn test_dynamic_import_disabled() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            allow_dynamic_import: false,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let dep = Module::new("dep.js", "export const value = 42;");
+        runtime.load_module(&dep).expect("Could not load module");
+
+        let main = Module::new(
+            "main.js",
+            "
+            import { value } from './dep.js';
+            export { value };
+            export async function load(specifier) {
+                try {
+                    await import(specifier);
+                    return 'loaded';
+                } catch (e) {
+                    return e.message;
+                }
+            }
+        ",
+        );
+        let handle = runtime.load_module(&main).expect("Could not load module");
+
+        // Static imports still resolve
+        let value: usize = runtime
+            .get_value(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 42);
+
+        // Dynamic imports are rejected, even of modules that are already loaded
+        let result: String = runtime
+            .call_function(Some(&handle), "load", json_args!("./dep.js"))
+            .expect("Could not call function");
+        assert!(result.contains("dynamic import of ./dep.js is not allowed"));
+    }
+
+    #[test]
+    fn test_serialize_value() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let value: crate::js_value::Value = runtime
+            .eval(
+                "
+                const value = {
+                    map: new Map([['a', new Set([1, 2])]]),
+                    bytes: new Uint8Array([1, 2, 3]),
+                    big: 10n ** 20n,
+                    date: new Date(0),
+                };
+                value.self = value;
+                value
+            ",
+            )
+            .expect("Could not eval");
+        let bytes = runtime
+            .serialize_value(&value)
+            .expect("Could not serialize value");
+
+        // Restored into a fresh runtime, with its types intact
+        let mut other =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let restored = other
+            .deserialize_value(&bytes)
+            .expect("Could not deserialize value");
+        other.with_scope(|scope| {
+            let key =
+                deno_core::v8::String::new(scope, "restored").expect("Could not create string");
+            let restored = deno_core::v8::Local::new(scope, restored.as_v8());
+            let global = scope.get_current_context().global(scope);
+            global.set(scope, key.into(), restored);
+        });
+        let checks: deno_core::serde_json::Value = other
+            .eval(
+                "
+                const v = globalThis.restored;
+                [v.map.get('a').has(2), v.bytes[2], String(v.big), v.date.getTime(), v.self === v]
+            ",
+            )
+            .expect("Could not eval");
+        assert_eq!(
+            checks,
+            deno_core::serde_json::json!([true, 3, "100000000000000000000", 0, true])
+        );
+
+        // Functions cannot be cloned
+        let function: crate::js_value::Value = runtime.eval("() => 1").expect("Could not eval");
+        let error = runtime.serialize_value(&function).unwrap_err();
+        assert!(error.to_string().contains("could not be cloned"));
+
+        other.deserialize_value(&[0xff]).unwrap_err();
+    }
+
+    #[test]
+    fn test_instantiate_module() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let dependency = Module::new(
+            "dependency.js",
+            "globalThis.log = []; log.push('dependency'); export const value = 2;",
+        );
+        runtime
+            .load_module(&dependency)
+            .expect("Could not load module");
+
+        let module = Module::new(
+            "plugin.js",
+            "
+            import { value } from './dependency.js';
+            log.push('plugin');
+            export function double() { return value * 2; }
+            export default () => 'entry';
+        ",
+        );
+        let handle = runtime
+            .instantiate_module(&module)
+            .expect("Could not instantiate module");
+
+        // Linked, but not yet run
+        let log: Vec<String> = runtime.eval("log").expect("Could not eval");
+        assert_eq!(log, vec!["dependency"]);
+
+        let error = runtime
+            .call_function::<usize>(Some(&handle), "double", json_args!())
+            .unwrap_err();
+        assert!(error.to_string().contains("not been evaluated"));
+
+        let handle = runtime
+            .evaluate_module(&handle)
+            .expect("Could not evaluate module");
+        let log: Vec<String> = runtime.eval("log").expect("Could not eval");
+        assert_eq!(log, vec!["dependency", "plugin"]);
+
+        let result: usize = runtime
+            .call_function(Some(&handle), "double", json_args!())
+            .expect("Could not call function");
+        assert_eq!(result, 4);
+        let result: String = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(result, "entry");
+
+        runtime.evaluate_module(&handle).unwrap_err();
+
+        // Evaluation is bounded by the module evaluation timeout, like any other module load
+        let mut runtime = Runtime::new(RuntimeOptions {
+            module_eval_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let spin = Module::new("spin.js", "while (true) {}");
+        let handle = runtime
+            .instantiate_module(&spin)
+            .expect("Could not instantiate module");
+        assert!(matches!(
+            runtime.evaluate_module(&handle),
+            Err(Error::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_sandboxed_env() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            env: Some([("MODE".to_string(), "test".to_string())].into()),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let result: Vec<Option<String>> = runtime
+            .eval(
+                "
+                Deno.env.set('X', '1');
+                const x = Deno.env.get('X');
+                Deno.env.delete('MODE');
+                [x, Deno.env.get('MODE') ?? null, Deno.env.get('PATH') ?? null]
+            ",
+            )
+            .expect("Could not eval");
+        assert_eq!(result, vec![Some("1".to_string()), None, None]);
+
+        let vars: std::collections::HashMap<String, String> =
+            runtime.eval("Deno.env.toObject()").expect("Could not eval");
+        assert_eq!(vars, [("X".to_string(), "1".to_string())].into());
+        assert!(std::env::var_os("X").is_none());
+
+        let error = runtime.eval::<()>("Deno.env.set('', '1')").unwrap_err();
+        assert!(error.to_string().contains("Key is an empty string"));
+
+        #[cfg(feature = "node_experimental")]
+        {
+            let result: Vec<Option<String>> = runtime
+                .eval(
+                    "
+                    process.env.Y = '2';
+                    delete process.env.X;
+                    [Deno.env.get('Y') ?? null, process.env.X ?? null, process.env.Y]
+                ",
+                )
+                .expect("Could not eval");
+            assert_eq!(
+                result,
+                vec![Some("2".to_string()), None, Some("2".to_string())]
+            );
+
+            let keys: Vec<String> = runtime
+                .eval("Object.keys(process.env)")
+                .expect("Could not eval");
+            assert_eq!(keys, vec!["Y"]);
+        }
+    }
+
+    #[test]
+    fn test_resolve_specifier() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            schema_whlist: ["custom:".to_string()].into(),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        runtime
+            .load_module(&Module::new("src/util.ts", "export const x = 1;"))
+            .expect("Could not load module");
+
+        let cwd = deno_core::ModuleSpecifier::from_directory_path(runtime.current_dir())
+            .expect("Invalid path");
+        let url = runtime
+            .resolve_specifier("./util.ts", Some("src/main.ts"))
+            .expect("Could not resolve specifier");
+        assert_eq!(url, cwd.join("src/util.ts").expect("Invalid url"));
+
+        // Other files can only be imported with `fs_import`
+        let result = runtime.resolve_specifier("../lib.js", Some("file:///project/src/main.ts"));
+        #[cfg(feature = "fs_import")]
+        assert_eq!(
+            result.expect("Could not resolve specifier").as_str(),
+            "file:///project/lib.js"
+        );
+        #[cfg(not(feature = "fs_import"))]
+        assert!(result.unwrap_err().to_string().contains("is not loaded"));
+
+        let url = runtime
+            .resolve_specifier("custom:thing", None)
+            .expect("Could not resolve specifier");
+        assert_eq!(url.as_str(), "custom:thing");
+
+        runtime.resolve_specifier("bare", None).unwrap_err();
+        runtime.resolve_specifier("other:thing", None).unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_stdio_redirect() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().expect("Lock poisoned").extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let stdout = Buffer::default();
+        let stderr = Buffer::default();
+        let mut runtime = Runtime::new(RuntimeOptions {
+            extension_options: crate::ExtensionOptions {
+                stdin: Some(Box::new(std::io::Cursor::new(b"input".to_vec()))),
+                stdout: Some(Box::new(stdout.clone())),
+                stderr: Some(Box::new(stderr.clone())),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let input: String = runtime
+            .eval(
+                "
+                const buf = new Uint8Array(16);
+                const n = Deno.stdin.readSync(buf);
+                const encoder = new TextEncoder();
+                Deno.stdout.writeSync(encoder.encode('out'));
+                Deno.stderr.writeSync(encoder.encode('err'));
+                new TextDecoder().decode(buf.subarray(0, n))
+            ",
+            )
+            .expect("Could not eval");
+        assert_eq!(input, "input");
+
+        // Output is forwarded by a background thread
+        let read = |buffer: &Buffer| {
+            String::from_utf8(buffer.0.lock().expect("Lock poisoned").clone())
+                .expect("Invalid UTF-8")
+        };
+        let start = std::time::Instant::now();
+        while (read(&stdout) != "out" || read(&stderr) != "err")
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(read(&stdout), "out");
+        assert_eq!(read(&stderr), "err");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_load_module_with_cache_key() {
+        use crate::module_loader::ModuleCacheProvider;
+        use deno_core::{ModuleSource, ModuleSourceCode, ModuleSpecifier, ModuleType};
+        use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+        #[derive(Default, Clone)]
+        struct SharedCache(Rc<RefCell<HashMap<ModuleSpecifier, String>>>);
+        impl ModuleCacheProvider for SharedCache {
+            fn set(&mut self, specifier: &ModuleSpecifier, source: ModuleSource) {
+                if let ModuleSourceCode::String(code) = source.code {
+                    self.0
+                        .borrow_mut()
+                        .insert(specifier.clone(), code.as_str().to_string());
+                }
+            }
+
+            fn get(&self, specifier: &ModuleSpecifier) -> Option<ModuleSource> {
+                let code = self.0.borrow().get(specifier)?.clone();
+                Some(ModuleSource::new(
+                    ModuleType::JavaScript,
+                    ModuleSourceCode::String(code.into()),
+                    specifier,
+                    None,
+                ))
+            }
+        }
+
+        let cache = SharedCache::default();
+        let mut runtime = Runtime::new(RuntimeOptions {
+            module_cache: Some(Box::new(cache.clone())),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let first = Module::new("upload-1.ts", "export const value: number = 1;");
+        let handle = runtime
+            .load_module_with_cache_key(&first, "content-hash")
+            .expect("Could not load module");
+        let value: usize = runtime
+            .get_value(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 1);
+
+        // Stored under the key, not the specifier, with the source map beside it
+        let mut keys: Vec<String> = cache.0.borrow().keys().map(ToString::to_string).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "rustyscript-cache:/content-hash",
+                "rustyscript-cache:/content-hash#sourcemap"
+            ]
+        );
+
+        // A second module under the same key is served from the cache
+        let second = Module::new("upload-2.ts", "export const value: number = 2;");
+        let handle = runtime
+            .load_module_with_cache_key(&second, "content-hash")
+            .expect("Could not load module");
+        let value: usize = runtime
+            .get_value(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, 1);
+
+        // The module evaluation timeout applies, as it does to any other module
+        let mut runtime = Runtime::new(RuntimeOptions {
+            module_cache: Some(Box::new(cache)),
+            module_eval_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let spin = Module::new("spin.js", "while (true) {}");
+        assert!(matches!(
+            runtime.load_module_with_cache_key(&spin, "spin"),
+            Err(Error::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_call_function_json() {
+        let module = Module::new(
+            "rpc.js",
+            "
+            export function nothing() {}
+            export function echo(params) { return { params, ok: true }; }
+            export async function later() { return [1, 'two']; }
+        ",
+        );
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let result = runtime
+            .call_function_json(Some(&handle), "nothing", json_args!())
+            .expect("Could not call function");
+        assert_eq!(result, deno_core::serde_json::Value::Null);
+
+        let result = runtime
+            .call_function_json(Some(&handle), "echo", json_args!("x"))
+            .expect("Could not call function");
+        assert_eq!(
+            result,
+            deno_core::serde_json::json!({ "params": "x", "ok": true })
+        );
+
+        let result = runtime
+            .call_function_json(Some(&handle), "later", json_args!())
+            .expect("Could not call function");
+        assert_eq!(result, deno_core::serde_json::json!([1, "two"]));
+    }
+
+    #[test]
+    #[cfg(feature = "node_experimental")]
+    fn test_load_napi_addon_errors() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        let error = runtime.load_napi_addon("missing/addon.node").unwrap_err();
+        assert!(matches!(error, Error::ModuleNotFound(_)));
+
+        // Not a shared library, so it must fail to load rather than crash
+        let path = std::env::temp_dir().join("rustyscript_not_an_addon.node");
+        std::fs::write(&path, "export default 1;").expect("Could not write file");
+        runtime.load_napi_addon(&path).unwrap_err();
+        std::fs::remove_file(&path).expect("Could not remove file");
+
+        // The loader is not exposed to scripts as a global
+        let exposed: bool = runtime
+            .eval("Object.getOwnPropertyNames(globalThis).some((name) => name.includes('napi'))")
+            .expect("Could not eval");
+        assert!(!exposed);
+    }
+
+    #[test]
+    #[cfg(feature = "node_experimental")]
+    fn test_async_local_storage() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        // Concurrent requests must each see their own store, across awaits and timers
+        let module = Module::new(
+            "async_local_storage.js",
+            "
+            import { AsyncLocalStorage } from 'node:async_hooks';
+            const storage = new AsyncLocalStorage();
+
+            async function handle() {
+                await Promise.resolve();
+                await new Promise((resolve) => setTimeout(resolve, 5));
+                return new Promise((resolve) => setTimeout(() => resolve(storage.getStore().id), 1));
+            }
+
+            export async function correlate() {
+                const ids = await Promise.all([1, 2, 3].map((id) => storage.run({ id }, handle)));
+                return [...ids, storage.getStore() ?? null];
+            }
+            ",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let ids: Vec<Option<u32>> = runtime
+            .call_function(Some(&handle), "correlate", json_args!())
+            .expect("Could not call function");
+        assert_eq!(ids, vec![Some(1), Some(2), Some(3), None]);
+    }
+
+    #[test]
+    fn test_module_preprocessors() {
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_module_preprocessor(|_, code| {
+                Ok(format!(
+                    "globalThis.loaded = (globalThis.loaded ?? 0) + 1;\n{}",
+                    code.replace("MARKER", "'first'")
+                ))
+            })
+            .with_module_preprocessor(|_, code| Ok(code.replace("'first'", "'second'")))
+            .build()
+            .expect("Could not build the runtime");
+
+        let module = Module::new("a.ts", "export const value: string = MARKER;");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: String = runtime
+            .get_value(Some(&handle), "value")
+            .expect("Could not get value");
+        assert_eq!(value, "second");
+
+        let module = Module::new("b.js", "export const value = 1;");
+        runtime.load_module(&module).expect("Could not load module");
+        let loaded: usize = runtime.eval("globalThis.loaded").expect("Could not eval");
+        assert_eq!(loaded, 2);
+
+        // A failing preprocessor fails the load
+        let mut runtime = crate::RuntimeBuilder::new()
+            .with_module_preprocessor(|specifier, _| {
+                Err(Error::Runtime(format!("rejected {specifier}")))
+            })
+            .build()
+            .expect("Could not build the runtime");
+        let error = runtime.load_module(&module).unwrap_err();
+        assert!(error.to_string().contains("rejected"));
+    }
+
+    #[test]
+    fn test_memory_pressure_callback() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut runtime = Runtime::new(RuntimeOptions {
+            max_heap_size: Some(50 * 1024 * 1024),
+            on_memory_pressure: Some({
+                let calls = calls.clone();
+                Arc::new(move |stats: HeapStats| {
+                    // Raise the limit once, as a soft threshold would
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        MemoryPressureAction::IncreaseLimit(stats.current_heap_limit * 4)
+                    } else {
+                        MemoryPressureAction::Terminate
+                    }
+                })
+            }),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        // Fits within the raised limit
+        let module = Module::new(
+            "test.js",
+            "globalThis.data = new Array(8 * 1024 * 1024).fill(0);",
+        );
+        runtime.load_module(&module).expect("Could not load module");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Exceeds it, and is terminated on the second call
+        let module = Module::new(
+            "test2.js",
+            "globalThis.more = new Array(64 * 1024 * 1024).fill('a');",
+        );
+        runtime.load_module(&module).unwrap_err();
+        assert!(calls.load(Ordering::SeqCst) >= 2);
+    }
+
+    #[test]
+    fn test_snapshot_and_rebuild() {
+        let version = |greeting: &str| {
+            Module::new(
+                "plugin.js",
+                format!(
+                    "
+                let state = {{ hits: new Map(), started: new Date(0) }};
+                export function hit(page) {{
+                    state.hits.set(page, (state.hits.get(page) ?? 0) + 1);
+                    return state.hits.get(page);
+                }}
+                export const greet = () => '{greeting}';
+                export const exportState = () => state;
+                export const importState = (saved) => {{ state = saved; }};
+            "
+                ),
+            )
+        };
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime
+            .load_module(&version("v1"))
+            .expect("Could not load module");
+        runtime
+            .call_function::<usize>(Some(&handle), "hit", json_args!("/"))
+            .expect("Could not call function");
+        runtime
+            .call_function::<usize>(Some(&handle), "hit", json_args!("/"))
+            .expect("Could not call function");
+
+        let handle = runtime
+            .snapshot_and_rebuild(&handle, &version("v2"))
+            .expect("Could not rebuild the runtime");
+
+        // The data survived, and the functions were replaced
+        let hits: usize = runtime
+            .call_function(Some(&handle), "hit", json_args!("/"))
+            .expect("Could not call function");
+        assert_eq!(hits, 3);
+        let greeting: String = runtime
+            .call_function(Some(&handle), "greet", json_args!())
+            .expect("Could not call function");
+        assert_eq!(greeting, "v2");
+
+        // A module without the hooks cannot be swapped
+        let plain = runtime
+            .load_module(&Module::new("plain.js", "export const x = 1;"))
+            .expect("Could not load module");
+        runtime.export_state(&plain).unwrap_err();
+    }
+
+    #[test]
+    fn test_deterministic_time_and_random() {
+        let script =
+            "[Date.now(), new Date().getTime(), Date() === new Date(1700000000000).toString(),
+            new Date(0).getTime(), new Date() instanceof Date, Math.random(), Math.random()]";
+        let run = |seed| {
+            let mut runtime = crate::RuntimeBuilder::new()
+                .with_deterministic_time(1_700_000_000_000)
+                .with_deterministic_random_seed(seed)
+                .build()
+                .expect("Could not create the runtime");
+            runtime
+                .eval::<deno_core::serde_json::Value>(script)
+                .expect("Could not evaluate the script")
+        };
+
+        let first = run(42);
+        assert_eq!(first, run(42));
+        assert_ne!(first[5], run(43)[5]);
+
+        assert_eq!(first[0], 1_700_000_000_000u64);
+        assert_eq!(first[1], 1_700_000_000_000u64);
+        assert_eq!(first[2], true);
+        assert_eq!(first[3], 0);
+        assert_eq!(first[4], true);
+        for n in [&first[5], &first[6]] {
+            let n = n.as_f64().expect("Expected a number");
+            assert!((0.0..1.0).contains(&n));
+        }
+        assert_ne!(first[5], first[6]);
+
+        // Unset, the real sources are used
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let now: f64 = runtime.eval("Date.now()").expect("Could not eval");
+        assert!(now > 1_700_000_000_000.0);
+    }
+
+    #[test]
+    fn test_microtask_policy() {
+        let script =
+            "globalThis.log = []; Promise.resolve().then(() => log.push('then')); undefined";
+
+        // The current behavior, microtasks run as soon as the script returns
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        runtime.eval::<Undefined>(script).expect("Could not eval");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert_eq!(log, vec!["then"]);
+
+        let mut runtime = Runtime::new(RuntimeOptions {
+            microtask_policy: crate::MicrotaskPolicy::Explicit,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        runtime.eval::<Undefined>(script).expect("Could not eval");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert!(log.is_empty());
+
+        runtime.run_microtasks().expect("Could not run microtasks");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert_eq!(log, vec!["then"]);
+
+        // An error does not switch the policy back
+        runtime
+            .eval::<Undefined>("throw new Error('oops')")
+            .unwrap_err();
+        runtime.eval::<Undefined>(script).expect("Could not eval");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert!(log.is_empty());
+
+        // Nor does one raised outside of a call, such as by a getter
+        runtime
+            .eval::<Undefined>(
+                "Object.defineProperty(globalThis, 'boom', { get() { throw new Error('oops') } })",
+            )
+            .expect("Could not eval");
+        runtime
+            .get_value_immediate::<Undefined>(None, "boom")
+            .unwrap_err();
+        runtime.eval::<Undefined>(script).expect("Could not eval");
+        let log: Vec<String> = runtime
+            .get_value_immediate(None, "log")
+            .expect("Could not get value");
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_load_and_get() {
+        #[derive(serde::Deserialize)]
+        struct Manifest {
+            name: String,
+            version: u32,
+        }
+
+        let module = Module::new(
+            "plugin.js",
+            "
+            globalThis.global_only = 1;
+            export const manifest = { name: 'example', version: 2 };
+            export const later = Promise.resolve('resolved');
+            export function run() { return manifest.version * 2; }
+        ",
+        );
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let (handle, manifest) = runtime
+            .load_and_get::<Manifest>(&module, "manifest")
+            .expect("Could not load module");
+        let manifest = manifest.expect("Missing export");
+        assert_eq!(manifest.name, "example");
+        assert_eq!(manifest.version, 2);
+
+        let result: u32 = runtime
+            .call_function(Some(&handle), "run", json_args!())
+            .expect("Could not call function");
+        assert_eq!(result, 4);
+
+        let module = Module::new("plugin-2.js", module.contents());
+        let (_, later) = runtime
+            .load_and_get::<String>(&module, "later")
+            .expect("Could not load module");
+        assert_eq!(later.expect("Missing export"), "resolved");
+
+        // A missing export is an error, but the handle is kept
+        let module = Module::new("plugin-3.js", module.contents());
+        let (handle, missing) = runtime
+            .load_and_get::<u32>(&module, "global_only")
+            .expect("Could not load module");
+        assert!(matches!(missing, Err(Error::ValueNotFound(_))));
+        let result: u32 = runtime
+            .call_function(Some(&handle), "run", json_args!())
+            .expect("Could not call function");
+        assert_eq!(result, 4);
+
+        let broken = Module::new("broken.js", "throw new Error('load failed');");
+        runtime
+            .load_and_get::<u32>(&broken, "manifest")
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_define_global_getter() {
+        let calls = Rc::new(std::cell::Cell::new(0));
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        let counter = calls.clone();
+        runtime
+            .define_global_getter("hostInfo", move || {
+                counter.set(counter.get() + 1);
+                Ok(deno_core::serde_json::json!({ "cpus": 8 }))
+            })
+            .expect("Could not define getter");
+        runtime
+            .define_global_getter("broken", || Err(Error::Runtime("unavailable".to_string())))
+            .expect("Could not define getter");
+
+        // Not computed until read
+        let defined: bool = runtime
+            .eval("'hostInfo' in globalThis")
+            .expect("Could not eval");
+        assert!(defined);
+        assert_eq!(calls.get(), 0);
+
+        let cpus: usize = runtime.eval("hostInfo.cpus").expect("Could not eval");
+        assert_eq!(cpus, 8);
+        let cpus: usize = runtime
+            .eval("globalThis.hostInfo.cpus")
+            .expect("Could not eval");
+        assert_eq!(cpus, 8);
+        assert_eq!(calls.get(), 1);
+
+        let e = runtime.eval::<Undefined>("broken").unwrap_err();
+        assert!(e.to_string().contains("unavailable"), "{e}");
+
+        let value: u32 = runtime.eval("broken = 5; broken").expect("Could not eval");
+        assert_eq!(value, 5);
+    }
+
+    #[test]
+    fn test_call_constructor() {
+        let module = Module::new(
+            "test.js",
+            "
+            export class Counter {
+                constructor(start, step = 1) { this.count = start; this.step = step; }
+                increment() { return this.count += this.step; }
+                async later() { return this.count; }
+            }
+            export const arrow = () => {};
+            export class Strict { constructor() { throw new Error('no'); } }
+        ",
+        );
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+
+        let class: crate::js_value::Value = runtime
+            .get_value(Some(&handle), "Counter")
+            .expect("Could not get value");
+        let counter = runtime
+            .call_constructor(&class, json_args!(5, 2))
+            .expect("Could not call constructor");
+        let other = runtime
+            .call_constructor(&class, json_args!(0))
+            .expect("Could not call constructor");
+
+        let count: u32 = runtime
+            .call_method(&counter, "increment", json_args!())
+            .expect("Could not call method");
+        assert_eq!(count, 7);
+        let count: u32 = runtime
+            .call_method(&other, "increment", json_args!())
+            .expect("Could not call method");
+        assert_eq!(count, 1);
+        let count: u32 = runtime
+            .call_method(&counter, "later", json_args!())
+            .expect("Could not call method");
+        assert_eq!(count, 7);
+        let step: Option<u32> = counter
+            .get_property(&mut runtime, "step")
+            .expect("Could not get property");
+        assert_eq!(step, Some(2));
+
+        runtime
+            .call_method::<Undefined>(&counter, "missing", json_args!())
+            .unwrap_err();
+
+        // Values that cannot be constructed are errors
+        let arrow: crate::js_value::Value = runtime
+            .get_value(Some(&handle), "arrow")
+            .expect("Could not get value");
+        runtime.call_constructor(&arrow, json_args!()).unwrap_err();
+        let number: crate::js_value::Value = runtime.eval("5").expect("Could not eval");
+        assert!(matches!(
+            runtime.call_constructor(&number, json_args!()),
+            Err(Error::ValueNotCallable(_))
+        ));
+        let strict: crate::js_value::Value = runtime
+            .get_value(Some(&handle), "Strict")
+            .expect("Could not get value");
+        runtime.call_constructor(&strict, json_args!()).unwrap_err();
+    }
+
+    #[test]
+    fn test_json_limits() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            json_limits: Some(crate::JsonLimits {
+                max_depth: 3,
+                max_length: 64,
+            }),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        // Brackets inside strings are not counted
+        let value: Vec<String> = runtime
+            .eval(r#"JSON.parse('[[["[[[[", "\\\\"]]]')[0][0]"#)
+            .expect("Could not eval");
+        assert_eq!(value, vec!["[[[[", "\\"]);
+        let value: u32 = runtime
+            .eval("JSON.parse('{\"a\": 1}', (k, v) => k === 'a' ? v + 1 : v).a")
+            .expect("Could not eval");
+        assert_eq!(value, 2);
+
+        let result: String = runtime
+            .eval(
+                "
+                const attempt = (text) => {
+                    try { JSON.parse(text); return 'parsed'; } catch (e) { return e.constructor.name; }
+                };
+                [attempt('[[[[1]]]]'), attempt(JSON.stringify('x'.repeat(64)))].join()
+            ",
+            )
+            .expect("Could not eval");
+        assert_eq!(result, "RangeError,RangeError");
+
+        // The unlimited parse is not reachable from javascript
+        let exposed: bool = runtime
+            .eval("Object.getOwnPropertySymbols(JSON.parse).length > 0")
+            .expect("Could not eval");
+        assert!(!exposed);
+
+        // Arguments from rust are not subject to the limits
+        let module = Module::new("args.js", "export const depth = (v) => v[0][0][0][0][0];");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let depth: u32 = runtime
+            .call_function(Some(&handle), "depth", json_args!([[[[[7]]]]]))
+            .expect("Could not call function");
+        assert_eq!(depth, 7);
+
+        // The default limits allow deep input, and reject absurd nesting without crashing
+        let mut runtime = Runtime::new(RuntimeOptions {
+            json_limits: Some(crate::JsonLimits::default()),
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let depth: usize = runtime
+            .eval("let v = JSON.parse('['.repeat(1000) + ']'.repeat(1000)); let d = 0; while (Array.isArray(v) && v.length) { v = v[0]; d++; } d + 1")
+            .expect("Could not eval");
+        assert_eq!(depth, 1000);
+        runtime
+            .eval::<Undefined>("JSON.parse('['.repeat(1000000) + ']'.repeat(1000000))")
+            .unwrap_err();
+
+        // No limits are applied by default
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let value: u32 = runtime
+            .eval("JSON.parse('[[[[[[1]]]]]]')[0][0][0][0][0][0]")
+            .expect("Could not eval");
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn test_global_keys() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        assert!(runtime.user_global_keys().is_empty());
+        assert!(runtime.global_keys().len() >= runtime.user_global_keys().len());
+
+        runtime
+            .eval::<()>("var foo = 1; function bar() {} let hidden = 2;")
+            .expect("Could not eval");
+        assert_eq!(runtime.user_global_keys(), vec!["foo", "bar"]);
+
+        let baseline = runtime.global_keys();
+        runtime
+            .eval::<()>("globalThis.baz = 3; foo = 4;")
+            .expect("Could not eval");
+        assert_eq!(runtime.globals_added_since(&baseline), vec!["baz"]);
+        assert_eq!(runtime.user_global_keys(), vec!["foo", "bar", "baz"]);
+
+        // Non-enumerable globals, and replaced builtins, are included
+        let baseline = runtime.global_keys();
+        runtime
+            .eval::<()>("Object.defineProperty(globalThis, 'qux', { value: 5 }); globalThis.escape = () => {};")
+            .expect("Could not eval");
+        assert_eq!(runtime.globals_added_since(&baseline), vec!["qux"]);
+        let keys = runtime.user_global_keys();
+        assert!(keys.contains(&"qux".to_string()));
+        assert!(keys.contains(&"escape".to_string()));
+        assert!(!keys.contains(&"unescape".to_string()));
+    }
+
+    #[test]
+    fn test_shebang() {
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+
+        let module = Module::new(
+            "cli.js",
+            "#!/usr/bin/env -S deno run\nexport default () => 'js';",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: String = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, "js");
+
+        let module = Module::new(
+            "cli.ts",
+            "#!/usr/bin/env -S deno run\r\nexport default (): string => 'ts';",
+        );
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: String = runtime
+            .call_entrypoint(&handle, json_args!())
+            .expect("Could not call entrypoint");
+        assert_eq!(value, "ts");
+
+        // Line numbers are unchanged
+        let module = Module::new(
+            "lines.js",
+            "#!/usr/bin/env deno\n\nthrow new Error('oops');",
+        );
+        let e = runtime.load_module(&module).unwrap_err();
+        assert!(e.to_string().contains("lines.js:3:"), "{e}");
+
+        // Only a leading shebang is stripped
+        let module = Module::new("late.js", "\n#!/usr/bin/env deno\nexport default 1;");
+        runtime.load_module(&module).unwrap_err();
+    }
+
+    #[test]
+    fn test_config_summary() {
+        let runtime = Runtime::new(RuntimeOptions {
+            timeout: Duration::from_secs(5),
+            max_heap_size: Some(64 * 1024 * 1024),
+            default_entrypoint: Some("main".to_string()),
+            allow_dynamic_import: false,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+
+        let config = runtime.config_summary();
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        assert_eq!(config.max_heap_size, Some(64 * 1024 * 1024));
+        assert_eq!(config.default_entrypoint.as_deref(), Some("main"));
+        assert!(!config.allow_dynamic_import);
+        assert!(!config.from_snapshot);
+        assert!(config.extensions.iter().any(|name| name == "rustyscript"));
+        assert_eq!(config.permissions.is_some(), cfg!(feature = "web"));
+
+        let runtime = Runtime::new(RuntimeOptions {
+            core_only: true,
+            ..Default::default()
+        })
+        .expect("Could not create the runtime");
+        let config = runtime.config_summary();
+        assert!(config.core_only);
+        assert_eq!(config.extensions, vec!["rustyscript"]);
+    }
 
-        extension!(test_extension);
-        Runtime::new(RuntimeOptions {
-            extensions: vec![test_extension::init()],
+    #[test]
+    fn test_code_generation_disabled() {
+        let mut runtime = Runtime::new(RuntimeOptions {
+            allow_code_generation: false,
             ..Default::default()
         })
-        .expect("Could not create runtime with extensions");
+        .expect("Could not create the runtime");
+        assert!(!runtime.config_summary().allow_code_generation);
+
+        // Code from rust still runs
+        let value: i64 = runtime
+            .eval("[1, 2, 3].map(x => x * 2).reduce((a, b) => a + b)")
+            .expect("Could not eval");
+        assert_eq!(value, 12);
+
+        let blocked: Vec<String> = runtime
+            .eval(
+                "[() => eval('1 + 1'), () => new Function('return 1')(), () => Function('return 1')()]
+                    .map(f => { try { f(); return 'allowed'; } catch (e) { return e.name; } })",
+            )
+            .expect("Could not eval");
+        assert_eq!(blocked, vec!["EvalError"; 3]);
+
+        // WebAssembly still compiles
+        let exports: usize = runtime
+            .eval(
+                "Object.keys(new WebAssembly.Instance(new WebAssembly.Module(new Uint8Array([0, 97, 115, 109, 1, 0, 0, 0]))).exports).length",
+            )
+            .expect("Could not eval");
+        assert_eq!(exports, 0);
+
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let value: i64 = runtime.eval("eval('1 + 1')").expect("Could not eval");
+        assert_eq!(value, 2);
     }
 
     #[test]
-    fn test_get_value() {
-        let module = Module::new(
-            "test.js",
-            "
-            globalThis.a = 2;
-            export const b = 'test';
-            export const fnc = null;
-        ",
-        );
+    fn test_bytes_from_value() {
+        use crate::js_value::Value;
 
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = runtime
-            .load_modules(&module, vec![])
-            .expect("Could not load module");
+        let bytes = |runtime: &mut Runtime, expr: &str| {
+            let value: Value = runtime.eval(expr).expect("Could not eval");
+            runtime.bytes_from_value(&value)
+        };
 
         assert_eq!(
-            2,
-            runtime
-                .get_value::<usize>(Some(&module), "a")
-                .expect("Could not find global")
+            bytes(&mut runtime, "new Uint8Array([1, 2, 255])").expect("Could not read bytes"),
+            vec![1, 2, 255]
         );
         assert_eq!(
-            "test",
-            runtime
-                .get_value::<String>(Some(&module), "b")
-                .expect("Could not find export")
+            bytes(&mut runtime, "new Uint8Array([1, 2, 3, 4]).buffer")
+                .expect("Could not read bytes"),
+            vec![1, 2, 3, 4]
         );
-        runtime
-            .get_value::<Undefined>(Some(&module), "c")
-            .expect_err("Could not detect null");
-        runtime
-            .get_value::<Undefined>(Some(&module), "d")
-            .expect_err("Could not detect undeclared");
+        assert_eq!(
+            bytes(&mut runtime, "new Uint8Array([1, 2, 3, 4]).subarray(1, 3)")
+                .expect("Could not read bytes"),
+            vec![2, 3]
+        );
+        assert_eq!(
+            bytes(&mut runtime, "new Uint16Array([0x0201])").expect("Could not read bytes"),
+            vec![1, 2]
+        );
+        assert_eq!(
+            bytes(
+                &mut runtime,
+                "new DataView(new Uint8Array([5, 6, 7]).buffer, 1)"
+            )
+            .expect("Could not read bytes"),
+            vec![6, 7]
+        );
+
+        bytes(&mut runtime, "[1, 2, 3]").unwrap_err();
+        bytes(&mut runtime, "'bytes'").unwrap_err();
     }
 
     #[test]
-    fn test_load_module() {
+    fn test_terminate_and_drain() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        let done = Arc::new(AtomicBool::new(false));
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = Module::new(
-            "test.js",
-            "
-            export default () => 2;
-        ",
-        );
-        let module = runtime
-            .load_modules(&module, vec![])
-            .expect("Could not load module");
-        assert_ne!(0, module.id());
+        {
+            let done = done.clone();
+            runtime
+                .register_function("done", move |_| {
+                    done.store(true, Ordering::SeqCst);
+                    Ok(deno_core::serde_json::Value::Null)
+                })
+                .expect("Could not register function");
+        }
+        runtime
+            .eval::<Undefined>(
+                "
+                setInterval(() => {}, 10);
+                setTimeout(() => rustyscript.functions.done(), 10);
+                setTimeout(() => {}, 60_000);
+            ",
+            )
+            .expect("Could not eval");
+
+        // The short timer fires, the interval is cancelled, and the long timer is reported
+        let report = runtime.terminate_and_drain(Duration::from_millis(500));
+        assert!(!report.idle && !report.terminated, "{report:?}");
+        assert_eq!(report.cancelled_intervals, 1);
+        assert_eq!(report.pending_timers, 1);
+        assert!(report.elapsed >= Duration::from_millis(500));
+        assert!(done.load(Ordering::SeqCst));
+
+        // No new calls are accepted once drained
+        assert!(matches!(
+            runtime.eval::<Undefined>("undefined"),
+            Err(Error::Runtime(_))
+        ));
 
+        // Nothing pending
+        let mut runtime =
+            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
+        let report = runtime.terminate_and_drain(Duration::from_secs(5));
+        assert!(report.idle && report.errors.is_empty(), "{report:?}");
+        assert!(report.pending_ops.is_empty() && report.pending_timers == 0);
+
+        // A script that never yields is terminated at the deadline
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module1 = Module::new(
-            "importme.js",
-            "
-            export const value = 2;
-        ",
-        );
-        let module2 = Module::new(
-            "test.js",
-            "
-            import { value } from './importme.js';
-            rustyscript.register_entrypoint(() => value);
-        ",
-        );
         runtime
-            .load_module(&module1)
-            .expect("Could not load modules");
-        let module = runtime
-            .load_module(&module2)
-            .expect("Could not load modules");
-        let value: usize = runtime
-            .call_entrypoint(&module, json_args!())
-            .expect("Could not call exported fn");
-        assert_eq!(2, value);
+            .eval::<Undefined>("setTimeout(() => { while (true) {} }, 50);")
+            .expect("Could not eval");
+        let report = runtime.terminate_and_drain(Duration::from_millis(500));
+        assert!(report.terminated && !report.idle, "{report:?}");
+        assert!(!runtime.interrupt_handle().is_interrupted());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_prefetch_graph() {
+        use crate::module_loader::{ClonableSource, ModuleCacheProvider};
+        use deno_core::{ModuleSource, ModuleSpecifier};
+        use std::{
+            cell::RefCell,
+            collections::HashMap,
+            rc::Rc,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+        };
 
+        #[derive(Default, Clone)]
+        struct SharedCache(Rc<RefCell<HashMap<ModuleSpecifier, ModuleSource>>>);
+        impl ModuleCacheProvider for SharedCache {
+            fn set(&mut self, specifier: &ModuleSpecifier, source: ModuleSource) {
+                self.0.borrow_mut().insert(specifier.clone(), source);
+            }
+
+            fn get(&self, specifier: &ModuleSpecifier) -> Option<ModuleSource> {
+                self.0.borrow().get(specifier).map(|s| s.clone(specifier))
+            }
+        }
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let handler = move |specifier: &ModuleSpecifier| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let path = specifier.path().to_string();
+            async move {
+                match path.as_str() {
+                    "/a.ts" => Ok(
+                        "import { b } from './b.js'; export const a: number = b + 1;".to_string(),
+                    ),
+                    "/b.js" => Ok("export const b = 1;".to_string()),
+                    _ => Err(deno_core::error::ModuleLoaderError::generic("not found")),
+                }
+            }
+        };
         let mut runtime = Runtime::new(RuntimeOptions {
-            timeout: Duration::from_millis(50),
+            module_cache: Some(Box::new(SharedCache::default())),
+            scheme_handlers: HashMap::from([(
+                "cdn".to_string(),
+                Arc::new(handler) as Arc<dyn crate::module_loader::SchemeHandler>,
+            )]),
             ..Default::default()
         })
         .expect("Could not create the runtime");
+
+        // Failures do not stop the rest of the graph
+        let warmup = Module::new(
+            "/warmup.ts",
+            "import 'cdn://host/a.ts'; export * from 'cdn://host/missing.js'; import type {} from 'cdn://host/types.ts';",
+        );
+        let report = runtime
+            .prefetch_graph(&warmup)
+            .expect("Could not prefetch graph");
+        let prefetched: Vec<&str> = report
+            .prefetched
+            .iter()
+            .map(ModuleSpecifier::as_str)
+            .collect();
+        assert_eq!(prefetched, vec!["cdn://host/a.ts", "cdn://host/b.js"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "cdn://host/missing.js");
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+
+        // Later loads come from the cache
         let module = Module::new(
-            "test.js",
-            "
-            await new Promise(r => setTimeout(r, 2000));
-        ",
+            "/main.js",
+            "export { a as default } from 'cdn://host/a.ts';",
         );
-        runtime
-            .load_modules(&module, vec![])
-            .expect_err("Did not interupt after timeout");
+        let handle = runtime.load_module(&module).expect("Could not load module");
+        let value: u32 = runtime
+            .get_value(Some(&handle), "default")
+            .expect("Could not get value");
+        assert_eq!(value, 2);
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
     }
 
     #[test]
-    fn test_load_modules() {
+    fn test_run_script_with_args() {
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = Module::new(
-            "test.js",
-            "
-            rustyscript.register_entrypoint(() => 2);
-        ",
-        );
-        let module = runtime
-            .load_modules(&module, vec![])
-            .expect("Could not load module");
-        assert_ne!(0, module.id());
 
-        let mut runtime =
-            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module1 = Module::new(
-            "importme.js",
-            "
-            export const value = 2;
-        ",
-        );
-        let module2 = Module::new(
-            "test.js",
-            "
-            import { value } from './importme.js';
-            rustyscript.register_entrypoint(() => value);
-        ",
-        );
-        let module = runtime
-            .load_modules(&module2, vec![&module1])
-            .expect("Could not load modules");
-        let value: usize = runtime
-            .call_entrypoint(&module, json_args!())
-            .expect("Could not call exported fn");
-        assert_eq!(2, value);
+        // Empty until set
+        let args: Vec<String> = runtime.eval("Deno.args").expect("Could not eval");
+        assert!(args.is_empty());
 
-        let mut runtime = Runtime::new(RuntimeOptions {
-            timeout: Duration::from_millis(50),
-            ..Default::default()
-        })
-        .expect("Could not create the runtime");
         let module = Module::new(
-            "test.js",
-            "
-            await new Promise(r => setTimeout(r, 5000));
-        ",
+            "cli.js",
+            "export const first = Deno.args[0]; export const count = Deno.args.length;",
         );
-        runtime
-            .load_modules(&module, vec![])
-            .expect_err("Did not interupt after timeout");
+        let handle = runtime
+            .run_script_with_args(&module, vec!["input.txt".to_string(), "-v".to_string()])
+            .expect("Could not run script");
+        let first: String = runtime
+            .get_value(Some(&handle), "first")
+            .expect("Could not get value");
+        let count: usize = runtime
+            .get_value(Some(&handle), "count")
+            .expect("Could not get value");
+        assert_eq!((first.as_str(), count), ("input.txt", 2));
+
+        // Scripts see the same frozen array until the arguments change
+        let stable: bool = runtime
+            .eval("Deno.args === Deno.args && Object.isFrozen(Deno.args)")
+            .expect("Could not eval");
+        assert!(stable);
+        let args: Vec<String> = runtime
+            .eval("try { Deno.args.push('x') } catch {} Deno.args")
+            .expect("Could not eval");
+        assert_eq!(args, vec!["input.txt", "-v"]);
+
+        #[cfg(feature = "node_experimental")]
+        {
+            let argv: Vec<String> = runtime
+                .eval("process.argv.slice(2)")
+                .expect("Could not eval");
+            assert_eq!(argv, vec!["input.txt", "-v"]);
+        }
     }
 
     #[test]
-    fn test_call_entrypoint() {
+    fn test_take_diagnostics() {
+        let source = "abstract class A { abstract m() {} }\nexport const ok = true;";
+
+        // Not collected by default
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = Module::new(
-            "test.js",
-            "
-            rustyscript.register_entrypoint(() => 2);
-        ",
-        );
-        let module = runtime
-            .load_modules(&module, vec![])
+        runtime
+            .load_module(&Module::new("default.ts", source))
             .expect("Could not load module");
-        let value: usize = runtime
-            .call_entrypoint(&module, json_args!())
-            .expect("Could not call registered fn");
-        assert_eq!(2, value);
+        assert!(runtime.take_diagnostics().is_empty());
 
         let mut runtime = Runtime::new(RuntimeOptions {
-            default_entrypoint: Some("load".to_string()),
+            collect_diagnostics: true,
             ..Default::default()
         })
         .expect("Could not create the runtime");
-        let module = Module::new(
-            "test.js",
-            "
-            export const load = () => 2;
-        ",
-        );
-        let module = runtime
-            .load_modules(&module, vec![])
-            .expect("Could not load module");
-        let value: usize = runtime
-            .call_entrypoint(&module, json_args!())
-            .expect("Could not call exported fn");
-        assert_eq!(2, value);
-
-        let mut runtime =
-            Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = Module::new(
-            "test.js",
-            "
-            export const load = () => 2;
-        ",
-        );
-        let module = runtime
-            .load_modules(&module, vec![])
+        let handle = runtime
+            .load_module(&Module::new("warn.ts", source))
             .expect("Could not load module");
-        runtime
-            .call_entrypoint::<Undefined>(&module, json_args!())
-            .expect_err("Did not detect no entrypoint");
-    }
+        let ok: bool = runtime
+            .get_value(Some(&handle), "ok")
+            .expect("Could not get value");
+        assert!(ok);
 
-    #[test]
-    fn test_execute_module() {
-        let module = Module::new(
-            "test.js",
-            "
-            rustyscript.register_entrypoint(() => 2);
-        ",
-        );
-        let value: usize =
-            Runtime::execute_module(&module, vec![], RuntimeOptions::default(), json_args!())
-                .expect("Could not exec module");
-        assert_eq!(2, value);
+        let diagnostics = runtime.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].specifier.as_str().ends_with("warn.ts"));
+        assert!(diagnostics[0].message.contains("Abstract method"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 20));
+        assert!(runtime.take_diagnostics().is_empty());
 
-        let module = Module::new(
-            "test.js",
-            "
-            function load() { return 2; }
-        ",
-        );
-        Runtime::execute_module::<Undefined>(
-            &module,
-            vec![],
-            RuntimeOptions::default(),
-            json_args!(),
-        )
-        .expect_err("Could not detect no entrypoint");
+        // Fatal errors still fail the load
+        runtime
+            .load_module(&Module::new("broken.ts", "const = ;"))
+            .unwrap_err();
+        runtime
+            .load_module(&Module::new("clean.ts", "export const a: number = 1;"))
+            .expect("Could not load module");
+        assert!(runtime.take_diagnostics().is_empty());
     }
 
     #[test]
-    fn call_function() {
-        let module = Module::new(
-            "test.js",
-            "
-            globalThis.fna = (i) => i;
-            export function fnb() { return 'test'; }
-            export const fnc = 2;
-            export const fne = () => {};
-        ",
-        );
-
+    fn test_with_scope() {
+        use deno_core::v8;
         let mut runtime =
             Runtime::new(RuntimeOptions::default()).expect("Could not create the runtime");
-        let module = runtime
-            .load_modules(&module, vec![])
-            .expect("Could not load module");
 
-        let result: usize = runtime
-            .call_function(Some(&module), "fna", json_args!(2))
-            .expect("Could not call global");
-        assert_eq!(2, result);
+        // Values created in the scope can be used from javascript
+        runtime.with_scope(|scope| {
+            let global = scope.get_current_context().global(scope);
+            let key = v8::String::new(scope, "array").expect("Could not create string");
+            let array = v8::Array::new(scope, 3);
+            global.set(scope, key.into(), array.into());
+        });
+        let length: usize = runtime.eval("array.length").expect("Could not eval");
+        assert_eq!(length, 3);
 
-        let result: String = runtime
-            .call_function(Some(&module), "fnb", json_args!())
-            .expect("Could not call export");
-        assert_eq!("test", result);
+        // Globals outlive the scope
+        let array = runtime.with_scope(|scope| {
+            let array: v8::Local<v8::Value> = v8::Array::new(scope, 2).into();
+            v8::Global::new(scope, array)
+        });
+        let array: Vec<Option<u32>> = crate::js_value::Value::from_v8(array)
+            .try_into(&mut runtime)
+            .expect("Could not convert value");
+        assert_eq!(array, vec![None, None]);
 
+        // And javascript state is visible in the scope
         runtime
-            .call_function::<Undefined>(Some(&module), "fnc", json_args!())
-            .expect_err("Did not detect non-function");
-        runtime
-            .call_function::<Undefined>(Some(&module), "fnd", json_args!())
-            .expect_err("Did not detect undefined");
-        runtime
-            .call_function::<Undefined>(Some(&module), "fne", json_args!())
-            .expect("Did not allow undefined return");
+            .eval::<()>("globalThis.answer = 42")
+            .expect("Could not eval");
+        let answer = runtime.with_scope(|scope| {
+            let global = scope.get_current_context().global(scope);
+            let key = v8::String::new(scope, "answer")?;
+            global.get(scope, key.into())?.int32_value(scope)
+        });
+        assert_eq!(answer, Some(42));
     }
 
     #[test]
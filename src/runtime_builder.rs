@@ -61,6 +61,22 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Set a separate time limit for evaluating a module's top-level code  
+    /// See [`RuntimeOptions::module_eval_timeout`]
+    #[must_use]
+    pub fn with_module_eval_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.0.module_eval_timeout = Some(timeout);
+        self
+    }
+
+    /// Use an existing tokio runtime to drive the event loop, instead of creating a new one
+    /// See [`RuntimeOptions::tokio_handle`]
+    #[must_use]
+    pub fn with_tokio_handle(mut self, handle: tokio::runtime::Handle) -> Self {
+        self.0.tokio_handle = Some(handle);
+        self
+    }
+
     /// Optional maximum heap size for the runtime
     #[must_use]
     pub fn with_max_heap_size(mut self, max_heap_size: usize) -> Self {
@@ -68,6 +84,238 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Set a callback to decide what happens when the heap nears its limit  
+    /// See [`RuntimeOptions::on_memory_pressure`]
+    #[must_use]
+    pub fn with_memory_pressure_callback(
+        mut self,
+        callback: impl Fn(crate::HeapStats) -> crate::MemoryPressureAction + Send + Sync + 'static,
+    ) -> Self {
+        self.0.on_memory_pressure = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    /// Pin `Date.now`, `new Date()` and `performance.now` to a fixed time, in milliseconds since the unix epoch  
+    /// See [`RuntimeOptions::deterministic_time`]
+    #[must_use]
+    pub fn with_deterministic_time(mut self, time: u64) -> Self {
+        self.0.deterministic_time = Some(time);
+        self
+    }
+
+    /// Seed `Math.random` with a deterministic generator  
+    /// See [`RuntimeOptions::deterministic_random_seed`]
+    #[must_use]
+    pub fn with_deterministic_random_seed(mut self, seed: u64) -> Self {
+        self.0.deterministic_random_seed = Some(seed);
+        self
+    }
+
+    /// Set an observer for the module loader's progress  
+    /// See [`RuntimeOptions::loader_event_sink`]
+    #[must_use]
+    pub fn with_loader_event_sink(
+        mut self,
+        sink: impl Fn(crate::module_loader::LoaderEvent, &deno_core::ModuleSpecifier)
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.0.loader_event_sink = Some(std::sync::Arc::new(sink));
+        self
+    }
+
+    /// Set a callback notified once each module has loaded, or failed to  
+    /// See [`RuntimeOptions::on_module_loaded`]
+    #[must_use]
+    pub fn with_module_loaded_callback(
+        mut self,
+        callback: impl Fn(&deno_core::ModuleSpecifier, &Result<(), Error>) + Send + Sync + 'static,
+    ) -> Self {
+        self.0.on_module_loaded = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    /// Set when queued microtasks are run  
+    /// See [`RuntimeOptions::microtask_policy`]
+    #[must_use]
+    pub fn with_microtask_policy(mut self, policy: crate::MicrotaskPolicy) -> Self {
+        self.0.microtask_policy = policy;
+        self
+    }
+
+    /// Count promises as they are created and settled  
+    /// See [`RuntimeOptions::track_pending_promises`] and [`crate::Runtime::pending_promise_count`]
+    #[must_use]
+    pub fn with_pending_promise_tracking(mut self) -> Self {
+        self.0.track_pending_promises = true;
+        self
+    }
+
+    /// Set the limits on the input accepted by `JSON.parse`  
+    /// See [`RuntimeOptions::json_limits`]
+    #[must_use]
+    pub fn with_json_limits(mut self, limits: crate::JsonLimits) -> Self {
+        self.0.json_limits = Some(limits);
+        self
+    }
+
+    /// Optional stack size, in bytes, for V8 - allows deeper recursion
+    ///
+    /// The thread running the runtime must have at least this much stack available
+    /// See [`RuntimeOptions::stack_size`]
+    #[must_use]
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.0.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Record `console.error` output and uncaught errors, so a run can be failed on them
+    /// See [`RuntimeOptions::fail_on_console_error`]
+    #[must_use]
+    pub fn with_fail_on_console_error(mut self) -> Self {
+        self.0.fail_on_console_error = true;
+        self
+    }
+
+    /// Allow [`crate::Runtime::load_module_from_url`] to load an insecure `http://` entry point
+    #[must_use]
+    pub fn with_http_entrypoint(mut self) -> Self {
+        self.0.allow_http_entrypoint = true;
+        self
+    }
+
+    /// Keep the non-fatal diagnostics the transpiler reports for loaded modules  
+    /// See [`RuntimeOptions::collect_diagnostics`] and [`crate::Runtime::take_diagnostics`]
+    #[must_use]
+    pub fn with_diagnostics(mut self) -> Self {
+        self.0.collect_diagnostics = true;
+        self
+    }
+
+    /// Prevent scripts from using dynamic `import()`, while still allowing static imports  
+    /// See [`RuntimeOptions::allow_dynamic_import`]
+    #[must_use]
+    pub fn with_dynamic_import_disabled(mut self) -> Self {
+        self.0.allow_dynamic_import = false;
+        self
+    }
+
+    /// Prevent scripts from generating code from strings, with `eval` or `new Function`  
+    /// See [`RuntimeOptions::allow_code_generation`]
+    #[must_use]
+    pub fn with_code_generation_disabled(mut self) -> Self {
+        self.0.allow_code_generation = false;
+        self
+    }
+
+    /// Add a variable to the sandboxed environment used by `Deno.env` and `process.env`  
+    /// See [`RuntimeOptions::env`]
+    #[must_use]
+    pub fn with_env_var(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.0
+            .env
+            .get_or_insert_with(std::collections::HashMap::new)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Add a function that rewrites the code of every module after it is transpiled  
+    /// Preprocessors are applied in the order they are added - see [`RuntimeOptions::module_preprocessors`]
+    #[must_use]
+    pub fn with_module_preprocessor(
+        mut self,
+        preprocessor: impl Fn(&deno_core::ModuleSpecifier, String) -> Result<String, Error>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.0
+            .module_preprocessors
+            .push(std::sync::Arc::new(preprocessor));
+        self
+    }
+
+    /// Register a parser for a custom module type, such as `toml`, used for imports with that
+    /// import attribute type or file extension - see [`RuntimeOptions::module_type_handlers`]
+    #[must_use]
+    pub fn with_module_type_handler(
+        mut self,
+        name: impl ToString,
+        handler: impl Fn(&[u8]) -> Result<deno_core::serde_json::Value, Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.0
+            .module_type_handlers
+            .insert(name.to_string(), std::sync::Arc::new(handler));
+        self
+    }
+
+    /// Register a handler that resolves and fetches modules imported with a custom URL scheme,
+    /// such as `s3` - see [`RuntimeOptions::scheme_handlers`]
+    #[must_use]
+    pub fn with_scheme_handler(
+        mut self,
+        scheme: impl ToString,
+        handler: impl crate::module_loader::SchemeHandler + 'static,
+    ) -> Self {
+        self.0
+            .scheme_handlers
+            .insert(scheme.to_string(), std::sync::Arc::new(handler));
+        self
+    }
+
+    /// Set a hook to rewrite errors before they are returned to the caller
+    /// See [`RuntimeOptions::error_transform`]
+    #[must_use]
+    pub fn with_error_transform(
+        mut self,
+        transform: impl Fn(Error) -> Error + Send + Sync + 'static,
+    ) -> Self {
+        self.0.error_transform = Some(std::sync::Arc::new(transform));
+        self
+    }
+
+    /// Set a hook to control how module specifiers are displayed in errors and logs
+    /// See [`RuntimeOptions::specifier_display`]
+    #[must_use]
+    pub fn with_specifier_display(
+        mut self,
+        display: impl Fn(&deno_core::ModuleSpecifier) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.0.specifier_display = Some(std::sync::Arc::new(display));
+        self
+    }
+
+    /// Receive console output as structured records, instead of printing it
+    /// See [`RuntimeOptions::console_handler`]
+    #[must_use]
+    pub fn with_console_handler(
+        mut self,
+        handler: impl Fn(crate::LogRecord) + Send + Sync + 'static,
+    ) -> Self {
+        self.0.console_handler = Some(std::sync::Arc::new(handler));
+        self
+    }
+
+    /// Optional provider to load source maps on demand, instead of keeping them all in memory
+    /// See [`RuntimeOptions::source_map_provider`]
+    #[must_use]
+    pub fn with_source_map_provider(
+        mut self,
+        provider: Box<dyn crate::module_loader::SourceMapProvider>,
+    ) -> Self {
+        self.0.source_map_provider = Some(provider);
+        self
+    }
+
+    /// Set what happens when a module is loaded more than once
+    /// See [`crate::module_loader::ReloadPolicy`]
+    #[must_use]
+    pub fn with_reload_policy(mut self, policy: crate::module_loader::ReloadPolicy) -> Self {
+        self.0.reload_policy = policy;
+        self
+    }
+
     /// Optional import provider for the module loader
     #[must_use]
     pub fn with_import_provider(mut self, import_provider: Box<dyn ImportProvider>) -> Self {
@@ -75,6 +323,14 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Apply `tsconfig.json` path mapping when resolving bare specifiers  
+    /// See [`crate::module_loader::TsConfigPaths`]
+    #[must_use]
+    pub fn with_tsconfig_paths(mut self, paths: crate::module_loader::TsConfigPaths) -> Self {
+        self.0.tsconfig_paths = Some(paths);
+        self
+    }
+
     /// Set the startup snapshot for the runtime
     ///
     /// This will reduce load times, but requires the same extensions to be loaded as when the snapshot was created
@@ -122,6 +378,33 @@ impl RuntimeBuilder {
     // Extension options
     //
 
+    /// Sort object keys when serializing values into JS, and when producing JSON
+    ///
+    /// See [`RuntimeOptions::sorted_keys`]
+    #[must_use]
+    pub fn with_sorted_keys(mut self) -> Self {
+        self.0.sorted_keys = true;
+        self
+    }
+
+    /// Load only the core `rustyscript` extension, and none of the built-in extensions
+    ///
+    /// See [`RuntimeOptions::minimal`]
+    #[must_use]
+    pub fn with_core_only(mut self) -> Self {
+        self.0.core_only = true;
+        self
+    }
+
+    /// Remove a built-in API from the runtime, such as `fetch` or `WebSocket`
+    ///
+    /// See [`crate::BuiltinExtension`]
+    #[must_use]
+    pub fn with_disabled_extension(mut self, extension: crate::BuiltinExtension) -> Self {
+        self.0.extension_options.disabled.insert(extension);
+        self
+    }
+
     /// Set the initial seed for the crypto extension
     #[cfg(feature = "crypto")]
     #[cfg_attr(docsrs, doc(cfg(feature = "crypto")))]
@@ -140,6 +423,33 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Set a reader to use as `Deno.stdin`, instead of the process stdin
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    #[must_use]
+    pub fn with_stdin(mut self, reader: impl std::io::Read + Send + 'static) -> Self {
+        self.0.extension_options.stdin = Some(Box::new(reader));
+        self
+    }
+
+    /// Set a writer to receive `Deno.stdout`, instead of the process stdout
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    #[must_use]
+    pub fn with_stdout(mut self, writer: impl std::io::Write + Send + 'static) -> Self {
+        self.0.extension_options.stdout = Some(Box::new(writer));
+        self
+    }
+
+    /// Set a writer to receive `Deno.stderr`, instead of the process stderr
+    #[cfg(feature = "io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "io")))]
+    #[must_use]
+    pub fn with_stderr(mut self, writer: impl std::io::Write + Send + 'static) -> Self {
+        self.0.extension_options.stderr = Some(Box::new(writer));
+        self
+    }
+
     /// Set the options for the webstorage extension
     #[cfg(feature = "webstorage")]
     #[cfg_attr(docsrs, doc(cfg(feature = "webstorage")))]
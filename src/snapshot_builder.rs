@@ -92,7 +92,10 @@ impl SnapshotBuilder {
     /// Or if the deno runtime initialization fails (usually issues with extensions)
     ///
     pub fn new(options: RuntimeOptions) -> Result<Self, Error> {
-        let tokio = AsyncBridge::new(options.timeout)?;
+        let tokio = AsyncBridge::with_optional_runtime_handle(
+            options.timeout,
+            options.tokio_handle.clone(),
+        )?;
         let inner = InnerRuntime::new(options, tokio.heap_exhausted_token())?;
         Ok(Self { inner, tokio })
     }
@@ -0,0 +1,278 @@
+//! Strings created ahead of time and reused as function arguments, see [`crate::Runtime::intern_string`]
+use std::collections::HashMap;
+
+use deno_core::{serde_v8::GlobalValue, v8};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::Error;
+
+/// Prefix of the struct names `serde_v8` uses to pass its magic types through serde
+/// Their fields carry raw pointers, so they must reach `serde_v8` untouched
+const MAGIC_PREFIX: &str = "$__v8_magic_";
+
+/// Internalized strings, reused by function arguments equal to one of them
+#[derive(Default)]
+pub(crate) struct StringCache(HashMap<String, GlobalValue>);
+
+impl StringCache {
+    /// Creates an internalized copy of `value`, to be reused by later calls
+    pub fn insert(&mut self, scope: &mut v8::PinScope<'_, '_>, value: &str) -> Result<(), Error> {
+        if self.0.contains_key(value) {
+            return Ok(());
+        }
+
+        let string =
+            v8::String::new_from_utf8(scope, value.as_bytes(), v8::NewStringType::Internalized)
+                .ok_or_else(|| Error::V8Encoding(value.to_string()))?;
+        let string: v8::Local<v8::Value> = string.into();
+        let isolate: &v8::Isolate = scope;
+        self.0
+            .insert(value.to_string(), v8::Global::new(isolate, string).into());
+        Ok(())
+    }
+
+    /// Removes every string from the cache
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The number of strings in the cache
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Wraps `value` so that any string in it matching a cached one, other than map keys,
+    /// serializes to the cached string instead of a new one
+    pub fn wrap<'c, T: ?Sized + Serialize>(&'c self, value: &'c T) -> Interned<'c, T> {
+        Interned { value, cache: self }
+    }
+}
+
+/// A value serialized through the cache, see `StringCache::wrap`
+pub(crate) struct Interned<'c, T: ?Sized> {
+    value: &'c T,
+    cache: &'c StringCache,
+}
+
+impl<T: ?Sized + Serialize> Serialize for Interned<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(InternSerializer {
+            inner: serializer,
+            cache: self.cache,
+        })
+    }
+}
+
+/// Forwards to another serializer, replacing cached strings
+struct InternSerializer<'c, S> {
+    inner: S,
+    cache: &'c StringCache,
+}
+
+/// Forwards the parts of a compound value, through the cache unless it is `None`
+struct Compound<'c, S> {
+    inner: S,
+    cache: Option<&'c StringCache>,
+}
+
+impl<'c, S> Compound<'c, S> {
+    fn new(inner: S, cache: &'c StringCache) -> Self {
+        Self {
+            inner,
+            cache: Some(cache),
+        }
+    }
+}
+
+/// Implements a compound serializer trait for `Compound`, wrapping each value it is given
+macro_rules! compound {
+    ($($trait:ident::$method:ident($($key:ident: $key_ty:ty),*);)*) => {
+        $(
+            impl<S: ser::$trait> ser::$trait for Compound<'_, S> {
+                type Ok = S::Ok;
+                type Error = S::Error;
+
+                fn $method<T: ?Sized + Serialize>(
+                    &mut self,
+                    $($key: $key_ty,)*
+                    value: &T,
+                ) -> Result<(), Self::Error> {
+                    match self.cache {
+                        Some(cache) => self.inner.$method($($key,)* &cache.wrap(value)),
+                        None => self.inner.$method($($key,)* value),
+                    }
+                }
+
+                fn end(self) -> Result<Self::Ok, Self::Error> {
+                    self.inner.end()
+                }
+            }
+        )*
+    };
+}
+
+compound! {
+    SerializeSeq::serialize_element();
+    SerializeTuple::serialize_element();
+    SerializeTupleStruct::serialize_field();
+    SerializeTupleVariant::serialize_field();
+    SerializeStruct::serialize_field(key: &'static str);
+    SerializeStructVariant::serialize_field(key: &'static str);
+}
+
+// Map keys are left as they are, since `serde_v8` needs to read them back as strings
+impl<S: ser::SerializeMap> ser::SerializeMap for Compound<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        match self.cache {
+            Some(cache) => self.inner.serialize_value(&cache.wrap(value)),
+            None => self.inner.serialize_value(value),
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+macro_rules! forward {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<Self::Ok, Self::Error> {
+                self.inner.$method($($arg),*)
+            }
+        )*
+    };
+}
+
+impl<'c, S: Serializer> Serializer for InternSerializer<'c, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'c, S::SerializeSeq>;
+    type SerializeTuple = Compound<'c, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'c, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'c, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'c, S::SerializeMap>;
+    type SerializeStruct = Compound<'c, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'c, S::SerializeStructVariant>;
+
+    forward! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_i128(v: i128);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_u128(v: u128);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        match self.cache.0.get(v) {
+            Some(string) => string.serialize(self.inner),
+            None => self.inner.serialize_str(v),
+        }
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_some(&self.cache.wrap(value))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner
+            .serialize_newtype_struct(name, &self.cache.wrap(value))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner
+            .serialize_newtype_variant(name, index, variant, &self.cache.wrap(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(Compound::new(self.inner.serialize_seq(len)?, self.cache))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(Compound::new(self.inner.serialize_tuple(len)?, self.cache))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Compound::new(inner, self.cache))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.cache))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(Compound::new(self.inner.serialize_map(len)?, self.cache))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let inner = self.inner.serialize_struct(name, len)?;
+        let cache = (!name.starts_with(MAGIC_PREFIX)).then_some(self.cache);
+        Ok(Compound { inner, cache })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.cache))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
@@ -5,13 +5,57 @@
 //! It will only transpile, not typecheck (like Deno's `--no-check` flag).
 use std::{borrow::Cow, rc::Rc};
 
-use deno_ast::{MediaType, ParseDiagnosticsError, ParseParams, SourceTextInfo, TranspileError};
+use deno_ast::{
+    diagnostics::Diagnostic as _,
+    swc::ast::{ModuleDecl, ModuleItem},
+    MediaType, ParseDiagnostic, ParseDiagnosticsError, ParseParams, ProgramRef, SourceTextInfo,
+    TranspileError,
+};
 use deno_core::{FastString, ModuleSpecifier, SourceMapData};
 use deno_error::JsErrorBox;
 
 /// Contains the results of transpilation
 pub type ModuleContents = (String, Option<SourceMapData>);
 
+/// A non-fatal problem reported by the transpiler for a module that still loaded successfully,
+/// such as an abstract method with a body - see [`crate::Runtime::take_diagnostics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The module the problem was found in
+    pub specifier: ModuleSpecifier,
+
+    /// A description of the problem
+    pub message: String,
+
+    /// The 1-indexed line the problem starts on
+    pub line: usize,
+
+    /// The 1-indexed column the problem starts at
+    pub column: usize,
+}
+
+impl From<&ParseDiagnostic> for Diagnostic {
+    fn from(diagnostic: &ParseDiagnostic) -> Self {
+        let position = diagnostic.display_position();
+        Self {
+            specifier: diagnostic.specifier().clone(),
+            message: diagnostic.message().to_string(),
+            line: position.line_number,
+            column: position.column_number,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}:{}:{}",
+            self.message, self.specifier, self.line, self.column
+        )
+    }
+}
+
 fn should_transpile(media_type: MediaType) -> bool {
     matches!(
         media_type,
@@ -26,18 +70,77 @@ fn should_transpile(media_type: MediaType) -> bool {
     )
 }
 
+/// Determine the media type of a module from its specifier
+fn media_type(module_specifier: &ModuleSpecifier) -> MediaType {
+    let media_type = MediaType::from_specifier(module_specifier);
+    if media_type == MediaType::Unknown && module_specifier.as_str().contains("/node:") {
+        MediaType::TypeScript
+    } else {
+        media_type
+    }
+}
+
+/// Blanks out a leading `#!` line, so that scripts written for the command line can be loaded as modules  
+/// The line break is kept, so line numbers in errors and source maps still match the original source
+fn strip_shebang(code: &str) -> Cow<'_, str> {
+    if !code.starts_with("#!") {
+        return Cow::Borrowed(code);
+    }
+
+    match code.find('\n') {
+        Some(end) => Cow::Owned(code[end..].to_string()),
+        None => Cow::Borrowed(""),
+    }
+}
+
+///
+/// Parses source code without transpiling or executing it  
+/// Returns the first syntax error found, if any
+///
+/// Unlike `transpile`, this also parses plain JS
+pub fn validate(module_specifier: &ModuleSpecifier, code: &str) -> Result<(), ParseDiagnostic> {
+    let media_type = match media_type(module_specifier) {
+        MediaType::Json | MediaType::Wasm | MediaType::Css => return Ok(()),
+        MediaType::Unknown => MediaType::JavaScript,
+        media_type => media_type,
+    };
+
+    let parsed = deno_ast::parse_module(ParseParams {
+        specifier: module_specifier.clone(),
+        text: strip_shebang(code).into(),
+        media_type,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })?;
+
+    match parsed.diagnostics().first() {
+        Some(diagnostic) => Err(diagnostic.clone()),
+        None => Ok(()),
+    }
+}
+
 ///
 /// Transpiles source code from TS to JS without typechecking
 pub fn transpile(
     module_specifier: &ModuleSpecifier,
     code: &str,
 ) -> Result<ModuleContents, TranspileError> {
-    let mut media_type = MediaType::from_specifier(module_specifier);
-
-    if media_type == MediaType::Unknown && module_specifier.as_str().contains("/node:") {
-        media_type = MediaType::TypeScript;
-    }
+    transpile_with_diagnostics(module_specifier, code).map(|(contents, _)| contents)
+}
 
+///
+/// Transpiles source code from TS to JS without typechecking  
+/// Also returns the non-fatal diagnostics found while parsing, which are otherwise discarded
+///
+/// Plain JS is not parsed, so it never has diagnostics
+pub fn transpile_with_diagnostics(
+    module_specifier: &ModuleSpecifier,
+    code: &str,
+) -> Result<(ModuleContents, Vec<Diagnostic>), TranspileError> {
+    let code = strip_shebang(code);
+    let mut diagnostics = Vec::new();
+    let media_type = media_type(module_specifier);
     let should_transpile = should_transpile(media_type);
 
     let code = if should_transpile {
@@ -52,6 +155,7 @@ pub fn transpile(
             maybe_syntax: None,
         })
         .map_err(|e| TranspileError::ParseErrors(ParseDiagnosticsError(vec![e])))?;
+        diagnostics.extend(parsed.diagnostics().iter().map(Diagnostic::from));
 
         let transpile_options = deno_ast::TranspileOptions {
             ..Default::default()
@@ -80,7 +184,42 @@ pub fn transpile(
         (code.to_string(), None)
     };
 
-    Ok(code)
+    Ok((code, diagnostics))
+}
+
+///
+/// Lists the specifiers of the static imports and re-exports of an already transpiled module  
+/// Code that does not parse is treated as having no imports
+pub fn static_imports(module_specifier: &ModuleSpecifier, code: &str) -> Vec<String> {
+    let Ok(parsed) = deno_ast::parse_module(ParseParams {
+        specifier: module_specifier.clone(),
+        text: code.into(),
+        media_type: MediaType::JavaScript,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    }) else {
+        return vec![];
+    };
+    let ProgramRef::Module(module) = parsed.program_ref() else {
+        return vec![];
+    };
+
+    module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) if !decl.type_only => Some(&decl.src),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(decl)) if !decl.type_only => {
+                Some(&decl.src)
+            }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(decl)) if !decl.type_only => {
+                decl.src.as_ref()
+            }
+            _ => None,
+        })
+        .map(|src| src.value.to_atom_lossy().to_string())
+        .collect()
 }
 
 ///
@@ -114,12 +114,110 @@ pub fn resolve_path(
     Ok(url)
 }
 
+/// Schemes of specifiers recognized without a `//` authority, such as `ext:core/mod.js`
+const OPAQUE_SPECIFIER_SCHEMES: [&str; 4] = ["ext", "node", "npm", "jsr"];
+
+/// Replaces each module specifier found in `text` with `display(specifier)`, see `RuntimeOptions::specifier_display`
+///
+/// A trailing `:line:column` position, and surrounding punctuation, are kept as-is
+pub(crate) fn display_specifiers(
+    text: &str,
+    display: &dyn Fn(&ModuleSpecifier) -> String,
+) -> String {
+    display_specifiers_where(text, display, &|_| true)
+}
+
+/// Like [`display_specifiers`], but only replaces the specifiers accepted by `filter`, leaving the rest untouched
+pub(crate) fn display_specifiers_where(
+    text: &str,
+    display: &dyn Fn(&ModuleSpecifier) -> String,
+    filter: &dyn Fn(&ModuleSpecifier) -> bool,
+) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = next_specifier(rest) {
+        output.push_str(&rest[..start]);
+        match ModuleSpecifier::parse(&rest[start..end]) {
+            Ok(specifier) if filter(&specifier) => output.push_str(&display(&specifier)),
+            _ => output.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Finds the byte range of the next specifier in `text`, without its position or trailing punctuation
+fn next_specifier(text: &str) -> Option<(usize, usize)> {
+    let is_scheme_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.');
+    let is_delimiter = |c: char| {
+        c.is_whitespace()
+            || matches!(
+                c,
+                '"' | '\'' | '`' | '(' | ')' | '<' | '>' | ',' | '[' | ']'
+            )
+    };
+
+    let mut search = 0;
+    while let Some(colon) = text[search..].find(':').map(|i| i + search) {
+        search = colon + 1;
+        let start = text[..colon]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_scheme_char(*c))
+            .last()
+            .map_or(colon, |(i, _)| i);
+        let scheme = &text[start..colon];
+        let after = &text[colon + 1..];
+
+        let is_specifier = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && (after.starts_with("//")
+                || (OPAQUE_SPECIFIER_SCHEMES.contains(&scheme)
+                    && after.starts_with(|c: char| !is_delimiter(c))));
+        if !is_specifier {
+            continue;
+        }
+
+        let len = after.find(is_delimiter).unwrap_or(after.len());
+        let token = text[start..colon + 1 + len].trim_end_matches(['.', ':', ';']);
+        let token = strip_position(token, scheme.len());
+        return Some((start, start + token.len()));
+    }
+    None
+}
+
+/// Removes up to two trailing `:number` groups, as long as something is left of the specifier's path
+fn strip_position(token: &str, scheme_len: usize) -> &str {
+    let mut token = token;
+    for _ in 0..2 {
+        let Some((head, digits)) = token.rsplit_once(':') else {
+            break;
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+
+        // `http://localhost:8080` is a port, not a position
+        let path = head.get(scheme_len + 1..).unwrap_or_default();
+        let has_path = match path.strip_prefix("//") {
+            Some(authority) => authority.contains('/'),
+            None => !path.is_empty(),
+        };
+        if !has_path {
+            break;
+        }
+        token = head;
+    }
+    token
+}
+
 /// Explicitly initialize the V8 platform  
 /// Note that all runtimes must have a common parent thread that initalized the V8 platform
 ///
 /// This is done automatically the first time [`Runtime::new`] is called,
 /// but for multi-threaded applications, it may be necessary to call this function manually
 pub fn init_platform(thread_pool_size: u32, idle_task_support: bool) {
+    crate::inner_runtime::lock_stack_size();
     let platform = deno_core::v8::Platform::new(thread_pool_size, idle_task_support);
     deno_core::JsRuntime::init_platform(Some(platform.into()), true);
 }
@@ -136,6 +234,12 @@ mod runtime_macros {
     ///
     /// You can also just pass a &tuple directly, or an &array, or even a single value
     ///
+    /// Arguments are converted directly into javascript values with `serde_v8`, not through JSON:
+    /// - `NaN` and infinite floats arrive as `NaN` and `Infinity`, where JSON turned them into `null`
+    /// - Integers outside of the safe integer range arrive as a `BigInt`
+    /// - Map keys must serialize to strings - integer keys, which JSON turned into strings, are an error
+    /// - Objects are created without a prototype, unless [`crate::RuntimeOptions::sorted_keys`] is set
+    ///
     /// # Example
     /// ```rust
     /// use rustyscript::{ Runtime, RuntimeOptions, Module, json_args };
@@ -270,6 +374,44 @@ mod test_runtime {
     use super::*;
     use deno_core::{futures::FutureExt, serde_json};
 
+    #[test]
+    fn test_display_specifiers() {
+        let display = |specifier: &ModuleSpecifier| match specifier.scheme() {
+            "file" => specifier.path().trim_start_matches("/app/").to_string(),
+            _ => format!("<{specifier}>"),
+        };
+
+        assert_eq!(
+            display_specifiers(
+                "Error: boom\n    at f (file:///app/src/foo.ts:3:5)\n    at ext:core/mod.js:10:2",
+                &display
+            ),
+            "Error: boom\n    at f (src/foo.ts:3:5)\n    at <ext:core/mod.js>:10:2"
+        );
+        assert_eq!(
+            display_specifiers(
+                "Module not found \"file:///app/a.js\". Imported from http://localhost:8080.",
+                &display
+            ),
+            "Module not found \"a.js\". Imported from <http://localhost:8080/>."
+        );
+        assert_eq!(
+            display_specifiers("TypeError: C:\\path is not node: a module", &display),
+            "TypeError: C:\\path is not node: a module"
+        );
+
+        // Specifiers that are not accepted are left exactly as written
+        let filter = |specifier: &ModuleSpecifier| specifier.scheme() == "file";
+        assert_eq!(
+            display_specifiers_where(
+                "see http://host:8080 at file:///app/a.js:1:2",
+                &display,
+                &filter
+            ),
+            "see http://host:8080 at a.js:1:2"
+        );
+    }
+
     #[test]
     fn test_callback() {
         let add = sync_callback!(|a: i64, b: i64| { Ok::<i64, Error>(a + b) });
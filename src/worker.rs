@@ -18,7 +18,11 @@
 use std::{
     cell::RefCell,
     rc::Rc,
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
     thread::{spawn, JoinHandle},
 };
 
@@ -116,6 +120,7 @@ where
     where
         T: serde::de::DeserializeOwned + Send + 'static,
     {
+        crate::inner_runtime::lock_stack_size();
         deno_core::JsRuntime::init_platform(None, true);
         std::thread::spawn(move || {
             let mut runtime = crate::Runtime::new(RuntimeOptions::default())?;
@@ -682,3 +687,577 @@ pub enum DefaultWorkerResponse {
     /// An error response
     Error(Error),
 }
+
+/// A job queued on a [`RuntimeActor`], run on the actor's thread
+type ActorJob = Box<dyn FnOnce(&mut ActorState) + Send>;
+
+/// The runtime owned by a [`RuntimeActor`], along with the modules loaded into it
+struct ActorState {
+    runtime: crate::Runtime,
+    modules: std::collections::HashMap<deno_core::ModuleId, crate::ModuleHandle>,
+}
+
+impl ActorState {
+    /// Call a function in a loaded module, or in the global scope if `id` is `None`
+    fn call_function<T>(
+        &mut self,
+        id: Option<deno_core::ModuleId>,
+        name: &str,
+        args: &[crate::serde_json::Value],
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let handle = match id {
+            Some(id) => Some(
+                self.modules
+                    .get(&id)
+                    .ok_or_else(|| Error::Runtime("Module not found".to_string()))?,
+            ),
+            None => None,
+        };
+        self.runtime.call_function(handle, name, &args)
+    }
+}
+
+/// A runtime running on a dedicated thread, controlled through message passing
+///
+/// Unlike [`Worker`], the actor is `Send + Clone` and can be shared between tasks and threads
+/// Requests from every handle are queued, and executed in order on the runtime's thread
+/// Results are delivered back over a oneshot channel, so they can be awaited from any async executor
+///
+/// The thread stops once every handle to the actor has been dropped, after finishing any queued requests
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{Error, Module, RuntimeOptions, worker::RuntimeActor};
+///
+/// # async fn run() -> Result<(), Error> {
+/// let actor = RuntimeActor::new(RuntimeOptions::default)?;
+///
+/// let module = Module::new("test.js", "export const add = (a, b) => a + b;");
+/// let id = actor.load_module(module).await?;
+///
+/// let result: i32 = actor.call_function(Some(id), "add", vec![1.into(), 2.into()]).await?;
+/// assert_eq!(result, 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RuntimeActor {
+    tx: tokio::sync::mpsc::UnboundedSender<ActorJob>,
+}
+
+impl RuntimeActor {
+    /// Start a new actor, creating its runtime on the new thread from the options returned by `options`
+    ///
+    /// The options are built on the runtime's thread, since `RuntimeOptions` is not `Send`
+    /// Blocks until the runtime has been initialized
+    ///
+    /// # Errors
+    /// Can fail if the runtime cannot be initialized (usually due to extension issues)
+    pub fn new<F>(options: F) -> Result<Self, Error>
+    where
+        F: FnOnce() -> RuntimeOptions + Send + 'static,
+    {
+        crate::init_platform(1, true);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ActorJob>();
+        let (init_tx, init_rx) = channel::<Result<(), Error>>();
+
+        spawn(move || {
+            let runtime = match crate::Runtime::new(options()) {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    init_tx.send(Err(e)).ok(); // Stopping anyway, so no need to check for errors
+                    return;
+                }
+            };
+
+            if init_tx.send(Ok(())).is_err() {
+                return;
+            }
+
+            let mut state = ActorState {
+                runtime,
+                modules: std::collections::HashMap::new(),
+            };
+            while let Some(job) = rx.blocking_recv() {
+                job(&mut state);
+            }
+        });
+
+        match init_rx.recv() {
+            Ok(Ok(())) => Ok(Self { tx }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(Error::Runtime("Could not start runtime thread".to_string())),
+        }
+    }
+
+    /// Queue a job on the actor's thread, and wait for its result
+    async fn send<T, F>(&self, job: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut ActorState) -> Result<T, Error> + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.tx
+            .send(Box::new(move |state| {
+                tx.send(job(state)).ok(); // The caller may have stopped waiting
+            }))
+            .map_err(|_| Error::WorkerHasStopped)?;
+
+        // The job is dropped without running if the thread panicked
+        rx.await.map_err(|_| Error::WorkerHasStopped)?
+    }
+
+    /// Run a closure with exclusive access to the actor's runtime, on the runtime's thread
+    /// Use this for anything not covered by the other methods
+    ///
+    /// # Errors
+    /// Will return an error if the actor has stopped, or if the closure returns an error
+    pub async fn with_runtime<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut crate::Runtime) -> Result<T, Error> + Send + 'static,
+    {
+        self.send(move |state| f(&mut state.runtime)).await
+    }
+
+    /// Evaluate a string of javascript code
+    /// Returns the result of the evaluation
+    ///
+    /// # Errors
+    /// Can fail if the actor has stopped, if a runtime error occurs during evaluation,
+    /// or if the return value cannot be deserialized into the requested type
+    pub async fn eval<T>(&self, code: impl ToString) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let code = code.to_string();
+        self.send(move |state| state.runtime.eval(&code)).await
+    }
+
+    /// Load a module into the actor's runtime as a side module
+    /// Returns the module id of the loaded module, which can be used to call its functions
+    ///
+    /// # Errors
+    /// Can fail if the actor has stopped, or if the module cannot be loaded
+    pub async fn load_module(&self, module: crate::Module) -> Result<deno_core::ModuleId, Error> {
+        self.send(move |state| {
+            let handle = state.runtime.load_module(&module)?;
+            let id = handle.id();
+            state.modules.insert(id, handle);
+            Ok(id)
+        })
+        .await
+    }
+
+    /// Call a function in a module, or in the global scope if `module_context` is `None`
+    /// Returns the result of the function call
+    ///
+    /// # Errors
+    /// Can fail if the actor has stopped, if the module or function cannot be found,
+    /// if the function throws, or if the result cannot be deserialized into the requested type
+    pub async fn call_function<T>(
+        &self,
+        module_context: Option<deno_core::ModuleId>,
+        name: impl ToString,
+        args: Vec<crate::serde_json::Value>,
+    ) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let name = name.to_string();
+        self.send(move |state| state.call_function(module_context, &name, &args))
+            .await
+    }
+}
+
+/// Defines the messaging globals of a [`ModuleWorker`], as in a web worker
+const MODULE_WORKER_SCRIPT: &str = "(() => {
+    globalThis.postMessage = (message) => rustyscript.functions.__rustyscript_worker_post(message);
+    Object.defineProperty(globalThis, '__rustyscript_worker_dispatch', {
+        value: (data) => globalThis.onmessage?.({ data }),
+    });
+})();";
+
+/// A module running on a dedicated thread, with its own isolate, that exchanges messages with the host
+///
+/// Works much like a web worker, which keeps CPU-bound scripts from blocking the host's own runtime:
+/// - The module calls `postMessage(value)` to send a message to the host, received with [`ModuleWorker::receive`]
+/// - Messages sent with [`ModuleWorker::post_message`] are passed to `onmessage({ data })`, if the module defines it
+///
+/// Messages are JSON values, and the worker's event loop keeps running between them  
+/// If the module fails to load, or a handler throws, the error is received in place of a message  
+/// A module that fails to load stops the worker
+///
+/// [`ModuleWorker::terminate`] interrupts any running script and stops the thread, as does dropping the worker
+///
+/// # Example
+/// ```no_run
+/// use rustyscript::{Error, Module, RuntimeOptions, worker::ModuleWorker};
+///
+/// # fn main() -> Result<(), Error> {
+/// let module = Module::new("plugin.js", "
+///     globalThis.onmessage = ({ data }) => postMessage(data.reduce((a, b) => a + b));
+/// ");
+/// let mut worker = ModuleWorker::new(RuntimeOptions::default, module)?;
+///
+/// worker.post_message(&[1, 2, 3])?;
+/// let sum: i32 = worker.receive()?;
+/// assert_eq!(sum, 6);
+///
+/// worker.terminate();
+/// # Ok(())
+/// # }
+/// ```
+pub struct ModuleWorker {
+    handle: Option<JoinHandle<()>>,
+    tx: Option<tokio::sync::mpsc::UnboundedSender<crate::serde_json::Value>>,
+    rx: Receiver<Result<crate::serde_json::Value, Error>>,
+    interrupt: crate::InterruptHandle,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ModuleWorker {
+    /// Start a worker running `module`, creating its runtime on the new thread from the options returned by `options`
+    ///
+    /// The options are built on the worker's thread, since `RuntimeOptions` is not `Send`  
+    /// Blocks until the runtime has been initialized, but not until the module has loaded
+    ///
+    /// # Errors
+    /// Can fail if the runtime cannot be initialized (usually due to extension issues)
+    pub fn new<F>(options: F, module: crate::Module) -> Result<Self, Error>
+    where
+        F: FnOnce() -> RuntimeOptions + Send + 'static,
+    {
+        crate::init_platform(1, true);
+        let (qtx, qrx) = tokio::sync::mpsc::unbounded_channel();
+        let (rtx, rrx) = channel();
+        let (init_tx, init_rx) = channel::<Result<crate::InterruptHandle, Error>>();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+
+        let handle = spawn(move || {
+            let runtime = crate::Runtime::new(options()).and_then(|mut runtime| {
+                let post_tx = rtx.clone();
+                runtime.register_function("__rustyscript_worker_post", move |args| {
+                    let message = args.first().cloned().unwrap_or_default();
+                    post_tx.send(Ok(message)).ok(); // The host may have stopped listening
+                    Ok(crate::serde_json::Value::Null)
+                })?;
+                runtime.eval::<crate::Undefined>(MODULE_WORKER_SCRIPT)?;
+                Ok(runtime)
+            });
+
+            match runtime {
+                Ok(runtime) => {
+                    if init_tx.send(Ok(runtime.interrupt_handle())).is_ok() {
+                        Self::thread(runtime, &module, qrx, &rtx, &thread_stopped);
+                    }
+                }
+                Err(e) => {
+                    init_tx.send(Err(e)).ok(); // Stopping anyway, so no need to check for errors
+                }
+            }
+        });
+
+        match init_rx.recv() {
+            Ok(Ok(interrupt)) => Ok(Self {
+                handle: Some(handle),
+                tx: Some(qtx),
+                rx: rrx,
+                interrupt,
+                stopped,
+            }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(Error::Runtime("Could not start runtime thread".to_string())),
+        }
+    }
+
+    /// Loads the module, then delivers messages while running the event loop, until the host stops the worker
+    fn thread(
+        mut runtime: crate::Runtime,
+        module: &crate::Module,
+        mut rx: tokio::sync::mpsc::UnboundedReceiver<crate::serde_json::Value>,
+        tx: &Sender<Result<crate::serde_json::Value, Error>>,
+        stopped: &AtomicBool,
+    ) {
+        let tokio = runtime.tokio_runtime();
+        tokio.block_on(async {
+            if let Err(e) = runtime.load_module_async(module).await {
+                tx.send(Err(e)).ok();
+                return;
+            }
+
+            let mut idle = false;
+            loop {
+                let message = if idle {
+                    rx.recv().await
+                } else {
+                    tokio::select! {
+                        message = rx.recv() => message,
+                        result = runtime.poll_event_loop_future(deno_core::PollEventLoopOptions::default()) => {
+                            idle = true;
+                            if let Err(e) = result {
+                                tx.send(Err(e)).ok();
+                            }
+                            continue;
+                        }
+                    }
+                };
+
+                // The host has dropped its sender, or is terminating the worker with messages still queued
+                let Some(message) = message else {
+                    break;
+                };
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                idle = false;
+                let result = runtime
+                    .call_function_async::<crate::Undefined>(
+                        None,
+                        "__rustyscript_worker_dispatch",
+                        &[message],
+                    )
+                    .await;
+                if let Err(e) = result {
+                    let recoverable = e.is_recoverable();
+                    tx.send(Err(e)).ok();
+                    if !recoverable {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Send a message to the worker, passed to its `onmessage` handler
+    /// This will not block the current thread
+    ///
+    /// # Errors
+    /// Will return an error if the message cannot be serialized, or if the worker has stopped
+    pub fn post_message(&self, message: impl serde::Serialize) -> Result<(), Error> {
+        let message = crate::serde_json::to_value(message)?;
+        self.tx
+            .as_ref()
+            .ok_or(Error::WorkerHasStopped)?
+            .send(message)
+            .map_err(|_| Error::WorkerHasStopped)
+    }
+
+    /// Receive the next message posted by the worker
+    /// This will block the current thread until a message is received
+    ///
+    /// # Errors
+    /// Will return the worker's error if the module failed to load or a handler threw,
+    /// an error if the message cannot be deserialized into the requested type,
+    /// or [`Error::WorkerHasStopped`] if the worker has stopped and no messages remain
+    pub fn receive<T>(&self) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let message = self.rx.recv().map_err(|_| Error::WorkerHasStopped)??;
+        Ok(crate::serde_json::from_value(message)?)
+    }
+
+    /// Try to receive a message posted by the worker without blocking
+    /// This will return `Ok(None)` if no message is available
+    ///
+    /// # Errors
+    /// Will return the worker's error if the module failed to load or a handler threw,
+    /// an error if the message cannot be deserialized into the requested type,
+    /// or [`Error::WorkerHasStopped`] if the worker has stopped and no messages remain
+    pub fn try_receive<T>(&self) -> Result<Option<T>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self.rx.try_recv() {
+            Ok(message) => Ok(Some(crate::serde_json::from_value(message?)?)),
+            Err(std::sync::mpsc::TryRecvError::Empty) => Ok(None),
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err(Error::WorkerHasStopped),
+        }
+    }
+
+    /// Returns true if the worker's thread is still running
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Stop the worker, interrupting any script it is running, and wait for its thread to finish
+    /// Messages it posted before stopping can still be received
+    pub fn terminate(&mut self) {
+        if let (Some(tx), Some(handle)) = (self.tx.take(), self.handle.take()) {
+            // The flag stops queued messages from being dispatched, and closing the channel stops the worker once idle
+            self.stopped.store(true, Ordering::SeqCst);
+            drop(tx);
+
+            // The runtime clears an interrupt once it has handled it, so keep interrupting
+            // until the thread exits, in case a script started after the first one
+            while !handle.is_finished() {
+                self.interrupt.terminate();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            handle.join().ok();
+        }
+    }
+}
+
+impl Drop for ModuleWorker {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Module;
+
+    #[test]
+    fn test_runtime_actor() {
+        let actor = RuntimeActor::new(RuntimeOptions::default).expect("Could not start the actor");
+        let tokio = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Could not build the runtime");
+
+        tokio.block_on(async {
+            let module = Module::new(
+                "test.js",
+                "
+                let count = 0;
+                export const increment = (n) => count += n;
+            ",
+            );
+            let id = actor
+                .load_module(module)
+                .await
+                .expect("Could not load module");
+
+            // Requests from every handle run in order on the same runtime
+            let handles: Vec<_> = (1..=4).map(|_| actor.clone()).collect();
+            let calls = handles
+                .iter()
+                .map(|actor| actor.call_function::<i64>(Some(id), "increment", vec![1.into()]));
+            let mut results = Vec::new();
+            for call in calls {
+                results.push(call.await.expect("Could not call function"));
+            }
+            assert_eq!(results, vec![1, 2, 3, 4]);
+
+            let sum: i64 = actor
+                .eval("[1, 2, 3].reduce((a, b) => a + b)")
+                .await
+                .expect("Could not eval");
+            assert_eq!(sum, 6);
+
+            let result: i64 = actor
+                .with_runtime(|runtime| runtime.eval("40 + 2"))
+                .await
+                .expect("Could not reach the runtime");
+            assert_eq!(result, 42);
+
+            actor
+                .call_function::<i64>(Some(id), "missing", vec![])
+                .await
+                .unwrap_err();
+            actor
+                .eval::<i64>("throw new Error('nope')")
+                .await
+                .unwrap_err();
+        });
+
+        // Runs on another thread, sharing the actor
+        let thread_actor = actor.clone();
+        let result: i64 = spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .expect("Could not build the tokio runtime")
+                .block_on(thread_actor.eval("2 * 21"))
+                .expect("Could not eval")
+        })
+        .join()
+        .expect("Thread panicked");
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    fn test_module_worker() {
+        let module = Module::new(
+            "test.js",
+            "
+            postMessage('ready');
+            globalThis.onmessage = ({ data }) => {
+                if (data === 'spin') while (true) {}
+                if (data === 'throw') throw new Error('bad message');
+                setTimeout(() => postMessage(data * 2), 10);
+            };
+        ",
+        );
+        let mut worker =
+            ModuleWorker::new(RuntimeOptions::default, module).expect("Could not start the worker");
+        assert_eq!(
+            worker
+                .receive::<String>()
+                .expect("Could not receive message"),
+            "ready"
+        );
+
+        // Timers keep running between messages
+        worker.post_message(1).expect("Could not post message");
+        worker.post_message(2).expect("Could not post message");
+        assert_eq!(
+            worker.receive::<i64>().expect("Could not receive message"),
+            2
+        );
+        assert_eq!(
+            worker.receive::<i64>().expect("Could not receive message"),
+            4
+        );
+        assert_eq!(
+            worker
+                .try_receive::<i64>()
+                .expect("Could not receive message"),
+            None
+        );
+
+        // A handler that throws does not stop the worker
+        worker
+            .post_message("throw")
+            .expect("Could not post message");
+        worker.receive::<i64>().unwrap_err();
+        worker.post_message(5).expect("Could not post message");
+        assert_eq!(
+            worker.receive::<i64>().expect("Could not receive message"),
+            10
+        );
+
+        // A busy script is interrupted, and queued messages are not dispatched
+        worker.post_message("spin").expect("Could not post message");
+        worker.post_message("spin").expect("Could not post message");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let start = std::time::Instant::now();
+        worker.terminate();
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert!(!worker.is_running());
+        assert!(matches!(
+            worker.post_message(1),
+            Err(Error::WorkerHasStopped)
+        ));
+
+        // A module that fails to load stops the worker
+        let module = Module::new("test.js", "throw new Error('no');");
+        let worker =
+            ModuleWorker::new(RuntimeOptions::default, module).expect("Could not start the worker");
+        worker.receive::<i64>().unwrap_err();
+        assert!(matches!(
+            worker.receive::<i64>(),
+            Err(Error::WorkerHasStopped)
+        ));
+    }
+}
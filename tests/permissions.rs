@@ -9,8 +9,8 @@ use std::time::Duration;
 
 #[cfg(feature = "web")]
 use rustyscript::{
-    to_permissions_options, AllowlistWebPermissions, DefaultWebPermissions, ExtensionOptions,
-    WebOptions,
+    to_permissions_options, AllowlistWebPermissions, ClosureWebPermissions, DefaultWebPermissions,
+    ExtensionOptions, WebOptions, WebPermissions,
 };
 
 /// Test that AllowlistWebPermissions with no allowed hosts blocks fetch
@@ -168,3 +168,317 @@ fn test_to_permissions_options_allowlist() {
         "allow_import should be Some even for restrictive permissions"
     );
 }
+
+/// Test that ClosureWebPermissions delegates net, read and write checks to its closures
+#[test]
+#[cfg(feature = "web")]
+fn test_closure_permissions() {
+    use std::{borrow::Cow, path::Path};
+
+    let permissions = ClosureWebPermissions::new()
+        .with_net(|url| {
+            url.scheme() == "https" && url.host_str().is_some_and(|h| h.ends_with(".org"))
+        })
+        .with_read(|path| path.starts_with("/srv"))
+        .with_write(|path| path.starts_with("/srv/uploads"));
+
+    let url = |s: &str| rustyscript::deno_core::url::Url::parse(s).expect("Invalid url");
+    assert!(permissions
+        .check_url(&url("https://example.org/a"), "fetch")
+        .is_ok());
+    assert!(permissions
+        .check_url(&url("http://example.org/a"), "fetch")
+        .is_err());
+    assert!(permissions
+        .check_url(&url("https://example.com/a"), "fetch")
+        .is_err());
+
+    let read = |p: &str| {
+        permissions
+            .check_read(Cow::Borrowed(Path::new(p)), None)
+            .is_ok()
+    };
+    assert!(read("/srv/assets/logo.png"));
+    assert!(!read("/etc/passwd"));
+    assert!(permissions.check_read_all(None).is_err());
+
+    let write = |p: &str| {
+        permissions
+            .check_write(Cow::Borrowed(Path::new(p)), None)
+            .is_ok()
+    };
+    assert!(write("/srv/uploads/file.txt"));
+    assert!(!write("/srv/assets/logo.png"));
+
+    let open = |p: &str, read, write| {
+        permissions
+            .check_open(true, read, write, Cow::Borrowed(Path::new(p)), "open")
+            .is_some()
+    };
+    assert!(open("/srv/assets/logo.png", true, false));
+    assert!(!open("/srv/assets/logo.png", true, true));
+
+    // Checks without a closure are denied
+    assert!(permissions.check_env("HOME").is_err());
+    assert!(permissions
+        .check_host("example.org", Some(443), "connect")
+        .is_err());
+
+    let opts = to_permissions_options(&permissions);
+    assert!(opts.allow_net.is_none());
+    assert!(opts.allow_import.is_some());
+}
+
+/// Test that ClosureWebPermissions normalizes paths before handing them to its closures
+#[test]
+#[cfg(feature = "web")]
+fn test_closure_permissions_normalize_paths() {
+    use std::{borrow::Cow, path::Path};
+
+    let permissions = ClosureWebPermissions::new()
+        .with_read(|path| path.is_absolute() && path.starts_with("/srv/assets"))
+        .with_write(|path| path.starts_with("/srv/uploads"));
+
+    let read = |p: &str| permissions.check_read(Cow::Borrowed(Path::new(p)), None);
+
+    // `..` is resolved before the prefix check, so it cannot escape the allowed directory
+    assert!(read("/srv/assets/../../etc/passwd").is_err());
+    assert!(read("/srv/assets/../secrets/key").is_err());
+
+    // The normalized path is the one returned to the caller
+    let resolved = read("/srv/assets/./css/../logo.png").expect("path should be allowed");
+    assert_eq!(resolved, Path::new("/srv/assets/logo.png"));
+
+    assert!(permissions
+        .check_write(Cow::Borrowed(Path::new("/srv/uploads/../assets/x")), None)
+        .is_err());
+    assert!(permissions
+        .check_open(
+            true,
+            true,
+            false,
+            Cow::Borrowed(Path::new("/srv/assets/../../etc/passwd")),
+            "open"
+        )
+        .is_none());
+}
+
+/// Test that to_permissions_options detects ClosureWebPermissions allowing everything as allow-all
+#[test]
+#[cfg(feature = "web")]
+fn test_to_permissions_options_closure_allow_all() {
+    let permissions = ClosureWebPermissions::new()
+        .with_hrtime(true)
+        .with_exec(true)
+        .with_host(|_, _| true)
+        .with_read(|_| true)
+        .with_write(|_| true)
+        .with_env(|_| true);
+
+    let opts = to_permissions_options(&permissions);
+    assert!(opts.allow_read.is_some());
+    assert!(opts.allow_write.is_some());
+    assert!(opts.allow_net.is_some());
+    assert!(opts.allow_env.is_some());
+}
+
+/// Test that the sandboxed Deno.env is checked against the env allowlist
+#[test]
+#[cfg(feature = "web")]
+fn test_sandboxed_env_allowlist() {
+    let permissions = AllowlistWebPermissions::new();
+    permissions.allow_env("ALLOWED");
+
+    let mut runtime = Runtime::new(RuntimeOptions {
+        env: Some([("ALLOWED".to_string(), "yes".to_string())].into()),
+        extension_options: ExtensionOptions {
+            web: WebOptions {
+                permissions: Arc::new(permissions),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .expect("Failed to create runtime");
+
+    let value: String = runtime
+        .eval("Deno.env.get('ALLOWED')")
+        .expect("Allowed key should be readable");
+    assert_eq!(value, "yes");
+
+    let result: rustyscript::serde_json::Value = runtime
+        .eval(
+            "(() => {
+                try {
+                    Deno.env.get('SECRET');
+                    return { blocked: false };
+                } catch (e) {
+                    return { blocked: true, name: e.name, message: e.message };
+                }
+            })()",
+        )
+        .expect("Failed to evaluate");
+    assert_eq!(result["blocked"], true, "Got: {result:?}");
+    assert_eq!(result["name"], "NotCapable", "Got: {result:?}");
+    assert!(result["message"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("Requires env access to \"SECRET\""));
+}
+
+/// Test that WebSocketStream is gated by the same permission check as WebSocket
+#[test]
+#[cfg(feature = "websocket")]
+fn test_allowlist_blocks_websocket_stream() {
+    let permissions = AllowlistWebPermissions::new();
+    permissions.allow_url("ws://127.0.0.1:1/allowed");
+
+    let mut runtime = Runtime::new(RuntimeOptions {
+        timeout: Duration::from_secs(10),
+        extension_options: ExtensionOptions {
+            web: WebOptions {
+                permissions: Arc::new(permissions),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .expect("Failed to create runtime");
+
+    let result: rustyscript::serde_json::Value = runtime
+        .eval(
+            "(() => {
+                const blocked = (create) => {
+                    try {
+                        const socket = create();
+                        socket.opened?.catch(() => {});
+                        socket.closed?.catch(() => {});
+                        socket.onerror = () => {};
+                        return false;
+                    } catch (e) {
+                        return true;
+                    }
+                };
+                return {
+                    socket: blocked(() => new WebSocket('ws://127.0.0.1:1/denied')),
+                    stream: blocked(() => new WebSocketStream('ws://127.0.0.1:1/denied')),
+                    allowed: blocked(() => new WebSocketStream('ws://127.0.0.1:1/allowed')),
+                };
+            })()",
+        )
+        .expect("Failed to evaluate");
+    assert_eq!(result["socket"], true, "Got: {result:?}");
+    assert_eq!(result["stream"], true, "Got: {result:?}");
+    assert_eq!(result["allowed"], false, "Got: {result:?}");
+}
+
+/// Test that allowlisted URLs match the way requests are serialized
+#[test]
+#[cfg(feature = "web")]
+fn test_allowlist_normalizes_urls() {
+    let permissions = AllowlistWebPermissions::new();
+    permissions.allow_url("https://EXAMPLE.com");
+
+    let url = rustyscript::deno_core::url::Url::parse("https://example.com/").expect("Invalid URL");
+    assert!(permissions.check_url(&url, "fetch()").is_ok());
+
+    permissions.deny_url("https://example.com/");
+    assert!(permissions.check_url(&url, "fetch()").is_err());
+}
+
+#[test]
+#[cfg(feature = "web")]
+fn test_call_function_with_permissions() {
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not bind listener");
+    let addr = listener.local_addr().expect("Could not get address");
+    let url = format!("http://{addr}/");
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            server_hits.fetch_add(1, Ordering::SeqCst);
+            let mut stream = stream.expect("Could not accept connection");
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).expect("Could not read request");
+            let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+            stream
+                .write_all(response.as_bytes())
+                .expect("Could not write response");
+        }
+    });
+
+    let mut runtime = Runtime::new(RuntimeOptions {
+        timeout: Duration::from_secs(10),
+        ..Default::default()
+    })
+    .expect("Failed to create runtime");
+
+    let module = Module::new(
+        "callbacks.js",
+        "
+        export async function get(url) {
+            await new Promise((resolve) => setTimeout(resolve, 1));
+            return (await fetch(url)).text();
+        }
+
+        export function listen(url) {
+            return new Promise((resolve) => {
+                const source = new EventSource(url);
+                source.onerror = () => {
+                    source.close();
+                    resolve();
+                };
+            });
+        }
+        ",
+    );
+    let module = runtime.load_module(&module).expect("Failed to load module");
+
+    // Denied for the duration of the call, including after an await
+    let error = runtime
+        .call_function_with_permissions::<String>(
+            Some(&module),
+            "get",
+            rustyscript::json_args!(&url),
+            Arc::new(AllowlistWebPermissions::new()),
+        )
+        .expect_err("Fetch should be denied during the call");
+    assert!(
+        error.to_string().contains(&addr.ip().to_string()),
+        "{error}"
+    );
+
+    // Restored once the call fails
+    let text: String = runtime
+        .call_function(Some(&module), "get", rustyscript::json_args!(&url))
+        .expect("Fetch should succeed after the call");
+    assert_eq!(text, "ok");
+
+    // And only tightened, so an allowing scope lets the request through
+    let text: String = runtime
+        .call_function_with_permissions(
+            Some(&module),
+            "get",
+            rustyscript::json_args!(&url),
+            Arc::new(DefaultWebPermissions),
+        )
+        .expect("Allowed fetch should succeed");
+    assert_eq!(text, "ok");
+
+    // Checked by the deno permissions too, so requests made from inside the extensions are covered
+    let before = hits.load(Ordering::SeqCst);
+    runtime
+        .call_function_with_permissions::<()>(
+            Some(&module),
+            "listen",
+            rustyscript::json_args!(&url),
+            Arc::new(AllowlistWebPermissions::new()),
+        )
+        .expect("Failed to call function");
+    assert_eq!(hits.load(Ordering::SeqCst), before);
+}
@@ -0,0 +1,43 @@
+//! Tests for `RuntimeOptions::stack_size`
+//!
+//! The stack size is a process-wide V8 setting, fixed by the first runtime created,
+//! so these tests live in their own test binary.
+
+use rustyscript::{Runtime, RuntimeOptions};
+
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Test that a raised stack size allows recursion that would overflow the default limit
+#[test]
+fn test_deep_recursion() {
+    // The thread must have more stack than we allow V8 to use
+    let handle = std::thread::Builder::new()
+        .stack_size(STACK_SIZE * 2)
+        .spawn(|| {
+            let mut runtime = Runtime::new(RuntimeOptions {
+                stack_size: Some(STACK_SIZE),
+                ..Default::default()
+            })
+            .expect("Failed to create runtime");
+
+            let depth: u32 = runtime
+                .eval(
+                    "
+                    function depth(n) { return n === 0 ? 0 : 1 + depth(n - 1); }
+                    depth(100000)
+                ",
+                )
+                .expect("Recursion overflowed the raised stack limit");
+            assert_eq!(depth, 100_000);
+
+            // Other sizes cannot be applied once V8 is running
+            let result = Runtime::new(RuntimeOptions {
+                stack_size: Some(STACK_SIZE / 2),
+                ..Default::default()
+            });
+            assert!(result.is_err());
+        })
+        .unwrap();
+
+    handle.join().unwrap();
+}
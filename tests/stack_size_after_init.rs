@@ -0,0 +1,21 @@
+//! Tests for `RuntimeOptions::stack_size` once V8 has been initialized without it
+//!
+//! V8's flags cannot be changed after the platform is initialized,
+//! so these tests live in their own test binary.
+
+use rustyscript::{Runtime, RuntimeOptions};
+
+/// Test that a stack size is refused rather than ignored once the platform is initialized
+#[test]
+fn test_stack_size_after_init_platform() {
+    rustyscript::init_platform(2, true);
+
+    let result = Runtime::new(RuntimeOptions {
+        stack_size: Some(64 * 1024 * 1024),
+        ..Default::default()
+    });
+    assert!(result.is_err());
+
+    // Runtimes that leave it unset are unaffected
+    Runtime::new(RuntimeOptions::default()).expect("Failed to create runtime");
+}